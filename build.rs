@@ -0,0 +1,12 @@
+// Generates the gRPC code of the fib-grpc binary from proto/fib.proto, with the `grpc` feature. protoc comes from
+// protoc-bin-vendored, so the build doesn't need one installed
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("protoc-bin-vendored has a protoc for this platform");
+        std::env::set_var("PROTOC", protoc);
+        tonic_build::compile_protos("proto/fib.proto").expect("proto/fib.proto compiles");
+    }
+}
@@ -0,0 +1,50 @@
+// Proves and verifies a Fibonacci statement through the C interface of the library (src/ffi.rs).
+// From the root of the repository:
+//
+//   cargo build --release --lib --features ffi
+//   cc examples/c/main.c -Iinclude -Ltarget/release -lhalo2_fibonacci_ex -o fib-c
+//   LD_LIBRARY_PATH=target/release ./fib-c
+
+#include <stdio.h>
+
+#include "halo2_fibonacci_ex.h"
+
+static int fail(const char *step) {
+    fprintf(stderr, "%s: %s\n", step, fib_last_error());
+    return 1;
+}
+
+int main(void) {
+    FibKeys *keys;
+    if (fib_keys_new(4, 8, &keys) != FIB_OK) {
+        return fail("keygen");
+    }
+
+    uint8_t fingerprint[32];
+    if (fib_keys_fingerprint(keys, fingerprint) != FIB_OK) {
+        return fail("fingerprint");
+    }
+    printf("verifying key ");
+    for (size_t i = 0; i < sizeof(fingerprint); i++) {
+        printf("%02x", fingerprint[i]);
+    }
+    printf("\n");
+
+    FibBuffer proof;
+    if (fib_prove(keys, "1", "1", &proof) != FIB_OK) {
+        return fail("prove");
+    }
+    printf("proof of %zu bytes\n", proof.len);
+
+    if (fib_verify(keys, "1", "1", "55", proof.data, proof.len) != FIB_OK) {
+        return fail("verify");
+    }
+    printf("1, 1 -> 55: valid\n");
+
+    int32_t code = fib_verify(keys, "1", "1", "56", proof.data, proof.len);
+    printf("1, 1 -> 56: code %d, %s\n", code, fib_last_error());
+
+    fib_buffer_free(proof);
+    fib_keys_free(keys);
+    return code == 7 ? 0 : 1;
+}
@@ -0,0 +1,62 @@
+/*
+ * The prover and the verifier of the Fibonacci circuit of halo2-fibonacci-ex, built with
+ * `cargo build --release --lib --features ffi` (target/release/libhalo2_fibonacci_ex.so / .dylib / .dll).
+ *
+ * - Every function returns FIB_OK or the code of an error (7 for a proof that doesn't verify), and
+ *   fib_last_error() returns the last error of the calling thread as JSON {"code", "kind", "message"}.
+ * - A FibKeys handle comes from fib_keys_new and goes back to fib_keys_free. It can be shared by threads.
+ * - A FibBuffer filled by the library goes back to fib_buffer_free.
+ * - Field elements are NUL-terminated strings, decimal or 0x-prefixed hex.
+ */
+
+#ifndef HALO2_FIBONACCI_EX_H
+#define HALO2_FIBONACCI_EX_H
+
+/* Generated by cbindgen from src/ffi.rs, don't edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define FIB_OK 0
+
+typedef struct FibKeys FibKeys;
+
+typedef struct FibBuffer {
+  uint8_t *data;
+  size_t len;
+} FibBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+int32_t fib_keys_new(uint32_t k, size_t n, struct FibKeys **keys);
+
+void fib_keys_free(struct FibKeys *keys);
+
+int32_t fib_keys_fingerprint(const struct FibKeys *keys, uint8_t *out);
+
+int32_t fib_prove(const struct FibKeys *keys,
+                  const char *a,
+                  const char *b,
+                  struct FibBuffer *proof);
+
+int32_t fib_verify(const struct FibKeys *keys,
+                   const char *a,
+                   const char *b,
+                   const char *out,
+                   const uint8_t *proof,
+                   size_t len);
+
+void fib_buffer_free(struct FibBuffer buffer);
+
+const char *fib_last_error(void);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* HALO2_FIBONACCI_EX_H */
@@ -0,0 +1,225 @@
+use halo2_proofs::{
+    pasta::{group::ff::PrimeField, Fp},
+    plonk::BatchVerifier,
+};
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    bundle::{hex_fields, ProofBundle, BACKEND},
+    error::FibError,
+    registry::{check_bundle, Keys, Registry, Verified},
+    transcript::TranscriptKind,
+};
+
+// Several proof bundles, for different statements (a, b, n), checked at once and summed up by one attestation.
+//
+// The proofs are verified in a batch: halo2_proofs folds the final check of every IPA proof, a multiscalar
+// multiplication of the size of the circuit, into a single one, each scaled by a random factor so that an invalid
+// proof can't cancel another one out. The proofs are grouped by k since a batch uses the parameters of one k. Each
+// bundle goes through the checks of the registry (src/registry.rs) first, as with verify_bundle.
+//
+// The attestation lists the statements and their verifying keys, and a digest binding them to the proofs:
+//
+//   SHA-256 over, for every bundle in order:
+//     len(circuit_id) u64 LE, circuit_id, k u32 LE, len(instance) u64 LE, the instance as 32-byte representations,
+//     len(proof) u64 LE, proof
+//
+// so whoever holds the bundles can recompute it, and whoever trusts the aggregator only needs the attestation. It is
+// not a proof itself: an attestation checkable without the bundles is a proof verifying the proofs inside a circuit.
+//
+// A batch only tells that *some* proof is invalid. verify_batch finds which ones: it splits a failing batch in halves
+// and verifies each half as a batch again, down to the invalid proofs, so that the valid ones still share the work.
+// With a few invalid proofs among many, that takes a few more batches rather than one verification per proof.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestedStatement {
+    pub circuit_id: String,
+    pub k: u32,
+    #[serde(with = "hex_fields")]
+    pub instance: Vec<Fp>,
+    // The fingerprint of the verifying key, see prover::fingerprint
+    pub fingerprint: String,
+}
+
+/// What a batch of verified bundles proves.
+///
+/// ```
+/// use halo2_proofs::pasta::Fp;
+/// use halo2_fibonacci_ex::{
+///     aggregate::aggregate,
+///     bundle::{Metadata, ProofBundle},
+///     error::FibError,
+///     fibonacci::FibInputs,
+///     prover::{prove, CancellationToken},
+///     registry::Registry,
+/// };
+///
+/// let mut registry = Registry::new();
+/// let bundles: Vec<ProofBundle> = [(1, 1, 8), (2, 3, 5), (1, 2, 20)]
+///     .iter()
+///     .map(|&(a, b, n)| {
+///         let k = if n > 9 { 5 } else { 4 };
+///         registry.register_fibonacci(k, n);
+///         let inputs = FibInputs::new(Fp::from(a), Fp::from(b), n);
+///         let proof = prove(k, &inputs.circuit(), &inputs.public_inputs(), &CancellationToken::new()).unwrap();
+///         ProofBundle::new(Metadata::new(k, inputs.circuit_id()), inputs.public_inputs(), proof)
+///     })
+///     .collect();
+///
+/// let attestation = aggregate(&bundles, &registry).unwrap();
+/// assert_eq!(attestation.statements.len(), 3);
+/// assert_eq!(attestation.statements[0].instance[2], Fp::from(55));
+/// assert_eq!(attestation.digest, aggregate(&bundles, &registry).unwrap().digest);
+///
+/// // One invalid proof fails the whole batch
+/// let mut tampered = bundles.clone();
+/// tampered[1].instance[2] += Fp::one();
+/// assert!(matches!(aggregate(&tampered, &registry), Err(FibError::InvalidProof)));
+/// ```
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attestation {
+    pub backend: String,
+    pub statements: Vec<AttestedStatement>,
+    // Hex encoded, see the top of the file
+    pub digest: String,
+}
+
+impl Attestation {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("an attestation can always be serialized")
+    }
+}
+
+// Verifies every bundle and returns the attestation of the batch. The bundles were created with prover::prove, i.e.
+// with a Blake2b transcript
+pub fn aggregate(bundles: &[ProofBundle], registry: &Registry) -> Result<Attestation, FibError> {
+    if bundles.is_empty() {
+        return Err(FibError::InvalidInput("no proof to aggregate".to_string()));
+    }
+    let checked = bundles.iter().map(|bundle| check_bundle(bundle, registry)).collect::<Result<Vec<_>, _>>()?;
+
+    let mut ks: Vec<u32> = checked.iter().map(|(_, verified)| verified.k).collect();
+    ks.sort_unstable();
+    ks.dedup();
+    for k in ks {
+        let batch: Vec<_> = checked.iter().zip(bundles).filter(|((_, verified), _)| verified.k == k).collect();
+        // The bundles of a k share the same parameters
+        let ((keys, _), _) = batch[0];
+        let params = &keys.0;
+        let mut verifier = BatchVerifier::new(params, OsRng);
+        for ((keys, _), bundle) in batch {
+            verifier = TranscriptKind::Blake2b
+                .verify_with_strategy(params, &keys.1, verifier, &bundle.instance, &bundle.proof)
+                .map_err(|_| FibError::InvalidProof)?;
+        }
+        if !verifier.finalize() {
+            return Err(FibError::InvalidProof);
+        }
+    }
+
+    let statements = checked
+        .into_iter()
+        .map(|(_, verified)| AttestedStatement {
+            circuit_id: verified.circuit_id,
+            k: verified.k,
+            instance: verified.instance,
+            fingerprint: verified.fingerprint,
+        })
+        .collect();
+    Ok(Attestation { backend: BACKEND.to_string(), statements, digest: digest(bundles) })
+}
+
+/// Whether each bundle verifies, sharing the work between the proofs as `aggregate` does.
+///
+/// ```
+/// use halo2_proofs::pasta::Fp;
+/// use halo2_fibonacci_ex::{
+///     aggregate::verify_batch,
+///     bundle::{Metadata, ProofBundle},
+///     fibonacci::FibInputs,
+///     prover::{prove, CancellationToken},
+///     registry::Registry,
+/// };
+///
+/// let mut registry = Registry::new();
+/// registry.register_fibonacci(4, 8);
+/// let mut bundles: Vec<ProofBundle> = (1..=5u64)
+///     .map(|a| {
+///         let inputs = FibInputs::new(Fp::from(a), Fp::one(), 8);
+///         let proof = prove(4, &inputs.circuit(), &inputs.public_inputs(), &CancellationToken::new()).unwrap();
+///         ProofBundle::new(Metadata::new(4, inputs.circuit_id()), inputs.public_inputs(), proof)
+///     })
+///     .collect();
+/// assert_eq!(verify_batch(&bundles, &registry), vec![true; 5]);
+///
+/// bundles[1].instance[2] += Fp::one();
+/// bundles[3].proof.truncate(10);
+/// bundles[4].metadata.circuit_id = "fibonacci-9".to_string();
+/// assert_eq!(verify_batch(&bundles, &registry), vec![true, false, true, false, false]);
+/// ```
+#[must_use = "the bundles are only verified if the results are checked"]
+pub fn verify_batch(bundles: &[ProofBundle], registry: &Registry) -> Vec<bool> {
+    let mut valid = vec![false; bundles.len()];
+    // A bundle that fails the checks of the registry is invalid without verifying its proof
+    let checked: Vec<(usize, (Keys, Verified))> = bundles
+        .iter()
+        .enumerate()
+        .filter_map(|(i, bundle)| check_bundle(bundle, registry).ok().map(|checked| (i, checked)))
+        .collect();
+
+    let mut ks: Vec<u32> = checked.iter().map(|(_, (_, verified))| verified.k).collect();
+    ks.sort_unstable();
+    ks.dedup();
+    for k in ks {
+        let batch: Vec<(&Keys, &ProofBundle, usize)> =
+            checked.iter().filter(|(_, (_, verified))| verified.k == k).map(|(i, (keys, _))| (keys, &bundles[*i], *i)).collect();
+        bisect(&batch, &mut valid);
+    }
+    valid
+}
+
+// Marks the bundles of `batch` that verify, all at once if the whole batch does
+fn bisect(batch: &[(&Keys, &ProofBundle, usize)], valid: &mut [bool]) {
+    if batch_verifies(batch) {
+        for &(_, _, i) in batch {
+            valid[i] = true;
+        }
+    } else if batch.len() > 1 {
+        let (left, right) = batch.split_at(batch.len() / 2);
+        bisect(left, valid);
+        bisect(right, valid);
+    }
+}
+
+// The bundles of a batch share the parameters of their k
+fn batch_verifies(batch: &[(&Keys, &ProofBundle, usize)]) -> bool {
+    let params = &batch[0].0 .0;
+    let mut verifier = BatchVerifier::new(params, OsRng);
+    for (keys, bundle, _) in batch {
+        verifier = match TranscriptKind::Blake2b.verify_with_strategy(params, &keys.1, verifier, &bundle.instance, &bundle.proof) {
+            Ok(verifier) => verifier,
+            Err(_) => return false,
+        };
+    }
+    verifier.finalize()
+}
+
+fn digest(bundles: &[ProofBundle]) -> String {
+    let mut hasher = Sha256::new();
+    for bundle in bundles {
+        let circuit_id = bundle.metadata.circuit_id.as_bytes();
+        hasher.update((circuit_id.len() as u64).to_le_bytes());
+        hasher.update(circuit_id);
+        hasher.update(bundle.metadata.k.to_le_bytes());
+        hasher.update((bundle.instance.len() as u64).to_le_bytes());
+        for value in &bundle.instance {
+            hasher.update(value.to_repr());
+        }
+        hasher.update((bundle.proof.len() as u64).to_le_bytes());
+        hasher.update(&bundle.proof);
+    }
+    hex::encode(hasher.finalize())
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use halo2_proofs::{dev::VerifyFailure, plonk::*};
+
+use crate::audit::ColumnId;
+
+// Names for the columns of a circuit, so that diagnostics say "column fib_a" rather than "advice column 0".
+// halo2_proofs 0.1.0 has no column annotations (later versions add `Region::name_column`), so the names are kept in a
+// map next to the config, filled by the `annotate` method of each config of the library (e.g. "a", "b", "lt" for
+// LtConfig). The complete circuits have a `column_names` function naming all their columns (SequenceCircuit,
+// MerkleCircuit). The diagnostics of the library take the map as a parameter:
+// - CircuitStats and EqualityReport print the names they are given with `with_names`
+// - describe_failure and FailureReport rewrite the columns of MockProver failures
+// The dev-graph rendering (CircuitLayout) of halo2_proofs 0.1.0 draws no column labels, so it can't use them.
+
+/// Column names, falling back to "advice column 2" for the columns without one.
+///
+/// ```
+/// use halo2_proofs::{dev::MockProver, pasta::Fp};
+/// use halo2_fibonacci_ex::{annotate::describe_failure, fibonacci::{FibCircuit, FibInputs}};
+///
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// let mut public_inputs = inputs.public_inputs();
+/// public_inputs[2] = Fp::from(56);
+///
+/// let failures = MockProver::run(4, &inputs.circuit(), vec![public_inputs]).unwrap().verify().unwrap_err();
+/// let names = FibCircuit::<Fp>::column_names();
+/// let described: Vec<String> = failures.iter().map(|failure| describe_failure(failure, &names)).collect();
+/// assert!(described.iter().any(|failure| failure.contains("column fib_b")));
+/// assert!(described.iter().all(|failure| !failure.contains("Advice")));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnNames {
+    names: HashMap<ColumnId, String>,
+}
+
+impl ColumnNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The names are only there for the diagnostics: a column ColumnId::of can't identify is left unnamed
+    pub fn annotate<C: ColumnType>(&mut self, column: Column<C>, name: impl Into<String>) -> &mut Self {
+        if let Ok(column) = ColumnId::of(column) {
+            self.names.insert(column, name.into());
+        }
+        self
+    }
+
+    pub fn get(&self, column: ColumnId) -> Option<&str> {
+        self.names.get(&column).map(String::as_str)
+    }
+
+    // "column fib_a" for an annotated column, "advice column 0" otherwise
+    pub fn describe(&self, column: ColumnId) -> String {
+        match self.get(column) {
+            Some(name) => format!("column {}", name),
+            None => column.to_string(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+// The Display of a MockProver failure, with the annotated columns replaced by their names. halo2_proofs 0.1.0 prints
+// columns as "Column('Advice', 0)" or as the Debug output of plonk::Column or of dev::metadata::Column, which list
+// their fields in opposite orders. All three are rewritten
+pub fn describe_failure(failure: &VerifyFailure, names: &ColumnNames) -> String {
+    rename_columns(&failure.to_string(), names)
+}
+
+// Rewrites the columns printed by halo2_proofs in any of the three forms above
+pub(crate) fn rename_columns(text: &str, names: &ColumnNames) -> String {
+    let mut described = text.to_string();
+    for (column, name) in &names.names {
+        let column_type = format!("{:?}", column.column_type);
+        let name = format!("column {}", name);
+        described = described
+            .replace(&format!("Column('{}', {})", column_type, column.index), &name)
+            .replace(&format!("Column {{ index: {}, column_type: {} }}", column.index, column_type), &name)
+            .replace(&format!("Column {{ column_type: {}, index: {} }}", column_type, column.index), &name);
+    }
+    described
+}
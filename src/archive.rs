@@ -0,0 +1,163 @@
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_vk, Circuit},
+    poly::commitment::Params,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::IpaPasta,
+    bundle::{hex_fields, Metadata, ProofBundle, BACKEND},
+    error::FibError,
+    fibonacci::FibInputs,
+    params::params_info,
+    prover,
+};
+
+// Many proofs of the same circuit in one file, for provers that create proofs in batches and ship them together. The
+// metadata every proof bundle would repeat is stored once, with what the verifier needs to check that it has the same
+// circuit and the same parameters as the prover before verifying anything:
+//
+//   {
+//     "metadata": {"crate_version": "0.1.0", "backend": "halo2_proofs 0.1.0, IPA over vesta, Blake2b transcript",
+//                  "k": 4, "circuit_id": "fibonacci-8", "fingerprint": "e91f...", "vk_hash": "e69f...",
+//                  "params_hash": "5c0b..."},
+//     "proofs": [{"created_at": "2026-10-18T09:00:00Z", "instance": ["0100...", ...], "proof": "a3f0..."}, ...]
+//   }
+//
+// fingerprint is prover::fingerprint, the identity of the verifying key that `fib verify --fingerprint` pins, vk_hash
+// is prover::vk_hash, which hashes it with the circuit id and k, and params_hash is the SHA-256 of the parameters as
+// params::write_params writes them. Like the metadata of a bundle, none of them is bound to the proofs: they tell a
+// verifier with other keys why its verifications fail, they don't replace them.
+//
+// `fib bundle` packs proof bundles into an archive and `fib unbundle` writes them back as bundles, e.g. for
+// `fib verify --all`.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveMetadata {
+    pub crate_version: String,
+    pub backend: String,
+    pub k: u32,
+    pub circuit_id: String,
+    pub fingerprint: String,
+    pub vk_hash: String,
+    pub params_hash: String,
+}
+
+impl ArchiveMetadata {
+    // The metadata of the Fibonacci circuit with n steps over 2^k rows, with the keys generated from `params`
+    pub fn fibonacci(k: u32, n: usize, params: &Params<EqAffine>) -> Result<Self, FibError> {
+        let inputs = FibInputs::new(Fp::zero(), Fp::zero(), n);
+        let vk = keygen_vk(params, &inputs.circuit().without_witnesses())?;
+        Ok(Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            backend: BACKEND.to_string(),
+            k,
+            circuit_id: inputs.circuit_id(),
+            fingerprint: prover::fingerprint(&vk),
+            vk_hash: prover::vk_hash(&inputs.circuit_id(), k, &vk),
+            params_hash: params_info::<IpaPasta>(params).sha256,
+        })
+    }
+}
+
+// A proof of the archive, without the shared metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    created_at: String,
+    #[serde(with = "hex_fields")]
+    instance: Vec<Fp>,
+    #[serde(with = "hex::serde")]
+    proof: Vec<u8>,
+}
+
+/// Proof bundles of one circuit sharing their metadata.
+///
+/// ```
+/// use halo2_proofs::{pasta::Fp, poly::commitment::Params};
+/// use halo2_fibonacci_ex::{
+///     archive::{ArchiveMetadata, ProofArchive},
+///     bundle::{Metadata, ProofBundle},
+///     fibonacci::FibInputs,
+///     prover::{prove, CancellationToken},
+/// };
+///
+/// let bundle = |a: u64| {
+///     let inputs = FibInputs::new(Fp::from(a), Fp::one(), 8);
+///     let proof = prove(4, &inputs.circuit(), &inputs.public_inputs(), &CancellationToken::new()).unwrap();
+///     ProofBundle::new(Metadata::new(4, inputs.circuit_id()), inputs.public_inputs(), proof)
+/// };
+///
+/// let mut archive = ProofArchive::new(ArchiveMetadata::fibonacci(4, 8, &Params::new(4)).unwrap());
+/// let bundles = [bundle(1), bundle(2)];
+/// for bundle in &bundles {
+///     archive.append(bundle.clone()).unwrap();
+/// }
+/// let archive = ProofArchive::from_json(&archive.to_json()).unwrap();
+/// assert_eq!(archive.iter().collect::<Vec<_>>(), bundles);
+///
+/// // A proof of another circuit doesn't share the metadata
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 9);
+/// let other = ProofBundle::new(Metadata::new(4, inputs.circuit_id()), inputs.public_inputs(), vec![]);
+/// assert!(archive.clone().append(other).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofArchive {
+    pub metadata: ArchiveMetadata,
+    proofs: Vec<Entry>,
+}
+
+impl ProofArchive {
+    pub fn new(metadata: ArchiveMetadata) -> Self {
+        Self { metadata, proofs: Vec::new() }
+    }
+
+    // Adds a bundle created by the same build for the circuit of the archive
+    pub fn append(&mut self, bundle: ProofBundle) -> Result<(), FibError> {
+        let (shared, metadata) = (&self.metadata, &bundle.metadata);
+        if metadata.circuit_id != shared.circuit_id || metadata.k != shared.k {
+            return Err(FibError::InvalidInput(format!(
+                "a proof of {} over 2^{} rows in an archive of {} over 2^{} rows",
+                metadata.circuit_id, metadata.k, shared.circuit_id, shared.k
+            )));
+        }
+        if metadata.backend != shared.backend || metadata.crate_version != shared.crate_version {
+            return Err(FibError::InvalidInput(format!(
+                "a proof of {} {} in an archive of {} {}",
+                metadata.backend, metadata.crate_version, shared.backend, shared.crate_version
+            )));
+        }
+        self.proofs.push(Entry { created_at: bundle.metadata.created_at, instance: bundle.instance, proof: bundle.proof });
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+
+    // The proofs as bundles, in the order they were appended
+    pub fn iter(&self) -> impl Iterator<Item = ProofBundle> + '_ {
+        self.proofs.iter().map(|entry| {
+            let metadata = Metadata {
+                crate_version: self.metadata.crate_version.clone(),
+                backend: self.metadata.backend.clone(),
+                k: self.metadata.k,
+                circuit_id: self.metadata.circuit_id.clone(),
+                created_at: entry.created_at.clone(),
+            };
+            ProofBundle::new(metadata, entry.instance.clone(), entry.proof.clone())
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("an archive can always be serialized")
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, FibError> {
+        serde_json::from_str(s).map_err(|e| FibError::Parse(format!("invalid proof archive: {}", e)))
+    }
+}
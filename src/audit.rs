@@ -0,0 +1,492 @@
+use std::{cell::RefCell, fmt};
+
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    pasta::{EqAffine, Fp},
+    plonk::*,
+    poly::commitment::Params,
+};
+
+use crate::{annotate::ColumnNames, error::FibError, stats::RegionStats};
+
+// Checks on the way a circuit uses `enable_equality`, which is a common source of confusing failures:
+// - a column with equality enabled that is never part of a copy constraint costs a permutation column for nothing,
+//   and is often the sign that the copy was meant for another column
+// - a copy constraint on a column without equality enabled makes the MockProver fail with ColumnNotInPermutation,
+//   which names the column but not the region the copy comes from
+//
+// The audit configures the circuit, runs its synthesis against a recorder of copy constraints and compares both.
+// halo2_proofs 0.1.0 keeps the permutation argument, the constant columns and the column indices crate private:
+// their Debug output is the only public view of them, so it is parsed here. Bump the parsing together with the
+// halo2_proofs dependency.
+//
+// check_witness_independence is the other half: keys are generated from the circuit without witnesses (the verifier
+// doesn't have them) and proofs from the circuit with witnesses, so both must lay out the same selectors, fixed
+// values and copy constraints. That is what the comments on without_witnesses keep saying ("the number of steps is
+// part of the shape of the circuit"), this checks it.
+
+// A column of any type, e.g. "advice column 2"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ColumnId {
+    pub column_type: Any,
+    pub index: usize,
+}
+
+impl ColumnId {
+    // FibError::Internal if the Debug output of the column isn't the one of halo2_proofs 0.1.0
+    pub fn of<C: ColumnType>(column: Column<C>) -> Result<Self, FibError> {
+        let debug = format!("{:?}", column);
+        parse_columns(&debug)?.into_iter().next().ok_or_else(|| unexpected(&debug))
+    }
+}
+
+impl fmt::Display for ColumnId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let column_type = match self.column_type {
+            Any::Advice => "advice",
+            Any::Fixed => "fixed",
+            Any::Instance => "instance",
+        };
+        write!(f, "{} column {}", column_type, self.index)
+    }
+}
+
+// A copy constraint involving a column without equality enabled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyAttempt {
+    pub region: String,
+    pub column: ColumnId,
+    pub row: usize,
+}
+
+impl CopyAttempt {
+    pub fn describe(&self, names: &ColumnNames) -> String {
+        format!(
+            "region {:?} copies the {} at row {}, which doesn't have equality enabled: call `meta.enable_equality` on it in configure",
+            self.region,
+            names.describe(self.column),
+            self.row
+        )
+    }
+}
+
+impl fmt::Display for CopyAttempt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.describe(&ColumnNames::new()))
+    }
+}
+
+/// The result of [`audit_equality`]: advice and instance columns with equality enabled that are never copied, and
+/// copies on columns without equality enabled. Fixed columns are not reported as unused, as `enable_constant`
+/// enables equality on them whether or not a constant is assigned.
+///
+/// ```
+/// use halo2_proofs::{circuit::*, pasta::Fp, plonk::*};
+/// use halo2_fibonacci_ex::{audit::{audit_equality, ColumnId}, fibonacci::FibInputs};
+///
+/// // The Fibonacci circuit of the library is clean
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// assert!(audit_equality(&inputs.circuit(), vec![inputs.public_inputs()]).unwrap().is_clean());
+///
+/// // Equality is enabled on a and c, but the region copies a into b
+/// #[derive(Default)]
+/// struct MyCircuit;
+///
+/// impl Circuit<Fp> for MyCircuit {
+///     type Config = [Column<Advice>; 3];
+///     type FloorPlanner = SimpleFloorPlanner;
+///
+///     fn without_witnesses(&self) -> Self {
+///         Self
+///     }
+///
+///     fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+///         let advice = [(); 3].map(|_| meta.advice_column());
+///         meta.enable_equality(advice[0]);
+///         meta.enable_equality(advice[2]);
+///         advice
+///     }
+///
+///     fn synthesize(&self, [a, b, _]: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+///         layouter.assign_region(|| "copy", |mut region| {
+///             let cell = region.assign_advice(|| "a", a, 0, || Ok(Fp::one()))?;
+///             cell.copy_advice(|| "b", &mut region, b, 0)?;
+///             Ok(())
+///         })
+///     }
+/// }
+///
+/// let report = audit_equality(&MyCircuit, vec![]).unwrap();
+/// assert_eq!(report.unused, vec![ColumnId { column_type: Any::Advice, index: 2 }]);
+/// assert_eq!(report.not_enabled[0].column, ColumnId { column_type: Any::Advice, index: 1 });
+/// assert_eq!(report.not_enabled[0].region, "copy");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EqualityReport {
+    pub unused: Vec<ColumnId>,
+    pub not_enabled: Vec<CopyAttempt>,
+    // Used by Display only
+    pub names: ColumnNames,
+}
+
+impl EqualityReport {
+    pub fn is_clean(&self) -> bool {
+        self.unused.is_empty() && self.not_enabled.is_empty()
+    }
+
+    pub fn with_names(self, names: ColumnNames) -> Self {
+        Self { names, ..self }
+    }
+}
+
+impl fmt::Display for EqualityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for column in &self.unused {
+            writeln!(f, "the {} has equality enabled but is never copied", self.names.describe(*column))?;
+        }
+        for attempt in &self.not_enabled {
+            writeln!(f, "{}", attempt.describe(&self.names))?;
+        }
+        Ok(())
+    }
+}
+
+// Runs the synthesis of `circuit` and compares its copy constraints with the columns that have equality enabled.
+// `instance` is only used to answer the instance queries of the circuit (e.g. assign_advice_from_instance)
+pub fn audit_equality<F: FieldExt, C: Circuit<F>>(circuit: &C, instance: Vec<Vec<F>>) -> Result<EqualityReport, FibError> {
+    let (recorder, enabled) = record(circuit, instance)?;
+
+    let mut report = EqualityReport::default();
+    let mut copied = vec![];
+    for (region, column, row) in recorder.copies() {
+        if !enabled.contains(&column) {
+            report.not_enabled.push(CopyAttempt { region, column, row });
+        }
+        copied.push(column);
+    }
+    report.unused = enabled
+        .into_iter()
+        .filter(|column| column.column_type != Any::Fixed && !copied.contains(column))
+        .collect();
+    Ok(report)
+}
+
+/// Generates the verifying key of `circuit` and of `circuit.without_witnesses()` and diffs them. A difference, returned
+/// as a [`WitnessDependence`], means that proofs created with the witnesses won't verify against the keys. Errors are
+/// left for the keygens that fail.
+///
+/// ```
+/// use halo2_proofs::{circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+/// use halo2_fibonacci_ex::{audit::check_witness_independence, fibonacci::FibInputs};
+///
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// assert_eq!(check_witness_independence(4, &inputs.circuit()).unwrap(), None);
+///
+/// // Checks that every value is a bit, but without_witnesses forgets how many values there are
+/// #[derive(Default)]
+/// struct MyCircuit {
+///     bits: Vec<Option<Fp>>,
+/// }
+///
+/// impl Circuit<Fp> for MyCircuit {
+///     type Config = (Column<Advice>, Selector);
+///     type FloorPlanner = SimpleFloorPlanner;
+///
+///     fn without_witnesses(&self) -> Self {
+///         Self::default()
+///     }
+///
+///     fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+///         let (bit, s) = (meta.advice_column(), meta.selector());
+///         meta.create_gate("bit", |meta| {
+///             let (s, b) = (meta.query_selector(s), meta.query_advice(bit, Rotation::cur()));
+///             vec![s * b.clone() * (Expression::Constant(Fp::one()) - b)]
+///         });
+///         (bit, s)
+///     }
+///
+///     fn synthesize(&self, (bit, s): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+///         layouter.assign_region(|| "bits", |mut region| {
+///             for (row, value) in self.bits.iter().enumerate() {
+///                 s.enable(&mut region, row)?;
+///                 region.assign_advice(|| "bit", bit, row, || value.ok_or(Error::Synthesis))?;
+///             }
+///             Ok(())
+///         })
+///     }
+/// }
+///
+/// let circuit = MyCircuit { bits: vec![Some(Fp::one()), Some(Fp::zero())] };
+/// let dependence = check_witness_independence(4, &circuit).unwrap().unwrap();
+/// assert_eq!(dependence.field, "fixed_commitments");
+/// ```
+pub fn check_witness_independence<C: Circuit<Fp>>(k: u32, circuit: &C) -> Result<Option<WitnessDependence>, FibError> {
+    let params = Params::<EqAffine>::new(k);
+    let with = format!("{:#?}", keygen_vk(&params, circuit)?.pinned());
+    let without = format!("{:#?}", keygen_vk(&params, &circuit.without_witnesses())?.pinned());
+
+    let Some((line, (a, b))) = with.lines().zip(without.lines()).enumerate().find(|(_, (a, b))| a != b) else {
+        let (with_len, without_len) = (with.lines().count(), without.lines().count());
+        return Ok((with_len != without_len).then(|| WitnessDependence {
+            field: "length".to_string(),
+            line: with_len.min(without_len) + 1,
+            with_witnesses: format!("{} lines", with_len),
+            without_witnesses: format!("{} lines", without_len),
+        }));
+    };
+
+    // The top level field of the pinned verifying key containing the line, e.g. "fixed_commitments"
+    let field = with
+        .lines()
+        .take(line + 1)
+        .filter_map(|l| l.strip_prefix("    ").filter(|l| !l.starts_with(' ')))
+        .last()
+        .and_then(|l| l.split(':').next())
+        .unwrap_or("?");
+    Ok(Some(WitnessDependence {
+        field: field.to_string(),
+        line: line + 1,
+        with_witnesses: a.trim().to_string(),
+        without_witnesses: b.trim().to_string(),
+    }))
+}
+
+// Where the verifying keys generated with and without the witnesses first differ: the top level field of the pinned
+// verifying key (e.g. "fixed_commitments") and the line of its pretty-printed Debug output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WitnessDependence {
+    pub field: String,
+    pub line: usize,
+    pub with_witnesses: String,
+    pub without_witnesses: String,
+}
+
+impl fmt::Display for WitnessDependence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the keys depend on the witnesses: the verifying keys differ in `{}` (line {}): {:?} with witnesses, {:?} without",
+            self.field, self.line, self.with_witnesses, self.without_witnesses
+        )
+    }
+}
+
+// The columns listed in the Debug output of a ConstraintSystem between `start` and the closing bracket
+fn section<'a>(pinned: &'a str, start: &str) -> Result<&'a str, FibError> {
+    let unexpected = || FibError::Internal(format!("unexpected Debug output of the constraint system, missing {:?}", start));
+    let from = pinned.find(start).ok_or_else(unexpected)? + start.len();
+    let len = pinned[from..].find(']').ok_or_else(unexpected)?;
+    Ok(&pinned[from..from + len])
+}
+
+// Parses "Column { index: 2, column_type: Advice }, ..."
+fn parse_columns(s: &str) -> Result<Vec<ColumnId>, FibError> {
+    s.split("Column { index: ")
+        .skip(1)
+        .map(|column| {
+            let (index, rest) = column.split_once(", column_type: ").ok_or_else(|| unexpected(column))?;
+            let column_type = match rest.split_whitespace().next() {
+                Some("Advice") => Any::Advice,
+                Some("Fixed") => Any::Fixed,
+                Some("Instance") => Any::Instance,
+                _ => return Err(unexpected(column)),
+            };
+            Ok(ColumnId { column_type, index: index.parse().map_err(|_| unexpected(column))? })
+        })
+        .collect()
+}
+
+fn unexpected(column: &str) -> FibError {
+    FibError::Internal(format!("unexpected Debug output of a column: {:?}", column))
+}
+
+// ColumnId::of for an Assignment, whose methods can only return a halo2 Error: the first FibError is kept here, the
+// synthesis fails with Error::Synthesis, and `result` returns the FibError in its place
+#[derive(Default)]
+pub(crate) struct Columns(RefCell<Option<FibError>>);
+
+impl Columns {
+    pub(crate) fn of<C: ColumnType>(&self, column: Column<C>) -> Result<ColumnId, Error> {
+        ColumnId::of(column).map_err(|e| {
+            self.0.borrow_mut().get_or_insert(e);
+            Error::Synthesis
+        })
+    }
+
+    pub(crate) fn result<T>(&self, synthesis: Result<T, Error>) -> Result<T, FibError> {
+        match self.0.borrow_mut().take() {
+            Some(e) => Err(e),
+            None => Ok(synthesis?),
+        }
+    }
+}
+
+// Columns can't be built from an index outside of halo2_proofs, but the columns of a fresh constraint system are
+// numbered from 0 and compare equal to the columns of the same index of any other one
+fn fixed_column<F: Field>(meta: &mut ConstraintSystem<F>, index: usize) -> Column<Fixed> {
+    (0..=index).map(|_| meta.fixed_column()).last().expect("at least one column")
+}
+
+// Configures the circuit and runs its synthesis against a Recorder. Also returns the columns with equality enabled
+pub(crate) fn record<F: FieldExt, C: Circuit<F>>(circuit: &C, instance: Vec<Vec<F>>) -> Result<(Recorder<F>, Vec<ColumnId>), FibError> {
+    let mut meta = ConstraintSystem::default();
+    let config = C::configure(&mut meta);
+
+    let pinned = format!("{:?}", meta.pinned());
+    let enabled = parse_columns(section(&pinned, "permutation: Argument { columns: [")?)?;
+    let constants = constant_columns(&pinned)?;
+
+    let mut recorder =
+        Recorder { instance, current: None, regions: vec![], copies: vec![], cells: vec![], columns: Columns::default() };
+    let synthesis = C::FloorPlanner::synthesize(&mut recorder, circuit, config, constants);
+    recorder.columns.result(synthesis)?;
+    Ok((recorder, enabled))
+}
+
+// The fixed columns of `meta.enable_constant`, which FloorPlanner::synthesize takes, from the Debug output of the pinned
+// constraint system
+pub(crate) fn constant_columns(pinned: &str) -> Result<Vec<Column<Fixed>>, FibError> {
+    Ok(parse_columns(section(pinned, "constants: [")?)?
+        .into_iter()
+        .map(|column| fixed_column(&mut ConstraintSystem::<Fp>::default(), column.index))
+        .collect())
+}
+
+// An Assignment that doesn't keep any value: it records the copy constraints, with the region they come from, and
+// counts what every region assigns. Both sides of a copy are recorded, so a copy between two columns without
+// equality enabled is reported twice. Copies made outside of any region (constrain_instance) are attributed to a
+// region named OUTSIDE
+pub(crate) struct Recorder<F> {
+    instance: Vec<Vec<F>>,
+    current: Option<usize>,
+    regions: Vec<RegionStats>,
+    copies: Vec<(usize, ColumnId, usize)>,
+    // The cells assigned in every column, in the order the columns were first assigned
+    cells: Vec<(ColumnId, usize)>,
+    columns: Columns,
+}
+
+pub(crate) const OUTSIDE: &str = "<outside of a region>";
+
+impl<F> Recorder<F> {
+    // The regions in the order they were assigned, the same region name may appear more than once
+    pub(crate) fn regions(&self) -> &[RegionStats] {
+        &self.regions
+    }
+
+    pub(crate) fn cells(&self) -> &[(ColumnId, usize)] {
+        &self.cells
+    }
+
+    fn count(&mut self, column: ColumnId) {
+        match self.cells.iter_mut().find(|(c, _)| *c == column) {
+            Some((_, cells)) => *cells += 1,
+            None => self.cells.push((column, 1)),
+        }
+    }
+
+    fn copies(&self) -> Vec<(String, ColumnId, usize)> {
+        self.copies.iter().map(|(region, column, row)| (self.regions[*region].name.clone(), *column, *row)).collect()
+    }
+
+    fn region(&mut self) -> usize {
+        match self.current {
+            Some(region) => region,
+            None => match self.regions.iter().position(|region| region.name == OUTSIDE) {
+                Some(region) => region,
+                None => {
+                    self.regions.push(RegionStats::new(OUTSIDE));
+                    self.regions.len() - 1
+                }
+            },
+        }
+    }
+}
+
+impl<F: Field> Assignment<F> for Recorder<F> {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.regions.push(RegionStats::new(name_fn()));
+        self.current = Some(self.regions.len() - 1);
+    }
+
+    fn exit_region(&mut self) {
+        self.current = None;
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let region = self.region();
+        self.regions[region].touch(row);
+        self.regions[region].selectors += 1;
+        Ok(())
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Option<F>, Error> {
+        let column = self.columns.of(column)?.index;
+        Ok(self.instance.get(column).and_then(|values| values.get(row)).copied())
+    }
+
+    // The values are computed so that the chips reading them back get them, but a missing witness is not an error
+    #[allow(clippy::let_underscore_must_use)]
+    fn assign_advice<V, VR, A, AR>(&mut self, _: A, column: Column<Advice>, row: usize, to: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let _ = to();
+        let region = self.region();
+        self.regions[region].touch(row);
+        self.regions[region].advice_cells += 1;
+        let column = self.columns.of(column)?;
+        self.count(column);
+        Ok(())
+    }
+
+    #[allow(clippy::let_underscore_must_use)]
+    fn assign_fixed<V, VR, A, AR>(&mut self, _: A, column: Column<Fixed>, row: usize, to: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let _ = to();
+        let region = self.region();
+        self.regions[region].touch(row);
+        self.regions[region].fixed_cells += 1;
+        let column = self.columns.of(column)?;
+        self.count(column);
+        Ok(())
+    }
+
+    fn copy(&mut self, left_column: Column<Any>, left_row: usize, right_column: Column<Any>, right_row: usize) -> Result<(), Error> {
+        let region = self.region();
+        self.regions[region].copies += 1;
+        let (left, right) = (self.columns.of(left_column)?, self.columns.of(right_column)?);
+        self.copies.push((region, left, left_row));
+        self.copies.push((region, right, right_row));
+        Ok(())
+    }
+
+    fn fill_from_row(&mut self, _: Column<Fixed>, _: usize, _: Option<Assigned<F>>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
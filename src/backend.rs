@@ -0,0 +1,102 @@
+use std::io::{self, Read};
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    pasta::{EqAffine, Fp},
+    plonk::*,
+    poly::commitment::Params,
+};
+
+use crate::{error::FibError, transcript::TranscriptKind};
+
+// The proof system behind a proof: the field of the circuits, the public parameters and the prove and verify calls.
+// The chips and circuits of the library are generic over FieldExt, so code written against Backend proves them with
+// any backend without change, e.g. `fn demo<B: Backend>() { B::prove(&B::setup(4), &FibCircuit::<B::Scalar>::...) }`.
+//
+// halo2_proofs 0.1.0 only has IPA commitments over the Pasta curves, which is IpaPasta: the backend of the prover
+// module (src/prover.rs) and of the proof bundles. There is no KZG backend. The fork of halo2_proofs maintained by PSE
+// has KZG over BN256 and could be added next to 0.1.0 under another name (`package = "halo2_proofs"` in Cargo.toml),
+// but its prover takes circuits implementing its own Circuit trait, over the fields of halo2curves. The chips of the
+// library implement the Circuit and FieldExt of halo2_proofs 0.1.0, which bn256::Fr doesn't, so `prove` can't be
+// written for the fork without porting every chip to its traits first. Everything built on KZG (snark-verifier, its
+// EVM verifier, proofs verified inside a circuit) waits on that port.
+
+pub trait Backend {
+    // Recorded in the metadata of the proof bundles
+    const NAME: &'static str;
+    // Short and usable in file names, e.g. by the parameters cache (src/params.rs)
+    const ID: &'static str;
+
+    type Scalar: FieldExt;
+    type Params;
+
+    // The public parameters for circuits of 2^k rows
+    fn setup(k: u32) -> Self::Params;
+
+    fn write_params<W: io::Write>(params: &Self::Params, writer: &mut W) -> io::Result<()>;
+
+    // Fails on parameters written for another k
+    fn read_params<R: io::Read>(reader: &mut R, k: u32) -> io::Result<Self::Params>;
+
+    fn prove<C: Circuit<Self::Scalar>>(params: &Self::Params, circuit: &C, instance: &[Self::Scalar]) -> Result<Vec<u8>, FibError>;
+
+    // The circuit is only used to generate the verifying key, so it can be the circuit without witnesses
+    fn verify<C: Circuit<Self::Scalar>>(params: &Self::Params, circuit: &C, instance: &[Self::Scalar], proof: &[u8]) -> Result<(), FibError>;
+}
+
+/// IPA commitments over Vesta, circuits over Fp, and a Blake2b transcript: the backend of halo2_proofs 0.1.0.
+///
+/// ```
+/// use halo2_proofs::arithmetic::Field;
+/// use halo2_fibonacci_ex::{backend::{Backend, IpaPasta}, error::FibError, fibonacci::FibInputs};
+///
+/// // Written once for any backend
+/// fn prove_and_verify<B: Backend>(k: u32, n: usize) -> Result<(), FibError> {
+///     let inputs = FibInputs::new(B::Scalar::one(), B::Scalar::one(), n);
+///     let params = B::setup(k);
+///     let proof = B::prove(&params, &inputs.circuit(), &inputs.public_inputs())?;
+///     B::verify(&params, &inputs.circuit(), &inputs.public_inputs(), &proof)
+/// }
+///
+/// prove_and_verify::<IpaPasta>(4, 8).unwrap();
+/// assert!(IpaPasta::NAME.contains("IPA"));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IpaPasta;
+
+impl Backend for IpaPasta {
+    const NAME: &'static str = "halo2_proofs 0.1.0, IPA over vesta, Blake2b transcript";
+    const ID: &'static str = "ipa-pasta";
+
+    type Scalar = Fp;
+    type Params = Params<EqAffine>;
+
+    fn setup(k: u32) -> Self::Params {
+        Params::new(k)
+    }
+
+    fn write_params<W: io::Write>(params: &Self::Params, writer: &mut W) -> io::Result<()> {
+        params.write(writer)
+    }
+
+    // Params::read takes the k the file starts with, and panics when 2^k overflows
+    fn read_params<R: io::Read>(reader: &mut R, k: u32) -> io::Result<Self::Params> {
+        let mut prefix = [0u8; 4];
+        reader.read_exact(&mut prefix)?;
+        if u32::from_le_bytes(prefix) != k {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("parameters for k = {}, not {}", u32::from_le_bytes(prefix), k)));
+        }
+        Params::read(&mut io::Cursor::new(prefix).chain(reader))
+    }
+
+    fn prove<C: Circuit<Fp>>(params: &Self::Params, circuit: &C, instance: &[Fp]) -> Result<Vec<u8>, FibError> {
+        let vk = keygen_vk(params, circuit)?;
+        let pk = keygen_pk(params, vk, circuit)?;
+        Ok(TranscriptKind::Blake2b.create_proof(params, &pk, circuit, instance)?)
+    }
+
+    fn verify<C: Circuit<Fp>>(params: &Self::Params, circuit: &C, instance: &[Fp], proof: &[u8]) -> Result<(), FibError> {
+        let vk = keygen_vk(params, circuit)?;
+        TranscriptKind::Blake2b.verify_proof(params, &vk, instance, proof).map_err(|_| FibError::InvalidProof)
+    }
+}
@@ -7,6 +7,7 @@ use halo2_proofs::{
     poly::Rotation,
     pasta::Fp, dev::MockProver,
 };
+use halo2_fibonacci_ex::report::ExpectSatisfied;
 
 // #[derive(Debug, Clone)] is a Rust attribute used to automatically generate implementations of the Debug and Clone traits for a struct
 #[derive(Debug, Clone)]
@@ -90,11 +91,12 @@ impl<F: FieldExt> FiboChip<F> {
     // This is the function used inside syntentize to assign value to the first raw of the circuit.
     // mut layouter: impl Layouter<F> specifies a function parameter named layouter, which is mutable (mut keyword), and implements the Layouter<F> trait.
     // a and b value will be provided to this function as input. This are the a and b to be assigned inside the first row.
+    #[allow(clippy::type_complexity)]
     fn assign_first_row(&self, mut layouter: impl Layouter<F>, a: Option<F>, b: Option<F>) -> Result<(ACell<F>, ACell<F>, ACell<F>), Error> {
         layouter.assign_region(|| "first row", |mut region| {
 
             // We need to enable the selector in that region because the constraint is set!
-            self.config.selector.enable(&mut region, 0);
+            self.config.selector.enable(&mut region, 0)?;
 
             // Assign the value to a and b. It returns an assigned cell!
             let a_cell = region.assign_advice(
@@ -133,7 +135,7 @@ impl<F: FieldExt> FiboChip<F> {
             || "next row",
             |mut region| {
                 // Here we turn on the selector gate
-                self.config.selector.enable(&mut region, 0);
+                self.config.selector.enable(&mut region, 0)?;
                 // In this line I'm trying to copy stuff from the previous row to the new region in the current row
                 // This is the copy constraint basically
                 // I'm copying the prev_b to the current region in advice column 0 (aka "a")
@@ -232,7 +234,7 @@ fn main() {
     // The instance vector is empty as we don't have any public input to pass to the function
     let prover = MockProver::run(k, &circuit, vec![]).unwrap();
 
-    prover.assert_satisfied();
+    prover.expect_satisfied();
 
 }
 
@@ -0,0 +1,145 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// In example3 the gate spans 3 rows, so the selector has to be enabled on every row of the table apart from the last 2.
+// That bookkeeping happens during the assignment: forget the `if row < nrows - 2` and the circuit breaks.
+// Here the same rule is written inside the constraint instead. q_add is 1 on every row of the table, q_tail marks the
+// last 2 rows, and the gate is
+//
+//   q_add * (1 - q_tail) * (a + b - c) = 0
+//
+// so it only fires on the interior rows. Both q_add and q_tail are fixed columns rather than Selectors:
+// - a simple selector (meta.selector()) can only multiply the whole constraint: `1 - q_tail` panics at configure time.
+//   A complex selector (meta.complex_selector()) can be used in any expression
+// - but as soon as a gate queries a Selector that is enabled on a row, the MockProver requires every cell queried by the
+//   gate to be assigned in the region, whatever the value of the full expression. With a Selector the gate enabled on
+//   the last 2 rows would look at rows outside of the table and fail
+// - a fixed column is a regular column whose values are fixed at keygen: it can hold any value and
+//   `Expression::Constant(1) - q_tail` is just another expression. The MockProver only evaluates the constraint
+// The cost of combining conditions is the degree: every factor we multiply adds 1 to the degree of the gate.
+
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub advice: Column<Advice>,
+    pub q_add: Column<Fixed>,
+    pub q_tail: Column<Fixed>,
+    pub instance: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        instance: Column<Instance>,
+    ) -> FiboConfig {
+        let q_add = meta.fixed_column();
+        let q_tail = meta.fixed_column();
+
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+
+        meta.create_gate("add on interior rows", |meta| {
+            let q_add = meta.query_fixed(q_add, Rotation::cur());
+            let q_tail = meta.query_fixed(q_tail, Rotation::cur());
+            let a = meta.query_advice(advice, Rotation::cur());
+            let b = meta.query_advice(advice, Rotation::next());
+            let c = meta.query_advice(advice, Rotation(2));
+
+            let interior = q_add * (Expression::Constant(F::one()) - q_tail);
+            vec![interior * (a + b - c)]
+        });
+
+        FiboConfig { advice, q_add, q_tail, instance }
+    }
+
+    fn assign(&self, mut layouter: impl Layouter<F>, nrows: usize) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(|| "entire fibonacci table", |mut region| {
+            // No more special cases: q_add is set on every row and the last two rows are marked as the tail.
+            // The unassigned cells of a fixed column are 0
+            for row in 0..nrows {
+                region.assign_fixed(|| "q_add", self.config.q_add, row, || Ok(F::one()))?;
+                if row >= nrows - 2 {
+                    region.assign_fixed(|| "q_tail", self.config.q_tail, row, || Ok(F::one()))?;
+                }
+            }
+
+            let mut a_cell = region.assign_advice_from_instance(|| "1", self.config.instance, 0, self.config.advice, 0)?;
+            let mut b_cell = region.assign_advice_from_instance(|| "1", self.config.instance, 1, self.config.advice, 1)?;
+
+            for row in 2..nrows {
+                let c_val = a_cell.value().and_then(|a| b_cell.value().map(|b| *a + *b));
+                let c_cell = region.assign_advice(|| "advice", self.config.advice, row, || c_val.ok_or(Error::Synthesis))?;
+                a_cell = b_cell;
+                b_cell = c_cell;
+            }
+
+            Ok(b_cell)
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default)]
+struct MyCircuit<F>(PhantomData<F>);
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        FiboChip::configure(meta, advice, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        let out_cell = chip.assign(layouter.namespace(|| "entire table"), 10)?;
+        chip.expose_public(layouter.namespace(|| "output"), out_cell, 2)
+    }
+}
+
+fn main() {
+    let k = 4;
+    let circuit = MyCircuit::<Fp>(PhantomData);
+
+    let public_input = vec![Fp::from(1), Fp::from(1), Fp::from(55)];
+    let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+    prover.expect_satisfied();
+
+    let public_input = vec![Fp::from(1), Fp::from(1), Fp::from(56)];
+    let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // The gate q_add * (1 - q_tail) * (a + b - c) has degree 3, one more than the gate of example3
+    let mut cs = ConstraintSystem::<Fp>::default();
+    MyCircuit::<Fp>::configure(&mut cs);
+    println!("degree of the constraint system: {}", cs.degree());
+}
@@ -0,0 +1,232 @@
+use halo2_proofs::{
+    arithmetic::{CurveAffine, CurveExt, Field},
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::{
+        group::{ff::PrimeField, Curve, Group},
+        pallas,
+    },
+    dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// Fixed-base scalar multiplication on Pallas: the prover knows a private scalar s and the circuit computes P = [s]G,
+// exposing the coordinates of P as public inputs (the same statement as "I know the secret key of this public key").
+// The circuit works over the Pallas base field, so the coordinates of the points are native field elements.
+//
+// G is fixed, so the multiples G_i = [2^i]G are constants. With the bits s_0, ..., s_254 of the scalar:
+//
+//   Acc_0 = O
+//   Acc_{i+1} = Acc_i + s_i * G_i
+//   P = Acc_255 - O
+//
+// O is an offset point with unknown discrete logarithm. Starting from O instead of the identity keeps the accumulator
+// away from the identity and from ±G_i, so the cheap incomplete addition formulas (which can't handle those cases)
+// are enough. The last subtraction fails only if P is the identity, i.e. for s = 0.
+//
+// Each row computes R = Acc_i + G_i and selects Acc_{i+1} = s_i ? R : Acc_i.
+//  bit | x_a | y_a | x_r | y_r | lambda | x_g (fixed) | y_g (fixed) | q_mul
+// ---------------------------------------------------------------------------
+//  s_0 | Acc_0     | R_0       |        | G_0                       |   1
+//  s_1 | Acc_1     | R_1       |        | G_1                       |   1
+//  ...
+//   1  | Acc_255   | P         |        | -O                        |   1     <- the last row always adds -O
+//      | P         |           |        |                           |   0
+
+const NUM_BITS: usize = 255;
+
+fn coordinates(point: pallas::Affine) -> (pallas::Base, pallas::Base) {
+    let coordinates = point.coordinates().unwrap();
+    (*coordinates.x(), *coordinates.y())
+}
+
+fn offset() -> pallas::Point {
+    pallas::Point::hash_to_curve("halo2-fibonacci-ex:offset")(b"O")
+}
+
+// The constant point added by each row: G_i for the bit rows, -O for the last one
+fn fixed_points() -> Vec<(pallas::Base, pallas::Base)> {
+    let mut points = Vec::with_capacity(NUM_BITS + 1);
+    let mut g = pallas::Point::generator();
+    for _ in 0..NUM_BITS {
+        points.push(coordinates(g.to_affine()));
+        g = g.double();
+    }
+    points.push(coordinates((-offset()).to_affine()));
+    points
+}
+
+#[derive(Debug, Clone)]
+struct ScalarMulConfig {
+    bit: Column<Advice>,
+    x_a: Column<Advice>,
+    y_a: Column<Advice>,
+    x_r: Column<Advice>,
+    y_r: Column<Advice>,
+    lambda: Column<Advice>,
+    x_g: Column<Fixed>,
+    y_g: Column<Fixed>,
+    q_mul: Selector,
+    instance: Column<Instance>,
+}
+
+struct ScalarMulChip {
+    config: ScalarMulConfig,
+}
+
+impl ScalarMulChip {
+    fn construct(config: ScalarMulConfig) -> Self {
+        Self { config }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> ScalarMulConfig {
+        let [bit, x_a, y_a, x_r, y_r, lambda] = [(); 6].map(|_| meta.advice_column());
+        let x_g = meta.fixed_column();
+        let y_g = meta.fixed_column();
+        let q_mul = meta.selector();
+        let instance = meta.instance_column();
+        let constants = meta.fixed_column();
+
+        meta.enable_equality(bit);
+        meta.enable_equality(x_a);
+        meta.enable_equality(y_a);
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+
+        // Incomplete addition R = Acc + G with slope lambda, then Acc' = Acc + bit * (R - Acc)
+        meta.create_gate("scalar mul step", |meta| {
+            let q = meta.query_selector(q_mul);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let x_a_next = meta.query_advice(x_a, Rotation::next());
+            let y_a_next = meta.query_advice(y_a, Rotation::next());
+            let x_a = meta.query_advice(x_a, Rotation::cur());
+            let y_a = meta.query_advice(y_a, Rotation::cur());
+            let x_r = meta.query_advice(x_r, Rotation::cur());
+            let y_r = meta.query_advice(y_r, Rotation::cur());
+            let lambda = meta.query_advice(lambda, Rotation::cur());
+            let x_g = meta.query_fixed(x_g, Rotation::cur());
+            let y_g = meta.query_fixed(y_g, Rotation::cur());
+            let one = Expression::Constant(pallas::Base::one());
+
+            vec![
+                q.clone() * bit.clone() * (one - bit.clone()),
+                q.clone() * (lambda.clone() * (x_g.clone() - x_a.clone()) - (y_g - y_a.clone())),
+                q.clone() * (x_r.clone() - (lambda.clone().square() - x_a.clone() - x_g)),
+                q.clone() * (y_r.clone() - (lambda * (x_a.clone() - x_r.clone()) - y_a.clone())),
+                q.clone() * (x_a_next - (x_a.clone() + bit.clone() * (x_r - x_a))),
+                q * (y_a_next - (y_a.clone() + bit * (y_r - y_a))),
+            ]
+        });
+
+        ScalarMulConfig { bit, x_a, y_a, x_r, y_r, lambda, x_g, y_g, q_mul, instance }
+    }
+
+    // Returns the cells holding the coordinates of [s]G
+    #[allow(clippy::type_complexity)]
+    fn mul(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        scalar: Option<pallas::Scalar>,
+    ) -> Result<(AssignedCell<pallas::Base, pallas::Base>, AssignedCell<pallas::Base, pallas::Base>), Error> {
+        let config = &self.config;
+        let bits: Vec<Option<bool>> = (0..NUM_BITS)
+            .map(|i| scalar.map(|s| (s.to_repr()[i / 8] >> (i % 8)) & 1 == 1))
+            .collect();
+
+        layouter.assign_region(
+            || "scalar mul",
+            |mut region| {
+                let points = fixed_points();
+                let (x_o, y_o) = coordinates(offset().to_affine());
+                region.assign_advice_from_constant(|| "x_o", config.x_a, 0, x_o)?;
+                region.assign_advice_from_constant(|| "y_o", config.y_a, 0, y_o)?;
+                let mut acc = Some((x_o, y_o));
+
+                let mut result = None;
+                for (row, (x_g, y_g)) in points.into_iter().enumerate() {
+                    config.q_mul.enable(&mut region, row)?;
+                    region.assign_fixed(|| "x_g", config.x_g, row, || Ok(x_g))?;
+                    region.assign_fixed(|| "y_g", config.y_g, row, || Ok(y_g))?;
+
+                    // The last row always adds -O, its bit is the constant 1
+                    let bit = if row < NUM_BITS {
+                        let bit = bits[row];
+                        region.assign_advice(
+                            || "bit",
+                            config.bit,
+                            row,
+                            || bit.map(|b| if b { pallas::Base::one() } else { pallas::Base::zero() }).ok_or(Error::Synthesis),
+                        )?;
+                        bit
+                    } else {
+                        region.assign_advice_from_constant(|| "bit", config.bit, row, pallas::Base::one())?;
+                        Some(true)
+                    };
+
+                    let lambda = acc.map(|(x_a, y_a)| (y_g - y_a) * (x_g - x_a).invert().unwrap());
+                    let r = acc.zip(lambda).map(|((x_a, y_a), lambda)| {
+                        let x_r = lambda.square() - x_a - x_g;
+                        (x_r, lambda * (x_a - x_r) - y_a)
+                    });
+                    let next = acc.zip(r).zip(bit).map(|((acc, r), bit)| if bit { r } else { acc });
+
+                    region.assign_advice(|| "lambda", config.lambda, row, || lambda.ok_or(Error::Synthesis))?;
+                    region.assign_advice(|| "x_r", config.x_r, row, || r.map(|r| r.0).ok_or(Error::Synthesis))?;
+                    region.assign_advice(|| "y_r", config.y_r, row, || r.map(|r| r.1).ok_or(Error::Synthesis))?;
+                    let x = region.assign_advice(|| "x_a", config.x_a, row + 1, || next.map(|p| p.0).ok_or(Error::Synthesis))?;
+                    let y = region.assign_advice(|| "y_a", config.y_a, row + 1, || next.map(|p| p.1).ok_or(Error::Synthesis))?;
+                    result = Some((x, y));
+                    acc = next;
+                }
+
+                Ok(result.unwrap())
+            },
+        )
+    }
+}
+
+#[derive(Default)]
+struct MyCircuit {
+    scalar: Option<pallas::Scalar>,
+}
+
+impl Circuit<pallas::Base> for MyCircuit {
+    type Config = ScalarMulConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        ScalarMulChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<pallas::Base>) -> Result<(), Error> {
+        let chip = ScalarMulChip::construct(config.clone());
+        let (x, y) = chip.mul(layouter.namespace(|| "[s]G"), self.scalar)?;
+        layouter.constrain_instance(x.cell(), config.instance, 0)?;
+        layouter.constrain_instance(y.cell(), config.instance, 1)
+    }
+}
+
+fn main() {
+    // 257 rows
+    let k = 9;
+
+    // The private scalar: the 90th Fibonacci number
+    let scalar = pallas::Scalar::from(2880067194370816120);
+    let (x, y) = coordinates((pallas::Point::generator() * scalar).to_affine());
+
+    let circuit = MyCircuit { scalar: Some(scalar) };
+    let prover = MockProver::run(k, &circuit, vec![vec![x, y]]).unwrap();
+    prover.expect_satisfied();
+
+    // The point of a different scalar is rejected
+    let circuit = MyCircuit {
+        scalar: Some(scalar + pallas::Scalar::one()),
+    };
+    let prover = MockProver::run(k, &circuit, vec![vec![x, y]]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,189 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+
+use halo2_fibonacci_ex::{counter::{CounterChip, CounterConfig}, report::ExpectSatisfied};
+
+// In the previous examples the number of steps is hardcoded in synthesize: the verifier only learns the output,
+// and has to trust that it is the output after the number of steps it had in mind.
+// Here the number of steps n is a public input. A counter column is incremented at every step, starts from 1 and
+// its last value is copied to the instance column, so a proof only verifies if exactly n steps were applied.
+//
+//  col_a | col_b | col_c | counter | q_add | q_next
+// --------------------------------------------------
+//    1   |   1   |   2   |    1    |   1   |   1
+//    1   |   2   |   3   |    2    |   1   |   1
+//   ...  |  ...  |  ...  |   ...   |   1   |   1
+//   21   |  34   |  55   |    n    |   1   |   0     <- c is copied to instance 1 and the counter to instance 0
+//
+// q_add enables a + b = c. q_next links a row to the next one: a' = b and b' = c. The counter column and its
+// counter' = counter + 1 gate come from the CounterChip of the library.
+// The number of rows is still fixed at keygen (the shape of the circuit can't depend on a public input), but the
+// verifier is now cryptographically bound to the claimed length instead of assuming it.
+
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub advice: [Column<Advice>; 3],
+    pub counter: CounterConfig,
+    pub q_add: Selector,
+    pub q_next: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        counter: Column<Advice>,
+        instance: Column<Instance>,
+    ) -> FiboConfig {
+        let [col_a, col_b, col_c] = advice;
+        let q_add = meta.selector();
+        let q_next = meta.selector();
+        let counter = CounterChip::configure(meta, counter, instance);
+
+        meta.enable_equality(col_c);
+        meta.enable_equality(instance);
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(q_add);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let c = meta.query_advice(col_c, Rotation::cur());
+            vec![s * (a + b - c)]
+        });
+
+        meta.create_gate("next", |meta| {
+            let s = meta.query_selector(q_next);
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let c = meta.query_advice(col_c, Rotation::cur());
+            let a_next = meta.query_advice(col_a, Rotation::next());
+            let b_next = meta.query_advice(col_b, Rotation::next());
+            vec![
+                s.clone() * (a_next - b),
+                s * (b_next - c),
+            ]
+        });
+
+        FiboConfig { advice, counter, q_add, q_next, instance }
+    }
+
+    // Returns the cells of the last c and of the last counter value
+    #[allow(clippy::type_complexity)]
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Option<F>,
+        b: Option<F>,
+        nrows: usize,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        layouter.assign_region(|| "fibonacci table with counter", |mut region| {
+            let counter = CounterChip::construct(self.config.counter).assign(&mut region, 0, nrows)?;
+
+            let (mut a, mut b) = (a, b);
+            let mut c_cell = None;
+
+            for row in 0..nrows {
+                self.config.q_add.enable(&mut region, row)?;
+                if row < nrows - 1 {
+                    self.config.q_next.enable(&mut region, row)?;
+                }
+
+                let c = a.zip(b).map(|(a, b)| a + b);
+                region.assign_advice(|| "a", self.config.advice[0], row, || a.ok_or(Error::Synthesis))?;
+                region.assign_advice(|| "b", self.config.advice[1], row, || b.ok_or(Error::Synthesis))?;
+                c_cell = Some(region.assign_advice(|| "c", self.config.advice[2], row, || c.ok_or(Error::Synthesis))?);
+
+                a = b;
+                b = c;
+            }
+
+            Ok((c_cell.unwrap(), counter))
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+// The number of steps is part of the shape of the circuit, so it is kept by without_witnesses
+#[derive(Default)]
+struct MyCircuit<F> {
+    pub a: Option<F>,
+    pub b: Option<F>,
+    pub steps: usize,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            a: None,
+            b: None,
+            steps: self.steps,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let counter = meta.advice_column();
+        let instance = meta.instance_column();
+        FiboChip::configure(meta, advice, counter, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let counter_chip = CounterChip::construct(config.counter);
+        let chip = FiboChip::construct(config);
+        let (out, counter) = chip.assign(layouter.namespace(|| "table"), self.a, self.b, self.steps)?;
+
+        // instance[0] = n, instance[1] = output
+        counter_chip.expose_public(layouter.namespace(|| "n"), &counter, 0)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 1)
+    }
+}
+
+fn main() {
+    let k = 4;
+    let circuit = MyCircuit {
+        a: Some(Fp::from(1)),
+        b: Some(Fp::from(1)),
+        steps: 8,
+    };
+
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(8), Fp::from(55)]]).unwrap();
+    prover.expect_satisfied();
+
+    // Claiming a different number of steps for the same output is rejected
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(9), Fp::from(55)]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // One more step
+    let circuit = MyCircuit {
+        a: Some(Fp::from(1)),
+        b: Some(Fp::from(1)),
+        steps: 9,
+    };
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(9), Fp::from(89)]]).unwrap();
+    prover.expect_satisfied();
+}
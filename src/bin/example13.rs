@@ -0,0 +1,472 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use sha2::{Digest, Sha256};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// Proves knowledge of a preimage of a public SHA-256 digest.
+// SHA-256 works on 32-bit words with bitwise operations (rotations, xor, and) and additions modulo 2^32, none of which is
+// native to a prime field. The simplest way to arithmetize it is to decompose the words into bits:
+//
+//  b_0 | b_1 | ... | b_31 | word | carry | q_word
+// ------------------------------------------------
+//   1  |  0  | ... |  1   |  x   |       |   1      <- word = sum b_i * 2^i, each b_i is boolean
+//
+// On a row where q_word is enabled the word is range checked to 32 bits, and each bitwise operation is a gate over the
+// bits of a few consecutive rows:
+// - sigma: the output row is the xor of three rotations (or shifts) of the input row
+// - ch / maj: the output row (row 3) is computed bit by bit from the three input rows (rows 0, 1, 2)
+// - add: word_2 + carry_2 * 2^32 = word_0 + word_1, with a boolean carry and the output range checked by q_word
+// The inputs of every operation are copied from the cells where they were computed, so the words can be moved around freely.
+// This is far from the most efficient layout (halo2_gadgets uses lookup tables over "spread" words), but every
+// constraint maps one to one to the specification.
+
+const BITS: usize = 32;
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+// The four sigma functions: two rotations and a third rotation (upper case sigmas) or shift (lower case sigmas)
+#[derive(Debug, Clone, Copy)]
+enum Sigma {
+    Upper0,
+    Upper1,
+    Lower0,
+    Lower1,
+}
+
+impl Sigma {
+    const ALL: [Sigma; 4] = [Sigma::Upper0, Sigma::Upper1, Sigma::Lower0, Sigma::Lower1];
+
+    // (first rotation, second rotation, third rotation or shift, whether the third one is a shift)
+    fn params(self) -> (usize, usize, usize, bool) {
+        match self {
+            Sigma::Upper0 => (2, 13, 22, false),
+            Sigma::Upper1 => (6, 11, 25, false),
+            Sigma::Lower0 => (7, 18, 3, true),
+            Sigma::Lower1 => (17, 19, 10, true),
+        }
+    }
+
+    fn apply(self, x: u32) -> u32 {
+        let (r1, r2, r3, shift) = self.params();
+        let third = if shift { x >> r3 } else { x.rotate_right(r3 as u32) };
+        x.rotate_right(r1 as u32) ^ x.rotate_right(r2 as u32) ^ third
+    }
+}
+
+fn to_u32<F: FieldExt>(value: &F) -> u32 {
+    value.get_lower_128() as u32
+}
+
+// The message bytes followed by 0x80, zeros and the length in bits, up to a multiple of 64 bytes.
+// The padding only depends on the length of the message, which is part of the shape of the circuit.
+fn padding(len: usize) -> Vec<u8> {
+    let mut padding = vec![0x80];
+    while (len + padding.len()) % 64 != 56 {
+        padding.push(0);
+    }
+    padding.extend_from_slice(&((len as u64) * 8).to_be_bytes());
+    padding
+}
+
+#[derive(Debug, Clone)]
+struct Sha256Config {
+    bits: [Column<Advice>; BITS],
+    word: Column<Advice>,
+    carry: Column<Advice>,
+    q_word: Selector,
+    q_sigma: [Selector; 4],
+    q_ch: Selector,
+    q_maj: Selector,
+    q_add: Selector,
+    instance: Column<Instance>,
+}
+
+struct Sha256Chip<F: FieldExt> {
+    config: Sha256Config,
+    _marker: PhantomData<F>,
+}
+
+type Word<F> = AssignedCell<F, F>;
+
+impl<F: FieldExt> Sha256Chip<F> {
+    fn construct(config: Sha256Config) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Sha256Config {
+        let bits = [(); BITS].map(|_| meta.advice_column());
+        let word = meta.advice_column();
+        let carry = meta.advice_column();
+        let q_word = meta.selector();
+        let q_sigma = [(); 4].map(|_| meta.selector());
+        let q_ch = meta.selector();
+        let q_maj = meta.selector();
+        let q_add = meta.selector();
+        let instance = meta.instance_column();
+        let constants = meta.fixed_column();
+
+        // The bits need equality too: the padding bits of a partially filled word are constrained to constants
+        for column in bits.iter() {
+            meta.enable_equality(*column);
+        }
+        meta.enable_equality(word);
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+
+        let one = || Expression::Constant(F::one());
+        let two = || Expression::Constant(F::from(2));
+
+        meta.create_gate("word decomposition", |meta| {
+            let q = meta.query_selector(q_word);
+            let word = meta.query_advice(word, Rotation::cur());
+            let bits: Vec<_> = bits.iter().map(|b| meta.query_advice(*b, Rotation::cur())).collect();
+
+            let recomposed = bits
+                .iter()
+                .rev()
+                .fold(Expression::Constant(F::zero()), |acc, bit| acc * two() + bit.clone());
+            let mut constraints: Vec<_> = bits.iter().map(|bit| q.clone() * bit.clone() * (one() - bit.clone())).collect();
+            constraints.push(q * (word - recomposed));
+            constraints
+        });
+
+        // xor of three bits: x + y + z - 2 (xy + xz + yz) + 4 xyz
+        let xor3 = move |x: Expression<F>, y: Expression<F>, z: Expression<F>| {
+            x.clone() + y.clone() + z.clone()
+                - two() * (x.clone() * y.clone() + x.clone() * z.clone() + y.clone() * z.clone())
+                + Expression::Constant(F::from(4)) * x * y * z
+        };
+
+        for (sigma, q_sigma) in Sigma::ALL.iter().zip(q_sigma.iter()) {
+            let (r1, r2, r3, shift) = sigma.params();
+            meta.create_gate("sigma", |meta| {
+                let q = meta.query_selector(*q_sigma);
+                let x: Vec<_> = bits.iter().map(|b| meta.query_advice(*b, Rotation::cur())).collect();
+                let out: Vec<_> = bits.iter().map(|b| meta.query_advice(*b, Rotation::next())).collect();
+
+                // bit i of ROTR^n(x) is bit i + n mod 32 of x, bit i of SHR^n(x) is bit i + n of x or 0
+                (0..BITS)
+                    .map(|i| {
+                        let third = if shift && i + r3 >= BITS {
+                            Expression::Constant(F::zero())
+                        } else {
+                            x[(i + r3) % BITS].clone()
+                        };
+                        let expected = xor3(x[(i + r1) % BITS].clone(), x[(i + r2) % BITS].clone(), third);
+                        q.clone() * (out[i].clone() - expected)
+                    })
+                    .collect::<Vec<_>>()
+            });
+        }
+
+        // ch(e, f, g) = (e and f) xor (not e and g) = e f + (1 - e) g
+        meta.create_gate("ch", |meta| {
+            let q = meta.query_selector(q_ch);
+            (0..BITS)
+                .map(|i| {
+                    let e = meta.query_advice(bits[i], Rotation::cur());
+                    let f = meta.query_advice(bits[i], Rotation::next());
+                    let g = meta.query_advice(bits[i], Rotation(2));
+                    let out = meta.query_advice(bits[i], Rotation(3));
+                    q.clone() * (out - (e.clone() * f + (one() - e) * g))
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // maj(a, b, c) = (a and b) xor (a and c) xor (b and c) = ab + ac + bc - 2abc
+        meta.create_gate("maj", |meta| {
+            let q = meta.query_selector(q_maj);
+            (0..BITS)
+                .map(|i| {
+                    let a = meta.query_advice(bits[i], Rotation::cur());
+                    let b = meta.query_advice(bits[i], Rotation::next());
+                    let c = meta.query_advice(bits[i], Rotation(2));
+                    let out = meta.query_advice(bits[i], Rotation(3));
+                    let maj = a.clone() * b.clone() + a.clone() * c.clone() + b.clone() * c.clone() - two() * a * b * c;
+                    q.clone() * (out - maj)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("add mod 2^32", |meta| {
+            let q = meta.query_selector(q_add);
+            let x = meta.query_advice(word, Rotation::cur());
+            let y = meta.query_advice(word, Rotation::next());
+            let out = meta.query_advice(word, Rotation(2));
+            let carry = meta.query_advice(carry, Rotation(2));
+            let modulus = Expression::Constant(F::from(1 << 32));
+            vec![
+                q.clone() * carry.clone() * (one() - carry.clone()),
+                q * (out + carry * modulus - x - y),
+            ]
+        });
+
+        Sha256Config { bits, word, carry, q_word, q_sigma, q_ch, q_maj, q_add, instance }
+    }
+
+    // Assign a decomposed word on `row`: the word cell and its bits
+    fn assign_bits(&self, region: &mut Region<'_, F>, row: usize, value: Option<u32>) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        self.config.q_word.enable(region, row)?;
+        self.config
+            .bits
+            .iter()
+            .enumerate()
+            .map(|(i, column)| region.assign_advice(|| "bit", *column, row, || value.map(|v| F::from(((v >> i) & 1) as u64)).ok_or(Error::Synthesis)))
+            .collect()
+    }
+
+    // Copy an existing word on `row` and decompose it
+    fn copy_word(&self, region: &mut Region<'_, F>, row: usize, word: &Word<F>) -> Result<Option<u32>, Error> {
+        word.copy_advice(|| "word", region, self.config.word, row)?;
+        let value = word.value().map(to_u32);
+        self.assign_bits(region, row, value)?;
+        Ok(value)
+    }
+
+    fn new_word(&self, region: &mut Region<'_, F>, row: usize, value: Option<u32>) -> Result<Word<F>, Error> {
+        self.assign_bits(region, row, value)?;
+        region.assign_advice(|| "word", self.config.word, row, || value.map(|v| F::from(v as u64)).ok_or(Error::Synthesis))
+    }
+
+    // A private word. The lowest `fixed_bits` bits are constrained to the ones of `fixed_value`: this is how the padding
+    // bytes that share a word with the last bytes of the message are enforced
+    fn load(&self, mut layouter: impl Layouter<F>, value: Option<u32>, fixed_bits: usize, fixed_value: u32) -> Result<Word<F>, Error> {
+        layouter.assign_region(
+            || "load word",
+            |mut region| {
+                let bits = self.assign_bits(&mut region, 0, value)?;
+                for (i, bit) in bits.iter().enumerate().take(fixed_bits) {
+                    region.constrain_constant(bit.cell(), F::from(((fixed_value >> i) & 1) as u64))?;
+                }
+                region.assign_advice(|| "word", self.config.word, 0, || value.map(|v| F::from(v as u64)).ok_or(Error::Synthesis))
+            },
+        )
+    }
+
+    fn constant(&self, mut layouter: impl Layouter<F>, value: u32) -> Result<Word<F>, Error> {
+        layouter.assign_region(
+            || "constant word",
+            |mut region| region.assign_advice_from_constant(|| "word", self.config.word, 0, F::from(value as u64)),
+        )
+    }
+
+    fn sigma(&self, mut layouter: impl Layouter<F>, sigma: Sigma, x: &Word<F>) -> Result<Word<F>, Error> {
+        layouter.assign_region(
+            || "sigma",
+            |mut region| {
+                self.config.q_sigma[sigma as usize].enable(&mut region, 0)?;
+                let x = self.copy_word(&mut region, 0, x)?;
+                self.new_word(&mut region, 1, x.map(|x| sigma.apply(x)))
+            },
+        )
+    }
+
+    fn ch(&self, mut layouter: impl Layouter<F>, e: &Word<F>, f: &Word<F>, g: &Word<F>) -> Result<Word<F>, Error> {
+        layouter.assign_region(
+            || "ch",
+            |mut region| {
+                self.config.q_ch.enable(&mut region, 0)?;
+                let e = self.copy_word(&mut region, 0, e)?;
+                let f = self.copy_word(&mut region, 1, f)?;
+                let g = self.copy_word(&mut region, 2, g)?;
+                let out = e.zip(f).zip(g).map(|((e, f), g)| (e & f) ^ (!e & g));
+                self.new_word(&mut region, 3, out)
+            },
+        )
+    }
+
+    fn maj(&self, mut layouter: impl Layouter<F>, a: &Word<F>, b: &Word<F>, c: &Word<F>) -> Result<Word<F>, Error> {
+        layouter.assign_region(
+            || "maj",
+            |mut region| {
+                self.config.q_maj.enable(&mut region, 0)?;
+                let a = self.copy_word(&mut region, 0, a)?;
+                let b = self.copy_word(&mut region, 1, b)?;
+                let c = self.copy_word(&mut region, 2, c)?;
+                let out = a.zip(b).zip(c).map(|((a, b), c)| (a & b) ^ (a & c) ^ (b & c));
+                self.new_word(&mut region, 3, out)
+            },
+        )
+    }
+
+    // The inputs don't need to be decomposed again: they are copies of words that have already been range checked
+    fn add(&self, mut layouter: impl Layouter<F>, x: &Word<F>, y: &Word<F>) -> Result<Word<F>, Error> {
+        layouter.assign_region(
+            || "add",
+            |mut region| {
+                self.config.q_add.enable(&mut region, 0)?;
+                x.copy_advice(|| "x", &mut region, self.config.word, 0)?;
+                y.copy_advice(|| "y", &mut region, self.config.word, 1)?;
+
+                let sum = x.value().zip(y.value()).map(|(x, y)| to_u32(x) as u64 + to_u32(y) as u64);
+                region.assign_advice(|| "carry", self.config.carry, 2, || sum.map(|s| F::from(s >> 32)).ok_or(Error::Synthesis))?;
+                self.new_word(&mut region, 2, sum.map(|s| s as u32))
+            },
+        )
+    }
+
+    fn add_many(&self, mut layouter: impl Layouter<F>, words: &[&Word<F>]) -> Result<Word<F>, Error> {
+        let mut sum = words[0].clone();
+        for word in words.iter().skip(1) {
+            sum = self.add(layouter.namespace(|| "add"), &sum, word)?;
+        }
+        Ok(sum)
+    }
+
+    // One application of the compression function on a block of 16 words
+    fn compress(&self, mut layouter: impl Layouter<F>, state: &[Word<F>], block: &[Word<F>]) -> Result<Vec<Word<F>>, Error> {
+        // Message schedule: w_t = sigma1(w_{t-2}) + w_{t-7} + sigma0(w_{t-15}) + w_{t-16}
+        let mut w = block.to_vec();
+        for t in 16..64 {
+            let s1 = self.sigma(layouter.namespace(|| "sigma1"), Sigma::Lower1, &w[t - 2])?;
+            let s0 = self.sigma(layouter.namespace(|| "sigma0"), Sigma::Lower0, &w[t - 15])?;
+            let next = self.add_many(layouter.namespace(|| "schedule"), &[&s1, &w[t - 7], &s0, &w[t - 16]])?;
+            w.push(next);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h]: [Word<F>; 8] = state.to_vec().try_into().unwrap();
+        for (t, w_t) in w.iter().enumerate() {
+            let k = self.constant(layouter.namespace(|| "k"), ROUND_CONSTANTS[t])?;
+            let s1 = self.sigma(layouter.namespace(|| "Sigma1"), Sigma::Upper1, &e)?;
+            let ch = self.ch(layouter.namespace(|| "ch"), &e, &f, &g)?;
+            let t1 = self.add_many(layouter.namespace(|| "t1"), &[&h, &s1, &ch, &k, w_t])?;
+
+            let s0 = self.sigma(layouter.namespace(|| "Sigma0"), Sigma::Upper0, &a)?;
+            let maj = self.maj(layouter.namespace(|| "maj"), &a, &b, &c)?;
+            let t2 = self.add(layouter.namespace(|| "t2"), &s0, &maj)?;
+
+            h = g;
+            g = f;
+            f = e;
+            e = self.add(layouter.namespace(|| "e"), &d, &t1)?;
+            d = c;
+            c = b;
+            b = a;
+            a = self.add(layouter.namespace(|| "a"), &t1, &t2)?;
+        }
+
+        [a, b, c, d, e, f, g, h]
+            .iter()
+            .zip(state.iter())
+            .map(|(working, initial)| self.add(layouter.namespace(|| "final add"), initial, working))
+            .collect()
+    }
+
+    // Pads the message, loads it and returns the 8 words of the digest
+    fn digest(&self, mut layouter: impl Layouter<F>, message: &[Option<u8>]) -> Result<Vec<Word<F>>, Error> {
+        let padding = padding(message.len());
+        let padded: Vec<Option<u8>> = message.iter().copied().chain(padding.iter().copied().map(Some)).collect();
+
+        // Words are big endian. Depending on how many of its bytes come from the message, a word is private, constant,
+        // or private with its lowest bytes fixed to the padding
+        let mut words = Vec::with_capacity(padded.len() / 4);
+        for (j, bytes) in padded.chunks(4).enumerate() {
+            let value = bytes.iter().try_fold(0u32, |acc, byte| byte.map(|b| (acc << 8) | b as u32));
+            let message_bytes = message.len().saturating_sub(4 * j).min(4);
+            let word = if message_bytes == 0 {
+                self.constant(layouter.namespace(|| "padding"), value.unwrap())?
+            } else {
+                let fixed_bits = 8 * (4 - message_bytes);
+                let fixed_value = bytes[message_bytes..].iter().fold(0u32, |acc, byte| (acc << 8) | byte.unwrap() as u32);
+                self.load(layouter.namespace(|| "message"), value, fixed_bits, fixed_value)?
+            };
+            words.push(word);
+        }
+
+        let mut state = INITIAL_STATE
+            .iter()
+            .map(|h| self.constant(layouter.namespace(|| "initial state"), *h))
+            .collect::<Result<Vec<_>, _>>()?;
+        for block in words.chunks(16) {
+            state = self.compress(layouter.namespace(|| "block"), &state, block)?;
+        }
+        Ok(state)
+    }
+
+    fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &Word<F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+// The length of the message is part of the shape of the circuit, so it is kept by without_witnesses
+#[derive(Default)]
+struct MyCircuit<F> {
+    message: Vec<Option<u8>>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = Sha256Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            message: vec![None; self.message.len()],
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        Sha256Chip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = Sha256Chip::construct(config);
+        let digest = chip.digest(layouter.namespace(|| "sha256"), &self.message)?;
+        for (row, word) in digest.iter().enumerate() {
+            chip.expose_public(layouter.namespace(|| "digest"), word, row)?;
+        }
+        Ok(())
+    }
+}
+
+// The digest as 8 big endian words, one public input each
+fn public_digest(message: &[u8]) -> Vec<Fp> {
+    Sha256::digest(message)
+        .chunks(4)
+        .map(|word| Fp::from(u32::from_be_bytes(word.try_into().unwrap()) as u64))
+        .collect()
+}
+
+fn main() {
+    // A single block with ~2800 rows
+    let k = 12;
+
+    // 35 bytes: the last word of the message is shared with the first padding byte
+    let message = b"fibonacci: 1 1 2 3 5 8 13 21 34 55 ";
+    let circuit = MyCircuit {
+        message: message.iter().copied().map(Some).collect(),
+        _marker: PhantomData,
+    };
+
+    // The expected digest is computed with the sha2 crate
+    let prover = MockProver::run(k, &circuit, vec![public_digest(message)]).unwrap();
+    prover.expect_satisfied();
+
+    // The digest of a different message is rejected
+    let prover = MockProver::run(k, &circuit, vec![public_digest(b"fibonacci: 1 1 2 3 5 8 13 21 34 56 ")]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,417 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use sha3::{Digest, Keccak256};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// Proves knowledge of a preimage of a public Keccak-256 digest (the hash used by Ethereum).
+// Keccak-f[1600] works on a state of 5x5 lanes of 64 bits. As in example13 every lane that takes part in a bitwise
+// operation is decomposed into bits on a row where q_word is enabled:
+//
+//  b_0 | b_1 | ... | b_63 | lane | q_word
+// ----------------------------------------
+//   1  |  0  | ... |  1   |  x   |   1      <- lane = sum b_i * 2^i, each b_i is boolean
+//
+// and each step of a round is a gate over a few consecutive rows:
+// - xor: row 2 = row 0 xor row 1, bit by bit (theta and iota)
+// - rot: the lane of row 1 is the lane of row 0 rotated by a fixed amount. A rotation just moves bits around, so it is a
+//   single constraint lane_1 = sum b_i * 2^((i + r) mod 64). There is one selector for each rotation amount (rho and theta)
+// - chi: row 3 = row 0 xor (not row 1 and row 2), bit by bit
+// The permutation pi only changes which lane is copied where, so it costs nothing.
+
+const LANE_BITS: usize = 64;
+const ROUNDS: usize = 24;
+// Keccak-256 absorbs 1088 bits (17 lanes) per block
+const RATE_LANES: usize = 17;
+
+const ROUND_CONSTANTS: [u64; ROUNDS] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+    0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+];
+
+// Rotation offsets of rho, indexed by [x][y]
+const RHO: [[u32; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+// Every rotation amount used by the permutation: the ones of rho and the rotation by 1 of theta
+fn rotations() -> Vec<u32> {
+    let mut rotations: Vec<u32> = RHO.iter().flatten().copied().filter(|r| *r != 0).collect();
+    rotations.push(1);
+    rotations.sort_unstable();
+    rotations.dedup();
+    rotations
+}
+
+fn to_u64<F: FieldExt>(value: &F) -> u64 {
+    value.get_lower_128() as u64
+}
+
+// Keccak padding (pad10*1 with the 0x01 domain byte used by Ethereum, not the 0x06 of SHA-3), up to a multiple of 136 bytes.
+// The padding only depends on the length of the message, which is part of the shape of the circuit.
+fn padding(len: usize) -> Vec<u8> {
+    let rate = RATE_LANES * 8;
+    let mut padding = vec![0u8; rate - len % rate];
+    padding[0] |= 0x01;
+    *padding.last_mut().unwrap() |= 0x80;
+    padding
+}
+
+#[derive(Debug, Clone)]
+struct KeccakConfig {
+    bits: [Column<Advice>; LANE_BITS],
+    lane: Column<Advice>,
+    q_word: Selector,
+    q_xor: Selector,
+    q_chi: Selector,
+    q_rot: Vec<(u32, Selector)>,
+    instance: Column<Instance>,
+}
+
+struct KeccakChip<F: FieldExt> {
+    config: KeccakConfig,
+    _marker: PhantomData<F>,
+}
+
+type Lane<F> = AssignedCell<F, F>;
+
+impl<F: FieldExt> KeccakChip<F> {
+    fn construct(config: KeccakConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> KeccakConfig {
+        let bits = [(); LANE_BITS].map(|_| meta.advice_column());
+        let lane = meta.advice_column();
+        let q_word = meta.selector();
+        let q_xor = meta.selector();
+        let q_chi = meta.selector();
+        let instance = meta.instance_column();
+        let constants = meta.fixed_column();
+
+        // The bits need equality too: the padding bits of a partially filled lane are constrained to constants
+        for column in bits.iter() {
+            meta.enable_equality(*column);
+        }
+        meta.enable_equality(lane);
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+
+        let one = || Expression::Constant(F::one());
+        let two = || Expression::Constant(F::from(2));
+
+        meta.create_gate("lane decomposition", |meta| {
+            let q = meta.query_selector(q_word);
+            let lane = meta.query_advice(lane, Rotation::cur());
+            let bits: Vec<_> = bits.iter().map(|b| meta.query_advice(*b, Rotation::cur())).collect();
+
+            let recomposed = bits
+                .iter()
+                .rev()
+                .fold(Expression::Constant(F::zero()), |acc, bit| acc * two() + bit.clone());
+            let mut constraints: Vec<_> = bits.iter().map(|bit| q.clone() * bit.clone() * (one() - bit.clone())).collect();
+            constraints.push(q * (lane - recomposed));
+            constraints
+        });
+
+        meta.create_gate("xor", |meta| {
+            let q = meta.query_selector(q_xor);
+            bits.iter()
+                .map(|column| {
+                    let x = meta.query_advice(*column, Rotation::cur());
+                    let y = meta.query_advice(*column, Rotation::next());
+                    let out = meta.query_advice(*column, Rotation(2));
+                    q.clone() * (out - (x.clone() + y.clone() - two() * x * y))
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // chi: a xor (not b and c), where not b and c = (1 - b) c
+        meta.create_gate("chi", |meta| {
+            let q = meta.query_selector(q_chi);
+            bits.iter()
+                .map(|column| {
+                    let a = meta.query_advice(*column, Rotation::cur());
+                    let b = meta.query_advice(*column, Rotation::next());
+                    let c = meta.query_advice(*column, Rotation(2));
+                    let out = meta.query_advice(*column, Rotation(3));
+                    let and = (one() - b) * c;
+                    q.clone() * (out - (a.clone() + and.clone() - two() * a * and))
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // bit i of the input ends up in position i + r mod 64 of the output
+        let q_rot = rotations()
+            .into_iter()
+            .map(|r| {
+                let q_rot = meta.selector();
+                meta.create_gate("rot", |meta| {
+                    let q = meta.query_selector(q_rot);
+                    let out = meta.query_advice(lane, Rotation::next());
+                    let rotated = bits.iter().enumerate().fold(Expression::Constant(F::zero()), |acc, (i, column)| {
+                        let position = (i + r as usize) % LANE_BITS;
+                        acc + meta.query_advice(*column, Rotation::cur()) * Expression::Constant(F::from_u128(1 << position))
+                    });
+                    vec![q * (out - rotated)]
+                });
+                (r, q_rot)
+            })
+            .collect();
+
+        KeccakConfig { bits, lane, q_word, q_xor, q_chi, q_rot, instance }
+    }
+
+    // Assign a decomposed lane on `row`: its bits and the lane cell
+    fn assign_bits(&self, region: &mut Region<'_, F>, row: usize, value: Option<u64>) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        self.config.q_word.enable(region, row)?;
+        self.config
+            .bits
+            .iter()
+            .enumerate()
+            .map(|(i, column)| region.assign_advice(|| "bit", *column, row, || value.map(|v| F::from((v >> i) & 1)).ok_or(Error::Synthesis)))
+            .collect()
+    }
+
+    // Copy an existing lane on `row` and decompose it
+    fn copy_lane(&self, region: &mut Region<'_, F>, row: usize, lane: &Lane<F>) -> Result<Option<u64>, Error> {
+        lane.copy_advice(|| "lane", region, self.config.lane, row)?;
+        let value = lane.value().map(to_u64);
+        self.assign_bits(region, row, value)?;
+        Ok(value)
+    }
+
+    fn new_lane(&self, region: &mut Region<'_, F>, row: usize, value: Option<u64>) -> Result<Lane<F>, Error> {
+        self.assign_bits(region, row, value)?;
+        region.assign_advice(|| "lane", self.config.lane, row, || value.map(F::from).ok_or(Error::Synthesis))
+    }
+
+    // A private lane. The highest `fixed_bits` bits are constrained to the ones of `fixed_value`: this is how the padding
+    // bytes that share a lane with the last bytes of the message are enforced (lanes are little endian)
+    fn load(&self, mut layouter: impl Layouter<F>, value: Option<u64>, fixed_bits: usize, fixed_value: u64) -> Result<Lane<F>, Error> {
+        layouter.assign_region(
+            || "load lane",
+            |mut region| {
+                let bits = self.assign_bits(&mut region, 0, value)?;
+                for (i, bit) in bits.iter().enumerate().skip(LANE_BITS - fixed_bits) {
+                    region.constrain_constant(bit.cell(), F::from((fixed_value >> i) & 1))?;
+                }
+                region.assign_advice(|| "lane", self.config.lane, 0, || value.map(F::from).ok_or(Error::Synthesis))
+            },
+        )
+    }
+
+    fn constant(&self, mut layouter: impl Layouter<F>, value: u64) -> Result<Lane<F>, Error> {
+        layouter.assign_region(
+            || "constant lane",
+            |mut region| region.assign_advice_from_constant(|| "lane", self.config.lane, 0, F::from(value)),
+        )
+    }
+
+    fn xor(&self, mut layouter: impl Layouter<F>, x: &Lane<F>, y: &Lane<F>) -> Result<Lane<F>, Error> {
+        layouter.assign_region(
+            || "xor",
+            |mut region| {
+                self.config.q_xor.enable(&mut region, 0)?;
+                let x = self.copy_lane(&mut region, 0, x)?;
+                let y = self.copy_lane(&mut region, 1, y)?;
+                self.new_lane(&mut region, 2, x.zip(y).map(|(x, y)| x ^ y))
+            },
+        )
+    }
+
+    fn rot(&self, mut layouter: impl Layouter<F>, x: &Lane<F>, r: u32) -> Result<Lane<F>, Error> {
+        if r == 0 {
+            return Ok(x.clone());
+        }
+        let q_rot = self.config.q_rot.iter().find(|(amount, _)| *amount == r).unwrap().1;
+        layouter.assign_region(
+            || "rot",
+            |mut region| {
+                q_rot.enable(&mut region, 0)?;
+                let x = self.copy_lane(&mut region, 0, x)?;
+                self.new_lane(&mut region, 1, x.map(|x| x.rotate_left(r)))
+            },
+        )
+    }
+
+    fn chi(&self, mut layouter: impl Layouter<F>, a: &Lane<F>, b: &Lane<F>, c: &Lane<F>) -> Result<Lane<F>, Error> {
+        layouter.assign_region(
+            || "chi",
+            |mut region| {
+                self.config.q_chi.enable(&mut region, 0)?;
+                let a = self.copy_lane(&mut region, 0, a)?;
+                let b = self.copy_lane(&mut region, 1, b)?;
+                let c = self.copy_lane(&mut region, 2, c)?;
+                self.new_lane(&mut region, 3, a.zip(b).zip(c).map(|((a, b), c)| a ^ (!b & c)))
+            },
+        )
+    }
+
+    // Keccak-f[1600] on a state indexed by x + 5y
+    fn permute(&self, mut layouter: impl Layouter<F>, mut state: Vec<Lane<F>>) -> Result<Vec<Lane<F>>, Error> {
+        for (round, rc) in ROUND_CONSTANTS.iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("round {}", round));
+
+            // theta: every lane is xored with the parity of two columns
+            let mut c = Vec::with_capacity(5);
+            for x in 0..5 {
+                let mut parity = state[x].clone();
+                for y in 1..5 {
+                    parity = self.xor(layouter.namespace(|| "theta c"), &parity, &state[x + 5 * y])?;
+                }
+                c.push(parity);
+            }
+            let mut d = Vec::with_capacity(5);
+            for x in 0..5 {
+                let rotated = self.rot(layouter.namespace(|| "theta rot"), &c[(x + 1) % 5], 1)?;
+                d.push(self.xor(layouter.namespace(|| "theta d"), &c[(x + 4) % 5], &rotated)?);
+            }
+            for (i, lane) in state.iter_mut().enumerate() {
+                *lane = self.xor(layouter.namespace(|| "theta"), lane, &d[i % 5])?;
+            }
+
+            // rho and pi: B[y, 2x + 3y] = rot(A[x, y], RHO[x][y])
+            let mut b = state.clone();
+            for x in 0..5 {
+                for y in 0..5 {
+                    b[y + 5 * ((2 * x + 3 * y) % 5)] = self.rot(layouter.namespace(|| "rho"), &state[x + 5 * y], RHO[x][y])?;
+                }
+            }
+
+            // chi: A[x, y] = B[x, y] xor (not B[x + 1, y] and B[x + 2, y])
+            for x in 0..5 {
+                for y in 0..5 {
+                    state[x + 5 * y] = self.chi(layouter.namespace(|| "chi"), &b[x + 5 * y], &b[(x + 1) % 5 + 5 * y], &b[(x + 2) % 5 + 5 * y])?;
+                }
+            }
+
+            // iota
+            let rc = self.constant(layouter.namespace(|| "round constant"), *rc)?;
+            state[0] = self.xor(layouter.namespace(|| "iota"), &state[0], &rc)?;
+        }
+        Ok(state)
+    }
+
+    // Pads the message, absorbs it and returns the 4 lanes of the digest
+    fn digest(&self, mut layouter: impl Layouter<F>, message: &[Option<u8>]) -> Result<Vec<Lane<F>>, Error> {
+        let padding = padding(message.len());
+        let padded: Vec<Option<u8>> = message.iter().copied().chain(padding.iter().copied().map(Some)).collect();
+
+        // Depending on how many of its bytes come from the message, a lane is private, constant,
+        // or private with its highest bytes fixed to the padding
+        let mut lanes = Vec::with_capacity(padded.len() / 8);
+        for (j, bytes) in padded.chunks(8).enumerate() {
+            let value = bytes.iter().rev().try_fold(0u64, |acc, byte| byte.map(|b| (acc << 8) | b as u64));
+            let message_bytes = message.len().saturating_sub(8 * j).min(8);
+            let lane = if message_bytes == 0 {
+                self.constant(layouter.namespace(|| "padding"), value.unwrap())?
+            } else {
+                let fixed_bits = 8 * (8 - message_bytes);
+                let fixed_value = bytes[message_bytes..].iter().rev().fold(0u64, |acc, byte| (acc << 8) | byte.unwrap() as u64)
+                    << (8 * message_bytes);
+                self.load(layouter.namespace(|| "message"), value, fixed_bits, fixed_value)?
+            };
+            lanes.push(lane);
+        }
+
+        let zero = self.constant(layouter.namespace(|| "zero"), 0)?;
+        let mut state = vec![zero; 25];
+        for (i, block) in lanes.chunks(RATE_LANES).enumerate() {
+            // The initial state is zero, so the first block doesn't need to be xored
+            for (lane, message_lane) in state.iter_mut().zip(block.iter()) {
+                *lane = if i == 0 {
+                    message_lane.clone()
+                } else {
+                    self.xor(layouter.namespace(|| "absorb"), lane, message_lane)?
+                };
+            }
+            state = self.permute(layouter.namespace(|| "keccak-f"), state)?;
+        }
+
+        // 256 bits of output: the first 4 lanes
+        state.truncate(4);
+        Ok(state)
+    }
+
+    fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &Lane<F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+// The length of the message is part of the shape of the circuit, so it is kept by without_witnesses
+#[derive(Default)]
+struct MyCircuit<F> {
+    message: Vec<Option<u8>>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = KeccakConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            message: vec![None; self.message.len()],
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        KeccakChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = KeccakChip::construct(config);
+        let digest = chip.digest(layouter.namespace(|| "keccak256"), &self.message)?;
+        for (row, lane) in digest.iter().enumerate() {
+            chip.expose_public(layouter.namespace(|| "digest"), lane, row)?;
+        }
+        Ok(())
+    }
+}
+
+// The digest as 4 little endian lanes, one public input each
+fn public_digest(message: &[u8]) -> Vec<Fp> {
+    Keccak256::digest(message)
+        .chunks(8)
+        .map(|lane| Fp::from(u64::from_le_bytes(lane.try_into().unwrap())))
+        .collect()
+}
+
+fn main() {
+    // A single block with ~7500 rows
+    let k = 13;
+
+    // 35 bytes: the last lane of the message is shared with the first padding byte
+    let message = b"fibonacci: 1 1 2 3 5 8 13 21 34 55 ";
+    let circuit = MyCircuit {
+        message: message.iter().copied().map(Some).collect(),
+        _marker: PhantomData,
+    };
+
+    // The expected digest is computed with the sha3 crate
+    let prover = MockProver::run(k, &circuit, vec![public_digest(message)]).unwrap();
+    prover.expect_satisfied();
+
+    // The digest of a different message is rejected
+    let prover = MockProver::run(k, &circuit, vec![public_digest(b"fibonacci: 1 1 2 3 5 8 13 21 34 56 ")]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,78 @@
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    pasta::{group::ff::PrimeField, pallas, vesta, Fp, Fq},
+    plonk::*,
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use rand_core::OsRng;
+
+use halo2_fibonacci_ex::{
+    fibonacci::{FibCircuit, FibCircuitBuilder},
+    field::display_field,
+    merkle::{MerkleCircuit, MerkleTree},
+};
+
+// Every chip of the library is generic over FieldExt, so the same circuit can be instantiated over both Pasta fields:
+// - Fp, the scalar field of Vesta: proofs are committed with the Vesta curve
+// - Fq, the scalar field of Pallas: proofs are committed with the Pallas curve
+// This example runs the same logical inputs through real proofs (keygen, prove, verify) in both fields and checks that
+// - the proofs are accepted in both fields
+// - the public outputs are the same integers, as long as the values don't wrap around the (slightly different) moduli
+// A gadget that silently relies on a specific modulus (a hardcoded constant, an overflow that happens to be harmless in
+// one field, ...) shows up as a mismatch here. Outputs that depend on field specific parameters by design, like the
+// Poseidon constants used by the Merkle tree, are only checked to be accepted.
+
+// Generates the keys, creates a proof and verifies it with the IPA commitment over the curve C
+fn prove_and_verify<C: CurveAffine, ConcreteCircuit: Circuit<C::Scalar>>(k: u32, circuit: &ConcreteCircuit, instance: &[C::Scalar]) -> bool {
+    let params = Params::<C>::new(k);
+    let vk = keygen_vk(&params, circuit).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, circuit).expect("keygen_pk should not fail");
+
+    let mut transcript = Blake2bWrite::<_, C, Challenge255<_>>::init(vec![]);
+    create_proof(&params, &pk, std::slice::from_ref(circuit), &[&[instance]], OsRng, &mut transcript).expect("proof generation should not fail");
+    let proof = transcript.finalize();
+
+    let strategy = SingleVerifier::new(&params);
+    let mut transcript = Blake2bRead::<_, C, Challenge255<_>>::init(&proof[..]);
+    verify_proof(&params, pk.get_vk(), strategy, &[&[instance]], &mut transcript).is_ok()
+}
+
+// Compare two elements of different fields as integers: the little endian representations have the same length
+fn same_integer(a: Fp, b: Fq) -> bool {
+    a.to_repr() == b.to_repr()
+}
+
+fn fibonacci<F: FieldExt>(steps: usize) -> (FibCircuit<F>, Vec<F>) {
+    FibCircuitBuilder::new().start(F::one(), F::one()).steps(steps).expose_output().build().unwrap()
+}
+
+fn merkle<F: FieldExt>() -> (MerkleCircuit<F>, Vec<F>) {
+    let leaves: Vec<F> = (1..=16u64).map(F::from).collect();
+    let tree = MerkleTree::new(&leaves, 4).unwrap();
+    (MerkleCircuit::new(leaves[9], &tree.path(9).unwrap()), vec![tree.root()])
+}
+
+fn main() {
+    // Fibonacci: F(100) fits in both fields, so the outputs must be the same integer
+    let k = 7;
+    let (circuit_fp, public_fp) = fibonacci::<Fp>(98);
+    let (circuit_fq, public_fq) = fibonacci::<Fq>(98);
+    assert!(prove_and_verify::<vesta::Affine, _>(k, &circuit_fp, &public_fp));
+    assert!(prove_and_verify::<pallas::Affine, _>(k, &circuit_fq, &public_fq));
+    assert!(public_fp.iter().zip(public_fq.iter()).all(|(a, b)| same_integer(*a, *b)));
+    println!("fibonacci: accepted in both fields, output {}", display_field(public_fp[2]));
+
+    // A wrong output is rejected
+    let (_, mut wrong_fp) = fibonacci::<Fp>(98);
+    wrong_fp[2] += Fp::one();
+    assert!(!prove_and_verify::<vesta::Affine, _>(k, &circuit_fp, &wrong_fp));
+
+    // Merkle inclusion: the root depends on the Poseidon constants of each field, only check that both are accepted
+    let k = 9;
+    let (circuit_fp, public_fp) = merkle::<Fp>();
+    let (circuit_fq, public_fq) = merkle::<Fq>();
+    assert!(prove_and_verify::<vesta::Affine, _>(k, &circuit_fp, &public_fp));
+    assert!(prove_and_verify::<pallas::Affine, _>(k, &circuit_fq, &public_fq));
+    println!("merkle: accepted in both fields");
+}
@@ -0,0 +1,472 @@
+use halo2_proofs::{
+    arithmetic::{CurveAffine, CurveExt, Field},
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::{
+        group::{ff::PrimeField, Curve, Group},
+        pallas, Fp, Fq,
+    },
+    dev::MockProver,
+};
+use rand_core::OsRng;
+
+use halo2_fibonacci_ex::{poseidon::{self, PoseidonChip, PoseidonConfig}, report::ExpectSatisfied};
+
+// Verifies a Schnorr signature over Pallas inside the circuit. The public key P and the message m are public,
+// the signature (R, s) is private: the statement is "I know a valid signature of m under P".
+//
+//   e = Poseidon(R.x, P.x, m)          (a Pallas base field element, read as an integer, is a valid Pallas scalar)
+//   [s]G = R + [e]P
+//
+// The circuit works over the Pallas base field, so point coordinates are native. It combines
+// - a fixed-base scalar multiplication [s]G, as in example11: the multiples of G are constants in fixed columns
+// - a variable-base scalar multiplication [e]P: P is a witness, so each row doubles the accumulator and conditionally
+//   adds P. The bits of e are also accumulated in a running sum z, which is constrained to be the output of Poseidon
+// - the Poseidon chip of the library for the challenge
+// - single point additions and on-curve checks for R and P
+// Both scalar multiplications start from an offset point with unknown discrete logarithm and remove it at the end, so
+// the incomplete addition formulas never meet the identity or a point and its opposite for honest inputs.
+//
+// The 255 bits of e are not checked to encode an integer smaller than the field modulus, so e and e + p have the same
+// decomposition constraints. A production circuit (like the Orchard one) adds a canonicity check on the top bits.
+
+const NUM_BITS: usize = 255;
+
+type Coordinates = (Fp, Fp);
+
+fn coordinates(point: pallas::Affine) -> Coordinates {
+    let coordinates = point.coordinates().unwrap();
+    (*coordinates.x(), *coordinates.y())
+}
+
+fn offset() -> pallas::Point {
+    pallas::Point::hash_to_curve("halo2-fibonacci-ex:offset")(b"O")
+}
+
+// A base field element is smaller than the scalar field modulus, so it can be read as a scalar
+fn to_scalar(e: Fp) -> Fq {
+    Fq::from_repr(e.to_repr()).unwrap()
+}
+
+fn bit(value: &[u8; 32], i: usize) -> bool {
+    (value[i / 8] >> (i % 8)) & 1 == 1
+}
+
+fn challenge(r: pallas::Affine, public_key: pallas::Affine, message: Fp) -> Fp {
+    poseidon::hash([coordinates(r).0, coordinates(public_key).0, message])
+}
+
+// Native signing: R = [k]G, s = k + e * x
+fn sign(secret_key: Fq, message: Fp) -> (pallas::Affine, Fq) {
+    let public_key = (pallas::Point::generator() * secret_key).to_affine();
+    let nonce = Fq::random(OsRng);
+    let r = (pallas::Point::generator() * nonce).to_affine();
+    let e = challenge(r, public_key, message);
+    (r, nonce + to_scalar(e) * secret_key)
+}
+
+#[derive(Debug, Clone)]
+struct Point {
+    x: AssignedCell<Fp, Fp>,
+    y: AssignedCell<Fp, Fp>,
+}
+
+impl Point {
+    fn value(&self) -> Option<Coordinates> {
+        self.x.value().zip(self.y.value()).map(|(x, y)| (*x, *y))
+    }
+}
+
+// The second operand of an addition: a point assigned somewhere else in the circuit or a constant
+enum Operand<'a> {
+    Assigned(&'a Point),
+    Constant(Coordinates),
+}
+
+// Incomplete addition, natively
+fn add(p: Coordinates, q: Coordinates) -> (Fp, Coordinates) {
+    let lambda = (q.1 - p.1) * (q.0 - p.0).invert().unwrap();
+    let x = lambda.square() - p.0 - q.0;
+    (lambda, (x, lambda * (p.0 - x) - p.1))
+}
+
+fn double(p: Coordinates) -> (Fp, Coordinates) {
+    let lambda = Fp::from(3) * p.0.square() * (Fp::from(2) * p.1).invert().unwrap();
+    let x = lambda.square() - p.0 - p.0;
+    (lambda, (x, lambda * (p.0 - x) - p.1))
+}
+
+#[derive(Debug, Clone)]
+struct SchnorrConfig {
+    bit: Column<Advice>,
+    z: Column<Advice>,
+    x_a: Column<Advice>,
+    y_a: Column<Advice>,
+    x_p: Column<Advice>,
+    y_p: Column<Advice>,
+    lambda_d: Column<Advice>,
+    x_d: Column<Advice>,
+    y_d: Column<Advice>,
+    lambda: Column<Advice>,
+    x_r: Column<Advice>,
+    y_r: Column<Advice>,
+    x_g: Column<Fixed>,
+    y_g: Column<Fixed>,
+    q_fixed_mul: Selector,
+    q_var_mul: Selector,
+    q_add: Selector,
+    q_on_curve: Selector,
+    instance: Column<Instance>,
+    poseidon: PoseidonConfig<Fp>,
+}
+
+struct SchnorrChip {
+    config: SchnorrConfig,
+}
+
+impl SchnorrChip {
+    fn construct(config: SchnorrConfig) -> Self {
+        Self { config }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> SchnorrConfig {
+        let [bit, z, x_a, y_a, x_p, y_p, lambda_d, x_d, y_d, lambda, x_r, y_r] = [(); 12].map(|_| meta.advice_column());
+        let x_g = meta.fixed_column();
+        let y_g = meta.fixed_column();
+        let q_fixed_mul = meta.selector();
+        let q_var_mul = meta.selector();
+        let q_add = meta.selector();
+        let q_on_curve = meta.selector();
+        let instance = meta.instance_column();
+        let constants = meta.fixed_column();
+
+        for column in [bit, z, x_a, y_a, x_p, y_p, x_r, y_r] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+
+        let one = || Expression::Constant(Fp::one());
+
+        // R = A + B with slope lambda, the constraints shared by every addition below
+        let addition = |lambda: Expression<Fp>, a: (Expression<Fp>, Expression<Fp>), b: (Expression<Fp>, Expression<Fp>), r: (Expression<Fp>, Expression<Fp>)| {
+            vec![
+                lambda.clone() * (b.0.clone() - a.0.clone()) - (b.1 - a.1.clone()),
+                r.0.clone() - (lambda.clone().square() - a.0.clone() - b.0),
+                r.1 - (lambda * (a.0 - r.0) - a.1),
+            ]
+        };
+
+        // y^2 = x^3 + 5
+        meta.create_gate("on curve", |meta| {
+            let q = meta.query_selector(q_on_curve);
+            let x = meta.query_advice(x_a, Rotation::cur());
+            let y = meta.query_advice(y_a, Rotation::cur());
+            vec![q * (y.clone().square() - x.clone().square() * x - Expression::Constant(pallas::Affine::b()))]
+        });
+
+        meta.create_gate("add", |meta| {
+            let q = meta.query_selector(q_add);
+            let a = (meta.query_advice(x_a, Rotation::cur()), meta.query_advice(y_a, Rotation::cur()));
+            let b = (meta.query_advice(x_p, Rotation::cur()), meta.query_advice(y_p, Rotation::cur()));
+            let r = (meta.query_advice(x_r, Rotation::cur()), meta.query_advice(y_r, Rotation::cur()));
+            let lambda = meta.query_advice(lambda, Rotation::cur());
+            addition(lambda, a, b, r).into_iter().map(|c| q.clone() * c).collect::<Vec<_>>()
+        });
+
+        // Fixed base, one row per bit (least significant first): R = Acc + G_i, Acc' = bit ? R : Acc
+        meta.create_gate("fixed-base mul step", |meta| {
+            let q = meta.query_selector(q_fixed_mul);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let next = (meta.query_advice(x_a, Rotation::next()), meta.query_advice(y_a, Rotation::next()));
+            let a = (meta.query_advice(x_a, Rotation::cur()), meta.query_advice(y_a, Rotation::cur()));
+            let g = (meta.query_fixed(x_g, Rotation::cur()), meta.query_fixed(y_g, Rotation::cur()));
+            let r = (meta.query_advice(x_r, Rotation::cur()), meta.query_advice(y_r, Rotation::cur()));
+            let lambda = meta.query_advice(lambda, Rotation::cur());
+
+            let mut constraints = vec![bit.clone() * (one() - bit.clone())];
+            constraints.extend(addition(lambda, a.clone(), g, r.clone()));
+            constraints.push(next.0 - (a.0.clone() + bit.clone() * (r.0 - a.0)));
+            constraints.push(next.1 - (a.1.clone() + bit * (r.1 - a.1)));
+            constraints.into_iter().map(|c| q.clone() * c).collect::<Vec<_>>()
+        });
+
+        // Variable base, one row per bit (most significant first): D = 2 Acc, R = D + P, Acc' = bit ? R : D, z' = 2z + bit.
+        // P is carried from row to row
+        meta.create_gate("variable-base mul step", |meta| {
+            let q = meta.query_selector(q_var_mul);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let z = meta.query_advice(z, Rotation::cur());
+            let next = (meta.query_advice(x_a, Rotation::next()), meta.query_advice(y_a, Rotation::next()));
+            let p_next = (meta.query_advice(x_p, Rotation::next()), meta.query_advice(y_p, Rotation::next()));
+            let a = (meta.query_advice(x_a, Rotation::cur()), meta.query_advice(y_a, Rotation::cur()));
+            let p = (meta.query_advice(x_p, Rotation::cur()), meta.query_advice(y_p, Rotation::cur()));
+            let d = (meta.query_advice(x_d, Rotation::cur()), meta.query_advice(y_d, Rotation::cur()));
+            let r = (meta.query_advice(x_r, Rotation::cur()), meta.query_advice(y_r, Rotation::cur()));
+            let lambda_d = meta.query_advice(lambda_d, Rotation::cur());
+            let lambda = meta.query_advice(lambda, Rotation::cur());
+            let two = || Expression::Constant(Fp::from(2));
+
+            let mut constraints = vec![
+                bit.clone() * (one() - bit.clone()),
+                z_next - (two() * z + bit.clone()),
+                // doubling: lambda_d * 2y = 3x^2
+                lambda_d.clone() * two() * a.1.clone() - Expression::Constant(Fp::from(3)) * a.0.clone().square(),
+                d.0.clone() - (lambda_d.clone().square() - two() * a.0.clone()),
+                d.1.clone() - (lambda_d * (a.0 - d.0.clone()) - a.1),
+            ];
+            constraints.extend(addition(lambda, d.clone(), p.clone(), r.clone()));
+            constraints.push(next.0 - (d.0.clone() + bit.clone() * (r.0 - d.0)));
+            constraints.push(next.1 - (d.1.clone() + bit * (r.1 - d.1)));
+            constraints.push(p_next.0 - p.0);
+            constraints.push(p_next.1 - p.1);
+            constraints.into_iter().map(|c| q.clone() * c).collect::<Vec<_>>()
+        });
+
+        let poseidon = PoseidonChip::configure(meta, [x_a, y_a, x_r]);
+
+        SchnorrConfig {
+            bit, z, x_a, y_a, x_p, y_p, lambda_d, x_d, y_d, lambda, x_r, y_r, x_g, y_g,
+            q_fixed_mul, q_var_mul, q_add, q_on_curve, instance, poseidon,
+        }
+    }
+
+    fn assign_point(&self, region: &mut Region<'_, Fp>, columns: (Column<Advice>, Column<Advice>), row: usize, value: Option<Coordinates>) -> Result<Point, Error> {
+        Ok(Point {
+            x: region.assign_advice(|| "x", columns.0, row, || value.map(|p| p.0).ok_or(Error::Synthesis))?,
+            y: region.assign_advice(|| "y", columns.1, row, || value.map(|p| p.1).ok_or(Error::Synthesis))?,
+        })
+    }
+
+    // A private point, checked to be on the curve
+    fn witness_point(&self, mut layouter: impl Layouter<Fp>, value: Option<Coordinates>) -> Result<Point, Error> {
+        layouter.assign_region(
+            || "witness point",
+            |mut region| {
+                self.config.q_on_curve.enable(&mut region, 0)?;
+                self.assign_point(&mut region, (self.config.x_a, self.config.y_a), 0, value)
+            },
+        )
+    }
+
+    // A public point, read from two rows of the instance column and checked to be on the curve
+    fn public_point(&self, mut layouter: impl Layouter<Fp>, row_x: usize, row_y: usize) -> Result<Point, Error> {
+        layouter.assign_region(
+            || "public point",
+            |mut region| {
+                self.config.q_on_curve.enable(&mut region, 0)?;
+                Ok(Point {
+                    x: region.assign_advice_from_instance(|| "x", self.config.instance, row_x, self.config.x_a, 0)?,
+                    y: region.assign_advice_from_instance(|| "y", self.config.instance, row_y, self.config.y_a, 0)?,
+                })
+            },
+        )
+    }
+
+    fn add(&self, mut layouter: impl Layouter<Fp>, a: &Point, b: Operand) -> Result<Point, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "add",
+            |mut region| {
+                config.q_add.enable(&mut region, 0)?;
+                a.x.copy_advice(|| "x_a", &mut region, config.x_a, 0)?;
+                a.y.copy_advice(|| "y_a", &mut region, config.y_a, 0)?;
+                let b_value = match b {
+                    Operand::Assigned(b) => {
+                        b.x.copy_advice(|| "x_b", &mut region, config.x_p, 0)?;
+                        b.y.copy_advice(|| "y_b", &mut region, config.y_p, 0)?;
+                        b.value()
+                    }
+                    Operand::Constant(constant) => {
+                        region.assign_advice_from_constant(|| "x_b", config.x_p, 0, constant.0)?;
+                        region.assign_advice_from_constant(|| "y_b", config.y_p, 0, constant.1)?;
+                        Some(constant)
+                    }
+                };
+
+                let sum = a.value().zip(b_value).map(|(a, b)| add(a, b));
+                region.assign_advice(|| "lambda", config.lambda, 0, || sum.map(|s| s.0).ok_or(Error::Synthesis))?;
+                self.assign_point(&mut region, (config.x_r, config.y_r), 0, sum.map(|s| s.1))
+            },
+        )
+    }
+
+    // [s]G, laid out as in example11: Acc starts from the offset O and the last row adds -O
+    fn fixed_base_mul(&self, mut layouter: impl Layouter<Fp>, scalar: Option<Fq>) -> Result<Point, Error> {
+        let config = &self.config;
+        let mut points = Vec::with_capacity(NUM_BITS + 1);
+        let mut g = pallas::Point::generator();
+        for _ in 0..NUM_BITS {
+            points.push(coordinates(g.to_affine()));
+            g = g.double();
+        }
+        points.push(coordinates((-offset()).to_affine()));
+
+        layouter.assign_region(
+            || "fixed-base mul",
+            |mut region| {
+                let o = coordinates(offset().to_affine());
+                region.assign_advice_from_constant(|| "x_o", config.x_a, 0, o.0)?;
+                region.assign_advice_from_constant(|| "y_o", config.y_a, 0, o.1)?;
+                let mut acc = Some(o);
+                let mut result = None;
+
+                for (row, g) in points.iter().enumerate() {
+                    config.q_fixed_mul.enable(&mut region, row)?;
+                    region.assign_fixed(|| "x_g", config.x_g, row, || Ok(g.0))?;
+                    region.assign_fixed(|| "y_g", config.y_g, row, || Ok(g.1))?;
+
+                    let bit = if row < NUM_BITS {
+                        let bit = scalar.map(|s| bit(&s.to_repr(), row));
+                        region.assign_advice(|| "bit", config.bit, row, || bit.map(|b| Fp::from(b as u64)).ok_or(Error::Synthesis))?;
+                        bit
+                    } else {
+                        region.assign_advice_from_constant(|| "bit", config.bit, row, Fp::one())?;
+                        Some(true)
+                    };
+
+                    let sum = acc.map(|acc| add(acc, *g));
+                    region.assign_advice(|| "lambda", config.lambda, row, || sum.map(|s| s.0).ok_or(Error::Synthesis))?;
+                    self.assign_point(&mut region, (config.x_r, config.y_r), row, sum.map(|s| s.1))?;
+
+                    let next = acc.zip(sum).zip(bit).map(|((acc, sum), bit)| if bit { sum.1 } else { acc });
+                    result = Some(self.assign_point(&mut region, (config.x_a, config.y_a), row + 1, next)?);
+                    acc = next;
+                }
+                Ok(result.unwrap())
+            },
+        )
+    }
+
+    // [e]P where e is an assigned cell: the running sum of the bits must end up equal to e.
+    // Acc starts from O, so after 255 doublings the result is [2^255]O + [e]P and the constant [2^255]O is removed at the end
+    fn variable_base_mul(&self, mut layouter: impl Layouter<Fp>, e: &AssignedCell<Fp, Fp>, p: &Point) -> Result<Point, Error> {
+        let config = &self.config;
+        let shifted_offset = (0..NUM_BITS).fold(offset(), |acc, _| acc.double());
+
+        let acc = layouter.assign_region(
+            || "variable-base mul",
+            |mut region| {
+                let o = coordinates(offset().to_affine());
+                region.assign_advice_from_constant(|| "x_o", config.x_a, 0, o.0)?;
+                region.assign_advice_from_constant(|| "y_o", config.y_a, 0, o.1)?;
+                region.assign_advice_from_constant(|| "z", config.z, 0, Fp::zero())?;
+                let mut acc = Some(o);
+                let mut z = Some(Fp::zero());
+                let mut result = None;
+
+                for row in 0..NUM_BITS {
+                    config.q_var_mul.enable(&mut region, row)?;
+                    if row == 0 {
+                        p.x.copy_advice(|| "x_p", &mut region, config.x_p, 0)?;
+                        p.y.copy_advice(|| "y_p", &mut region, config.y_p, 0)?;
+                    }
+                    self.assign_point(&mut region, (config.x_p, config.y_p), row + 1, p.value())?;
+
+                    let bit = e.value().map(|e| bit(&e.to_repr(), NUM_BITS - 1 - row));
+                    region.assign_advice(|| "bit", config.bit, row, || bit.map(|b| Fp::from(b as u64)).ok_or(Error::Synthesis))?;
+
+                    let doubled = acc.map(double);
+                    region.assign_advice(|| "lambda_d", config.lambda_d, row, || doubled.map(|d| d.0).ok_or(Error::Synthesis))?;
+                    self.assign_point(&mut region, (config.x_d, config.y_d), row, doubled.map(|d| d.1))?;
+
+                    let sum = doubled.zip(p.value()).map(|(d, p)| add(d.1, p));
+                    region.assign_advice(|| "lambda", config.lambda, row, || sum.map(|s| s.0).ok_or(Error::Synthesis))?;
+                    self.assign_point(&mut region, (config.x_r, config.y_r), row, sum.map(|s| s.1))?;
+
+                    let next = doubled.zip(sum).zip(bit).map(|((d, sum), bit)| if bit { sum.1 } else { d.1 });
+                    result = Some(self.assign_point(&mut region, (config.x_a, config.y_a), row + 1, next)?);
+                    acc = next;
+
+                    z = z.zip(bit).map(|(z, bit)| z.double() + Fp::from(bit as u64));
+                    let z_cell = region.assign_advice(|| "z", config.z, row + 1, || z.ok_or(Error::Synthesis))?;
+                    if row == NUM_BITS - 1 {
+                        region.constrain_equal(z_cell.cell(), e.cell())?;
+                    }
+                }
+                Ok(result.unwrap())
+            },
+        )?;
+
+        self.add(layouter.namespace(|| "remove offset"), &acc, Operand::Constant(coordinates((-shifted_offset).to_affine())))
+    }
+
+    fn verify(&self, mut layouter: impl Layouter<Fp>, r: Option<Coordinates>, s: Option<Fq>) -> Result<(), Error> {
+        let config = &self.config;
+
+        // instance = [P.x, P.y, m]
+        let public_key = self.public_point(layouter.namespace(|| "public key"), 0, 1)?;
+        let message = layouter.assign_region(
+            || "message",
+            |mut region| region.assign_advice_from_instance(|| "m", config.instance, 2, config.x_r, 0),
+        )?;
+        let r = self.witness_point(layouter.namespace(|| "R"), r)?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        let e = poseidon.hash(layouter.namespace(|| "challenge"), [r.x.clone(), public_key.x.clone(), message])?;
+
+        let lhs = self.fixed_base_mul(layouter.namespace(|| "[s]G"), s)?;
+        let e_p = self.variable_base_mul(layouter.namespace(|| "[e]P"), &e, &public_key)?;
+        let rhs = self.add(layouter.namespace(|| "R + [e]P"), &r, Operand::Assigned(&e_p))?;
+
+        layouter.assign_region(
+            || "[s]G = R + [e]P",
+            |mut region| {
+                region.constrain_equal(lhs.x.cell(), rhs.x.cell())?;
+                region.constrain_equal(lhs.y.cell(), rhs.y.cell())
+            },
+        )
+    }
+}
+
+#[derive(Default)]
+struct MyCircuit {
+    r: Option<Coordinates>,
+    s: Option<Fq>,
+}
+
+impl Circuit<Fp> for MyCircuit {
+    type Config = SchnorrConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        SchnorrChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        SchnorrChip::construct(config).verify(layouter, self.r, self.s)
+    }
+}
+
+fn main() {
+    // ~650 rows: two scalar multiplications of 256 rows and a Poseidon hash of 3 elements
+    let k = 10;
+
+    let secret_key = Fq::random(OsRng);
+    let public_key = (pallas::Point::generator() * secret_key).to_affine();
+    // The message is a field element, e.g. the hash of a document
+    let message = Fp::from(55);
+    let (r, s) = sign(secret_key, message);
+
+    // The signature also verifies natively
+    let e = to_scalar(challenge(r, public_key, message));
+    assert_eq!((pallas::Point::generator() * s).to_affine(), (r + public_key * e).to_affine());
+
+    let (p_x, p_y) = coordinates(public_key);
+    let circuit = MyCircuit {
+        r: Some(coordinates(r)),
+        s: Some(s),
+    };
+    let prover = MockProver::run(k, &circuit, vec![vec![p_x, p_y, message]]).unwrap();
+    prover.expect_satisfied();
+
+    // The same signature doesn't verify for another message
+    let prover = MockProver::run(k, &circuit, vec![vec![p_x, p_y, message + Fp::one()]]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,242 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// Proves "I know a valid solution of this Sudoku" for a public puzzle, without revealing the solution.
+// Nothing here is an arithmetic sequence: the constraints are about distinct values. "9 values in 1..9 are all distinct"
+// is not a polynomial identity on the values themselves (sums and products have collisions), so every cell is
+// one-hot encoded: 9 boolean indicators, exactly one of them set, and value = sum d * ind_d.
+// Then each row, column and box contains every digit exactly once if, for every digit, the indicators of the 9 cells sum to 1.
+//
+// The table is the grid itself: one row for each row of the Sudoku and, for each column of the Sudoku, 10 advice columns
+// (the value and its 9 indicators). The puzzle goes in 9 instance columns, one per column of the Sudoku, with 0 for the
+// empty cells. Gates can't look at instance cells directly, so the puzzle is copied into 9 more advice columns.
+//  value_0 | ind_0_1 ... ind_0_9 | ... | value_8 | ind_8_1 ... ind_8_9 | puzzle_0 ... puzzle_8 | q_cell | q_col | q_box
+// ------------------------------------------------------------------------------------------------------------------------
+//     5    |   0  0  0  0  1 ... | ... |    2    |   0  1  0  0  0 ... |    5    ...    0     |   1    |   1   |   1
+//    ...
+// - q_cell (every row): the one-hot encoding of each cell, the digits of the Sudoku row and the agreement with the puzzle
+// - q_col (row 0): looks at the 9 rows below and checks the columns
+// - q_box (rows 0, 3, 6): looks at the 3 rows below and checks the 3 boxes
+
+const N: usize = 9;
+
+#[derive(Debug, Clone)]
+struct SudokuConfig {
+    value: [Column<Advice>; N],
+    indicators: [[Column<Advice>; N]; N],
+    puzzle: [Column<Advice>; N],
+    instance: [Column<Instance>; N],
+    q_cell: Selector,
+    q_col: Selector,
+    q_box: Selector,
+}
+
+struct SudokuChip<F: FieldExt> {
+    config: SudokuConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> SudokuChip<F> {
+    fn construct(config: SudokuConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> SudokuConfig {
+        let value = [(); N].map(|_| meta.advice_column());
+        let indicators = [(); N].map(|_| [(); N].map(|_| meta.advice_column()));
+        let puzzle = [(); N].map(|_| meta.advice_column());
+        let instance = [(); N].map(|_| meta.instance_column());
+        let q_cell = meta.selector();
+        let q_col = meta.selector();
+        let q_box = meta.selector();
+        let one = || Expression::Constant(F::one());
+
+        for (puzzle, instance) in puzzle.iter().zip(instance.iter()) {
+            meta.enable_equality(*puzzle);
+            meta.enable_equality(*instance);
+        }
+
+        // The sum of the indicators of a digit over a set of cells, given as (rotation, column)
+        let digit_count = |meta: &mut VirtualCells<'_, F>, cells: &[(i32, usize)], digit: usize| {
+            cells.iter().fold(Expression::Constant(F::zero()), |acc, (rotation, col)| {
+                acc + meta.query_advice(indicators[*col][digit], Rotation(*rotation))
+            })
+        };
+
+        meta.create_gate("cells", |meta| {
+            let q = meta.query_selector(q_cell);
+            let mut constraints = vec![];
+            for col in 0..N {
+                let value = meta.query_advice(value[col], Rotation::cur());
+                let puzzle = meta.query_advice(puzzle[col], Rotation::cur());
+                let indicators: Vec<_> = indicators[col].iter().map(|ind| meta.query_advice(*ind, Rotation::cur())).collect();
+
+                // one-hot: boolean indicators, exactly one set, value = sum d * ind_d
+                for ind in indicators.iter() {
+                    constraints.push(ind.clone() * (one() - ind.clone()));
+                }
+                let set = indicators.iter().fold(Expression::Constant(F::zero()), |acc, ind| acc + ind.clone());
+                constraints.push(set - one());
+                let encoded = indicators
+                    .iter()
+                    .enumerate()
+                    .fold(Expression::Constant(F::zero()), |acc, (d, ind)| acc + ind.clone() * Expression::Constant(F::from(d as u64 + 1)));
+                constraints.push(value.clone() - encoded);
+
+                // either the cell is empty in the puzzle (0) or the solution agrees with it
+                constraints.push(puzzle.clone() * (value - puzzle));
+            }
+
+            // every digit appears once in the row
+            let row: Vec<_> = (0..N).map(|col| (0, col)).collect();
+            for digit in 0..N {
+                constraints.push(digit_count(meta, &row, digit) - one());
+            }
+            constraints.into_iter().map(|c| q.clone() * c).collect::<Vec<_>>()
+        });
+
+        meta.create_gate("columns", |meta| {
+            let q = meta.query_selector(q_col);
+            let mut constraints = vec![];
+            for col in 0..N {
+                let column: Vec<_> = (0..N as i32).map(|rotation| (rotation, col)).collect();
+                for digit in 0..N {
+                    constraints.push(q.clone() * (digit_count(meta, &column, digit) - one()));
+                }
+            }
+            constraints
+        });
+
+        meta.create_gate("boxes", |meta| {
+            let q = meta.query_selector(q_box);
+            let mut constraints = vec![];
+            for first_col in (0..N).step_by(3) {
+                let cells: Vec<_> = (0..3).flat_map(|rotation| (first_col..first_col + 3).map(move |col| (rotation, col))).collect();
+                for digit in 0..N {
+                    constraints.push(q.clone() * (digit_count(meta, &cells, digit) - one()));
+                }
+            }
+            constraints
+        });
+
+        SudokuConfig { value, indicators, puzzle, instance, q_cell, q_col, q_box }
+    }
+
+    fn assign(&self, mut layouter: impl Layouter<F>, solution: &[[Option<u64>; N]; N]) -> Result<(), Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "grid",
+            |mut region| {
+                config.q_col.enable(&mut region, 0)?;
+                for (row, cells) in solution.iter().enumerate() {
+                    config.q_cell.enable(&mut region, row)?;
+                    if row % 3 == 0 {
+                        config.q_box.enable(&mut region, row)?;
+                    }
+
+                    for (col, cell) in cells.iter().enumerate() {
+                        region.assign_advice_from_instance(|| "puzzle", config.instance[col], row, config.puzzle[col], row)?;
+                        region.assign_advice(|| "value", config.value[col], row, || cell.map(F::from).ok_or(Error::Synthesis))?;
+                        for (d, ind) in config.indicators[col].iter().enumerate() {
+                            let set = cell.map(|v| F::from((v == d as u64 + 1) as u64));
+                            region.assign_advice(|| "indicator", *ind, row, || set.ok_or(Error::Synthesis))?;
+                        }
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[derive(Default)]
+struct MyCircuit<F> {
+    solution: [[Option<u64>; N]; N],
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = SudokuConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        SudokuChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        SudokuChip::construct(config).assign(layouter.namespace(|| "sudoku"), &self.solution)
+    }
+}
+
+// One instance column for each column of the Sudoku
+fn public_inputs(puzzle: &[[u64; N]; N]) -> Vec<Vec<Fp>> {
+    (0..N).map(|col| (0..N).map(|row| Fp::from(puzzle[row][col])).collect()).collect()
+}
+
+fn circuit(solution: &[[u64; N]; N]) -> MyCircuit<Fp> {
+    MyCircuit {
+        solution: solution.map(|row| row.map(Some)),
+        _marker: PhantomData,
+    }
+}
+
+fn main() {
+    // 9 rows, plus the blinding rows
+    let k = 5;
+
+    let puzzle = [
+        [5, 3, 0, 0, 7, 0, 0, 0, 0],
+        [6, 0, 0, 1, 9, 5, 0, 0, 0],
+        [0, 9, 8, 0, 0, 0, 0, 6, 0],
+        [8, 0, 0, 0, 6, 0, 0, 0, 3],
+        [4, 0, 0, 8, 0, 3, 0, 0, 1],
+        [7, 0, 0, 0, 2, 0, 0, 0, 6],
+        [0, 6, 0, 0, 0, 0, 2, 8, 0],
+        [0, 0, 0, 4, 1, 9, 0, 0, 5],
+        [0, 0, 0, 0, 8, 0, 0, 7, 9],
+    ];
+    let solution = [
+        [5, 3, 4, 6, 7, 8, 9, 1, 2],
+        [6, 7, 2, 1, 9, 5, 3, 4, 8],
+        [1, 9, 8, 3, 4, 2, 5, 6, 7],
+        [8, 5, 9, 7, 6, 1, 4, 2, 3],
+        [4, 2, 6, 8, 5, 3, 7, 9, 1],
+        [7, 1, 3, 9, 2, 4, 8, 5, 6],
+        [9, 6, 1, 5, 3, 7, 2, 8, 4],
+        [2, 8, 7, 4, 1, 9, 6, 3, 5],
+        [3, 4, 5, 2, 8, 6, 1, 7, 9],
+    ];
+
+    let prover = MockProver::run(k, &circuit(&solution), public_inputs(&puzzle)).unwrap();
+    prover.expect_satisfied();
+
+    // Swapping two cells of a row keeps the row valid but breaks the columns
+    let mut wrong = solution;
+    wrong[0].swap(2, 3);
+    let prover = MockProver::run(k, &circuit(&wrong), public_inputs(&puzzle)).unwrap();
+    assert!(prover.verify().is_err());
+
+    // A valid grid that doesn't agree with the puzzle: swap the digits 1 and 2 everywhere
+    let relabeled = solution.map(|row| row.map(|v| match v {
+        1 => 2,
+        2 => 1,
+        v => v,
+    }));
+    let prover = MockProver::run(k, &circuit(&relabeled), public_inputs(&puzzle)).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,158 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::{field::display_field, report::ExpectSatisfied};
+
+// Proves sum_i a_i * b_i = c for private vectors a, b and a public c.
+// The Fibonacci examples only ever look at the previous values of the sequence. Here the state carried from row to row
+// is a running accumulator: it starts from 0 and every row adds one product to it (multiply-accumulate)
+//
+//   acc_next = acc + a * b
+//
+//  col_a | col_b | col_acc | q_mac
+// ---------------------------------
+//   a_0  |  b_0  |    0    |   1      <- acc is the constant 0
+//   a_1  |  b_1  |  acc_1  |   1
+//   ...  |  ...  |   ...   |   1
+//   a_n-1| b_n-1 | acc_n-1 |   1
+//        |       |  acc_n  |   0      <- acc_n = sum a_i * b_i is copied to the instance column
+//
+// The length of the vectors is part of the shape of the circuit, so it is fixed at keygen.
+
+#[derive(Debug, Clone)]
+struct DotProductConfig {
+    pub advice: [Column<Advice>; 3],
+    pub q_mac: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct DotProductChip<F: FieldExt> {
+    config: DotProductConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> DotProductChip<F> {
+    pub fn construct(config: DotProductConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3], instance: Column<Instance>) -> DotProductConfig {
+        let [col_a, col_b, col_acc] = advice;
+        let q_mac = meta.selector();
+        let constant = meta.fixed_column();
+
+        meta.enable_equality(col_acc);
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("multiply-accumulate", |meta| {
+            let s = meta.query_selector(q_mac);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let acc = meta.query_advice(col_acc, Rotation::cur());
+            let acc_next = meta.query_advice(col_acc, Rotation::next());
+            vec![s * (acc + a * b - acc_next)]
+        });
+
+        DotProductConfig { advice, q_mac, instance }
+    }
+
+    // Returns the cell of the final accumulator
+    pub fn assign(&self, mut layouter: impl Layouter<F>, a: &[Option<F>], b: &[Option<F>]) -> Result<AssignedCell<F, F>, Error> {
+        assert_eq!(a.len(), b.len());
+        let [col_a, col_b, col_acc] = self.config.advice;
+
+        layouter.assign_region(|| "dot product", |mut region| {
+            let mut acc = Some(F::zero());
+            let mut acc_cell = region.assign_advice_from_constant(|| "acc", col_acc, 0, F::zero())?;
+
+            for (row, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+                self.config.q_mac.enable(&mut region, row)?;
+                region.assign_advice(|| "a", col_a, row, || a.ok_or(Error::Synthesis))?;
+                region.assign_advice(|| "b", col_b, row, || b.ok_or(Error::Synthesis))?;
+
+                acc = acc.zip(a.zip(*b)).map(|(acc, (a, b))| acc + a * b);
+                acc_cell = region.assign_advice(|| "acc", col_acc, row + 1, || acc.ok_or(Error::Synthesis))?;
+            }
+
+            Ok(acc_cell)
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+// The length of the vectors is kept by without_witnesses
+#[derive(Default)]
+struct MyCircuit<F> {
+    pub a: Vec<Option<F>>,
+    pub b: Vec<Option<F>>,
+}
+
+impl<F: FieldExt> MyCircuit<F> {
+    fn new(a: &[F], b: &[F]) -> Self {
+        Self {
+            a: a.iter().copied().map(Some).collect(),
+            b: b.iter().copied().map(Some).collect(),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = DotProductConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            a: vec![None; self.a.len()],
+            b: vec![None; self.b.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        DotProductChip::configure(meta, advice, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = DotProductChip::construct(config);
+        let out = chip.assign(layouter.namespace(|| "table"), &self.a, &self.b)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 0)
+    }
+}
+
+fn dot(a: &[Fp], b: &[Fp]) -> Fp {
+    a.iter().zip(b.iter()).fold(Fp::zero(), |acc, (a, b)| acc + a * b)
+}
+
+fn main() {
+    let k = 5;
+
+    // Vectors of a few different lengths: n rows plus the final accumulator must fit in the usable rows
+    for n in [1, 4, 16] {
+        let a: Vec<Fp> = (1..=n).map(Fp::from).collect();
+        let b: Vec<Fp> = (1..=n).map(|i| Fp::from(2 * i + 1)).collect();
+        let c = dot(&a, &b);
+        let circuit = MyCircuit::new(&a, &b);
+
+        let prover = MockProver::run(k, &circuit, vec![vec![c]]).unwrap();
+        prover.expect_satisfied();
+
+        let prover = MockProver::run(k, &circuit, vec![vec![c + Fp::one()]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        println!("n = {}: sum a_i * b_i = {}", n, display_field(c));
+    }
+}
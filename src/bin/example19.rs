@@ -0,0 +1,184 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::{field::display_field, report::ExpectSatisfied};
+
+// Evaluates a polynomial p(X) = c_d X^d + ... + c_1 X + c_0 at a private point x with Horner's rule
+//
+//   acc_0 = c_d,   acc_i = acc_{i-1} * x + c_{d-i},   p(x) = acc_d
+//
+// and exposes p(x). The coefficients are part of the circuit, not of the witness: they are assigned to a fixed column,
+// so they are baked into the keys and a prover can't evaluate a different polynomial.
+//
+// As in example3 everything lives in a single advice column and the gate reaches the other rows with rotations.
+// The gate needs x on every step, so x is repeated on the even rows and the accumulator goes on the odd rows:
+//  advice | coeff | q_first | q_step
+// ------------------------------------
+//    x    |       |    1    |   1
+//   acc_0 |  c_d  |         |
+//    x    |       |         |   1
+//   acc_1 | c_d-1 |         |
+//   ...   |  ...  |         |  ...
+//    x    |       |         |   0
+//   acc_d |  c_0  |         |          <- copied to the instance column
+// - q_first: acc_0 = c_d
+// - q_step: acc_next = acc * x + coeff_next and x_next = x, the second constraint chains the copies of x together
+
+#[derive(Debug, Clone)]
+struct HornerConfig {
+    pub advice: Column<Advice>,
+    pub coeff: Column<Fixed>,
+    pub q_first: Selector,
+    pub q_step: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct HornerChip<F: FieldExt> {
+    config: HornerConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> HornerChip<F> {
+    pub fn construct(config: HornerConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: Column<Advice>, instance: Column<Instance>) -> HornerConfig {
+        let coeff = meta.fixed_column();
+        let q_first = meta.selector();
+        let q_step = meta.selector();
+
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+
+        meta.create_gate("leading coefficient", |meta| {
+            let s = meta.query_selector(q_first);
+            let acc = meta.query_advice(advice, Rotation::next());
+            let coeff = meta.query_fixed(coeff, Rotation::next());
+            vec![s * (acc - coeff)]
+        });
+
+        meta.create_gate("horner step", |meta| {
+            // advice | coeff
+            // ----------------
+            //   x    |          <- q_step
+            //  acc   |
+            //   x'   |
+            //  acc'  | coeff'
+            let s = meta.query_selector(q_step);
+            let x = meta.query_advice(advice, Rotation::cur());
+            let acc = meta.query_advice(advice, Rotation::next());
+            let x_next = meta.query_advice(advice, Rotation(2));
+            let acc_next = meta.query_advice(advice, Rotation(3));
+            let coeff_next = meta.query_fixed(coeff, Rotation(3));
+            vec![
+                s.clone() * (acc * x.clone() + coeff_next - acc_next),
+                s * (x_next - x),
+            ]
+        });
+
+        HornerConfig { advice, coeff, q_first, q_step, instance }
+    }
+
+    // `coefficients` goes from the leading coefficient c_d down to c_0. Returns the cell of p(x)
+    pub fn assign(&self, mut layouter: impl Layouter<F>, coefficients: &[F], x: Option<F>) -> Result<AssignedCell<F, F>, Error> {
+        assert!(!coefficients.is_empty());
+        let config = &self.config;
+
+        layouter.assign_region(|| "horner", |mut region| {
+            config.q_first.enable(&mut region, 0)?;
+
+            let mut acc = Some(coefficients[0]);
+            let mut acc_cell = None;
+            for (i, coeff) in coefficients.iter().enumerate() {
+                let row = 2 * i;
+                if i < coefficients.len() - 1 {
+                    config.q_step.enable(&mut region, row)?;
+                }
+                if i > 0 {
+                    acc = acc.zip(x).map(|(acc, x)| acc * x + coeff);
+                }
+
+                region.assign_advice(|| "x", config.advice, row, || x.ok_or(Error::Synthesis))?;
+                region.assign_fixed(|| "coeff", config.coeff, row + 1, || Ok(*coeff))?;
+                acc_cell = Some(region.assign_advice(|| "acc", config.advice, row + 1, || acc.ok_or(Error::Synthesis))?);
+            }
+
+            Ok(acc_cell.unwrap())
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+// The coefficients are part of the circuit shape, so they are kept by without_witnesses
+#[derive(Default)]
+struct MyCircuit<F> {
+    pub coefficients: Vec<F>,
+    pub x: Option<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = HornerConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            coefficients: self.coefficients.clone(),
+            x: None,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        HornerChip::configure(meta, advice, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = HornerChip::construct(config);
+        let out = chip.assign(layouter.namespace(|| "table"), &self.coefficients, self.x)?;
+        chip.expose_public(layouter.namespace(|| "p(x)"), &out, 0)
+    }
+}
+
+// Evaluate natively, from the highest degree down
+fn evaluate(coefficients: &[Fp], x: Fp) -> Fp {
+    coefficients.iter().fold(Fp::zero(), |acc, c| acc * x + c)
+}
+
+fn main() {
+    let k = 5;
+
+    // p(X) = 3X^4 + 2X^2 - X + 7
+    let coefficients = vec![Fp::from(3), Fp::zero(), Fp::from(2), -Fp::one(), Fp::from(7)];
+    let x = Fp::from(5);
+    let y = evaluate(&coefficients, x);
+    assert_eq!(y, Fp::from(3 * 625 + 2 * 25 - 5 + 7));
+
+    let circuit = MyCircuit { coefficients: coefficients.clone(), x: Some(x) };
+    let prover = MockProver::run(k, &circuit, vec![vec![y]]).unwrap();
+    prover.expect_satisfied();
+
+    // A wrong evaluation is rejected
+    let prover = MockProver::run(k, &circuit, vec![vec![y + Fp::one()]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // A constant polynomial: no step gate at all, only the leading coefficient
+    let circuit = MyCircuit { coefficients: vec![Fp::from(42)], x: Some(x) };
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(42)]]).unwrap();
+    prover.expect_satisfied();
+
+    println!("p(5) = {}", display_field(y));
+}
@@ -7,6 +7,7 @@ use halo2_proofs::{
     poly::Rotation,
     pasta::Fp, dev::MockProver,
 };
+use halo2_fibonacci_ex::report::ExpectSatisfied;
 
 // #[derive(Debug, Clone)] is a Rust attribute used to automatically generate implementations of the Debug and Clone traits for a struct
 #[derive(Debug, Clone)]
@@ -99,11 +100,12 @@ impl<F: FieldExt> FiboChip<F> {
     // This is the function used inside syntentize to assign value to the first raw of the circuit.
     // mut layouter: impl Layouter<F> specifies a function parameter named layouter, which is mutable (mut keyword), and implements the Layouter<F> trait.
     // a and b value will be provided to this function as input. This are the a and b to be assigned inside the first row.
+    #[allow(clippy::type_complexity)]
     fn assign_first_row(&self, mut layouter: impl Layouter<F>, a: Option<F>, b: Option<F>) -> Result<(ACell<F>, ACell<F>, ACell<F>), Error> {
         layouter.assign_region(|| "first row", |mut region| {
 
             // We need to enable the selector in that region because the constraint is set!
-            self.config.selector.enable(&mut region, 0);
+            self.config.selector.enable(&mut region, 0)?;
 
             // Assign the value to a and b. It returns an assigned cell!
             let a_cell = region.assign_advice(
@@ -142,7 +144,7 @@ impl<F: FieldExt> FiboChip<F> {
             || "next row",
             |mut region| {
                 // Here we turn on the selector gate
-                self.config.selector.enable(&mut region, 0);
+                self.config.selector.enable(&mut region, 0)?;
                 // In this line I'm trying to copy stuff from the previous row to the new region in the current row
                 // This is the copy constraint basically
                 // I'm copying the prev_b to the current region in advice column 0 (aka "a")
@@ -222,13 +224,13 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
         // This function will take as input the "a" and "b" value passed to instantiate the circuit
         // We also use a layouter as this is a good way to separate different regions of the circuit
         // We can also assign name to the layouter
-        let (mut prev_a, mut prev_b, mut prev_c) = chip.assign_first_row(layouter.namespace(|| "first row"), self.a, self.b)?;
+        let (prev_a, mut prev_b, mut prev_c) = chip.assign_first_row(layouter.namespace(|| "first row"), self.a, self.b)?;
 
         // Let's now use the expose_public function to enforce some assigned value to be equal to the ones described inside the instance column
         // In the row 0 of the instance column (public values) we are gonna pass the value that we want prev_a to be constrained to be equal to
-        chip.expose_public(layouter.namespace(|| "private a"), &prev_a, 0);
+        chip.expose_public(layouter.namespace(|| "private a"), &prev_a, 0)?;
         // same to b
-        chip.expose_public(layouter.namespace(|| "private b"), &prev_b, 1);
+        chip.expose_public(layouter.namespace(|| "private b"), &prev_b, 1)?;
 
         // Now we have assigned the first row! Now we have to assign the other rows! Remember that the idea of the circuit was
         // given f(0) = x, f(1) = y, we will prove f(9) = z. We already have assigned f(0) and f(1). We now need to assign values to the other rows. 
@@ -244,7 +246,7 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
         }
 
         // Also we want to expose the output of the circuit to the public
-        chip.expose_public(layouter.namespace(|| "output"), &prev_c, 2);
+        chip.expose_public(layouter.namespace(|| "output"), &prev_c, 2)?;
 
         Ok(())
     }
@@ -269,7 +271,13 @@ fn main() {
     // The instance vector is filled by the values that will be used inside the instance column
     let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
 
-    prover.assert_satisfied();
+    prover.expect_satisfied();
+
+    // Every fallible call of synthesize is propagated with `?`, so its errors reach MockProver::run instead of leaving
+    // a half assigned table behind. Dropping one of these Results is a compile error (see [lints] in Cargo.toml).
+    // With a missing witness, the first assign_advice returns Error::Synthesis
+    let result = MockProver::run(k, &MyCircuit { a: None, b: Some(b) }, vec![public_input]);
+    assert!(matches!(result, Err(Error::Synthesis)));
 
     print_circuit();
 
@@ -293,4 +301,7 @@ fn print_circuit() {
     halo2_proofs::dev::CircuitLayout::default()
         .render(4, &circuit, &root)
         .unwrap();
-}
\ No newline at end of file
+}
+
+#[cfg(not(feature = "dev-graph"))]
+fn print_circuit() {}
\ No newline at end of file
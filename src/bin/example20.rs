@@ -0,0 +1,256 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// Proves that g = gcd(a, b) for public 32-bit integers a, b > 0 and a public g.
+// Computing a gcd in the circuit would take a loop of divisions with a data dependent number of steps. Instead the
+// prover computes everything natively and the circuit only checks a certificate ("verify, don't compute"):
+// - g divides a and b: the prover provides the quotients, a = g * q_a and b = g * q_b
+// - every common divisor of a and b divides g: the prover provides Bezout coefficients, u * a - v * b = g
+// Together they mean g is the greatest common divisor.
+//
+// In the field every equation has solutions: q_a = a / g works for any g != 0. The certificate only means something
+// over the integers, so g, u, v, q_a, q_b are range checked to 32 bits. All the products are then below 2^64, much
+// smaller than the modulus, so the equations can't wrap around and they hold over the integers.
+// (Bezout coefficients can always be chosen with u in [1, b] and v in [0, a), which is why u * a - v * b is used
+// instead of u * a + v * b with a negative coefficient.)
+//
+// The certificate is a single row, the range checks are one row per value, each split into 4 bytes looked up in a
+// table of 256 values:
+//  a | b | g | u | v | q_a | q_b | q_gcd         value | byte_0 | byte_1 | byte_2 | byte_3 | q_range
+// ---------------------------------------------  ---------------------------------------------------
+//  12| 18| 6 | 2 | 1 |  2  |  3  |   1             6   |   6    |   0    |   0    |   0    |   1
+//                                                  ...     <- one row for each of g, u, v, q_a, q_b, copied from the left
+
+const BITS: usize = 32;
+
+#[derive(Debug, Clone)]
+struct GcdConfig {
+    // a, b, g, u, v, q_a, q_b
+    pub certificate: [Column<Advice>; 7],
+    pub value: Column<Advice>,
+    pub bytes: [Column<Advice>; BITS / 8],
+    pub table: TableColumn,
+    pub q_gcd: Selector,
+    // A complex selector: simple selectors can't be used inside a lookup argument
+    pub q_range: Selector,
+    pub instance: Column<Instance>,
+}
+
+// The witness: everything the prover computes natively
+#[derive(Debug, Clone, Copy, Default)]
+struct Certificate<F> {
+    g: Option<F>,
+    u: Option<F>,
+    v: Option<F>,
+    q_a: Option<F>,
+    q_b: Option<F>,
+}
+
+struct GcdChip<F: FieldExt> {
+    config: GcdConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> GcdChip<F> {
+    pub fn construct(config: GcdConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> GcdConfig {
+        let certificate = [(); 7].map(|_| meta.advice_column());
+        let value = meta.advice_column();
+        let bytes = [(); BITS / 8].map(|_| meta.advice_column());
+        let table = meta.lookup_table_column();
+        let q_gcd = meta.selector();
+        let q_range = meta.complex_selector();
+        let instance = meta.instance_column();
+
+        for column in certificate.iter().chain([value].iter()) {
+            meta.enable_equality(*column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("gcd certificate", |meta| {
+            let s = meta.query_selector(q_gcd);
+            let [a, b, g, u, v, q_a, q_b] = certificate.map(|column| meta.query_advice(column, Rotation::cur()));
+            vec![
+                s.clone() * (g.clone() * q_a - a.clone()),
+                s.clone() * (g.clone() * q_b - b.clone()),
+                s * (u * a - v * b - g),
+            ]
+        });
+
+        // value = byte_0 + 2^8 byte_1 + 2^16 byte_2 + 2^24 byte_3
+        meta.create_gate("byte decomposition", |meta| {
+            let s = meta.query_selector(q_range);
+            let value = meta.query_advice(value, Rotation::cur());
+            let recomposed = bytes.iter().rev().fold(Expression::Constant(F::zero()), |acc, byte| {
+                acc * Expression::Constant(F::from(256)) + meta.query_advice(*byte, Rotation::cur())
+            });
+            vec![s * (recomposed - value)]
+        });
+
+        // When q_range = 0 the lookup input is 0, which is in the table
+        for byte in bytes {
+            meta.lookup(|meta| {
+                let s = meta.query_selector(q_range);
+                let byte = meta.query_advice(byte, Rotation::cur());
+                vec![(s * byte, table)]
+            });
+        }
+
+        GcdConfig { certificate, value, bytes, table, q_gcd, q_range, instance }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "bytes",
+            |mut table| {
+                for i in 0..256 {
+                    table.assign_cell(|| "byte", self.config.table, i, || Ok(F::from(i as u64)))?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    // Assigns the certificate row, with a and b copied from the instance column, and returns the cells of g, u, v, q_a, q_b
+    pub fn assign_certificate(&self, mut layouter: impl Layouter<F>, certificate: &Certificate<F>) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "certificate",
+            |mut region| {
+                config.q_gcd.enable(&mut region, 0)?;
+                region.assign_advice_from_instance(|| "a", config.instance, 0, config.certificate[0], 0)?;
+                region.assign_advice_from_instance(|| "b", config.instance, 1, config.certificate[1], 0)?;
+
+                let witness = [certificate.g, certificate.u, certificate.v, certificate.q_a, certificate.q_b];
+                witness
+                    .iter()
+                    .zip(config.certificate[2..].iter())
+                    .map(|(value, column)| region.assign_advice(|| "witness", *column, 0, || value.ok_or(Error::Synthesis)))
+                    .collect()
+            },
+        )
+    }
+
+    // Constrains the value of each cell to fit in 32 bits
+    pub fn range_check(&self, mut layouter: impl Layouter<F>, cells: &[AssignedCell<F, F>]) -> Result<(), Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "range checks",
+            |mut region| {
+                for (row, cell) in cells.iter().enumerate() {
+                    config.q_range.enable(&mut region, row)?;
+                    cell.copy_advice(|| "value", &mut region, config.value, row)?;
+
+                    // The bytes of the little endian representation: the value is smaller than 2^32 only if the
+                    // bytes after the first 4 are 0, otherwise the recomposition can't match
+                    let repr = cell.value().map(|value| value.to_repr());
+                    for (i, column) in config.bytes.iter().enumerate() {
+                        let byte = repr.as_ref().map(|repr| F::from(repr.as_ref()[i] as u64));
+                        region.assign_advice(|| "byte", *column, row, || byte.ok_or(Error::Synthesis))?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default)]
+struct MyCircuit<F> {
+    pub certificate: Certificate<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = GcdConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        GcdChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = GcdChip::construct(config);
+        chip.load_table(layouter.namespace(|| "table"))?;
+        let cells = chip.assign_certificate(layouter.namespace(|| "certificate"), &self.certificate)?;
+        chip.range_check(layouter.namespace(|| "range checks"), &cells)?;
+
+        // instance = [a, b, g]
+        chip.expose_public(layouter.namespace(|| "g"), &cells[0], 2)
+    }
+}
+
+// The extended Euclidean algorithm: returns (g, x) with x * a = g mod b
+fn extended_gcd(a: i64, b: i64) -> (i64, i64) {
+    let (mut r0, mut r1) = (a, b);
+    let (mut x0, mut x1) = (1, 0);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+    }
+    (r0, x0)
+}
+
+// Computes the certificate natively: g, u in [1, b], v = (u * a - g) / b and the quotients
+fn certificate(a: u32, b: u32) -> Certificate<Fp> {
+    let (a, b) = (a as i64, b as i64);
+    let (g, x) = extended_gcd(a, b);
+    let u = match x.rem_euclid(b / g) {
+        0 => b / g,
+        u => u,
+    };
+    let v = (u * a - g) / b;
+    assert_eq!(u * a - v * b, g);
+
+    let f = |x: i64| Some(Fp::from(x as u64));
+    Certificate { g: f(g), u: f(u), v: f(v), q_a: f(a / g), q_b: f(b / g) }
+}
+
+fn main() {
+    // 256 table rows plus the blinding rows
+    let k = 9;
+
+    for (a, b, g) in [(12, 18, 6), (17, 5, 1), (7, 7, 7), (1, 4_000_000_000, 1), (3_000_000_000, 2_000_000_000, 1_000_000_000)] {
+        let circuit = MyCircuit { certificate: certificate(a, b) };
+        let public_inputs = vec![Fp::from(a as u64), Fp::from(b as u64), Fp::from(g)];
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        prover.expect_satisfied();
+        println!("gcd({}, {}) = {}", a, b, g);
+    }
+
+    // Claiming a common divisor that isn't the greatest one: 3 divides 12 and 18, but 12 u - 18 v = 3 has no integer
+    // solution. It has one in the field (u = 3 / 12, v = 0), which the range check on u rejects
+    let (a, b, g) = (12u64, 18u64, 3u64);
+    let certificate = Certificate {
+        g: Some(Fp::from(g)),
+        u: Some(Fp::from(g) * Fp::from(a).invert().unwrap()),
+        v: Some(Fp::zero()),
+        q_a: Some(Fp::from(a / g)),
+        q_b: Some(Fp::from(b / g)),
+    };
+    let circuit = MyCircuit { certificate };
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(a), Fp::from(b), Fp::from(g)]]).unwrap();
+    assert!(prover.verify().is_err());
+}
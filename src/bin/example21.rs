@@ -0,0 +1,173 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::{field::display_field, report::ExpectSatisfied};
+
+// Proves x^e = y for a private x, a public exponent e < 2^64 and a public y, with square-and-multiply.
+// The exponent is decomposed into bits, most significant first, and every step squares the accumulator and
+// multiplies it by x only if the bit is set:
+//
+//   acc_0 = 1,   acc_{i+1} = acc_i^2 * (bit_i ? x : 1)
+//
+// A circuit can't branch, so the "if" becomes a selection: bit ? x : 1 = 1 + bit * (x - 1), which is only a
+// selection if bit is 0 or 1. That is the job of the boolean constraint bit * (1 - bit) = 0.
+// The bits must also be the bits of e: a running sum e_{i+1} = 2 e_i + bit_i starts from 0 and ends equal to e.
+//
+//    x  | bit | acc  | e_acc | q_step
+// ------------------------------------
+//    x  | b63 |  1   |   0   |   1      <- acc and e_acc start from constants
+//    x  | b62 | acc1 |  e_1  |   1
+//   ... | ... | ...  |  ...  |  ...
+//    x  | b0  | acc63| e_63  |   1
+//    x  |     |  y   |   e   |   0      <- acc is copied to instance 1, e_acc to instance 0
+// x is repeated on every row and the gate constrains x_next = x, so the prover can't switch to another base.
+
+const BITS: usize = 64;
+
+#[derive(Debug, Clone)]
+struct PowConfig {
+    // x, bit, acc, e_acc
+    pub advice: [Column<Advice>; 4],
+    pub q_step: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct PowChip<F: FieldExt> {
+    config: PowConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> PowChip<F> {
+    pub fn construct(config: PowConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 4], instance: Column<Instance>) -> PowConfig {
+        let [x, bit, acc, e_acc] = advice;
+        let q_step = meta.selector();
+        let constant = meta.fixed_column();
+
+        meta.enable_equality(acc);
+        meta.enable_equality(e_acc);
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("square and multiply", |meta| {
+            let s = meta.query_selector(q_step);
+            let one = Expression::Constant(F::one());
+            let x_next = meta.query_advice(x, Rotation::next());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let e_next = meta.query_advice(e_acc, Rotation::next());
+            let x = meta.query_advice(x, Rotation::cur());
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let e_acc = meta.query_advice(e_acc, Rotation::cur());
+
+            let selected = one.clone() + bit.clone() * (x.clone() - one.clone());
+            vec![
+                s.clone() * bit.clone() * (one - bit.clone()),
+                s.clone() * (acc.clone() * acc * selected - acc_next),
+                s.clone() * (e_acc * Expression::Constant(F::from(2)) + bit - e_next),
+                s * (x_next - x),
+            ]
+        });
+
+        PowConfig { advice, q_step, instance }
+    }
+
+    // Returns the cells of the final e_acc and acc, i.e. e and x^e
+    #[allow(clippy::type_complexity)]
+    pub fn assign(&self, mut layouter: impl Layouter<F>, x: Option<F>, e: u64) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let config = &self.config;
+        let [col_x, col_bit, col_acc, col_e] = config.advice;
+
+        layouter.assign_region(|| "square and multiply", |mut region| {
+            let mut acc = Some(F::one());
+            let mut e_acc = 0u64;
+            let mut acc_cell = region.assign_advice_from_constant(|| "acc", col_acc, 0, F::one())?;
+            let mut e_cell = region.assign_advice_from_constant(|| "e_acc", col_e, 0, F::zero())?;
+
+            for row in 0..BITS {
+                config.q_step.enable(&mut region, row)?;
+                let bit = (e >> (BITS - 1 - row)) & 1;
+                region.assign_advice(|| "x", col_x, row, || x.ok_or(Error::Synthesis))?;
+                region.assign_advice(|| "bit", col_bit, row, || Ok(F::from(bit)))?;
+
+                acc = acc.zip(x).map(|(acc, x)| acc.square() * if bit == 1 { x } else { F::one() });
+                e_acc = 2 * e_acc + bit;
+                acc_cell = region.assign_advice(|| "acc", col_acc, row + 1, || acc.ok_or(Error::Synthesis))?;
+                e_cell = region.assign_advice(|| "e_acc", col_e, row + 1, || Ok(F::from(e_acc)))?;
+            }
+            region.assign_advice(|| "x", col_x, BITS, || x.ok_or(Error::Synthesis))?;
+
+            Ok((e_cell, acc_cell))
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+// The exponent is public, but the bits are assigned by the prover: the running sum checks them against instance 0
+#[derive(Default)]
+struct MyCircuit<F> {
+    pub x: Option<F>,
+    pub e: u64,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = PowConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        PowChip::configure(meta, advice, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = PowChip::construct(config);
+        let (e, y) = chip.assign(layouter.namespace(|| "table"), self.x, self.e)?;
+
+        // instance = [e, y]
+        chip.expose_public(layouter.namespace(|| "e"), &e, 0)?;
+        chip.expose_public(layouter.namespace(|| "y"), &y, 1)
+    }
+}
+
+fn main() {
+    // 65 rows plus the blinding rows
+    let k = 7;
+
+    let x = Fp::from(3);
+    for e in [0u64, 1, 5, 13, u64::MAX] {
+        let y = x.pow_vartime([e]);
+        let circuit = MyCircuit { x: Some(x), e };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(e), y]]).unwrap();
+        prover.expect_satisfied();
+    }
+    println!("3^13 = {}", display_field(x.pow_vartime([13])));
+
+    // The same y claimed for another exponent: the bits don't sum up to it
+    let circuit = MyCircuit { x: Some(x), e: 13 };
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(12), x.pow_vartime([13])]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // A wrong result
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(13), x.pow_vartime([12])]]).unwrap();
+    assert!(prover.verify().is_err());
+}
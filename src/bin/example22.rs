@@ -0,0 +1,249 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// Proves that the Collatz trajectory of a public starting value reaches 1 within N steps
+//
+//   v_next = v / 2       if v is even
+//   v_next = 3 v + 1     if v is odd
+//
+// The Fibonacci examples apply the same linear rule on every row. Here the rule depends on the parity of v, and a
+// circuit can't branch: both branches are encoded in one polynomial and a boolean witness selects one of them
+//
+//   v_next = p * (3 v + 1) + (1 - p) * h,   where v = 2 h + p and p * (1 - p) = 0
+//
+// p and h are provided by the prover. In the field v = 2 h + p has a solution for both values of p (h = (v - p) / 2),
+// so h is range checked to 64 bits with byte lookups: then 2 h + p < 2^65 can't wrap around the modulus and p is
+// really the parity of v. This also bounds every value of the trajectory to 65 bits.
+//
+// The number of rows is fixed at keygen, while the trajectory may reach 1 earlier. A "halted" flag d handles this:
+// once d is set it stays set and v stays 1, and the flag of the last row must be set.
+//    v  | p | h  | d | byte_0 ... byte_7 | q_step
+// ------------------------------------------------
+//    6  | 0 | 3  | 0 |  3   ...    0    |   1      <- v copied from the instance column
+//    3  | 1 | 1  | 0 |  1   ...    0    |   1
+//   10  | 0 | 5  | 0 |  5   ...    0    |   1
+//   ... |   |    |   |                   |
+//    1  | 1 | 0  | 1 |  0   ...    0    |   1      <- halted, v stays 1
+//    1  |   |    | 1 |                   |   0      <- d is the constant 1
+//
+// q_step (a complex selector, as it is used in the lookups) enables on each row:
+// - d * (1 - d) = 0, p * (1 - p) = 0, v = 2 h + p, h = sum 2^(8i) byte_i, byte_i in [0, 256)
+// - halted rows hold 1 and stay halted: d * (v - 1) = 0, d * (1 - d_next) = 0, d_next * (v_next - 1) = 0
+// - rows that are not halted take a Collatz step: (1 - d) * (v_next - p (3 v + 1) - (1 - p) h) = 0
+
+const BYTES: usize = 8;
+
+#[derive(Debug, Clone)]
+struct CollatzConfig {
+    pub v: Column<Advice>,
+    pub parity: Column<Advice>,
+    pub half: Column<Advice>,
+    pub halted: Column<Advice>,
+    pub bytes: [Column<Advice>; BYTES],
+    pub table: TableColumn,
+    pub q_step: Selector,
+    pub instance: Column<Instance>,
+}
+
+// One row of the trajectory, computed natively
+#[derive(Debug, Clone, Copy)]
+struct Row<F> {
+    v: F,
+    parity: F,
+    half: F,
+    halted: F,
+}
+
+struct CollatzChip<F: FieldExt> {
+    config: CollatzConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> CollatzChip<F> {
+    pub fn construct(config: CollatzConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> CollatzConfig {
+        let [v, parity, half, halted] = [(); 4].map(|_| meta.advice_column());
+        let bytes = [(); BYTES].map(|_| meta.advice_column());
+        let table = meta.lookup_table_column();
+        let q_step = meta.complex_selector();
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+
+        meta.enable_equality(v);
+        meta.enable_equality(halted);
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("collatz step", |meta| {
+            let s = meta.query_selector(q_step);
+            let one = Expression::Constant(F::one());
+            let v_next = meta.query_advice(v, Rotation::next());
+            let d_next = meta.query_advice(halted, Rotation::next());
+            let v = meta.query_advice(v, Rotation::cur());
+            let p = meta.query_advice(parity, Rotation::cur());
+            let h = meta.query_advice(half, Rotation::cur());
+            let d = meta.query_advice(halted, Rotation::cur());
+            let recomposed = bytes.iter().rev().fold(Expression::Constant(F::zero()), |acc, byte| {
+                acc * Expression::Constant(F::from(256)) + meta.query_advice(*byte, Rotation::cur())
+            });
+
+            let odd = v.clone() * Expression::Constant(F::from(3)) + one.clone();
+            let step = p.clone() * odd + (one.clone() - p.clone()) * h.clone();
+            vec![
+                s.clone() * d.clone() * (one.clone() - d.clone()),
+                s.clone() * p.clone() * (one.clone() - p.clone()),
+                s.clone() * (h.clone() * Expression::Constant(F::from(2)) + p - v.clone()),
+                s.clone() * (recomposed - h),
+                s.clone() * d.clone() * (v.clone() - one.clone()),
+                s.clone() * d.clone() * (one.clone() - d_next.clone()),
+                s.clone() * d_next * (v_next.clone() - one.clone()),
+                s * (one - d) * (v_next - step),
+            ]
+        });
+
+        // When q_step = 0 the lookup input is 0, which is in the table
+        for byte in bytes {
+            meta.lookup(|meta| {
+                let s = meta.query_selector(q_step);
+                let byte = meta.query_advice(byte, Rotation::cur());
+                vec![(s * byte, table)]
+            });
+        }
+
+        CollatzConfig { v, parity, half, halted, bytes, table, q_step, instance }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "bytes",
+            |mut table| {
+                for i in 0..256 {
+                    table.assign_cell(|| "byte", self.config.table, i, || Ok(F::from(i as u64)))?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    // `rows` has one row for each step plus the last one. Without witnesses it only gives the number of rows
+    pub fn assign(&self, mut layouter: impl Layouter<F>, rows: &[Option<Row<F>>]) -> Result<(), Error> {
+        let config = &self.config;
+        let steps = rows.len() - 1;
+
+        layouter.assign_region(|| "trajectory", |mut region| {
+            region.assign_advice_from_instance(|| "start", config.instance, 0, config.v, 0)?;
+
+            for (offset, row) in rows.iter().enumerate() {
+                if offset > 0 {
+                    region.assign_advice(|| "v", config.v, offset, || row.map(|row| row.v).ok_or(Error::Synthesis))?;
+                }
+                if offset == steps {
+                    // The trajectory must have halted on the last row
+                    let halted = region.assign_advice(|| "halted", config.halted, offset, || row.map(|row| row.halted).ok_or(Error::Synthesis))?;
+                    region.constrain_constant(halted.cell(), F::one())?;
+                    break;
+                }
+
+                config.q_step.enable(&mut region, offset)?;
+                region.assign_advice(|| "parity", config.parity, offset, || row.map(|row| row.parity).ok_or(Error::Synthesis))?;
+                region.assign_advice(|| "half", config.half, offset, || row.map(|row| row.half).ok_or(Error::Synthesis))?;
+                region.assign_advice(|| "halted", config.halted, offset, || row.map(|row| row.halted).ok_or(Error::Synthesis))?;
+
+                // The little endian bytes of h. If h doesn't fit in 64 bits the recomposition fails
+                let repr = row.map(|row| row.half.to_repr());
+                for (i, column) in config.bytes.iter().enumerate() {
+                    let byte = repr.as_ref().map(|repr| F::from(repr.as_ref()[i] as u64));
+                    region.assign_advice(|| "byte", *column, offset, || byte.ok_or(Error::Synthesis))?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+// The number of steps N is part of the circuit shape, so it is kept by without_witnesses
+#[derive(Default)]
+struct MyCircuit<F> {
+    pub rows: Vec<Option<Row<F>>>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = CollatzConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { rows: vec![None; self.rows.len()] }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        CollatzChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = CollatzChip::construct(config);
+        chip.load_table(layouter.namespace(|| "table"))?;
+        chip.assign(layouter.namespace(|| "trajectory"), &self.rows)
+    }
+}
+
+// Runs the trajectory natively for `steps` steps, halting at 1
+fn trajectory(start: u64, steps: usize) -> Vec<Option<Row<Fp>>> {
+    let mut v = start;
+    let mut halted = false;
+    let mut rows = vec![];
+    for _ in 0..=steps {
+        halted = halted || v == 1;
+        rows.push(Some(Row {
+            v: Fp::from(v),
+            parity: Fp::from(v % 2),
+            half: Fp::from(v / 2),
+            halted: Fp::from(halted as u64),
+        }));
+        if !halted {
+            v = if v.is_multiple_of(2) { v / 2 } else { 3 * v + 1 };
+        }
+    }
+    rows
+}
+
+fn main() {
+    // 256 table rows plus the blinding rows
+    let k = 9;
+    let steps = 120;
+
+    // 27 takes 111 steps and climbs up to 9232
+    for start in [1, 6, 27, 97] {
+        let circuit = MyCircuit { rows: trajectory(start, steps) };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(start)]]).unwrap();
+        prover.expect_satisfied();
+        println!("{} reaches 1 within {} steps", start, steps);
+    }
+
+    // 871 takes 178 steps: the trajectory hasn't halted on the last row
+    let circuit = MyCircuit { rows: trajectory(871, steps) };
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(871)]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // Lying about a parity: 3 is odd, but the prover claims p = 0 and h = 3 / 2 to take the short branch.
+    // The field equation v = 2 h + p holds, the range check on h doesn't
+    let mut rows = trajectory(3, steps);
+    let half = Fp::from(3) * Fp::from(2).invert().unwrap();
+    rows[0] = Some(Row { v: Fp::from(3), parity: Fp::zero(), half, halted: Fp::zero() });
+    rows[1] = Some(Row { v: half, ..rows[1].unwrap() });
+    let prover = MockProver::run(k, &MyCircuit { rows }, vec![vec![Fp::from(3)]]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,151 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    pasta::Fp, dev::MockProver,
+};
+
+use halo2_fibonacci_ex::{
+    fibonacci::{FibInputs, Fibonacci},
+    instance::{InstanceAllocator, InstanceLayout},
+    merkle::{MerkleChip, MerkleConfig, MerklePath, MerkleTree},
+    poseidon::{self, PoseidonChip},
+    report::ExpectSatisfied,
+    sequence::{SequenceChip, SequenceConfig},
+};
+
+// A composite circuit exposing values of three chips through a single instance column:
+// - the Fibonacci sequence of the library (a, b and the output)
+// - the root of a Merkle tree containing a private leaf
+// - a nullifier Poseidon(leaf, secret), computed from the same leaf cell, which lets the leaf be "spent" only once
+//   without revealing it
+// Instead of deciding in configure that the root is row 3 and the nullifier row 4, and repeating the same numbers in
+// main, the rows are handed out by an InstanceAllocator (src/instance.rs). The resulting layout is stored in the config
+// and main builds the public inputs from named values, so adding a chip or reordering them only touches configure.
+
+#[derive(Debug, Clone)]
+struct CompositeConfig<F: FieldExt> {
+    fibonacci: SequenceConfig,
+    merkle: MerkleConfig<F>,
+    root_row: usize,
+    nullifier_row: usize,
+    layout: InstanceLayout,
+}
+
+#[derive(Debug, Clone)]
+struct MyCircuit<F: FieldExt> {
+    pub fibonacci: FibInputs<F>,
+    pub leaf: Option<F>,
+    pub secret: Option<F>,
+    pub siblings: Vec<Option<F>>,
+    pub is_right: Vec<Option<bool>>,
+}
+
+impl<F: FieldExt> MyCircuit<F> {
+    fn new(fibonacci: FibInputs<F>, leaf: F, secret: F, path: &MerklePath<F>) -> Self {
+        Self {
+            fibonacci,
+            leaf: Some(leaf),
+            secret: Some(secret),
+            siblings: path.siblings.iter().copied().map(Some).collect(),
+            is_right: path.is_right.iter().copied().map(Some).collect(),
+        }
+    }
+
+    // The layout decided by configure, without running the circuit
+    fn instance_layout() -> InstanceLayout {
+        let mut meta = ConstraintSystem::default();
+        Self::configure(&mut meta).layout
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = CompositeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // The number of steps and the depth of the tree are part of the shape of the circuit
+    fn without_witnesses(&self) -> Self {
+        Self {
+            fibonacci: self.fibonacci,
+            leaf: None,
+            secret: None,
+            siblings: vec![None; self.siblings.len()],
+            is_right: vec![None; self.is_right.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let mut instance = InstanceAllocator::new(meta.instance_column());
+
+        // The boundaries of the sequence are relative to the first row of its block
+        let mut fibonacci = SequenceChip::<F, Fibonacci>::configure(meta, instance.column());
+        fibonacci.instance_offset = instance.allocate_block("fibonacci", &["a", "b", "out"]);
+
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let merkle = MerkleChip::configure(meta, advice, instance.column());
+        let root_row = instance.allocate("merkle.root");
+        let nullifier_row = instance.allocate("nullifier");
+
+        CompositeConfig { fibonacci, merkle, root_row, nullifier_row, layout: instance.layout() }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let fibonacci = SequenceChip::<F, Fibonacci>::construct(config.fibonacci.clone());
+        let last = fibonacci.assign(layouter.namespace(|| "fibonacci"), &[Some(self.fibonacci.a), Some(self.fibonacci.b)], self.fibonacci.n)?;
+        fibonacci.expose_last(layouter.namespace(|| "fibonacci output"), &last)?;
+
+        let advice = config.merkle.advice;
+        let (leaf, secret) = layouter.assign_region(
+            || "leaf and secret",
+            |mut region| {
+                let leaf = region.assign_advice(|| "leaf", advice[0], 0, || self.leaf.ok_or(Error::Synthesis))?;
+                let secret = region.assign_advice(|| "secret", advice[1], 0, || self.secret.ok_or(Error::Synthesis))?;
+                Ok((leaf, secret))
+            },
+        )?;
+
+        let merkle = MerkleChip::construct(config.merkle.clone());
+        let root = merkle.compute_root(layouter.namespace(|| "merkle path"), leaf.clone(), &self.siblings, &self.is_right)?;
+        merkle.expose_public(layouter.namespace(|| "root"), &root, config.root_row)?;
+
+        let poseidon = PoseidonChip::construct(config.merkle.poseidon.clone());
+        let nullifier = poseidon.hash(layouter.namespace(|| "nullifier"), [leaf, secret])?;
+        layouter.constrain_instance(nullifier.cell(), config.fibonacci.instance, config.nullifier_row)
+    }
+}
+
+fn main() {
+    let k = 10;
+
+    let fibonacci = FibInputs::new(Fp::one(), Fp::one(), 8);
+    let leaves: Vec<Fp> = (1..=16u64).map(Fp::from).collect();
+    let tree = MerkleTree::new(&leaves, 4).unwrap();
+    let (leaf, secret) = (leaves[9], Fp::from(12345));
+    let circuit = MyCircuit::new(fibonacci, leaf, secret, &tree.path(9).unwrap());
+
+    let layout = MyCircuit::<Fp>::instance_layout();
+    println!("instance layout:\n{}", layout);
+
+    let fib = fibonacci.public_inputs();
+    let nullifier = poseidon::hash([leaf, secret]);
+    let public_inputs = |nullifier: Fp| {
+        layout.build(&[
+            ("fibonacci.a", fib[0]),
+            ("fibonacci.b", fib[1]),
+            ("fibonacci.out", fib[2]),
+            ("merkle.root", tree.root()),
+            ("nullifier", nullifier),
+        ])
+    };
+
+    let prover = MockProver::run(k, &circuit, vec![public_inputs(nullifier).unwrap()]).unwrap();
+    prover.expect_satisfied();
+
+    // The nullifier of another secret is rejected
+    let wrong = poseidon::hash([leaf, secret + Fp::one()]);
+    let prover = MockProver::run(k, &circuit, vec![public_inputs(wrong).unwrap()]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // Forgetting a value is caught while building the public inputs, not by a failed proof
+    assert!(layout.build(&[("merkle.root", tree.root())]).is_err());
+}
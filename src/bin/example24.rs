@@ -0,0 +1,159 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+
+use halo2_fibonacci_ex::{
+    error::FibError,
+    prover::{self, CancellationToken},
+    report::ExpectSatisfied,
+};
+
+// The Fibonacci circuit of example 2, except that the output is not compared to the instance column: an "output" gate
+// compares it to a fixed column, whose value is written when the keys are generated
+//
+//   a  | b  | c  | expected | q_add | q_out
+// ------------------------------------------
+//   1  | 1  | 2  |          |   1   |   0      <- a and b copied from the instance column
+//   1  | 2  | 3  |          |   1   |   0
+//  ... |    |    |          |       |
+//  21  | 34 | 55 |    55    |   1   |   1      <- q_out * (c - expected) = 0
+//
+// Fixed columns are committed in the verifying key, so the output becomes part of the circuit rather than of the
+// statement. This changes how the keys are reused:
+// - with the instance column, one pair of keys proves and verifies every output. The verifier must be told which
+//   output a proof is about, and a proof only convinces them if they check that output
+// - with the fixed column, every output has its own keys. A verifier holding the keys of "out = 55" can't be fooled
+//   into accepting a proof of another output, whatever instance it receives, but proving another output needs new keys
+// The second fits deployments where the statement never changes (e.g. a verifier contract deployed for one value).
+
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub advice: [Column<Advice>; 3],
+    pub expected: Column<Fixed>,
+    pub q_add: Selector,
+    pub q_out: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FiboConfig {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let expected = meta.fixed_column();
+        let q_add = meta.selector();
+        let q_out = meta.selector();
+        let instance = meta.instance_column();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(q_add);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let c = meta.query_advice(advice[2], Rotation::cur());
+            vec![s * (a + b - c)]
+        });
+
+        meta.create_gate("output", |meta| {
+            let s = meta.query_selector(q_out);
+            let c = meta.query_advice(advice[2], Rotation::cur());
+            let expected = meta.query_fixed(expected, Rotation::cur());
+            vec![s * (c - expected)]
+        });
+
+        FiboConfig { advice, expected, q_add, q_out, instance }
+    }
+
+    // n rows, each one computing c = a + b from the b and c of the previous row
+    pub fn assign(&self, mut layouter: impl Layouter<F>, n: usize, out: F) -> Result<(), Error> {
+        let config = &self.config;
+        let [col_a, col_b, col_c] = config.advice;
+
+        layouter.assign_region(|| "table", |mut region| {
+            let mut a = region.assign_advice_from_instance(|| "a", config.instance, 0, col_a, 0)?;
+            let mut b = region.assign_advice_from_instance(|| "b", config.instance, 1, col_b, 0)?;
+
+            for row in 0..n {
+                config.q_add.enable(&mut region, row)?;
+                if row > 0 {
+                    a = a.copy_advice(|| "a", &mut region, col_a, row)?;
+                    b = b.copy_advice(|| "b", &mut region, col_b, row)?;
+                }
+                let c_val = a.value().zip(b.value()).map(|(a, b)| *a + b);
+                let c = region.assign_advice(|| "c", col_c, row, || c_val.ok_or(Error::Synthesis))?;
+                (a, b) = (b, c);
+            }
+
+            // The expected output is a constant of the circuit, assigned whether or not there are witnesses
+            config.q_out.enable(&mut region, n - 1)?;
+            region.assign_fixed(|| "expected", config.expected, n - 1, || Ok(out))?;
+            Ok(())
+        })
+    }
+}
+
+// a and b are public inputs, the number of rows and the output are the shape of the circuit: without_witnesses keeps them
+struct MyCircuit<F> {
+    pub n: usize,
+    pub out: F,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { n: self.n, out: self.out }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        FiboChip::construct(config).assign(layouter.namespace(|| "fibonacci"), self.n, self.out)
+    }
+}
+
+fn main() {
+    let k = 4;
+    let n = 8;
+    let instance = vec![Fp::one(), Fp::one()];
+
+    let circuit = MyCircuit { n, out: Fp::from(55) };
+    MockProver::run(k, &circuit, vec![instance.clone()]).unwrap().expect_satisfied();
+
+    // The circuit of another output: no instance makes 1, 1 reach 89 in 8 rows
+    let other = MyCircuit { n, out: Fp::from(89) };
+    assert!(MockProver::run(k, &other, vec![instance.clone()]).unwrap().verify().is_err());
+
+    // A real proof verifies with the keys of its own output only
+    let proof = prover::prove(k, &circuit, &instance, &CancellationToken::new()).unwrap();
+    prover::verify(k, &circuit, &instance, &proof).unwrap();
+    assert!(matches!(prover::verify(k, &other, &instance, &proof), Err(FibError::InvalidProof)));
+    println!("the proof of out = 55 verifies with the keys of out = 55, and not with the keys of out = 89");
+
+    // a and b are still public: changing them breaks the proof even with the right keys
+    let instance = vec![Fp::one(), Fp::from(2)];
+    assert!(prover::verify(k, &circuit, &instance, &proof).is_err());
+}
@@ -0,0 +1,183 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// XOR is not a polynomial of a and b of low degree, but on bytes it is a finite relation: the 2^16 triples
+// (a, b, a ^ b). They are loaded into three table columns and each row of the circuit looks up its (a, b, c) as a
+// whole, so c = a ^ b is enforced by a single lookup argument:
+//
+//   a  | b  | c  | q_xor        table_a | table_b | table_c
+// ----------------------        ---------------------------
+//   a0 | b0 | c0 |   1             0    |    0    |    0
+//   a1 | b1 | c1 |   1             0    |    1    |    1
+//  ... |    |    |                ...   |   ...   |   ...
+//                                 255   |   255   |    0
+//
+// A multi-column lookup is not the same as three single-column lookups: those would only check that a, b and c are
+// bytes, each found on a different row of the table. Looking up the tuple requires the three values on the same row.
+// As a side effect a and b are range checked to bytes. Example 9 uses the same mechanism for the points of Sinsemilla.
+//
+// The circuit proves that the public bytes c are the XOR of two private byte strings a and b (e.g. a ciphertext and
+// the one-time pad that decrypts it to a known message).
+
+#[derive(Debug, Clone)]
+struct XorConfig {
+    // a, b, c
+    pub advice: [Column<Advice>; 3],
+    pub table: [TableColumn; 3],
+    pub q_xor: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct XorChip<F: FieldExt> {
+    config: XorConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> XorChip<F> {
+    pub fn construct(config: XorConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> XorConfig {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let table = [(); 3].map(|_| meta.lookup_table_column());
+        let q_xor = meta.complex_selector();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(advice[2]);
+        meta.enable_equality(instance);
+
+        // When q_xor = 0 the input is (0, 0, 0), which is in the table since 0 ^ 0 = 0
+        meta.lookup(|meta| {
+            let s = meta.query_selector(q_xor);
+            advice
+                .iter()
+                .zip(table)
+                .map(|(column, table)| (s.clone() * meta.query_advice(*column, Rotation::cur()), table))
+                .collect()
+        });
+
+        XorConfig { advice, table, q_xor, instance }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let [table_a, table_b, table_c] = self.config.table;
+        layouter.assign_table(
+            || "xor",
+            |mut table| {
+                for a in 0..256u64 {
+                    for b in 0..256u64 {
+                        let row = (a * 256 + b) as usize;
+                        table.assign_cell(|| "a", table_a, row, || Ok(F::from(a)))?;
+                        table.assign_cell(|| "b", table_b, row, || Ok(F::from(b)))?;
+                        table.assign_cell(|| "a ^ b", table_c, row, || Ok(F::from(a ^ b)))?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    // One row per byte. The c column is computed by the prover, the lookup checks it. Returns the cells of c
+    pub fn assign(&self, mut layouter: impl Layouter<F>, a: &[Option<u64>], b: &[Option<u64>]) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let config = &self.config;
+        let [col_a, col_b, col_c] = config.advice;
+
+        layouter.assign_region(|| "xor", |mut region| {
+            a.iter()
+                .zip(b)
+                .enumerate()
+                .map(|(row, (a, b))| {
+                    config.q_xor.enable(&mut region, row)?;
+                    region.assign_advice(|| "a", col_a, row, || a.map(F::from).ok_or(Error::Synthesis))?;
+                    region.assign_advice(|| "b", col_b, row, || b.map(F::from).ok_or(Error::Synthesis))?;
+                    let c = a.zip(*b).map(|(a, b)| F::from(a ^ b));
+                    region.assign_advice(|| "c", col_c, row, || c.ok_or(Error::Synthesis))
+                })
+                .collect()
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+// The length of the strings is part of the circuit shape, so it is kept by without_witnesses
+struct MyCircuit {
+    pub a: Vec<Option<u64>>,
+    pub b: Vec<Option<u64>>,
+}
+
+impl MyCircuit {
+    fn new(a: &[u8], b: &[u8]) -> Self {
+        Self {
+            a: a.iter().map(|byte| Some(*byte as u64)).collect(),
+            b: b.iter().map(|byte| Some(*byte as u64)).collect(),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit {
+    type Config = XorConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: vec![None; self.a.len()], b: vec![None; self.b.len()] }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        XorChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = XorChip::construct(config);
+        chip.load_table(layouter.namespace(|| "table"))?;
+        let c = chip.assign(layouter.namespace(|| "bytes"), &self.a, &self.b)?;
+        for (row, cell) in c.iter().enumerate() {
+            chip.expose_public(layouter.namespace(|| "c"), cell, row)?;
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    // 2^16 table rows plus the blinding rows
+    let k = 17;
+
+    let message = b"fibonacci";
+    let pad = b"\x13\x37\xc0\xff\xee\x00\x42\x99\x7f";
+    let ciphertext: Vec<u8> = message.iter().zip(pad).map(|(m, p)| m ^ p).collect();
+    let public_inputs: Vec<Fp> = message.iter().map(|byte| Fp::from(*byte as u64)).collect();
+
+    // The ciphertext and the pad decrypt to the public message
+    let circuit = MyCircuit::new(&ciphertext, pad);
+    let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
+    prover.expect_satisfied();
+    println!("{:?} xor pad = {:?}", ciphertext, String::from_utf8_lossy(message));
+
+    // Another message
+    let mut wrong = public_inputs.clone();
+    wrong[0] = Fp::from(b'F' as u64);
+    let prover = MockProver::run(k, &circuit, vec![wrong]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // a is not a byte: no row of the table holds it, whatever c the prover claims
+    let mut circuit = MyCircuit::new(&ciphertext, pad);
+    circuit.a[0] = Some(256 + ciphertext[0] as u64);
+    let mut public_inputs = public_inputs;
+    public_inputs[0] = Fp::from((256 + ciphertext[0] as u64) ^ pad[0] as u64);
+    let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,203 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// Proves that some public values are Fibonacci numbers, by looking them up in a table of the first N Fibonacci
+// numbers that the circuit computes itself, in an advice column, during synthesis.
+//
+// With halo2_proofs 0.1.0 this can't be a lookup argument: `meta.lookup` only accepts TableColumns, which are fixed
+// columns whose values are committed in the verifying key (later versions of halo2 add `lookup_any`, which accepts
+// advice expressions on the table side). A table that is computed by the prover needs another membership argument.
+// Here it is the product of the differences
+//
+//   x is in {t_0, ..., t_{N-1}}   <=>   (x - t_0) (x - t_1) ... (x - t_{N-1}) = 0
+//
+// which holds in a field since a product is zero iff one of its factors is. It is accumulated over N rows, next to a
+// copy of the table:
+//
+//   table region            membership region of x
+//   t    | q_fib            t    | x | p                         | q_first | q_prod
+//  -------------           ----------------------------------------------------------
+//   1    |  1               1    | x | x - 1                     |    1    |   1
+//   1    |  1               1    | x | (x - 1)(x - 1)            |    0    |   1
+//   2    |  1               2    | x | (x - 1)(x - 1)(x - 2)     |    0    |   1
+//  ...   |                 ...   |   | ...                       |         |
+//   t_N-1|  0               t_N-1| x | 0                         |    0    |   0    <- p is the constant 0
+//
+// - q_fib: t_{i+2} = t_{i+1} + t_i, and t_0 = t_1 = 1 are constants
+// - every t of a membership region is copied from the table region, and x is copied from the instance column
+// - q_first: p = x - t, q_prod: x_next = x and p_next = p * (x_next - t_next)
+// Each membership check costs N rows, against a few cells for a lookup, so this only fits small tables.
+
+const N: usize = 16;
+
+#[derive(Debug, Clone)]
+struct MembershipConfig {
+    // t, x, p
+    pub advice: [Column<Advice>; 3],
+    pub q_fib: Selector,
+    pub q_first: Selector,
+    pub q_prod: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct MembershipChip<F: FieldExt> {
+    config: MembershipConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> MembershipChip<F> {
+    pub fn construct(config: MembershipConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MembershipConfig {
+        let [t, x, p] = [(); 3].map(|_| meta.advice_column());
+        let q_fib = meta.selector();
+        let q_first = meta.selector();
+        let q_prod = meta.selector();
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+
+        meta.enable_equality(t);
+        meta.enable_equality(x);
+        meta.enable_equality(p);
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("fibonacci table", |meta| {
+            let s = meta.query_selector(q_fib);
+            let t0 = meta.query_advice(t, Rotation::cur());
+            let t1 = meta.query_advice(t, Rotation::next());
+            let t2 = meta.query_advice(t, Rotation(2));
+            vec![s * (t0 + t1 - t2)]
+        });
+
+        meta.create_gate("first factor", |meta| {
+            let s = meta.query_selector(q_first);
+            let t = meta.query_advice(t, Rotation::cur());
+            let x = meta.query_advice(x, Rotation::cur());
+            let p = meta.query_advice(p, Rotation::cur());
+            vec![s * (x - t - p)]
+        });
+
+        meta.create_gate("product of differences", |meta| {
+            let s = meta.query_selector(q_prod);
+            let t_next = meta.query_advice(t, Rotation::next());
+            let x_next = meta.query_advice(x, Rotation::next());
+            let p_next = meta.query_advice(p, Rotation::next());
+            let x = meta.query_advice(x, Rotation::cur());
+            let p = meta.query_advice(p, Rotation::cur());
+            vec![
+                s.clone() * (x_next.clone() - x),
+                s * (p * (x_next - t_next) - p_next),
+            ]
+        });
+
+        MembershipConfig { advice: [t, x, p], q_fib, q_first, q_prod, instance }
+    }
+
+    // Computes the first N Fibonacci numbers in an advice column and returns their cells
+    pub fn assign_table(&self, mut layouter: impl Layouter<F>) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let config = &self.config;
+        let t = config.advice[0];
+
+        layouter.assign_region(|| "table", |mut region| {
+            let mut table = vec![
+                region.assign_advice_from_constant(|| "t", t, 0, F::one())?,
+                region.assign_advice_from_constant(|| "t", t, 1, F::one())?,
+            ];
+            for row in 2..N {
+                config.q_fib.enable(&mut region, row - 2)?;
+                let value = table[row - 2].value().zip(table[row - 1].value()).map(|(a, b)| *a + b);
+                table.push(region.assign_advice(|| "t", t, row, || value.ok_or(Error::Synthesis))?);
+            }
+            Ok(table)
+        })
+    }
+
+    // Constrains the value of row `row` of the instance column to be one of the cells of the table
+    pub fn assert_member(&self, mut layouter: impl Layouter<F>, table: &[AssignedCell<F, F>], row: usize) -> Result<(), Error> {
+        let config = &self.config;
+        let [col_t, col_x, col_p] = config.advice;
+
+        layouter.assign_region(|| "membership", |mut region| {
+            config.q_first.enable(&mut region, 0)?;
+            let mut p = None;
+            for (offset, t) in table.iter().enumerate() {
+                if offset + 1 < table.len() {
+                    config.q_prod.enable(&mut region, offset)?;
+                }
+                let t = t.copy_advice(|| "t", &mut region, col_t, offset)?;
+                let x = region.assign_advice_from_instance(|| "x", config.instance, row, col_x, offset)?;
+
+                let factor = x.value().zip(t.value()).map(|(x, t)| *x - t);
+                p = match offset {
+                    0 => factor,
+                    _ => p.zip(factor).map(|(p, factor)| p * factor),
+                };
+                let cell = region.assign_advice(|| "p", col_p, offset, || p.ok_or(Error::Synthesis))?;
+                if offset + 1 == table.len() {
+                    region.constrain_constant(cell.cell(), F::zero())?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+// The number of values is part of the circuit shape, the values themselves are public
+struct MyCircuit {
+    pub queries: usize,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit {
+    type Config = MembershipConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { queries: self.queries }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        MembershipChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = MembershipChip::construct(config);
+        let table = chip.assign_table(layouter.namespace(|| "table"))?;
+        for row in 0..self.queries {
+            chip.assert_member(layouter.namespace(|| "membership"), &table, row)?;
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    // N table rows and N rows per value, plus the blinding rows
+    let k = 7;
+    let circuit = MyCircuit { queries: 3 };
+
+    // 987 is the last of the first 16 Fibonacci numbers
+    let values = vec![Fp::from(21), Fp::from(1), Fp::from(987)];
+    let prover = MockProver::run(k, &circuit, vec![values]).unwrap();
+    prover.expect_satisfied();
+    println!("21, 1 and 987 are among the first {} Fibonacci numbers", N);
+
+    // 4 is not a Fibonacci number, 1597 is the 17th
+    for outsider in [4, 1597] {
+        let values = vec![Fp::from(21), Fp::from(outsider), Fp::from(987)];
+        let prover = MockProver::run(k, &circuit, vec![values]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
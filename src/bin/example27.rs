@@ -0,0 +1,161 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::{commitment::Params, Rotation},
+    pasta::{EqAffine, Fp}, dev::MockProver,
+};
+use halo2_fibonacci_ex::{prover::fingerprint, report::ExpectSatisfied};
+
+// Example 3 with the first two terms fixed by the circuit instead of given in the instance column: the sequence
+// always starts from (a, b) chosen when the circuit is built, and only the output is public.
+//
+//   advice | fixed (constants) | instance
+//  ------------------------------------------
+//   a      | a                 | out
+//   b      | b                 |
+//   ...    |                   |
+//   out    |                   |
+//
+// The config changes compared to example 3:
+// - a fixed column registered with `meta.enable_constant`. The layouter writes every constant used by the circuit in
+//   it, and `enable_constant` also enables equality on it, as constants are bound to their cells by copy constraints
+// - the advice column still needs `enable_equality`, for these copy constraints and for the output
+// - the instance column only holds the output, at row 0
+// In synthesize, `assign_advice_from_constant` assigns a cell and constrains it to the constant in one call. It is
+// the same as an `assign_advice` followed by `region.constrain_constant(cell.cell(), value)`, which is how a cell
+// computed by the circuit is pinned to a constant (the table region below does that with `expected`).
+//
+// Fixed columns are part of the verifying key: the constants are checked by the verifier without appearing in the
+// proof or the instance, and a circuit with other constants has another verifying key.
+
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub advice: Column<Advice>,
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FiboConfig {
+        let advice = meta.advice_column();
+        let selector = meta.selector();
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(advice, Rotation::cur());
+            let b = meta.query_advice(advice, Rotation::next());
+            let c = meta.query_advice(advice, Rotation(2));
+            vec![s * (a + b - c)]
+        });
+
+        FiboConfig { advice, selector, instance }
+    }
+
+    // The whole table in one region, starting from the constants a and b, and with the last term pinned to `expected`
+    // if given. Returns the cell of the last term
+    pub fn assign(&self, mut layouter: impl Layouter<F>, a: F, b: F, expected: Option<F>, nrows: usize) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(|| "entire fibonacci table", |mut region| {
+            let mut a_cell = region.assign_advice_from_constant(|| "a", config.advice, 0, a)?;
+            let mut b_cell = region.assign_advice_from_constant(|| "b", config.advice, 1, b)?;
+
+            for row in 2..nrows {
+                config.selector.enable(&mut region, row - 2)?;
+                let c = a_cell.value().zip(b_cell.value()).map(|(a, b)| *a + b);
+                let c_cell = region.assign_advice(|| "c", config.advice, row, || c.ok_or(Error::Synthesis))?;
+                a_cell = b_cell;
+                b_cell = c_cell;
+            }
+            if let Some(expected) = expected {
+                region.constrain_constant(b_cell.cell(), expected)?;
+            }
+            Ok(b_cell)
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+// a and b are not witnesses but parameters of the circuit, so without_witnesses keeps them. `expected` optionally
+// pins the output to a constant as well, in which case the instance is still checked against it
+#[derive(Clone)]
+struct MyCircuit<F> {
+    pub a: F,
+    pub b: F,
+    pub expected: Option<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        let out = chip.assign(layouter.namespace(|| "entire table"), self.a, self.b, self.expected, 10)?;
+        chip.expose_public(layouter.namespace(|| "output"), &out, 0)
+    }
+}
+
+fn main() {
+    let k = 4;
+    let circuit = MyCircuit { a: Fp::one(), b: Fp::one(), expected: None };
+
+    // Only the output is public
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(55)]]).unwrap();
+    prover.expect_satisfied();
+    println!("starting from the constants 1, 1 the 10th term is 55");
+
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(89)]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // Starting from 2, 3 the 10th term is 144
+    let other = MyCircuit { a: Fp::from(2), b: Fp::from(3), expected: None };
+    let prover = MockProver::run(k, &other, vec![vec![Fp::from(144)]]).unwrap();
+    prover.expect_satisfied();
+
+    // The constants are in the verifying key: the two circuits have different keys
+    let params = Params::<EqAffine>::new(k);
+    let vk = keygen_vk(&params, &circuit).unwrap();
+    let other_vk = keygen_vk(&params, &other).unwrap();
+    assert_ne!(fingerprint(&vk), fingerprint(&other_vk));
+    println!("verifying key of (1, 1): {}", fingerprint(&vk));
+    println!("verifying key of (2, 3): {}", fingerprint(&other_vk));
+
+    // constrain_constant on a computed cell: the output must be 55 whatever the instance says
+    let pinned = MyCircuit { a: Fp::one(), b: Fp::one(), expected: Some(Fp::from(55)) };
+    MockProver::run(k, &pinned, vec![vec![Fp::from(55)]]).unwrap().expect_satisfied();
+    let pinned = MyCircuit { a: Fp::one(), b: Fp::one(), expected: Some(Fp::from(89)) };
+    assert!(MockProver::run(k, &pinned, vec![vec![Fp::from(55)]]).unwrap().verify().is_err());
+}
@@ -0,0 +1,166 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp,
+};
+use halo2_fibonacci_ex::prover::{self, CancellationToken};
+
+// Example 3 binds the first two terms to the instance column with copy constraints (assign_advice_from_instance).
+// Here the first row is bound by a gate instead, which queries the instance column like any other column:
+//
+//   advice | instance | q_first | q_add
+//  -------------------------------------
+//   a      | a        |    1    |   1       q_first: advice_cur - instance_cur = 0, advice_next - instance_next = 0
+//   b      | b        |    0    |   1
+//   ...    | out      |         |
+//   out    |          |    0    |   0       <- copied to row 2 of the instance column with constrain_instance
+//
+// The two approaches differ in how rows are matched:
+// - a copy constraint links two cells wherever they are: constrain_instance takes an absolute instance row, and the
+//   region holding the advice cell can be placed anywhere by the floor planner
+// - a gate only relates cells of the same rows (up to its rotations). `meta.query_instance(instance, Rotation::cur())`
+//   on the row where q_first is enabled reads the instance cell of that absolute row, so the table must start at
+//   row 0 of the circuit for a and b to be read from rows 0 and 1 of the instance column
+// The gate needs no enable_equality on the advice and instance columns (the output still does, as it is copied) and
+// no permutation argument for a and b, but the layout is now part of the statement: main shows that a region placed
+// before the table shifts the rows that the gate reads.
+//
+// main uses the real prover: the MockProver of halo2_proofs 0.1.0 checks that every cell queried by an enabled gate
+// was assigned in the region, and instance cells are never assigned by regions, so it reports them as "cell not
+// assigned" for any gate calling query_instance.
+
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub advice: Column<Advice>,
+    pub q_first: Selector,
+    pub q_add: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FiboConfig {
+        let advice = meta.advice_column();
+        let q_first = meta.selector();
+        let q_add = meta.selector();
+        let instance = meta.instance_column();
+
+        // Only for the output
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+
+        meta.create_gate("first row", |meta| {
+            let s = meta.query_selector(q_first);
+            let a = meta.query_advice(advice, Rotation::cur());
+            let b = meta.query_advice(advice, Rotation::next());
+            let public_a = meta.query_instance(instance, Rotation::cur());
+            let public_b = meta.query_instance(instance, Rotation::next());
+            vec![s.clone() * (a - public_a), s * (b - public_b)]
+        });
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(q_add);
+            let a = meta.query_advice(advice, Rotation::cur());
+            let b = meta.query_advice(advice, Rotation::next());
+            let c = meta.query_advice(advice, Rotation(2));
+            vec![s * (a + b - c)]
+        });
+
+        FiboConfig { advice, q_first, q_add, instance }
+    }
+
+    // a and b are private witnesses here: the "first row" gate is what makes them equal to the public values
+    pub fn assign(&self, mut layouter: impl Layouter<F>, a: Option<F>, b: Option<F>, nrows: usize) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(|| "entire fibonacci table", |mut region| {
+            config.q_first.enable(&mut region, 0)?;
+            let mut a_cell = region.assign_advice(|| "a", config.advice, 0, || a.ok_or(Error::Synthesis))?;
+            let mut b_cell = region.assign_advice(|| "b", config.advice, 1, || b.ok_or(Error::Synthesis))?;
+
+            for row in 2..nrows {
+                config.q_add.enable(&mut region, row - 2)?;
+                let c = a_cell.value().zip(b_cell.value()).map(|(a, b)| *a + b);
+                let c_cell = region.assign_advice(|| "c", config.advice, row, || c.ok_or(Error::Synthesis))?;
+                a_cell = b_cell;
+                b_cell = c_cell;
+            }
+            Ok(b_cell)
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+// `padding` assigns a one-row region before the table, which moves the table down by one row
+#[derive(Default, Clone)]
+struct MyCircuit<F> {
+    pub a: Option<F>,
+    pub b: Option<F>,
+    pub padding: bool,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: None, b: None, padding: self.padding }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        if self.padding {
+            layouter.assign_region(|| "padding", |mut region| {
+                region.assign_advice(|| "unused", config.advice, 0, || Ok(F::zero()))
+            })?;
+        }
+        let chip = FiboChip::construct(config);
+        let out = chip.assign(layouter.namespace(|| "entire table"), self.a, self.b, 10)?;
+        chip.expose_public(layouter.namespace(|| "output"), &out, 2)
+    }
+}
+
+fn main() {
+    // 10 rows of table, 11 with the padding, plus the blinding rows
+    let k = 5;
+    let token = CancellationToken::new();
+    let circuit = MyCircuit { a: Some(Fp::one()), b: Some(Fp::one()), padding: false };
+    let public_inputs = vec![Fp::one(), Fp::one(), Fp::from(55)];
+
+    let proof = prover::prove(k, &circuit, &public_inputs, &token).unwrap();
+    prover::verify(k, &circuit, &public_inputs, &proof).unwrap();
+    println!("the first row is bound to the instance column by a gate");
+
+    // The prover starts from other terms than the public ones: the "first row" gate fails
+    let forged = MyCircuit { a: Some(Fp::from(2)), b: Some(Fp::from(3)), padding: false };
+    let proof = prover::prove(k, &forged, &public_inputs, &token).unwrap();
+    assert!(prover::verify(k, &forged, &public_inputs, &proof).is_err());
+
+    // The table starts at row 1: the gate compares a and b with rows 1 and 2 of the instance column, that is b and
+    // out. A copy constraint would have followed the cells wherever the floor planner placed them
+    let shifted = MyCircuit { padding: true, ..circuit };
+    let proof = prover::prove(k, &shifted, &public_inputs, &token).unwrap();
+    assert!(prover::verify(k, &shifted, &public_inputs, &proof).is_err());
+    println!("the same circuit, placed one row lower, reads the wrong public inputs");
+}
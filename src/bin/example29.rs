@@ -0,0 +1,138 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// Example 3 with two instance columns: one for the inputs a, b and one for the output.
+//
+//   advice | inputs | outputs
+//  ---------------------------
+//   a      | a      | out
+//   b      | b      |
+//   ...    |        |
+//   out    |        |
+//
+// - every call to `meta.instance_column()` creates a new column, numbered in the order of the calls
+// - `MockProver::run` (like create_proof and verify_proof) takes one vector of values per instance column, in that
+//   order: vec![inputs, outputs]. The vectors may have different lengths, the missing rows are 0
+// - the rows of each column are numbered from 0: constrain_instance and assign_advice_from_instance take the column
+//   and a row of that column, so the output is at row 0 of `outputs`
+// Separate columns let each kind of public value be addressed by its own row numbers, e.g. a circuit can add inputs
+// without moving its outputs. Each instance column costs one more polynomial commitment in the proof though.
+
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub advice: Column<Advice>,
+    pub selector: Selector,
+    pub inputs: Column<Instance>,
+    pub outputs: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FiboConfig {
+        let advice = meta.advice_column();
+        let selector = meta.selector();
+        // Instance column 0, then instance column 1
+        let inputs = meta.instance_column();
+        let outputs = meta.instance_column();
+
+        meta.enable_equality(advice);
+        meta.enable_equality(inputs);
+        meta.enable_equality(outputs);
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(advice, Rotation::cur());
+            let b = meta.query_advice(advice, Rotation::next());
+            let c = meta.query_advice(advice, Rotation(2));
+            vec![s * (a + b - c)]
+        });
+
+        FiboConfig { advice, selector, inputs, outputs }
+    }
+
+    // a and b are copied from rows 0 and 1 of the inputs column. Returns the cell of the last term
+    pub fn assign(&self, mut layouter: impl Layouter<F>, nrows: usize) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(|| "entire fibonacci table", |mut region| {
+            let mut a_cell = region.assign_advice_from_instance(|| "a", config.inputs, 0, config.advice, 0)?;
+            let mut b_cell = region.assign_advice_from_instance(|| "b", config.inputs, 1, config.advice, 1)?;
+
+            for row in 2..nrows {
+                config.selector.enable(&mut region, row - 2)?;
+                let c = a_cell.value().zip(b_cell.value()).map(|(a, b)| *a + b);
+                let c_cell = region.assign_advice(|| "c", config.advice, row, || c.ok_or(Error::Synthesis))?;
+                a_cell = b_cell;
+                b_cell = c_cell;
+            }
+            Ok(b_cell)
+        })
+    }
+
+    // Copies the cell to a row of the outputs column
+    pub fn expose_output(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.outputs, row)
+    }
+}
+
+#[derive(Default)]
+struct MyCircuit<F>(PhantomData<F>);
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        let out = chip.assign(layouter.namespace(|| "entire table"), 10)?;
+        chip.expose_output(layouter.namespace(|| "out"), &out, 0)
+    }
+}
+
+fn main() {
+    let k = 4;
+    let circuit = MyCircuit(PhantomData);
+
+    // One vector per instance column, in the order they were created
+    let inputs = vec![Fp::one(), Fp::one()];
+    let outputs = vec![Fp::from(55)];
+    let prover = MockProver::run(k, &circuit, vec![inputs.clone(), outputs.clone()]).unwrap();
+    prover.expect_satisfied();
+    println!("inputs [1, 1] in instance column 0, output [55] in instance column 1");
+
+    // The vectors in the wrong order: a, b are read from [55, 0] and the output is compared with 1
+    let prover = MockProver::run(k, &circuit, vec![outputs.clone(), inputs.clone()]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // A single vector for two columns is refused before anything is checked
+    let mut single = inputs;
+    single.extend(outputs);
+    assert!(MockProver::run(k, &circuit, vec![single]).is_err());
+}
@@ -6,16 +6,14 @@ use halo2_proofs::{
     poly::Rotation,
     pasta::Fp, dev::MockProver,
 };
-
-// #[derive(Debug, Clone)] is a Rust attribute used to automatically generate implementations of the Debug and Clone traits for a struct
-#[derive(Debug, Clone)]
-struct ACell<F: FieldExt>(AssignedCell<F, F>);
+use halo2_fibonacci_ex::{boundary::{BoundaryChip, BoundaryConfig}, report::ExpectSatisfied};
 
 #[derive(Debug, Clone)]
 // This new version only has a single advice column
 struct FiboConfig { 
     pub advice: Column<Advice>,
-    pub selector: Selector,
+    // The selectors are handled by the boundary helper (see src/boundary.rs)
+    pub boundary: BoundaryConfig,
     pub instance: Column<Instance>,
 }
 
@@ -41,8 +39,9 @@ impl<F: FieldExt> FiboChip<F> {
         advice: Column<Advice>,
         instance: Column<Instance>,  
     ) -> FiboConfig {
-        // create the selector
-        let selector: Selector = meta.selector();
+        // create the selectors. The gate below spans 3 rows (cur, next and Rotation(2)), so the step selector
+        // must be enabled on every row of the table but the last 2
+        let boundary = BoundaryChip::configure(meta, 3);
 
         // We still need to enable the equality here. But we won't use it to perform the permutation checks as in the old version,
         // rather we will use it to perform the permutation check with the instance column in order to expose the public input
@@ -51,23 +50,23 @@ impl<F: FieldExt> FiboChip<F> {
 
         // Now the copy constraint becomes a bit different! We have only one advise column and all the witness is passed to that advise column
         // a,b,c are all queriesd from the same advice column by performing some rotation. The custom gate has a different shape
-        meta.create_gate("add", |meta| {
+        // The step gate helper multiplies the constraints by the step selector for us
+        BoundaryChip::create_step_gate(meta, &boundary, "add", |meta| {
             // advice| selector
             // ----------------
             //  a    |    s
             //  b    |    
             //  c    |
-            let s = meta.query_selector(selector);
             let a = meta.query_advice(advice, Rotation::cur());
             let b = meta.query_advice(advice, Rotation::next());
             let c = meta.query_advice(advice, Rotation(2));
 
             // This remains the same!
-            vec![s * (a + b - c)] // s * (a + b - c) = 0
-        }); 
+            vec![("fib-add", a + b - c)] // s * (a + b - c) = 0
+        });
 
         // return the configuration of the circuit. This included the advice columns and the selector, while the custom gates have been mutated on `meta`.
-        FiboConfig { advice, selector, instance}
+        FiboConfig { advice, boundary, instance}
     }
 
     // The assignment is different now. We can no longer assign stuff row by row. If I were to assign values row by row, halo2 will be panicking
@@ -81,9 +80,8 @@ impl<F: FieldExt> FiboChip<F> {
         layouter.assign_region(|| "entire fibonacci table", |mut region| {
 
             // We need to enable the selector in that region because the constraint is set!
-            // The selector will be enable at each line as the copy constraint must be checked on each line!
-            self.config.selector.enable(&mut region, 0)?;
-            self.config.selector.enable(&mut region, 1)?;
+            // The boundary helper enables it on each row where the gate fits inside the table, that is all the rows apart from the last 2 ones
+            BoundaryChip::construct(self.config.boundary).enable(&mut region, nrows)?;
 
             // this api is performing the assignment and the copy constaint from the instance column
             let mut a_cell = region.assign_advice_from_instance(|| "1", self.config.instance, 0, self.config.advice, 0)?;
@@ -91,10 +89,6 @@ impl<F: FieldExt> FiboChip<F> {
 
             // we already assigned the first two rows, we need to assign all the other rows
             for row in 2..nrows {
-                // The selector must enable to each row apart from the last 2 ones where we won't have any copy constraint!
-                if row < nrows - 2 {
-                    self.config.selector.enable(&mut region, row)?;
-                }
                 // compute value of c
                 let c_val = a_cell.value().and_then(
                     |a| {
@@ -192,7 +186,7 @@ fn main() {
     // The instance vector is filled by the values that will be used inside the instance column
     let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
 
-    prover.assert_satisfied();
+    prover.expect_satisfied();
 
     print_circuit(&circuit);
 
@@ -210,3 +204,5 @@ fn print_circuit(circuit: &MyCircuit<Fp>) {
             .unwrap();
 }
 
+#[cfg(not(feature = "dev-graph"))]
+fn print_circuit(_circuit: &MyCircuit<Fp>) {}
@@ -0,0 +1,142 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// Example 3 with the gate anchored on the last row of each step instead of the first one. The gate of example 3 is
+// written from the row of a, looking forward; this one is written from the row of c, looking backward:
+//
+//   example 3                               this example
+//   advice | s                              advice | s
+//  ------------                            ------------
+//   1      | 1   a = cur                    1      | 0
+//   1      | 1   b = next                   1      | 0
+//   2      | 1   c = Rotation(2)            2      | 1   a = Rotation(-2), b = prev, c = cur
+//   ...    |                                ...    | 1
+//   34     | 0                              34     | 1
+//   55     | 0                              55     | 1
+//
+// Both gates enforce the same relation on the same table: where the gate is anchored is a design choice, and the
+// selector follows it. Here it is enabled on rows 2..n instead of 0..n-2, so the row where the selector is on is the
+// row that the step computes. That is convenient when something else must happen on that row, e.g. a lookup or a
+// range check on the new value, which can then use the same selector.
+//
+// Enabling the selector of this gate as in example 3 makes it query rows -2 and -1 on row 0. Rotations wrap around
+// the 2^k rows of the circuit, so it reads the last rows, which are the blinding rows, and the MockProver rejects it
+// ("active on an unusable row"). Even without the wrap around it would be wrong: the last two terms would be left
+// unconstrained.
+
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub advice: Column<Advice>,
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FiboConfig {
+        let advice = meta.advice_column();
+        let selector = meta.selector();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(advice, Rotation(-2));
+            let b = meta.query_advice(advice, Rotation::prev());
+            let c = meta.query_advice(advice, Rotation::cur());
+            vec![s * (a + b - c)]
+        });
+
+        FiboConfig { advice, selector, instance }
+    }
+
+    // The selector is enabled on the rows of c, 2..nrows, or of a, 0..nrows-2, when `anchor_on_a` is set (the
+    // mistake described at the top of the file). Returns the cell of the last term
+    pub fn assign(&self, mut layouter: impl Layouter<F>, nrows: usize, anchor_on_a: bool) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(|| "entire fibonacci table", |mut region| {
+            let mut a_cell = region.assign_advice_from_instance(|| "a", config.instance, 0, config.advice, 0)?;
+            let mut b_cell = region.assign_advice_from_instance(|| "b", config.instance, 1, config.advice, 1)?;
+
+            for row in 2..nrows {
+                let selector_row = if anchor_on_a { row - 2 } else { row };
+                config.selector.enable(&mut region, selector_row)?;
+                let c = a_cell.value().zip(b_cell.value()).map(|(a, b)| *a + b);
+                let c_cell = region.assign_advice(|| "c", config.advice, row, || c.ok_or(Error::Synthesis))?;
+                a_cell = b_cell;
+                b_cell = c_cell;
+            }
+            Ok(b_cell)
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default)]
+struct MyCircuit<F> {
+    pub anchor_on_a: bool,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { anchor_on_a: self.anchor_on_a, _marker: PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        let out = chip.assign(layouter.namespace(|| "entire table"), 10, self.anchor_on_a)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 2)
+    }
+}
+
+fn main() {
+    let k = 4;
+    let public_inputs = vec![Fp::one(), Fp::one(), Fp::from(55)];
+
+    let circuit = MyCircuit::<Fp>::default();
+    let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
+    prover.expect_satisfied();
+    println!("the gate anchored on c, with the selector on rows 2..10, accepts F(10) = 55");
+
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::one(), Fp::one(), Fp::from(56)]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // The selector enabled on rows 0..8, as for the gate of example 3
+    let circuit = MyCircuit::<Fp> { anchor_on_a: true, _marker: PhantomData };
+    let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+    assert!(prover.verify().is_err());
+    println!("with the selector on rows 0..8 the gate reads the blinding rows and fails");
+}
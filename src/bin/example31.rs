@@ -0,0 +1,174 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{floor_planner::V1, *},
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::{report::ExpectSatisfied, stats::{circuit_stats, CircuitStats}};
+
+// The same circuit with two floor planners. The floor planner decides the absolute row where each region starts:
+// - SimpleFloorPlanner places the regions in the order they are assigned, each one starting below the last row used
+//   so far in the columns it uses. It never goes back to rows left free above
+// - floor_planner::V1 first measures the shape of every region (the columns it uses and its number of rows), then
+//   places the regions from the largest to the smallest, each at the first rows where all its columns are free
+//
+// The circuit computes 16 terms of Fibonacci in two tables of 9 terms, one in column x and one in column y. Between
+// them, a checkpoint region copies the last two terms of the first table onto one row of both columns, and the
+// second table starts again from these two terms:
+//
+//   SimpleFloorPlanner             V1
+//   x          | y                 x          | y
+//  ---------------------------    ---------------------------
+//   fib x      |                   fib x      | fib y           rows 0..9
+//   ...        |                   ...        | ...
+//   checkpoint | checkpoint        checkpoint | checkpoint      row 9
+//              | fib y                                          rows 10..19 with SimpleFloorPlanner only
+//              | ...
+//
+// The checkpoint uses both columns, so SimpleFloorPlanner places it below the first table, and the second table below
+// it. V1 places both tables first, side by side, and the checkpoint below them: the circuit takes 10 rows instead of
+// 19 and fits in 2^4 rows instead of 2^5. Copy constraints don't care where the cells are, so the circuit is the
+// same. The price is the measurement pass of V1, which runs the synthesis of every region one more time.
+//
+// main prints the placement of each region for both planners, using the statistics of src/stats.rs.
+
+#[derive(Debug, Clone, Copy)]
+struct FiboConfig {
+    pub advice: Column<Advice>,
+    pub selector: Selector,
+}
+
+// The first two terms of a table: private values, or copies of cells assigned elsewhere
+enum Start<'a, F: FieldExt> {
+    Private(Option<F>, Option<F>),
+    Copy(&'a AssignedCell<F, F>, &'a AssignedCell<F, F>),
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: Column<Advice>) -> FiboConfig {
+        let selector = meta.selector();
+        meta.enable_equality(advice);
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(advice, Rotation::cur());
+            let b = meta.query_advice(advice, Rotation::next());
+            let c = meta.query_advice(advice, Rotation(2));
+            vec![s * (a + b - c)]
+        });
+
+        FiboConfig { advice, selector }
+    }
+
+    // Returns the cells of the last two terms
+    pub fn assign(&self, mut layouter: impl Layouter<F>, start: Start<F>, nrows: usize) -> Result<[AssignedCell<F, F>; 2], Error> {
+        let config = &self.config;
+
+        layouter.assign_region(|| "fibonacci", |mut region| {
+            let (mut a_cell, mut b_cell) = match start {
+                Start::Private(a, b) => (
+                    region.assign_advice(|| "a", config.advice, 0, || a.ok_or(Error::Synthesis))?,
+                    region.assign_advice(|| "b", config.advice, 1, || b.ok_or(Error::Synthesis))?,
+                ),
+                Start::Copy(a, b) => (
+                    a.copy_advice(|| "a", &mut region, config.advice, 0)?,
+                    b.copy_advice(|| "b", &mut region, config.advice, 1)?,
+                ),
+            };
+
+            for row in 2..nrows {
+                config.selector.enable(&mut region, row - 2)?;
+                let c = a_cell.value().zip(b_cell.value()).map(|(a, b)| *a + b);
+                let c_cell = region.assign_advice(|| "c", config.advice, row, || c.ok_or(Error::Synthesis))?;
+                a_cell = b_cell;
+                b_cell = c_cell;
+            }
+            Ok([a_cell, b_cell])
+        })
+    }
+}
+
+// The floor planner is a type parameter, so that both versions of the circuit share everything else
+#[derive(Default)]
+struct MyCircuit<F, P> {
+    pub a: Option<F>,
+    pub b: Option<F>,
+    _marker: PhantomData<P>,
+}
+
+impl<F: FieldExt, P: FloorPlanner> MyCircuit<F, P> {
+    fn new(a: F, b: F) -> Self {
+        Self { a: Some(a), b: Some(b), _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt, P: FloorPlanner> Circuit<F> for MyCircuit<F, P> {
+    type Config = (FiboConfig, FiboConfig, Column<Instance>);
+    type FloorPlanner = P;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: None, b: None, _marker: PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let x = meta.advice_column();
+        let y = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (FiboChip::configure(meta, x), FiboChip::configure(meta, y), instance)
+    }
+
+    fn synthesize(&self, (x, y, instance): Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let [a, b] = FiboChip::construct(x).assign(layouter.namespace(|| "fib x"), Start::Private(self.a, self.b), 9)?;
+        let [a, b] = layouter.assign_region(|| "checkpoint", |mut region| {
+            Ok([a.copy_advice(|| "a", &mut region, x.advice, 0)?, b.copy_advice(|| "b", &mut region, y.advice, 0)?])
+        })?;
+        let [_, out] = FiboChip::construct(y).assign(layouter.namespace(|| "fib y"), Start::Copy(&a, &b), 9)?;
+        layouter.constrain_instance(out.cell(), instance, 0)
+    }
+}
+
+type SimpleCircuit = MyCircuit<Fp, SimpleFloorPlanner>;
+type V1Circuit = MyCircuit<Fp, V1>;
+
+fn report(planner: &str, stats: &CircuitStats) {
+    println!("{}: {} rows", planner, stats.rows());
+    for region in stats.regions.iter().filter(|region| region.rows() > 0) {
+        println!("  {:<12} rows {:>2}..{:>2}", region.name, region.first_row.unwrap_or(0), region.last_row.unwrap_or(0) + 1);
+    }
+}
+
+fn main() {
+    // F(16) = 987
+    let public_inputs = vec![Fp::from(987)];
+    let simple = SimpleCircuit::new(Fp::one(), Fp::one());
+    let v1 = V1Circuit::new(Fp::one(), Fp::one());
+    let simple_stats = circuit_stats(&simple, vec![public_inputs.clone()]).unwrap();
+    let v1_stats = circuit_stats(&v1, vec![public_inputs.clone()]).unwrap();
+    report("SimpleFloorPlanner", &simple_stats);
+    report("V1", &v1_stats);
+    assert_eq!((simple_stats.rows(), v1_stats.rows()), (19, 10));
+
+    // Both are correct, but only the V1 version fits in 2^4 rows
+    MockProver::run(5, &simple, vec![public_inputs.clone()]).unwrap().expect_satisfied();
+    MockProver::run(4, &v1, vec![public_inputs.clone()]).unwrap().expect_satisfied();
+    assert!(MockProver::run(4, &simple, vec![public_inputs.clone()]).is_err());
+
+    let prover = MockProver::run(4, &v1, vec![vec![Fp::from(986)]]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,208 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// Two chips sharing the same advice columns, as in example 2 the columns are created by the circuit and passed to the
+// `configure` of each chip. Each chip only adds its own selector and its own gate:
+//
+//   a     | b     | c     | s_add | s_mul
+//  ---------------------------------------
+//   x     | y     |       | 0     | 0       "inputs"
+//   x     | y     | x + y | 1     | 0       AddChip: s_add * (a + b - c)
+//   x + y | x     | p     | 0     | 1       MulChip: s_mul * (a * b - c)
+//   p     | y     | out   | 1     | 0       AddChip
+//
+// The circuit proves out = (x + y) * x + y. Each operation is a region of its own, and the result of one chip gets
+// to the next one through a copy constraint: `copy_advice` assigns the cell in the new region and constrains it to be
+// equal to the cell of the other region. The gates only constrain the cells of their own row, so the copies are the
+// only thing linking the chips. Without them each chip proves its own operation on whatever inputs the prover
+// chooses: with `forged_sum` set, the multiplication starts from a fresh private cell instead of the output of the
+// addition, and the circuit accepts a wrong output.
+
+#[derive(Debug, Clone)]
+struct AddConfig {
+    pub advice: [Column<Advice>; 3],
+    pub selector: Selector,
+}
+
+struct AddChip<F: FieldExt> {
+    config: AddConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> AddChip<F> {
+    pub fn construct(config: AddConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3]) -> AddConfig {
+        let selector = meta.selector();
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let c = meta.query_advice(advice[2], Rotation::cur());
+            vec![s * (a + b - c)]
+        });
+
+        AddConfig { advice, selector }
+    }
+
+    // Copies a and b into a new region and returns the cell of a + b
+    pub fn add(&self, mut layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(|| "add", |mut region| {
+            config.selector.enable(&mut region, 0)?;
+            let a = a.copy_advice(|| "a", &mut region, config.advice[0], 0)?;
+            let b = b.copy_advice(|| "b", &mut region, config.advice[1], 0)?;
+            let c = a.value().zip(b.value()).map(|(a, b)| *a + b);
+            region.assign_advice(|| "a + b", config.advice[2], 0, || c.ok_or(Error::Synthesis))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MulConfig {
+    pub advice: [Column<Advice>; 3],
+    pub selector: Selector,
+}
+
+struct MulChip<F: FieldExt> {
+    config: MulConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> MulChip<F> {
+    pub fn construct(config: MulConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3]) -> MulConfig {
+        let selector = meta.selector();
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("mul", |meta| {
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let c = meta.query_advice(advice[2], Rotation::cur());
+            vec![s * (a * b - c)]
+        });
+
+        MulConfig { advice, selector }
+    }
+
+    // Copies a and b into a new region and returns the cell of a * b
+    pub fn mul(&self, mut layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(|| "mul", |mut region| {
+            config.selector.enable(&mut region, 0)?;
+            let a = a.copy_advice(|| "a", &mut region, config.advice[0], 0)?;
+            let b = b.copy_advice(|| "b", &mut region, config.advice[1], 0)?;
+            let c = a.value().zip(b.value()).map(|(a, b)| *a * b);
+            region.assign_advice(|| "a * b", config.advice[2], 0, || c.ok_or(Error::Synthesis))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MyConfig {
+    pub advice: [Column<Advice>; 3],
+    pub instance: Column<Instance>,
+    pub add: AddConfig,
+    pub mul: MulConfig,
+}
+
+#[derive(Default)]
+struct MyCircuit<F> {
+    pub x: Option<F>,
+    pub y: Option<F>,
+    // When set, the multiplication reads this value instead of a copy of x + y (the mistake described at the top)
+    pub forged_sum: Option<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = MyConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { x: None, y: None, forged_sum: self.forged_sum.map(|_| F::zero()) }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        // The columns belong to the circuit, the chips share them
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        let add = AddChip::configure(meta, advice);
+        let mul = MulChip::configure(meta, advice);
+        MyConfig { advice, instance, add, mul }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let add_chip = AddChip::construct(config.add.clone());
+        let mul_chip = MulChip::construct(config.mul.clone());
+
+        let (x, y) = layouter.assign_region(|| "inputs", |mut region| {
+            let x = region.assign_advice(|| "x", config.advice[0], 0, || self.x.ok_or(Error::Synthesis))?;
+            let y = region.assign_advice(|| "y", config.advice[1], 0, || self.y.ok_or(Error::Synthesis))?;
+            Ok((x, y))
+        })?;
+
+        let sum = add_chip.add(layouter.namespace(|| "x + y"), &x, &y)?;
+        let sum = match self.forged_sum {
+            // A fresh cell, not linked to the output of the AddChip
+            Some(forged) => layouter.assign_region(|| "forged sum", |mut region| {
+                region.assign_advice(|| "sum", config.advice[0], 0, || Ok(forged))
+            })?,
+            None => sum,
+        };
+        let product = mul_chip.mul(layouter.namespace(|| "(x + y) * x"), &sum, &x)?;
+        let out = add_chip.add(layouter.namespace(|| "(x + y) * x + y"), &product, &y)?;
+
+        layouter.constrain_instance(out.cell(), config.instance, 0)
+    }
+}
+
+fn main() {
+    let k = 4;
+    let (x, y) = (Fp::from(2), Fp::from(3));
+
+    // (2 + 3) * 2 + 3 = 13
+    let circuit = MyCircuit { x: Some(x), y: Some(y), forged_sum: None };
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(13)]]).unwrap();
+    prover.expect_satisfied();
+    println!("AddChip and MulChip over the same columns prove (2 + 3) * 2 + 3 = 13");
+
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(15)]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // The multiplication starts from 6 instead of the output of the AddChip: every gate holds, and the circuit
+    // accepts (2 + 3) * 2 + 3 = 15
+    let circuit = MyCircuit { x: Some(x), y: Some(y), forged_sum: Some(Fp::from(6)) };
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(15)]]).unwrap();
+    prover.expect_satisfied();
+    println!("without the copy between the chips, the circuit also accepts 15");
+}
@@ -0,0 +1,168 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::{counter::{CounterChip, CounterConfig}, report::ExpectSatisfied};
+
+// Example 3 with the length n of the sequence in the instance column: [a, b, n, out].
+//
+// In example 3 the number of terms is only the number of rows of the table, which is fixed by the circuit (and so by
+// its keys) but appears nowhere in the public inputs. The verifier has to know which circuit the key was generated
+// for: nothing in the proof says that 55 is the 10th term rather than the 7th. Here the counter of example 12 (the
+// CounterChip of the library) counts the terms next to the sequence, and its last cell is copied to the instance column:
+//
+//   fib | counter | s_add | q_inc      instance
+//  -------------------------------     ----------
+//   a   | 1       | 1     | 1          a
+//   b   | 2       | 1     | 1          b
+//   2   | 3       | 1     | 1          n
+//   ... | ...     | ...   | ...        out
+//   34  | 9       | 0     | 1
+//   55  | 10      | 0     | 0
+//
+// - the counter starts at the constant 1 and its gate constrains counter(next) = counter(cur) + 1 on every row but
+//   the last, so the row holding counter = i holds the i-th term
+// - the last fib cell is copied to `out` and the last counter cell to `n`
+// Unlike example 12, where a row is a step and the counter counts steps, a row here is a term: the same chip counts
+// whatever the rows of the table are. A proof for the table of 10 terms only verifies with n = 10: claiming that 55,
+// or any other value, is the 7th term fails, whatever the witness. The structure of the table still decides n;
+// example 34 shows how to prove a private n, and example 35 how to serve several values of n with the same keys.
+
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub fib: Column<Advice>,
+    pub counter: CounterConfig,
+    pub s_add: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FiboConfig {
+        let fib = meta.advice_column();
+        let counter = meta.advice_column();
+        let s_add = meta.selector();
+        let instance = meta.instance_column();
+        let counter = CounterChip::configure(meta, counter, instance);
+
+        meta.enable_equality(fib);
+        meta.enable_equality(instance);
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(s_add);
+            let a = meta.query_advice(fib, Rotation::cur());
+            let b = meta.query_advice(fib, Rotation::next());
+            let c = meta.query_advice(fib, Rotation(2));
+            vec![s * (a + b - c)]
+        });
+
+        FiboConfig { fib, counter, s_add, instance }
+    }
+
+    // a and b are copied from rows 0 and 1 of the instance column. Returns the cells of the last term and of its index
+    #[allow(clippy::type_complexity)]
+    pub fn assign(&self, mut layouter: impl Layouter<F>, nrows: usize) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let config = &self.config;
+
+        layouter.assign_region(|| "entire fibonacci table", |mut region| {
+            let n_cell = CounterChip::construct(config.counter).assign(&mut region, 0, nrows)?;
+
+            let mut a_cell = region.assign_advice_from_instance(|| "a", config.instance, 0, config.fib, 0)?;
+            let mut b_cell = region.assign_advice_from_instance(|| "b", config.instance, 1, config.fib, 1)?;
+            for row in 2..nrows {
+                config.s_add.enable(&mut region, row - 2)?;
+                let c = a_cell.value().zip(b_cell.value()).map(|(a, b)| *a + b);
+                let c_cell = region.assign_advice(|| "c", config.fib, row, || c.ok_or(Error::Synthesis))?;
+                a_cell = b_cell;
+                b_cell = c_cell;
+            }
+            Ok((b_cell, n_cell))
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+struct MyCircuit<F> {
+    pub nrows: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> MyCircuit<F> {
+    fn new(nrows: usize) -> Self {
+        Self { nrows, _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.nrows)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        let (out, n) = chip.assign(layouter.namespace(|| "entire table"), self.nrows)?;
+        chip.expose_public(layouter.namespace(|| "n"), &n, 2)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 3)
+    }
+}
+
+// The public inputs by name, in the order of the instance column [a, b, n, out]
+struct PublicInputs {
+    a: Fp,
+    b: Fp,
+    n: u64,
+    out: u64,
+}
+
+impl PublicInputs {
+    fn to_instance_columns(&self) -> Vec<Vec<Fp>> {
+        vec![vec![self.a, self.b, Fp::from(self.n), Fp::from(self.out)]]
+    }
+}
+
+fn main() {
+    let k = 4;
+    let public_inputs = |n, out| PublicInputs { a: Fp::one(), b: Fp::one(), n, out }.to_instance_columns();
+
+    let circuit = MyCircuit::<Fp>::new(10);
+    MockProver::run(k, &circuit, public_inputs(10, 55)).unwrap().expect_satisfied();
+    println!("the table of 10 terms proves n = 10, out = 55");
+
+    // The same table can't pass for a shorter sequence, whether the output is the real one or the 7th term
+    for (n, out) in [(7, 55), (7, 13)] {
+        let prover = MockProver::run(k, &circuit, public_inputs(n, out)).unwrap();
+        assert!(prover.verify().is_err());
+    }
+    println!("and fails for n = 7, with out = 55 or out = 13");
+
+    // The 7th term needs a table of 7 terms
+    let circuit = MyCircuit::<Fp>::new(7);
+    MockProver::run(k, &circuit, public_inputs(7, 13)).unwrap().expect_satisfied();
+    println!("the table of 7 terms proves n = 7, out = 13");
+}
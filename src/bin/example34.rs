@@ -0,0 +1,222 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// Proves "I know n such that F(n) = y" with n private and y public: an existential statement over the sequence.
+//
+// The table always holds the first MAX_TERMS terms, starting from the constants 1, 1, so its shape doesn't depend on
+// n. Next to it, the prover fills a flag column with 1 on the row of the n-th term and 0 elsewhere, and two running
+// sums accumulate the flags and the flagged terms:
+//
+//   fib | flag | count | out      count(0) = flag(0)            count(i) = count(i-1) + flag(i)
+//  ------------------------      out(0) = flag(0) * fib(0)     out(i) = out(i-1) + flag(i) * fib(i)
+//   1   | 0    | 0     | 0
+//   1   | 0    | 0     | 0       flag(i) * (1 - flag(i)) = 0 on every row
+//   2   | 0    | 0     | 0
+//   ... |      |       |         count(last) = 1          (a constant)
+//   55  | 1    | 1     | 55      out(last) = y            (the instance)
+//   89  | 0    | 1     | 55
+//   ... |      |       |
+//
+// The flags are booleans and sum to 1, so exactly one row is flagged, and out(last) is the term on that row. The
+// verifier learns y, but not which row was flagged. Without the copy of count(last) to 1, a prover could flag no row
+// and prove y = 0, or flag two rows and prove the sum of two terms.
+
+const MAX_TERMS: usize = 20;
+
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub fib: Column<Advice>,
+    pub flag: Column<Advice>,
+    pub count: Column<Advice>,
+    pub out: Column<Advice>,
+    pub s_add: Selector,
+    pub s_first: Selector,
+    pub s_acc: Selector,
+    pub s_bool: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FiboConfig {
+        let fib = meta.advice_column();
+        let flag = meta.advice_column();
+        let count = meta.advice_column();
+        let out = meta.advice_column();
+        let s_add = meta.selector();
+        let s_first = meta.selector();
+        let s_acc = meta.selector();
+        let s_bool = meta.selector();
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+
+        for column in [fib, count, out] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(s_add);
+            let a = meta.query_advice(fib, Rotation::cur());
+            let b = meta.query_advice(fib, Rotation::next());
+            let c = meta.query_advice(fib, Rotation(2));
+            vec![s * (a + b - c)]
+        });
+
+        meta.create_gate("boolean flag", |meta| {
+            let s = meta.query_selector(s_bool);
+            let flag = meta.query_advice(flag, Rotation::cur());
+            vec![s * flag.clone() * (Expression::Constant(F::one()) - flag)]
+        });
+
+        meta.create_gate("first row", |meta| {
+            let s = meta.query_selector(s_first);
+            let fib = meta.query_advice(fib, Rotation::cur());
+            let flag = meta.query_advice(flag, Rotation::cur());
+            let count = meta.query_advice(count, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+            vec![s.clone() * (count - flag.clone()), s * (out - flag * fib)]
+        });
+
+        meta.create_gate("accumulate", |meta| {
+            let s = meta.query_selector(s_acc);
+            let fib = meta.query_advice(fib, Rotation::next());
+            let flag = meta.query_advice(flag, Rotation::next());
+            let count_prev = meta.query_advice(count, Rotation::cur());
+            let count = meta.query_advice(count, Rotation::next());
+            let out_prev = meta.query_advice(out, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::next());
+            vec![s.clone() * (count - count_prev - flag.clone()), s * (out - out_prev - flag * fib)]
+        });
+
+        FiboConfig { fib, flag, count, out, s_add, s_first, s_acc, s_bool, instance }
+    }
+
+    // Assigns the table with the flags of `flagged` (term numbers, from 1) set. Returns the cells of the last row of
+    // count and out
+    #[allow(clippy::type_complexity)]
+    pub fn assign(&self, mut layouter: impl Layouter<F>, flagged: Option<&[usize]>) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let config = &self.config;
+
+        layouter.assign_region(|| "entire fibonacci table", |mut region| {
+            let mut fib = vec![
+                region.assign_advice_from_constant(|| "a", config.fib, 0, F::one())?,
+                region.assign_advice_from_constant(|| "b", config.fib, 1, F::one())?,
+            ];
+            for row in 2..MAX_TERMS {
+                config.s_add.enable(&mut region, row - 2)?;
+                let c = fib[row - 2].value().zip(fib[row - 1].value()).map(|(a, b)| *a + b);
+                fib.push(region.assign_advice(|| "c", config.fib, row, || c.ok_or(Error::Synthesis))?);
+            }
+
+            config.s_first.enable(&mut region, 0)?;
+            let mut count = Some(F::zero());
+            let mut out = Some(F::zero());
+            let mut cells = None;
+            for (row, fib) in fib.iter().enumerate() {
+                config.s_bool.enable(&mut region, row)?;
+                if row > 0 {
+                    config.s_acc.enable(&mut region, row - 1)?;
+                }
+                let flag = flagged.map(|flagged| if flagged.contains(&(row + 1)) { F::one() } else { F::zero() });
+                count = count.zip(flag).map(|(count, flag)| count + flag);
+                out = out.zip(flag).zip(fib.value()).map(|((out, flag), fib)| out + flag * fib);
+                region.assign_advice(|| "flag", config.flag, row, || flag.ok_or(Error::Synthesis))?;
+                let count_cell = region.assign_advice(|| "count", config.count, row, || count.ok_or(Error::Synthesis))?;
+                let out_cell = region.assign_advice(|| "out", config.out, row, || out.ok_or(Error::Synthesis))?;
+                cells = Some((count_cell, out_cell));
+            }
+            Ok(cells.unwrap())
+        })
+    }
+
+    // Exactly one flag: the last count must be the constant 1
+    pub fn constrain_one_flag(&self, mut layouter: impl Layouter<F>, count: &AssignedCell<F, F>) -> Result<(), Error> {
+        let config = &self.config;
+        layouter.assign_region(|| "one flag", |mut region| {
+            let one = region.assign_advice_from_constant(|| "one", config.count, 0, F::one())?;
+            region.constrain_equal(one.cell(), count.cell())
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default)]
+struct MyCircuit<F> {
+    // The term numbers flagged by the prover: one for an honest prover, who knows n
+    pub flagged: Option<Vec<usize>>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> MyCircuit<F> {
+    fn new(flagged: Vec<usize>) -> Self {
+        Self { flagged: Some(flagged), _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        let (count, out) = chip.assign(layouter.namespace(|| "entire table"), self.flagged.as_deref())?;
+        chip.constrain_one_flag(layouter.namespace(|| "one flag"), &count)?;
+        chip.expose_public(layouter.namespace(|| "y"), &out, 0)
+    }
+}
+
+fn main() {
+    let k = 5;
+
+    // The prover knows that F(10) = 55; the verifier only sees 55
+    let circuit = MyCircuit::<Fp>::new(vec![10]);
+    MockProver::run(k, &circuit, vec![vec![Fp::from(55)]]).unwrap().expect_satisfied();
+    println!("proved that some n <= {} has F(n) = 55, without revealing n", MAX_TERMS);
+
+    // 56 is not a Fibonacci number: no choice of n works
+    for n in 1..=MAX_TERMS {
+        let prover = MockProver::run(k, &MyCircuit::<Fp>::new(vec![n]), vec![vec![Fp::from(56)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+    println!("no n <= {} has F(n) = 56", MAX_TERMS);
+
+    // F(9) + F(5) = 34 + 5 = 39, and no flag at all gives 0: both are rejected by the count
+    for flagged in [vec![9, 5], vec![]] {
+        let y: u64 = if flagged.is_empty() { 0 } else { 39 };
+        let prover = MockProver::run(k, &MyCircuit::<Fp>::new(flagged), vec![vec![Fp::from(y)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+    println!("flagging two rows, or none, fails");
+}
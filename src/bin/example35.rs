@@ -0,0 +1,205 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    pasta::{EqAffine, Fp}, dev::MockProver,
+    plonk::*,
+    poly::{commitment::Params, Rotation},
+};
+use halo2_fibonacci_ex::{prover::{self, fingerprint, CancellationToken}, report::ExpectSatisfied};
+
+// One circuit, and so one pair of keys, for every length n up to MAX_TERMS. The instance column is [a, b, n, out], as
+// in example 33.
+//
+// The table always has MAX_TERMS rows. Turning the selector off after the n-th term is not an option: selectors are
+// fixed columns, so they are part of the keys, and each n would get its own keys. Instead the selectors are enabled
+// on every row and an advice column `active`, chosen by the prover, switches the gate between the recurrence and a
+// copy of the previous term:
+//
+//   fib | active | idx        c = b + active * a    on rows 2..MAX_TERMS (a, b, c = rows -2, -1, 0)
+//  ---------------------     idx(i) = idx(i-1) + active(i)
+//   1   | 1      | 1
+//   1   | 1      | 2         active(1) = 1, idx(0) = 1    (constants)
+//   2   | 1      | 3         active(i) * (1 - active(i)) = 0
+//   ... |        |           active(i) * (1 - active(i-1)) = 0
+//   55  | 1      | 10
+//   55  | 0      | 10        padding: c = b
+//   ... |        |
+//   55  | 0      | 10        idx(last) = n, fib(last) = out    (the instance)
+//
+// active is boolean and can only go from 1 to 0, so the rows with active = 1 are the first ones, the last of them is
+// the n-th term, and the padding rows carry it forward to the last row. idx counts the active rows, like the counter
+// column of example 33, so the padding can't start earlier or later than the claimed n. The price is MAX_TERMS rows
+// whatever n, and one multiplication more in the gate.
+
+const MAX_TERMS: usize = 20;
+
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub fib: Column<Advice>,
+    pub active: Column<Advice>,
+    pub idx: Column<Advice>,
+    pub s_step: Selector,
+    pub s_active: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FiboConfig {
+        let fib = meta.advice_column();
+        let active = meta.advice_column();
+        let idx = meta.advice_column();
+        let s_step = meta.selector();
+        let s_active = meta.selector();
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+
+        for column in [fib, active, idx] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        // Anchored on the row of c, as in example 30
+        meta.create_gate("step or pad", |meta| {
+            let s = meta.query_selector(s_step);
+            let a = meta.query_advice(fib, Rotation(-2));
+            let b = meta.query_advice(fib, Rotation::prev());
+            let c = meta.query_advice(fib, Rotation::cur());
+            let active = meta.query_advice(active, Rotation::cur());
+            vec![s * (c - b - active * a)]
+        });
+
+        meta.create_gate("active prefix", |meta| {
+            let s = meta.query_selector(s_active);
+            let one = Expression::Constant(F::one());
+            let active_prev = meta.query_advice(active, Rotation::prev());
+            let active = meta.query_advice(active, Rotation::cur());
+            let idx_prev = meta.query_advice(idx, Rotation::prev());
+            let idx = meta.query_advice(idx, Rotation::cur());
+            vec![
+                s.clone() * active.clone() * (one.clone() - active.clone()),
+                s.clone() * active.clone() * (one - active_prev),
+                s * (idx - idx_prev - active),
+            ]
+        });
+
+        FiboConfig { fib, active, idx, s_step, s_active, instance }
+    }
+
+    // The first n terms, then copies of the n-th. Returns the cells of the last term and of the last index
+    #[allow(clippy::type_complexity)]
+    pub fn assign(&self, mut layouter: impl Layouter<F>, n: Option<usize>) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let config = &self.config;
+
+        layouter.assign_region(|| "entire fibonacci table", |mut region| {
+            let mut a_cell = region.assign_advice_from_instance(|| "a", config.instance, 0, config.fib, 0)?;
+            let mut b_cell = region.assign_advice_from_instance(|| "b", config.instance, 1, config.fib, 1)?;
+            // Row 0 only matters as the predecessor of row 1, which must be active: n is at least 2
+            region.assign_advice(|| "active", config.active, 0, || Ok(F::one()))?;
+            region.assign_advice_from_constant(|| "active", config.active, 1, F::one())?;
+            let mut idx_cell = region.assign_advice_from_constant(|| "idx", config.idx, 0, F::one())?;
+
+            for row in 1..MAX_TERMS {
+                config.s_active.enable(&mut region, row)?;
+                let active = n.map(|n| if row < n { F::one() } else { F::zero() });
+                if row > 1 {
+                    region.assign_advice(|| "active", config.active, row, || active.ok_or(Error::Synthesis))?;
+                }
+                let idx = idx_cell.value().zip(active).map(|(idx, active)| *idx + active);
+                idx_cell = region.assign_advice(|| "idx", config.idx, row, || idx.ok_or(Error::Synthesis))?;
+
+                if row > 1 {
+                    config.s_step.enable(&mut region, row)?;
+                    let c = a_cell.value().zip(b_cell.value()).zip(active).map(|((a, b), active)| *b + active * a);
+                    let c_cell = region.assign_advice(|| "c", config.fib, row, || c.ok_or(Error::Synthesis))?;
+                    a_cell = b_cell;
+                    b_cell = c_cell;
+                }
+            }
+            Ok((b_cell, idx_cell))
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default)]
+struct MyCircuit<F> {
+    pub n: Option<usize>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> MyCircuit<F> {
+    fn new(n: usize) -> Self {
+        Self { n: Some(n), _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        let (out, n) = chip.assign(layouter.namespace(|| "entire table"), self.n)?;
+        chip.expose_public(layouter.namespace(|| "n"), &n, 2)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 3)
+    }
+}
+
+fn main() {
+    let k = 5;
+    let public_inputs = |n: u64, out: u64| vec![Fp::one(), Fp::one(), Fp::from(n), Fp::from(out)];
+
+    for (n, out) in [(2, 1), (10, 55), (20, 6765)] {
+        MockProver::run(k, &MyCircuit::<Fp>::new(n), vec![public_inputs(n as u64, out)]).unwrap().expect_satisfied();
+    }
+    println!("the same table proves F(2) = 1, F(10) = 55 and F(20) = 6765");
+
+    // The padding must start right after the claimed n: F(10) can't pass for the 12th term, nor F(12) for the 10th
+    let prover = MockProver::run(k, &MyCircuit::<Fp>::new(10), vec![public_inputs(12, 55)]).unwrap();
+    assert!(prover.verify().is_err());
+    let prover = MockProver::run(k, &MyCircuit::<Fp>::new(12), vec![public_inputs(10, 144)]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // The keys don't depend on n: every circuit gives the verifying key of the circuit without witnesses
+    let params = Params::<EqAffine>::new(k);
+    let vk = keygen_vk(&params, &MyCircuit::<Fp>::default()).unwrap();
+    for n in [10, 15] {
+        assert_eq!(fingerprint(&keygen_vk(&params, &MyCircuit::<Fp>::new(n)).unwrap()), fingerprint(&vk));
+    }
+    println!("verifying key for every n: {}", fingerprint(&vk));
+
+    // So proofs for different lengths verify with the same key
+    let token = CancellationToken::new();
+    for (n, out) in [(10, 55), (15, 610)] {
+        let instance = public_inputs(n, out);
+        let proof = prover::prove(k, &MyCircuit::<Fp>::new(n as usize), &instance, &token).unwrap();
+        prover::verify_with_vk(&params, &vk, &instance, &proof).unwrap();
+    }
+    println!("proofs for n = 10 and n = 15 verify with it");
+}
@@ -0,0 +1,41 @@
+use halo2_proofs::{dev::MockProver, pasta::Fp};
+use num_bigint::BigUint;
+use halo2_fibonacci_ex::{
+    fibonacci::{first_reduction, integer_out, max_steps, modulus, to_biguint, FibInputs},
+    report::ExpectSatisfied,
+};
+
+// The circuits compute Fibonacci in the field, not over the integers. Every cell holds an element of Fp, so every
+// addition is reduced modulo p (about 2^254 for the Pasta curves): as long as the terms are smaller than p nothing
+// shows, but after 366 steps from (1, 1) the output is F(368) > p, and the circuit proves F(368) mod p.
+//
+// Nothing in the proof tells the two apart: the output in the instance column is a field element, and the proof is
+// just as valid for the reduced value as for any other step. When the integer result matters, the statement must
+// bound the terms itself, e.g. with range checks (example 37). Here the library computes the integer output with
+// num-bigint and compares it with the field output, step by step around the first reduction.
+
+fn main() {
+    let one = BigUint::from(1u8);
+    let p = modulus::<Fp>();
+    let n = first_reduction::<Fp>(&one, &one).unwrap();
+    println!("p = {}", p);
+    println!("the output first exceeds p after {} steps", n);
+
+    let k = 9;
+    assert!(n < max_steps::<Fp>(k));
+    for steps in [n - 1, n, n + 1] {
+        let inputs = FibInputs::new(Fp::one(), Fp::one(), steps);
+        let field = to_biguint(inputs.public_inputs()[2]);
+        let integer = integer_out(&one, &one, steps);
+
+        // The circuit accepts the output reduced modulo p...
+        MockProver::run(k, &inputs.circuit(), vec![inputs.public_inputs()]).unwrap().expect_satisfied();
+        assert_eq!(field, &integer % &p);
+        println!("{} steps: integer {}", steps, integer);
+        println!("{} steps: field   {}{}", steps, field, if field == integer { "" } else { " (reduced)" });
+    }
+
+    // ...and the larger the inputs, the sooner the reduction
+    let big = &p - &one;
+    println!("from (1, p - 1) the output exceeds p after {} step", first_reduction::<Fp>(&one, &big).unwrap());
+}
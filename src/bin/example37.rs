@@ -0,0 +1,156 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::{bits::{BitsChip, BitsConfig}, report::ExpectSatisfied};
+
+// Example 3 with every term range checked to 64 bits, so that the circuit proves the Fibonacci sequence over the
+// integers rather than modulo p (see example 36).
+//
+// Each term of the table is decomposed into 64 bits by the BitsChip of src/bits.rs: a value that doesn't fit in 64
+// bits has no decomposition, so the proof fails as soon as one term is 2^64 or more. If a and b fit in 64 bits, a + b
+// is below 2^65, far below p, so no addition of the table wraps around the modulus and every term is the integer term.
+// F(93) = 12200160415121876738 is the last Fibonacci number below 2^64: the circuit proves it for a table of 93 terms,
+// and fails for 94.
+//
+// The decompositions cost 65 rows per term, 2^13 rows for 93 terms, against 2^7 for the table alone. A real circuit
+// would use a lookup table of 8 or 16 bits per row instead (as example 22 does for bytes), which is several times
+// cheaper.
+
+const BITS: usize = 64;
+
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub advice: Column<Advice>,
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+    pub bits: BitsConfig,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FiboConfig {
+        let advice = meta.advice_column();
+        let selector = meta.selector();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(advice, Rotation::cur());
+            let b = meta.query_advice(advice, Rotation::next());
+            let c = meta.query_advice(advice, Rotation(2));
+            vec![s * (a + b - c)]
+        });
+
+        let (bit, acc) = (meta.advice_column(), meta.advice_column());
+        let bits = BitsChip::configure(meta, bit, acc);
+        FiboConfig { advice, selector, instance, bits }
+    }
+
+    // Returns the cells of all the terms
+    pub fn assign(&self, mut layouter: impl Layouter<F>, nrows: usize) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(|| "entire fibonacci table", |mut region| {
+            let mut terms = vec![
+                region.assign_advice_from_instance(|| "a", config.instance, 0, config.advice, 0)?,
+                region.assign_advice_from_instance(|| "b", config.instance, 1, config.advice, 1)?,
+            ];
+            for row in 2..nrows {
+                config.selector.enable(&mut region, row - 2)?;
+                let c = terms[row - 2].value().zip(terms[row - 1].value()).map(|(a, b)| *a + b);
+                terms.push(region.assign_advice(|| "c", config.advice, row, || c.ok_or(Error::Synthesis))?);
+            }
+            Ok(terms)
+        })
+    }
+
+    // Every term must fit in 64 bits. The bits themselves aren't needed
+    pub fn range_check(&self, mut layouter: impl Layouter<F>, terms: &[AssignedCell<F, F>]) -> Result<(), Error> {
+        let bits_chip = BitsChip::construct(self.config.bits);
+        for (i, term) in terms.iter().enumerate() {
+            bits_chip.decompose(layouter.namespace(|| format!("term {}", i + 1)), term, BITS)?;
+        }
+        Ok(())
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+struct MyCircuit<F> {
+    pub nrows: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> MyCircuit<F> {
+    fn new(nrows: usize) -> Self {
+        Self { nrows, _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.nrows)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        let terms = chip.assign(layouter.namespace(|| "entire table"), self.nrows)?;
+        chip.range_check(layouter.namespace(|| "range checks"), &terms)?;
+        chip.expose_public(layouter.namespace(|| "out"), terms.last().unwrap(), 2)
+    }
+}
+
+// The n-th term over the integers, None past u64::MAX
+fn fib_u64(n: usize) -> Option<u64> {
+    let (mut a, mut b) = (1u64, 1u64);
+    for _ in 2..n {
+        (a, b) = (b, a.checked_add(b)?);
+    }
+    Some(b)
+}
+
+fn main() {
+    let k = 13;
+
+    let out = fib_u64(93).unwrap();
+    let circuit = MyCircuit::<Fp>::new(93);
+    MockProver::run(k, &circuit, vec![vec![Fp::one(), Fp::one(), Fp::from(out)]]).unwrap().expect_satisfied();
+    println!("F(93) = {} fits in 64 bits", out);
+
+    // F(94) = F(92) + F(93) is still far below p, so the table is fine, but the last term has no 64-bit decomposition
+    assert_eq!(fib_u64(94), None);
+    let out = Fp::from(fib_u64(92).unwrap()) + Fp::from(fib_u64(93).unwrap());
+    let circuit = MyCircuit::<Fp>::new(94);
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::one(), Fp::one(), out]]).unwrap();
+    assert!(prover.verify().is_err());
+    println!("F(94) doesn't, and the proof fails");
+}
@@ -0,0 +1,174 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// An under-constrained circuit: example 3 with an off-by-one in the loop enabling the selector. The gate spans 3 rows,
+// so it must be enabled on rows 0..n-2; the buggy version stops one row earlier:
+//
+//   advice | s (fixed) | s (buggy)
+//  --------------------------------
+//   1      | 1         | 1
+//   ...    | ...       | ...
+//   13     | 1         | 1
+//   21     | 1         | 0        <- off in the buggy version, so nothing constrains 55 = 21 + 34
+//   34     | 0         | 0
+//   55     | 0         | 0
+//
+// With honest witnesses both versions behave the same, which is why such bugs survive: the MockProver only checks the
+// witness it is given. The last term is free in the buggy version, and a prover that assigns 56 to it proves
+// F(10) = 56.
+//
+// The detection below is a small mutation test: for each row of the table, change the witness on that row, expose
+// whatever output the changed table has, and check that the MockProver rejects it. A row where the changed witness is
+// accepted is a cell that no constraint pins down.
+
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub advice: Column<Advice>,
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FiboConfig {
+        let advice = meta.advice_column();
+        let selector = meta.selector();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(advice, Rotation::cur());
+            let b = meta.query_advice(advice, Rotation::next());
+            let c = meta.query_advice(advice, Rotation(2));
+            vec![s * (a + b - c)]
+        });
+
+        FiboConfig { advice, selector, instance }
+    }
+
+    // `tamper` adds a value to the witness of one row, as a malicious prover would. Returns the cell of the last term
+    pub fn assign(&self, mut layouter: impl Layouter<F>, nrows: usize, buggy: bool, tamper: Option<(usize, F)>) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(|| "entire fibonacci table", |mut region| {
+            // The bug: 0..nrows-3 instead of 0..nrows-2
+            let last = if buggy { nrows - 3 } else { nrows - 2 };
+            for row in 0..last {
+                config.selector.enable(&mut region, row)?;
+            }
+
+            let mut a_cell = region.assign_advice_from_instance(|| "a", config.instance, 0, config.advice, 0)?;
+            let mut b_cell = region.assign_advice_from_instance(|| "b", config.instance, 1, config.advice, 1)?;
+            for row in 2..nrows {
+                let mut c = a_cell.value().zip(b_cell.value()).map(|(a, b)| *a + b);
+                if let Some((_, delta)) = tamper.filter(|(tampered, _)| *tampered == row) {
+                    c = c.map(|c| c + delta);
+                }
+                let c_cell = region.assign_advice(|| "c", config.advice, row, || c.ok_or(Error::Synthesis))?;
+                a_cell = b_cell;
+                b_cell = c_cell;
+            }
+            Ok(b_cell)
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default)]
+struct MyCircuit<F> {
+    pub buggy: bool,
+    pub tamper: Option<(usize, F)>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { buggy: self.buggy, tamper: None }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        let out = chip.assign(layouter.namespace(|| "entire table"), NROWS, self.buggy, self.tamper)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 2)
+    }
+}
+
+const NROWS: usize = 10;
+
+// The output of the table with `delta` added on `row`, computed natively
+fn tampered_out(row: usize, delta: Fp) -> Fp {
+    let mut terms = vec![Fp::one(), Fp::one()];
+    for i in 2..NROWS {
+        let c = terms[i - 2] + terms[i - 1];
+        terms.push(if i == row { c + delta } else { c });
+    }
+    terms[NROWS - 1]
+}
+
+// The rows where a changed witness is accepted
+fn unconstrained_rows(buggy: bool) -> Vec<usize> {
+    (2..NROWS)
+        .filter(|&row| {
+            let circuit = MyCircuit { buggy, tamper: Some((row, Fp::one())) };
+            let public_inputs = vec![Fp::one(), Fp::one(), tampered_out(row, Fp::one())];
+            MockProver::run(4, &circuit, vec![public_inputs]).unwrap().verify().is_ok()
+        })
+        .collect()
+}
+
+fn main() {
+    let k = 4;
+    let honest = vec![Fp::one(), Fp::one(), Fp::from(55)];
+    let forged = vec![Fp::one(), Fp::one(), Fp::from(56)];
+
+    // The honest witness passes both versions
+    for buggy in [false, true] {
+        MockProver::run(k, &MyCircuit { buggy, tamper: None }, vec![honest.clone()]).unwrap().expect_satisfied();
+    }
+    println!("F(10) = 55 passes the fixed and the buggy circuit");
+
+    // 1 more on the last row: the buggy circuit accepts F(10) = 56, the fixed one doesn't
+    let tamper = Some((NROWS - 1, Fp::one()));
+    let prover = MockProver::run(k, &MyCircuit { buggy: true, tamper }, vec![forged.clone()]).unwrap();
+    prover.expect_satisfied();
+    println!("the buggy circuit also accepts F(10) = 56");
+    let prover = MockProver::run(k, &MyCircuit { buggy: false, tamper }, vec![forged]).unwrap();
+    assert!(prover.verify().is_err());
+    println!("the fixed circuit rejects it");
+
+    // The mutation test finds the unconstrained row of the buggy version, and none in the fixed one
+    assert_eq!(unconstrained_rows(true), vec![NROWS - 1]);
+    assert_eq!(unconstrained_rows(false), Vec::<usize>::new());
+    println!("unconstrained rows: {:?} in the buggy circuit, none in the fixed one", unconstrained_rows(true));
+}
@@ -0,0 +1,198 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::{FailureLocation, MockProver, VerifyFailure},
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// How broken copy constraints show up, on the three-column table of example 2, where `assign_row` copies the b and c
+// cells of the previous row to the a and b cells of the new one:
+//
+//   a      | b      | c      | s
+//  -------------------------------
+//   1      | 1      | 2      | 1    "first row"  (region 0)
+//   1 <-b  | 2 <-c  | 3      | 1    "next row"   (region 1)
+//   ...
+//
+// `copy_advice` assigns the value of the source cell to the target cell and adds the copy constraint between them, so
+// its witness always satisfies the copy. Two mistakes in `assign_row` of region 1:
+// - the copy targets swapped (b to column b, c to column a): the witness is 2 | 1 | 3, which still satisfies the gate
+//   a + b = c, as the addition doesn't care about the order. The MockProver accepts it, and nothing needs to: the
+//   table proves the same sequence. Swapped copies only surface when the gate isn't symmetric
+// - the copy done by hand, with assign_advice and constrain_equal, and the cells given to constrain_equal swapped:
+//   the witness is 1 | 2 | 3, but a is constrained to the previous c and b to the previous b. The MockProver walks each
+//   copy cycle (the cells constrained equal to each other) and reports a VerifyFailure::Permutation for each cell whose
+//   value differs from the next cell of the cycle. These are not always the cells with the mistake: the cycle of the
+//   previous c also holds its copy to column a of region 2, which gets a failure too, and c of region 0 gets none
+//
+// The failures carry the column, as dev::metadata::Column, and the location, as dev::FailureLocation, which the
+// example compares with the expected ones rather than with strings.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mistake {
+    SwappedCopyTargets,
+    SwappedEqualities,
+}
+
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub advice: [Column<Advice>; 3],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3], instance: Column<Instance>) -> FiboConfig {
+        let selector = meta.selector();
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let c = meta.query_advice(advice[2], Rotation::cur());
+            vec![s * (a + b - c)]
+        });
+
+        FiboConfig { advice, selector, instance }
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn assign_first_row(&self, mut layouter: impl Layouter<F>, a: Option<F>, b: Option<F>) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let config = &self.config;
+
+        layouter.assign_region(|| "first row", |mut region| {
+            config.selector.enable(&mut region, 0)?;
+            let a_cell = region.assign_advice(|| "a", config.advice[0], 0, || a.ok_or(Error::Synthesis))?;
+            let b_cell = region.assign_advice(|| "b", config.advice[1], 0, || b.ok_or(Error::Synthesis))?;
+            let c = a.zip(b).map(|(a, b)| a + b);
+            let c_cell = region.assign_advice(|| "c", config.advice[2], 0, || c.ok_or(Error::Synthesis))?;
+            Ok((a_cell, b_cell, c_cell))
+        })
+    }
+
+    pub fn assign_row(&self, mut layouter: impl Layouter<F>, prev_b: &AssignedCell<F, F>, prev_c: &AssignedCell<F, F>, mistake: Option<Mistake>) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(|| "next row", |mut region| {
+            config.selector.enable(&mut region, 0)?;
+            match mistake {
+                None => {
+                    prev_b.copy_advice(|| "a", &mut region, config.advice[0], 0)?;
+                    prev_c.copy_advice(|| "b", &mut region, config.advice[1], 0)?;
+                }
+                Some(Mistake::SwappedCopyTargets) => {
+                    prev_b.copy_advice(|| "a", &mut region, config.advice[1], 0)?;
+                    prev_c.copy_advice(|| "b", &mut region, config.advice[0], 0)?;
+                }
+                Some(Mistake::SwappedEqualities) => {
+                    let a = region.assign_advice(|| "a", config.advice[0], 0, || prev_b.value().copied().ok_or(Error::Synthesis))?;
+                    let b = region.assign_advice(|| "b", config.advice[1], 0, || prev_c.value().copied().ok_or(Error::Synthesis))?;
+                    region.constrain_equal(a.cell(), prev_c.cell())?;
+                    region.constrain_equal(b.cell(), prev_b.cell())?;
+                }
+            }
+
+            let c = prev_b.value().zip(prev_c.value()).map(|(b, c)| *b + c);
+            region.assign_advice(|| "c", config.advice[2], 0, || c.ok_or(Error::Synthesis))
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default)]
+struct MyCircuit<F> {
+    pub a: Option<F>,
+    pub b: Option<F>,
+    // The mistake made in the first `assign_row`, region 1
+    pub mistake: Option<Mistake>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: None, b: None, mistake: self.mistake }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        FiboChip::configure(meta, advice, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        let (a, mut prev_b, mut prev_c) = chip.assign_first_row(layouter.namespace(|| "first row"), self.a, self.b)?;
+        chip.expose_public(layouter.namespace(|| "a"), &a, 0)?;
+        chip.expose_public(layouter.namespace(|| "b"), &prev_b, 1)?;
+
+        for i in 3..10 {
+            let mistake = if i == 3 { self.mistake } else { None };
+            let c = chip.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c, mistake)?;
+            prev_b = prev_c;
+            prev_c = c;
+        }
+
+        chip.expose_public(layouter.namespace(|| "out"), &prev_c, 2)
+    }
+}
+
+// The failure of an advice cell at offset 0 of a region
+fn permutation_failure(column: usize, region: (usize, &str)) -> VerifyFailure {
+    VerifyFailure::Permutation {
+        column: (Any::Advice, column).into(),
+        location: FailureLocation::InRegion { region: region.into(), offset: 0 },
+    }
+}
+
+fn main() {
+    let k = 4;
+    let public_inputs = vec![Fp::one(), Fp::one(), Fp::from(55)];
+    let circuit = |mistake| MyCircuit { a: Some(Fp::one()), b: Some(Fp::one()), mistake };
+
+    MockProver::run(k, &circuit(None), vec![public_inputs.clone()]).unwrap().expect_satisfied();
+
+    // Swapped copy targets: a different but valid table
+    MockProver::run(k, &circuit(Some(Mistake::SwappedCopyTargets)), vec![public_inputs.clone()]).unwrap().expect_satisfied();
+    println!("swapped copy targets: accepted, the gate a + b = c is symmetric");
+
+    // Swapped equalities: the cells of both copy cycles disagree
+    let failures = MockProver::run(k, &circuit(Some(Mistake::SwappedEqualities)), vec![public_inputs]).unwrap().verify().unwrap_err();
+    for failure in &failures {
+        println!("swapped equalities: {}", failure);
+    }
+    // The cycle of the previous b: b of region 0 = 1, b of region 1 = 2, row 1 of the instance = 1
+    // The cycle of the previous c: c of region 0 = 2, a of region 1 = 1, a of region 2 = 2
+    let expected = [
+        permutation_failure(0, (1, "next row")),
+        permutation_failure(0, (2, "next row")),
+        permutation_failure(1, (0, "first row")),
+        permutation_failure(1, (1, "next row")),
+    ];
+    assert_eq!(failures.len(), expected.len());
+    assert!(expected.iter().all(|failure| failures.contains(failure)));
+}
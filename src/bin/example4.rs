@@ -0,0 +1,183 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::{poseidon::{self, PoseidonChip, PoseidonConfig}, report::ExpectSatisfied};
+
+// In example2 a, b and the output were all public: anyone looking at the instance column learns the whole statement.
+// In this example a, b and F(n) stay private. The instance column only contains Poseidon(F(n)), so the verifier
+// is convinced that the prover knows a Fibonacci sequence ending in a value with that hash, without learning the value itself.
+
+#[derive(Debug, Clone)]
+struct ACell<F: FieldExt>(AssignedCell<F, F>);
+
+// The FiboConfig is the same as example2
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub advice: [Column<Advice>; 3],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        instance: Column<Instance>,
+    ) -> FiboConfig {
+        let col_a = advice[0];
+        let col_b = advice[1];
+        let col_c = advice[2];
+        let selector = meta.selector();
+
+        meta.enable_equality(col_a);
+        meta.enable_equality(col_b);
+        meta.enable_equality(col_c);
+        meta.enable_equality(instance);
+
+        // col_a | col_b | col_c | selector
+        // ---------------------------------
+        //  a    |   b   |   c   |   s
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let c = meta.query_advice(col_c, Rotation::cur());
+            vec![s * (a + b - c)]
+        });
+
+        FiboConfig { advice: [col_a, col_b, col_c], selector, instance }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn assign_first_row(&self, mut layouter: impl Layouter<F>, a: Option<F>, b: Option<F>) -> Result<(ACell<F>, ACell<F>, ACell<F>), Error> {
+        layouter.assign_region(|| "first row", |mut region| {
+            self.config.selector.enable(&mut region, 0)?;
+
+            // a and b are assigned as plain advice cells: they are private and never copied to the instance column
+            let a_cell = region.assign_advice(|| "a", self.config.advice[0], 0, || a.ok_or(Error::Synthesis)).map(ACell)?;
+            let b_cell = region.assign_advice(|| "b", self.config.advice[1], 0, || b.ok_or(Error::Synthesis)).map(ACell)?;
+
+            let c_val = a.and_then(|a| b.map(|b| a + b));
+            let c_cell = region.assign_advice(|| "c", self.config.advice[2], 0, || c_val.ok_or(Error::Synthesis)).map(ACell)?;
+
+            Ok((a_cell, b_cell, c_cell))
+        })
+    }
+
+    fn assign_row(&self, mut layouter: impl Layouter<F>, prev_b: &ACell<F>, prev_c: &ACell<F>) -> Result<ACell<F>, Error> {
+        layouter.assign_region(|| "next row", |mut region| {
+            self.config.selector.enable(&mut region, 0)?;
+
+            prev_b.0.copy_advice(|| "a", &mut region, self.config.advice[0], 0)?;
+            prev_c.0.copy_advice(|| "b", &mut region, self.config.advice[1], 0)?;
+
+            let c_val = prev_b.0.value().and_then(|b| prev_c.0.value().map(|c| *b + *c));
+            let c_cell = region.assign_advice(|| "c", self.config.advice[2], 0, || c_val.ok_or(Error::Synthesis)).map(ACell)?;
+
+            Ok(c_cell)
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &ACell<F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
+    }
+}
+
+// The circuit config is now made of the configs of the two chips that we are using
+#[derive(Debug, Clone)]
+struct CircuitConfig<F: FieldExt> {
+    fibo: FiboConfig,
+    poseidon: PoseidonConfig<F>,
+}
+
+#[derive(Default)]
+struct MyCircuit<F> {
+    pub a: Option<F>,
+    pub b: Option<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = CircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        let fibo = FiboChip::configure(meta, advice, instance);
+
+        // The Poseidon chip gets its own 3 advice columns to store the sponge state
+        let state = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let poseidon = PoseidonChip::configure(meta, state);
+
+        CircuitConfig { fibo, poseidon }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let fibo_chip = FiboChip::construct(config.fibo);
+        let poseidon_chip = PoseidonChip::construct(config.poseidon);
+
+        // Differently from example2 we don't call expose_public on a and b: they stay private
+        let (_, mut prev_b, mut prev_c) = fibo_chip.assign_first_row(layouter.namespace(|| "first row"), self.a, self.b)?;
+
+        for _i in 3..10 {
+            let c_cell = fibo_chip.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
+            prev_b = prev_c;
+            prev_c = c_cell;
+        }
+
+        // The output cell is copied into the Poseidon region: the copy constraint binds the hashed value to the
+        // last Fibonacci number, so the prover can't hash something else
+        let digest = poseidon_chip.hash(layouter.namespace(|| "hash output"), [prev_c.0])?;
+
+        // Only the digest is exposed, in row 0 of the instance column
+        fibo_chip.expose_public(layouter.namespace(|| "digest"), &ACell(digest), 0)?;
+
+        Ok(())
+    }
+}
+
+fn main() {
+    // The Poseidon permutation takes 64 rows, so we need a bigger circuit than in the previous examples
+    let k = 7;
+    let a = Fp::from(1);
+    let b = Fp::from(1);
+    let out = Fp::from(55);
+
+    let circuit = MyCircuit {
+        a: Some(a),
+        b: Some(b),
+    };
+
+    // The public input is computed natively with the same hash function used by the chip
+    let digest = poseidon::hash([out]);
+    let public_input = vec![digest];
+
+    let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+    prover.expect_satisfied();
+
+    // Publishing the output itself instead of its hash doesn't work anymore
+    let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,158 @@
+use std::time::Instant;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    pasta::{vesta, EqAffine, Fp},
+    plonk::*,
+    poly::commitment::{Guard, Params, MSM},
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge},
+};
+use rand_core::OsRng;
+
+use halo2_fibonacci_ex::fibonacci::FibInputs;
+
+// Native batch verification of Fibonacci proofs, by accumulating their IPA opening claims. This is not recursion: no
+// proof is verified inside a circuit.
+//
+// Verifying an IPA proof is cheap except for one step: the opening argument ends with a claim that a point G is the
+// commitment <s(u), g> to a polynomial s defined by the k challenges u of the proof, over the 2^k generators g of the
+// parameters. Checking it takes an MSM of size 2^k, everything else takes O(k) group operations. So the prover sends
+// G, and the verifier checks the proof up to that claim and keeps it, the accumulator (G, u): a point and k scalars,
+// whatever the size of the circuit. Many accumulators are checked at once by a random linear combination,
+//
+//   sum r_i G_i = < sum r_i s(u_i), g >
+//
+// one MSM of size 2^k for all of them, together with the rest of the checks of the proofs, folded the same way. The
+// example times it against verifying the proofs one by one (computing G is left out, it's the prover's work).
+//
+// Recursion would go one step further and run the cheap part of the verifier inside a circuit, taking the accumulator
+// as a public input. That needs a verifier gadget (elliptic curve arithmetic over the other Pasta curve, the
+// transcript in the circuit), which halo2_proofs 0.1.0 doesn't have, and it isn't attempted here.
+
+// The claim left over by the verifier: G = <s(u), g>
+struct Accumulator {
+    g: EqAffine,
+    u: Vec<Fp>,
+}
+
+// The prover's side: the G of a proof, sent along with it. Computing it is the MSM of size 2^k
+struct ComputeG<'params> {
+    msm: MSM<'params, EqAffine>,
+}
+
+impl<'params> VerificationStrategy<'params, EqAffine> for ComputeG<'params> {
+    type Output = EqAffine;
+
+    fn process<E: EncodedChallenge<EqAffine>>(
+        self,
+        f: impl FnOnce(MSM<'params, EqAffine>) -> Result<Guard<'params, EqAffine, E>, Error>,
+    ) -> Result<Self::Output, Error> {
+        Ok(f(self.msm)?.compute_g())
+    }
+}
+
+// The verifier's side: takes the G claimed by the prover and adds the remaining checks of the proof to a running MSM,
+// scaled by a random factor as in BatchVerifier so that the checks of different proofs can't cancel out
+struct Accumulate<'params> {
+    msm: MSM<'params, EqAffine>,
+    g: EqAffine,
+}
+
+impl<'params> VerificationStrategy<'params, EqAffine> for Accumulate<'params> {
+    type Output = (MSM<'params, EqAffine>, Accumulator);
+
+    fn process<E: EncodedChallenge<EqAffine>>(
+        mut self,
+        f: impl FnOnce(MSM<'params, EqAffine>) -> Result<Guard<'params, EqAffine, E>, Error>,
+    ) -> Result<Self::Output, Error> {
+        self.msm.scale(Fp::random(OsRng));
+        let (msm, accumulator) = f(self.msm)?.use_g(self.g);
+        Ok((msm, Accumulator { g: accumulator.g, u: accumulator.u_packed.iter().map(|u| u.get_scalar()).collect() }))
+    }
+}
+
+// The coefficients of s(X) = prod_i (1 + u_{k-1-i} X^(2^i)), times init, as in halo2_proofs
+fn compute_s(u: &[Fp], init: Fp) -> Vec<Fp> {
+    let mut s = vec![Fp::zero(); 1 << u.len()];
+    s[0] = init;
+    for (i, u_j) in u.iter().rev().enumerate() {
+        let (left, right) = s.split_at_mut(1 << i);
+        for (r, l) in right[..1 << i].iter_mut().zip(left.iter()) {
+            *r = *l * u_j;
+        }
+    }
+    s
+}
+
+// Checks the folded checks of the proofs and every accumulator with a single MSM
+fn decide(mut msm: MSM<EqAffine>, accumulators: &[Accumulator]) -> bool {
+    for accumulator in accumulators {
+        let r = Fp::random(OsRng);
+        msm.add_to_g_scalars(&compute_s(&accumulator.u, r));
+        msm.append_term(-r, accumulator.g);
+    }
+    msm.eval()
+}
+
+fn main() {
+    let k = 10;
+    let params = Params::<EqAffine>::new(k);
+    let n = 200;
+    let vk = keygen_vk(&params, &FibInputs::new(Fp::zero(), Fp::zero(), n).circuit()).unwrap();
+    let pk = keygen_pk(&params, vk, &FibInputs::new(Fp::zero(), Fp::zero(), n).circuit()).unwrap();
+
+    // Proofs of the same circuit for different starting values
+    let statements: Vec<(Vec<Fp>, Vec<u8>)> = (1..=8u64)
+        .map(|a| {
+            let inputs = FibInputs::new(Fp::from(a), Fp::from(a + 1), n);
+            let instance = inputs.public_inputs();
+            let mut transcript = Blake2bWrite::<_, vesta::Affine, Challenge255<_>>::init(vec![]);
+            create_proof(&params, &pk, &[inputs.circuit()], &[&[&instance]], OsRng, &mut transcript).unwrap();
+            (instance, transcript.finalize())
+        })
+        .collect();
+
+    let start = Instant::now();
+    for (instance, proof) in &statements {
+        let mut transcript = Blake2bRead::<_, vesta::Affine, Challenge255<_>>::init(&proof[..]);
+        verify_proof(&params, pk.get_vk(), SingleVerifier::new(&params), &[&[instance]], &mut transcript).unwrap();
+    }
+    println!("{} proofs verified one by one in {:.1?}", statements.len(), start.elapsed());
+
+    // The prover sends G with each proof
+    let claims: Vec<EqAffine> = statements
+        .iter()
+        .map(|(instance, proof)| {
+            let mut transcript = Blake2bRead::<_, vesta::Affine, Challenge255<_>>::init(&proof[..]);
+            let strategy = ComputeG { msm: params.empty_msm() };
+            verify_proof(&params, pk.get_vk(), strategy, &[&[instance]], &mut transcript).unwrap()
+        })
+        .collect();
+
+    let accumulate = |claims: &[EqAffine]| {
+        let mut msm = params.empty_msm();
+        let mut accumulators = vec![];
+        for ((instance, proof), g) in statements.iter().zip(claims) {
+            let mut transcript = Blake2bRead::<_, vesta::Affine, Challenge255<_>>::init(&proof[..]);
+            let strategy = Accumulate { msm, g: *g };
+            let (folded, accumulator) = verify_proof(&params, pk.get_vk(), strategy, &[&[instance]], &mut transcript).unwrap();
+            msm = folded;
+            accumulators.push(accumulator);
+        }
+        (msm, accumulators)
+    };
+
+    let start = Instant::now();
+    let (msm, accumulators) = accumulate(&claims);
+    println!("{} proofs reduced to accumulators of {} challenges in {:.1?}", accumulators.len(), k, start.elapsed());
+    let start = Instant::now();
+    assert!(decide(msm, &accumulators));
+    println!("batch verified with one MSM in {:.1?}", start.elapsed());
+
+    // A wrong claim goes through the accumulation and is caught by the final check
+    let mut wrong = claims;
+    wrong[3] = (wrong[3] + wrong[4]).into();
+    let (msm, accumulators) = accumulate(&wrong);
+    assert!(!decide(msm, &accumulators));
+    println!("a wrong claim is rejected");
+}
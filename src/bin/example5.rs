@@ -0,0 +1,198 @@
+use std::marker::PhantomData;
+
+use halo2_gadgets::poseidon::{
+    primitives::{self as poseidon, ConstantLength, P128Pow5T3},
+    Hash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// This example shows how to compose a chip written by someone else with our own FiboChip: the Pow5Chip of
+// halo2_gadgets, the Poseidon chip of Zcash, with its P128Pow5T3 spec (x^5 S-box, width 3, rate 2) and a constant
+// length domain. The spec is only implemented for the Pasta fields, so the circuit is over Fp rather than generic.
+// The statement is: "I know a and b such that Poseidon(a, b) = digest and the Fibonacci sequence starting from a, b reaches out".
+// Both digest and out are public, a and b are private.
+
+const WIDTH: usize = 3;
+const RATE: usize = 2;
+
+#[derive(Debug, Clone)]
+struct ACell<F: FieldExt>(AssignedCell<F, F>);
+
+// The FiboConfig is the same as example2
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub advice: [Column<Advice>; 3],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        instance: Column<Instance>,
+    ) -> FiboConfig {
+        let col_a = advice[0];
+        let col_b = advice[1];
+        let col_c = advice[2];
+        let selector = meta.selector();
+
+        meta.enable_equality(col_a);
+        meta.enable_equality(col_b);
+        meta.enable_equality(col_c);
+        meta.enable_equality(instance);
+
+        // col_a | col_b | col_c | selector
+        // ---------------------------------
+        //  a    |   b   |   c   |   s
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let c = meta.query_advice(col_c, Rotation::cur());
+            vec![s * (a + b - c)]
+        });
+
+        FiboConfig { advice: [col_a, col_b, col_c], selector, instance }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn assign_first_row(&self, mut layouter: impl Layouter<F>, a: Option<F>, b: Option<F>) -> Result<(ACell<F>, ACell<F>, ACell<F>), Error> {
+        layouter.assign_region(|| "first row", |mut region| {
+            self.config.selector.enable(&mut region, 0)?;
+
+            let a_cell = region.assign_advice(|| "a", self.config.advice[0], 0, || a.ok_or(Error::Synthesis)).map(ACell)?;
+            let b_cell = region.assign_advice(|| "b", self.config.advice[1], 0, || b.ok_or(Error::Synthesis)).map(ACell)?;
+
+            let c_val = a.and_then(|a| b.map(|b| a + b));
+            let c_cell = region.assign_advice(|| "c", self.config.advice[2], 0, || c_val.ok_or(Error::Synthesis)).map(ACell)?;
+
+            Ok((a_cell, b_cell, c_cell))
+        })
+    }
+
+    fn assign_row(&self, mut layouter: impl Layouter<F>, prev_b: &ACell<F>, prev_c: &ACell<F>) -> Result<ACell<F>, Error> {
+        layouter.assign_region(|| "next row", |mut region| {
+            self.config.selector.enable(&mut region, 0)?;
+
+            prev_b.0.copy_advice(|| "a", &mut region, self.config.advice[0], 0)?;
+            prev_c.0.copy_advice(|| "b", &mut region, self.config.advice[1], 0)?;
+
+            let c_val = prev_b.0.value().and_then(|b| prev_c.0.value().map(|c| *b + *c));
+            let c_cell = region.assign_advice(|| "c", self.config.advice[2], 0, || c_val.ok_or(Error::Synthesis)).map(ACell)?;
+
+            Ok(c_cell)
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &ACell<F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CircuitConfig {
+    fibo: FiboConfig,
+    poseidon: Pow5Config<Fp, WIDTH, RATE>,
+}
+
+#[derive(Default)]
+struct MyCircuit {
+    pub a: Option<Fp>,
+    pub b: Option<Fp>,
+}
+
+impl Circuit<Fp> for MyCircuit {
+    type Config = CircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    // Here we use the column passing pattern of example2: the 3 advice columns are created once and handed to both chips.
+    // The FiboChip uses them as a, b, c while the Pow5Chip uses them as its sponge state. The Pow5Chip also needs an
+    // advice column for the partial S-box and two sets of fixed columns for the round constants, the first of which
+    // holds the constants of the initial state and of the padding.
+    // Each chip brings its own selectors and gates, so the gates of one chip are switched off inside the regions of the other.
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        let partial_sbox = meta.advice_column();
+        let rc_a = [meta.fixed_column(), meta.fixed_column(), meta.fixed_column()];
+        let rc_b = [meta.fixed_column(), meta.fixed_column(), meta.fixed_column()];
+        meta.enable_constant(rc_b[0]);
+
+        let fibo = FiboChip::configure(meta, advice, instance);
+        let poseidon = Pow5Chip::configure::<P128Pow5T3>(meta, advice, partial_sbox, rc_a, rc_b);
+
+        CircuitConfig { fibo, poseidon }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let fibo_chip = FiboChip::construct(config.fibo);
+        let poseidon_chip = Pow5Chip::construct(config.poseidon);
+
+        let (a_cell, b_cell, mut prev_c) = fibo_chip.assign_first_row(layouter.namespace(|| "first row"), self.a, self.b)?;
+
+        // The chips talk to each other through assigned cells: the sponge copies a and b from the first row of the
+        // Fibonacci table, so the digest is bound to the same values used to start the sequence
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init(poseidon_chip, layouter.namespace(|| "init"))?;
+        let digest = hasher.hash(layouter.namespace(|| "hash a and b"), [a_cell.0, b_cell.0.clone()])?;
+        fibo_chip.expose_public(layouter.namespace(|| "digest"), &ACell(digest), 0)?;
+
+        let mut prev_b = b_cell;
+        for _i in 3..10 {
+            let c_cell = fibo_chip.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
+            prev_b = prev_c;
+            prev_c = c_cell;
+        }
+
+        fibo_chip.expose_public(layouter.namespace(|| "output"), &prev_c, 1)?;
+
+        Ok(())
+    }
+}
+
+fn main() {
+    let k = 7;
+    let a = Fp::from(1);
+    let b = Fp::from(1);
+    let out = Fp::from(55);
+
+    let circuit = MyCircuit {
+        a: Some(a),
+        b: Some(b),
+    };
+
+    let hash = |message| poseidon::Hash::<_, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init().hash(message);
+    let digest = hash([a, b]);
+    let public_input = vec![digest, out];
+
+    let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+    prover.expect_satisfied();
+
+    // A digest committing to different starting values is rejected, even though the output is correct
+    let wrong_digest = hash([Fp::from(0), Fp::from(1)]);
+    let prover = MockProver::run(k, &circuit, vec![vec![wrong_digest, out]]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,82 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    plonk::*,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::{report::ExpectSatisfied, sequence::{Boundary, Sequence, SequenceCircuit}};
+
+// In example3 we wrote the config, the gate and the assignment loop of the Fibonacci circuit by hand.
+// With the sequence DSL (src/sequence.rs) we only describe the sequence: how wide the state is, how a state
+// is constrained with respect to the previous one, how to compute it and which words are public.
+
+// Fibonacci: the state is the pair (a, b) and each step maps it to (b, a + b)
+struct Fibonacci;
+
+impl<F: FieldExt> Sequence<F> for Fibonacci {
+    const WIDTH: usize = 2;
+
+    fn transition(cur: &[Expression<F>], next: &[Expression<F>]) -> Vec<(&'static str, Expression<F>)> {
+        vec![
+            ("fib-shift", next[0].clone() - cur[1].clone()),
+            ("fib-add", next[1].clone() - (cur[0].clone() + cur[1].clone())),
+        ]
+    }
+
+    fn step(cur: &[F]) -> Vec<F> {
+        vec![cur[1], cur[0] + cur[1]]
+    }
+
+    // Same instance column as example3: a, b and the output
+    fn boundaries() -> Vec<Boundary> {
+        vec![Boundary::first(0, 0), Boundary::first(1, 1), Boundary::last(1, 2)]
+    }
+}
+
+// Tribonacci: every element is the sum of the previous three. Here the inputs are private and only the output is public
+struct Tribonacci;
+
+impl<F: FieldExt> Sequence<F> for Tribonacci {
+    const WIDTH: usize = 3;
+
+    fn transition(cur: &[Expression<F>], next: &[Expression<F>]) -> Vec<(&'static str, Expression<F>)> {
+        vec![
+            ("trib-shift a", next[0].clone() - cur[1].clone()),
+            ("trib-shift b", next[1].clone() - cur[2].clone()),
+            ("trib-add", next[2].clone() - (cur[0].clone() + cur[1].clone() + cur[2].clone())),
+        ]
+    }
+
+    fn step(cur: &[F]) -> Vec<F> {
+        vec![cur[1], cur[2], cur[0] + cur[1] + cur[2]]
+    }
+
+    fn boundaries() -> Vec<Boundary> {
+        vec![Boundary::last(2, 0)]
+    }
+}
+
+fn main() {
+    let k = 4;
+
+    // 8 steps starting from (1, 1) end in (34, 55), the same table of example3
+    let initial = vec![Fp::from(1), Fp::from(1)];
+    let public_input = <Fibonacci as Sequence<Fp>>::public_inputs(&initial, 8);
+    assert_eq!(public_input, vec![Fp::from(1), Fp::from(1), Fp::from(55)]);
+
+    let circuit = SequenceCircuit::<Fp, Fibonacci>::new(initial.into_iter().map(Some).collect(), 8);
+    let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+    prover.expect_satisfied();
+
+    // 0, 0, 1, 1, 2, 4, 7, 13, 24, 44, 81
+    let initial = vec![Fp::from(0), Fp::from(0), Fp::from(1)];
+    let public_input = <Tribonacci as Sequence<Fp>>::public_inputs(&initial, 8);
+    assert_eq!(public_input, vec![Fp::from(81)]);
+
+    let circuit = SequenceCircuit::<Fp, Tribonacci>::new(initial.into_iter().map(Some).collect(), 8);
+    let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+    prover.expect_satisfied();
+
+    // A wrong output is rejected by the boundary constraint
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(80)]]).unwrap();
+    assert!(prover.verify().is_err());
+}
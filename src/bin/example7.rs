@@ -0,0 +1,38 @@
+use halo2_proofs::{
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::{merkle::{MerkleCircuit, MerkleTree}, report::ExpectSatisfied};
+
+// Merkle inclusion proof: "I know a leaf that belongs to the tree with this public root".
+// The MerkleChip (src/merkle.rs) combines a swap gate, which puts the current node on the left or on the right
+// depending on a boolean witness, with the Poseidon chip, which hashes the pair to get the parent node.
+// The depth of the tree is configurable: it is the length of the authentication path passed to the circuit.
+
+fn main() {
+    // Each level costs a Poseidon permutation (~70 rows), so a tree of depth 4 needs 2^9 rows
+    let k = 9;
+    let depth = 4;
+
+    // The leaves are the first Fibonacci numbers, the tree is built natively
+    let mut leaves = vec![Fp::from(1), Fp::from(1)];
+    while leaves.len() < 1 << depth {
+        let next = leaves[leaves.len() - 1] + leaves[leaves.len() - 2];
+        leaves.push(next);
+    }
+    let tree = MerkleTree::new(&leaves, depth).unwrap();
+    let root = tree.root();
+
+    // Prove that the leaf at index 9 (the value 55) is in the tree
+    let index = 9;
+    let path = tree.path(index).unwrap();
+    assert_eq!(path.root(leaves[index]), root);
+
+    let circuit = MerkleCircuit::new(leaves[index], &path);
+    let prover = MockProver::run(k, &circuit, vec![vec![root]]).unwrap();
+    prover.expect_satisfied();
+
+    // A leaf which is not in the tree can't produce the same root
+    let circuit = MerkleCircuit::new(Fp::from(56), &path);
+    let prover = MockProver::run(k, &circuit, vec![vec![root]]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,153 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::Fp, dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// In the previous examples every region only used one kind of gate. Here the same 3 advice columns are used by two gates,
+// each one switched on by its own selector, and both gate types live in the same region:
+//
+//  col_a | col_b | col_c | q_add | q_out
+// ---------------------------------------
+//    1   |   1   |   2   |   1   |   0
+//    1   |   2   |   3   |   1   |   0
+//   ...  |  ...  |  ...  |   1   |   0
+//   21   |  34   |  55   |   1   |   0
+//   55   |       |       |   0   |   1      <- output row, col_a is copied from the instance column
+//
+// q_add enables the addition gate a + b = c on the current row.
+// q_out enables the output equality gate: the output cell must be equal to col_c of the previous row.
+// A row can enable any combination of selectors: the gates that are switched off are satisfied whatever the values are.
+
+#[derive(Debug, Clone)]
+struct FiboConfig {
+    pub advice: [Column<Advice>; 3],
+    pub q_add: Selector,
+    pub q_out: Selector,
+    pub instance: Column<Instance>,
+}
+
+struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        instance: Column<Instance>,
+    ) -> FiboConfig {
+        let [col_a, col_b, col_c] = advice;
+        // Two selectors over the same columns
+        let q_add = meta.selector();
+        let q_out = meta.selector();
+
+        meta.enable_equality(col_a);
+        meta.enable_equality(col_b);
+        meta.enable_equality(col_c);
+        meta.enable_equality(instance);
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(q_add);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let c = meta.query_advice(col_c, Rotation::cur());
+            vec![s * (a + b - c)]
+        });
+
+        // The output gate looks back one row with Rotation::prev()
+        meta.create_gate("output", |meta| {
+            let s = meta.query_selector(q_out);
+            let out = meta.query_advice(col_a, Rotation::cur());
+            let last_c = meta.query_advice(col_c, Rotation::prev());
+            vec![s * (out - last_c)]
+        });
+
+        FiboConfig { advice, q_add, q_out, instance }
+    }
+
+    // The whole table, including the output row, is assigned in a single region
+    fn assign(&self, mut layouter: impl Layouter<F>, a: Option<F>, b: Option<F>, nrows: usize) -> Result<(), Error> {
+        layouter.assign_region(|| "fibonacci table with output", |mut region| {
+            let mut a_cell = region.assign_advice(|| "a", self.config.advice[0], 0, || a.ok_or(Error::Synthesis))?;
+            let mut b_cell = region.assign_advice(|| "b", self.config.advice[1], 0, || b.ok_or(Error::Synthesis))?;
+
+            for row in 0..nrows {
+                self.config.q_add.enable(&mut region, row)?;
+
+                // From the second row on a and b are copied from the previous row, as in example1 but inside the same region
+                if row > 0 {
+                    a_cell = a_cell.copy_advice(|| "a", &mut region, self.config.advice[0], row)?;
+                    b_cell = b_cell.copy_advice(|| "b", &mut region, self.config.advice[1], row)?;
+                }
+
+                let c_val = a_cell.value().and_then(|a| b_cell.value().map(|b| *a + *b));
+                let c_cell = region.assign_advice(|| "c", self.config.advice[2], row, || c_val.ok_or(Error::Synthesis))?;
+
+                a_cell = b_cell;
+                b_cell = c_cell;
+            }
+
+            // The last row only switches on the output gate. The output comes from the instance column, and the gate
+            // checks it against the last c
+            self.config.q_out.enable(&mut region, nrows)?;
+            region.assign_advice_from_instance(|| "out", self.config.instance, 0, self.config.advice[0], nrows)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[derive(Default)]
+struct MyCircuit<F> {
+    pub a: Option<F>,
+    pub b: Option<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        FiboChip::configure(meta, advice, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        chip.assign(layouter.namespace(|| "table"), self.a, self.b, 8)
+    }
+}
+
+fn main() {
+    let k = 4;
+    let circuit = MyCircuit {
+        a: Some(Fp::from(1)),
+        b: Some(Fp::from(1)),
+    };
+
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(55)]]).unwrap();
+    prover.expect_satisfied();
+
+    // The output gate catches a wrong output
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(56)]]).unwrap();
+    assert!(prover.verify().is_err());
+}
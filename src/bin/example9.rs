@@ -0,0 +1,250 @@
+use halo2_proofs::{
+    arithmetic::{CurveAffine, CurveExt, Field},
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+    pasta::{group::Curve, pallas}, dev::MockProver,
+};
+use halo2_fibonacci_ex::report::ExpectSatisfied;
+
+// Sinsemilla is the hash function used by Zcash Orchard. It is designed to be cheap inside a halo2 circuit over the pasta curves:
+// the message is split in chunks of K = 10 bits, each chunk selects one of 2^10 fixed Pallas points S(0), ..., S(1023), and the
+// points are accumulated with
+//
+//   Acc_0 = Q(domain)
+//   Acc_{i+1} = (Acc_i + S(m_i)) + Acc_i
+//
+// The hash is the x coordinate of the last accumulator. Since the coordinates of a Pallas point are elements of the
+// Pallas base field, which is exactly the field of our circuit, the point additions are just a few native constraints.
+// The S(j) points are not computed in the circuit: they are loaded into a lookup table of 1024 rows and each chunk
+// looks up the triple (m_i, x(S(m_i)), y(S(m_i))). This is why Sinsemilla is a "lookup heavy" gadget.
+
+const K: usize = 10;
+const DOMAIN: &str = "halo2-fibonacci-ex";
+
+// The generators, derived with the same hash-to-curve domains used by Zcash
+fn q_point(domain: &str) -> pallas::Affine {
+    pallas::Point::hash_to_curve("z.cash:SinsemillaQ")(domain.as_bytes()).to_affine()
+}
+
+fn s_point(chunk: u32) -> pallas::Affine {
+    pallas::Point::hash_to_curve("z.cash:SinsemillaS")(&chunk.to_le_bytes()).to_affine()
+}
+
+fn coordinates(point: pallas::Affine) -> (pallas::Base, pallas::Base) {
+    let coordinates = point.coordinates().unwrap();
+    (*coordinates.x(), *coordinates.y())
+}
+
+// Native Sinsemilla, used to compute the expected public output
+fn sinsemilla_hash(domain: &str, chunks: &[u32]) -> pallas::Base {
+    let mut acc: pallas::Point = q_point(domain).into();
+    for chunk in chunks {
+        acc = (acc + s_point(*chunk)) + acc;
+    }
+    coordinates(acc.to_affine()).0
+}
+
+#[derive(Debug, Clone)]
+struct SinsemillaConfig {
+    // the chunk of the message
+    m: Column<Advice>,
+    // the point S(m) looked up from the table
+    x_s: Column<Advice>,
+    y_s: Column<Advice>,
+    // the accumulator: the current row holds Acc_i, the next row holds Acc_{i+1}
+    x_a: Column<Advice>,
+    y_a: Column<Advice>,
+    // the intermediate point T = Acc_i + S(m_i) and the slopes of the two additions
+    x_t: Column<Advice>,
+    y_t: Column<Advice>,
+    lambda_1: Column<Advice>,
+    lambda_2: Column<Advice>,
+    // the lookup table (j, x(S(j)), y(S(j)))
+    table_idx: TableColumn,
+    table_x: TableColumn,
+    table_y: TableColumn,
+    // A complex selector: simple selectors can't be used inside a lookup argument
+    q_step: Selector,
+    instance: Column<Instance>,
+}
+
+struct SinsemillaChip {
+    config: SinsemillaConfig,
+}
+
+impl SinsemillaChip {
+    fn construct(config: SinsemillaConfig) -> Self {
+        Self { config }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> SinsemillaConfig {
+        let [m, x_s, y_s, x_a, y_a, x_t, y_t, lambda_1, lambda_2] = [(); 9].map(|_| meta.advice_column());
+        let table_idx = meta.lookup_table_column();
+        let table_x = meta.lookup_table_column();
+        let table_y = meta.lookup_table_column();
+        let q_step = meta.complex_selector();
+        let instance = meta.instance_column();
+        let constants = meta.fixed_column();
+
+        meta.enable_equality(x_a);
+        meta.enable_equality(y_a);
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+
+        // Every input of a lookup must be in the table, even on the rows where the selector is off.
+        // When q_step = 0 we look up the first row of the table (0, S(0)) instead of (0, 0, 0), which is not in the table
+        let (default_x, default_y) = coordinates(s_point(0));
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_step);
+            let not_q = Expression::Constant(pallas::Base::one()) - q.clone();
+            let m = meta.query_advice(m, Rotation::cur());
+            let x_s = meta.query_advice(x_s, Rotation::cur());
+            let y_s = meta.query_advice(y_s, Rotation::cur());
+
+            vec![
+                (q.clone() * m, table_idx),
+                (q.clone() * x_s + not_q.clone() * default_x, table_x),
+                (q * y_s + not_q * default_y, table_y),
+            ]
+        });
+
+        // Incomplete addition R = P + Q with slope lambda:
+        //   lambda * (x_q - x_p) = y_q - y_p
+        //   x_r = lambda^2 - x_p - x_q
+        //   y_r = lambda * (x_p - x_r) - y_p
+        // applied twice: T = Acc + S, then Acc' = T + Acc
+        meta.create_gate("sinsemilla step", |meta| {
+            let q = meta.query_selector(q_step);
+            let x_a_next = meta.query_advice(x_a, Rotation::next());
+            let y_a_next = meta.query_advice(y_a, Rotation::next());
+            let x_s = meta.query_advice(x_s, Rotation::cur());
+            let y_s = meta.query_advice(y_s, Rotation::cur());
+            let x_a = meta.query_advice(x_a, Rotation::cur());
+            let y_a = meta.query_advice(y_a, Rotation::cur());
+            let x_t = meta.query_advice(x_t, Rotation::cur());
+            let y_t = meta.query_advice(y_t, Rotation::cur());
+            let lambda_1 = meta.query_advice(lambda_1, Rotation::cur());
+            let lambda_2 = meta.query_advice(lambda_2, Rotation::cur());
+
+            vec![
+                q.clone() * (lambda_1.clone() * (x_s.clone() - x_a.clone()) - (y_s.clone() - y_a.clone())),
+                q.clone() * (x_t.clone() - (lambda_1.clone().square() - x_a.clone() - x_s)),
+                q.clone() * (y_t.clone() - (lambda_1 * (x_a.clone() - x_t.clone()) - y_a.clone())),
+                q.clone() * (lambda_2.clone() * (x_a.clone() - x_t.clone()) - (y_a - y_t.clone())),
+                q.clone() * (x_a_next.clone() - (lambda_2.clone().square() - x_t.clone() - x_a)),
+                q * (y_a_next - (lambda_2 * (x_t - x_a_next) - y_t)),
+            ]
+        });
+
+        SinsemillaConfig { m, x_s, y_s, x_a, y_a, x_t, y_t, lambda_1, lambda_2, table_idx, table_x, table_y, q_step, instance }
+    }
+
+    // The table is the same for every proof: it is made of fixed columns, so it is part of the verifying key
+    fn load_table(&self, mut layouter: impl Layouter<pallas::Base>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "sinsemilla S table",
+            |mut table| {
+                for j in 0..(1 << K) {
+                    let (x, y) = coordinates(s_point(j as u32));
+                    table.assign_cell(|| "idx", self.config.table_idx, j, || Ok(pallas::Base::from(j as u64)))?;
+                    table.assign_cell(|| "x", self.config.table_x, j, || Ok(x))?;
+                    table.assign_cell(|| "y", self.config.table_y, j, || Ok(y))?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn hash(&self, mut layouter: impl Layouter<pallas::Base>, chunks: &[Option<u32>]) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "sinsemilla hash",
+            |mut region| {
+                // The accumulator starts from the constant Q(domain)
+                let q = q_point(DOMAIN);
+                let (x_q, y_q) = coordinates(q);
+                let mut x_a = region.assign_advice_from_constant(|| "x_q", config.x_a, 0, x_q)?;
+                region.assign_advice_from_constant(|| "y_q", config.y_a, 0, y_q)?;
+                let mut acc: Option<pallas::Point> = Some(q.into());
+
+                for (row, chunk) in chunks.iter().enumerate() {
+                    config.q_step.enable(&mut region, row)?;
+
+                    let s = chunk.map(s_point);
+                    let t = acc.zip(s).map(|(acc, s)| acc + s);
+                    let next = acc.zip(t).map(|(acc, t)| t + acc);
+
+                    // Witness the chunk, the looked up point, T and both slopes
+                    let value = |point: Option<pallas::Point>| point.map(|p| coordinates(p.to_affine()));
+                    let (a, s_xy, t_xy, next_xy) = (value(acc), s.map(coordinates), value(t), value(next));
+                    let slope = |p: Option<(pallas::Base, pallas::Base)>, q: Option<(pallas::Base, pallas::Base)>| {
+                        p.zip(q).map(|(p, q)| (q.1 - p.1) * (q.0 - p.0).invert().unwrap())
+                    };
+
+                    region.assign_advice(|| "m", config.m, row, || chunk.map(|c| pallas::Base::from(c as u64)).ok_or(Error::Synthesis))?;
+                    region.assign_advice(|| "x_s", config.x_s, row, || s_xy.map(|p| p.0).ok_or(Error::Synthesis))?;
+                    region.assign_advice(|| "y_s", config.y_s, row, || s_xy.map(|p| p.1).ok_or(Error::Synthesis))?;
+                    region.assign_advice(|| "lambda_1", config.lambda_1, row, || slope(a, s_xy).ok_or(Error::Synthesis))?;
+                    region.assign_advice(|| "x_t", config.x_t, row, || t_xy.map(|p| p.0).ok_or(Error::Synthesis))?;
+                    region.assign_advice(|| "y_t", config.y_t, row, || t_xy.map(|p| p.1).ok_or(Error::Synthesis))?;
+                    region.assign_advice(|| "lambda_2", config.lambda_2, row, || slope(t_xy, a).ok_or(Error::Synthesis))?;
+
+                    x_a = region.assign_advice(|| "x_a", config.x_a, row + 1, || next_xy.map(|p| p.0).ok_or(Error::Synthesis))?;
+                    region.assign_advice(|| "y_a", config.y_a, row + 1, || next_xy.map(|p| p.1).ok_or(Error::Synthesis))?;
+                    acc = next;
+                }
+
+                // The hash is the x coordinate of the final accumulator
+                Ok(x_a)
+            },
+        )
+    }
+}
+
+#[derive(Default)]
+struct MyCircuit {
+    chunks: Vec<Option<u32>>,
+}
+
+impl Circuit<pallas::Base> for MyCircuit {
+    type Config = SinsemillaConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { chunks: vec![None; self.chunks.len()] }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        SinsemillaChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<pallas::Base>) -> Result<(), Error> {
+        let chip = SinsemillaChip::construct(config.clone());
+        chip.load_table(layouter.namespace(|| "table"))?;
+        let hash = chip.hash(layouter.namespace(|| "hash"), &self.chunks)?;
+        layouter.constrain_instance(hash.cell(), config.instance, 0)
+    }
+}
+
+fn main() {
+    // The table has 2^10 rows, so the circuit needs at least 2^11 rows
+    let k = 11;
+
+    // The message is private: the first 10 Fibonacci numbers, one per 10-bit chunk
+    let message: Vec<u32> = vec![1, 1, 2, 3, 5, 8, 13, 21, 34, 55];
+    let commitment = sinsemilla_hash(DOMAIN, &message);
+
+    let circuit = MyCircuit {
+        chunks: message.iter().copied().map(Some).collect(),
+    };
+    let prover = MockProver::run(k, &circuit, vec![vec![commitment]]).unwrap();
+    prover.expect_satisfied();
+
+    // A different message gives a different commitment
+    let circuit = MyCircuit {
+        chunks: message.iter().map(|m| Some(m + 1)).collect(),
+    };
+    let prover = MockProver::run(k, &circuit, vec![vec![commitment]]).unwrap();
+    assert!(prover.verify().is_err());
+}
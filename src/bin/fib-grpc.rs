@@ -0,0 +1,269 @@
+use std::{fs, net::SocketAddr, path::PathBuf, process::ExitCode, sync::Arc};
+
+use clap::{Parser, Subcommand};
+use halo2_proofs::pasta::{group::ff::PrimeField, Fp};
+use tonic::{transport::Server, Code, Request, Response, Status};
+
+use halo2_fibonacci_ex::{
+    bundle::{Metadata, ProofBundle},
+    error::FibError,
+    params::ParamsCache,
+    prover::CancellationToken,
+    service::Service,
+};
+
+mod proto {
+    tonic::include_proto!("fib.v1");
+}
+
+use proto::{
+    fib_prover_client::FibProverClient,
+    fib_prover_server::{FibProver, FibProverServer},
+    GetVerifyingKeyRequest, ProofArtifact, ProveRequest, VerifyResponse, VerifyingKey,
+};
+
+// The prover and the verifier of the Fibonacci circuit as a gRPC service, with the `grpc` feature, for deployments
+// where the services talk gRPC rather than HTTP/JSON. proto/fib.proto defines the service FibProver with Prove, Verify
+// and GetVerifyingKey, and the ProofArtifact message, the proof bundle of src/bundle.rs in protobuf:
+//
+//   cargo run --release --features grpc --bin fib-grpc -- serve --steps 8 --steps 100
+//
+// The binary is its own client as well, for scripts and smoke tests of a deployment:
+//
+//   fib-grpc prove -n 8 --a 1 --b 1 --output proof.json
+//   fib-grpc verify proof.json
+//   fib-grpc vk -n 8
+//
+// The server works as fib-server does (src/bin/fib-server.rs): the keys of the circuits given with --steps are
+// generated at startup (src/service.rs), and the proofs run on the blocking thread pool of tokio. Errors are returned
+// as a status whose message is the JSON of FibError::to_json, and a proof that doesn't verify is a VerifyResponse with
+// valid = false.
+//
+// The code of the service comes from proto/fib.proto, generated by tonic-build in build.rs.
+
+#[derive(Parser)]
+#[command(name = "fib-grpc", about = "Prove and verify Fibonacci statements over gRPC")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Serve the circuits with the given numbers of steps
+    Serve {
+        /// The address to listen on
+        #[arg(long, default_value = "127.0.0.1:50051")]
+        addr: SocketAddr,
+        /// Serve the circuit with this many steps. Repeat for several circuits
+        #[arg(long = "steps", required = true)]
+        steps: Vec<usize>,
+        /// Rows of the circuits are 2^k. Defaults to the smallest k each circuit fits in
+        #[arg(short, long)]
+        k: Option<u32>,
+        /// Load the public parameters from this directory instead of generating them, see `fib --params-dir`
+        #[arg(long)]
+        params_dir: Option<PathBuf>,
+    },
+    /// Ask a server for a proof and write the bundle as JSON
+    Prove {
+        #[arg(long, default_value = "http://127.0.0.1:50051")]
+        server: String,
+        /// Number of steps
+        #[arg(short, long)]
+        n: u64,
+        #[arg(long, default_value = "1")]
+        a: String,
+        #[arg(long, default_value = "1")]
+        b: String,
+        #[arg(short, long, default_value = "proof.json")]
+        output: PathBuf,
+    },
+    /// Ask a server to verify a proof bundle
+    Verify {
+        #[arg(long, default_value = "http://127.0.0.1:50051")]
+        server: String,
+        bundle: PathBuf,
+    },
+    /// Print the verifying key a server uses for the circuit with n steps
+    Vk {
+        #[arg(long, default_value = "http://127.0.0.1:50051")]
+        server: String,
+        /// Number of steps
+        #[arg(short, long)]
+        n: u64,
+    },
+}
+
+fn status(e: FibError) -> Status {
+    let code = match e {
+        FibError::InvalidInput(_) | FibError::Parse(_) | FibError::LimitExceeded(_) | FibError::Unsatisfied(_) => {
+            Code::InvalidArgument
+        }
+        FibError::KeyMismatch { .. } => Code::FailedPrecondition,
+        FibError::Timeout(_) => Code::DeadlineExceeded,
+        FibError::Cancelled => Code::Cancelled,
+        _ => Code::Internal,
+    };
+    Status::new(code, e.to_json().to_string())
+}
+
+// The FibError in the message of a status returned by the server, or the status itself
+fn fib_error(status: Status) -> FibError {
+    let json: Option<serde_json::Value> = serde_json::from_str(status.message()).ok();
+    match json.as_ref().and_then(|json| json["message"].as_str()) {
+        Some(message) => FibError::Internal(format!("the server failed: {}", message)),
+        None => FibError::Internal(format!("the server failed: {}", status)),
+    }
+}
+
+impl From<ProofBundle> for ProofArtifact {
+    fn from(bundle: ProofBundle) -> Self {
+        let metadata = bundle.metadata;
+        ProofArtifact {
+            metadata: Some(proto::Metadata {
+                crate_version: metadata.crate_version,
+                backend: metadata.backend,
+                k: metadata.k,
+                circuit_id: metadata.circuit_id,
+                created_at: metadata.created_at,
+            }),
+            instance: bundle.instance.iter().map(|value| value.to_repr().to_vec()).collect(),
+            proof: bundle.proof,
+        }
+    }
+}
+
+impl TryFrom<ProofArtifact> for ProofBundle {
+    type Error = FibError;
+
+    // Rejects the encodings of field elements that are not canonical, as ProofBundle::from_json does
+    fn try_from(artifact: ProofArtifact) -> Result<Self, FibError> {
+        let metadata = artifact.metadata.ok_or_else(|| FibError::Parse("the artifact has no metadata".to_string()))?;
+        let instance = artifact
+            .instance
+            .iter()
+            .map(|bytes| {
+                let repr = <[u8; 32]>::try_from(bytes.as_slice())
+                    .map_err(|_| FibError::Parse(format!("a field element of {} bytes instead of 32", bytes.len())))?;
+                Option::from(Fp::from_repr(repr))
+                    .ok_or_else(|| FibError::Parse(format!("{} is not a canonical field element", hex::encode(repr))))
+            })
+            .collect::<Result<_, _>>()?;
+        let metadata = Metadata {
+            crate_version: metadata.crate_version,
+            backend: metadata.backend,
+            k: metadata.k,
+            circuit_id: metadata.circuit_id,
+            created_at: metadata.created_at,
+        };
+        Ok(ProofBundle::new(metadata, instance, artifact.proof))
+    }
+}
+
+struct FibService(Arc<Service>);
+
+// Runs `f` on the blocking thread pool, where the prover doesn't stall the requests served meanwhile
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T, FibError> + Send + 'static) -> Result<T, FibError> {
+    tokio::task::spawn_blocking(f).await.map_err(|e| FibError::Internal(format!("the task didn't complete: {}", e)))?
+}
+
+fn steps(n: u64) -> Result<usize, FibError> {
+    usize::try_from(n).map_err(|_| FibError::InvalidInput(format!("{} steps", n)))
+}
+
+#[tonic::async_trait]
+impl FibProver for FibService {
+    async fn prove(&self, request: Request<ProveRequest>) -> Result<Response<ProofArtifact>, Status> {
+        let request = request.into_inner();
+        let service = self.0.clone();
+        let n = steps(request.n).map_err(status)?;
+        // tonic drops the future of a request whose client went away, which cancels a proof that didn't start yet
+        let token = CancellationToken::new();
+        let _cancel = token.cancel_on_drop();
+        let bundle = blocking(move || service.prove(n, &request.a, &request.b, &token)).await.map_err(status)?;
+        Ok(Response::new(bundle.into()))
+    }
+
+    async fn verify(&self, request: Request<ProofArtifact>) -> Result<Response<VerifyResponse>, Status> {
+        let bundle = ProofBundle::try_from(request.into_inner()).map_err(status)?;
+        let service = self.0.clone();
+        match blocking(move || service.verify(&bundle)).await {
+            Ok(verified) => Ok(Response::new(VerifyResponse {
+                valid: true,
+                circuit_id: verified.circuit_id,
+                k: verified.k,
+                fingerprint: verified.fingerprint,
+            })),
+            Err(FibError::InvalidProof) => Ok(Response::new(VerifyResponse { valid: false, ..Default::default() })),
+            Err(e) => Err(status(e)),
+        }
+    }
+
+    async fn get_verifying_key(&self, request: Request<GetVerifyingKeyRequest>) -> Result<Response<VerifyingKey>, Status> {
+        let key = self.0.verifying_key(steps(request.into_inner().n).map_err(status)?).map_err(status)?;
+        Ok(Response::new(VerifyingKey { circuit_id: key.circuit_id, k: key.k, fingerprint: key.fingerprint, pinned: key.pinned }))
+    }
+}
+
+async fn serve(addr: SocketAddr, steps: &[usize], k: Option<u32>, params_dir: Option<PathBuf>) -> Result<(), FibError> {
+    let service = Service::new(steps, k, params_dir.map(ParamsCache::new))?;
+    for n in service.steps() {
+        let key = service.verifying_key(n)?;
+        eprintln!("serving {} over 2^{} rows, verifying key {}", key.circuit_id, key.k, key.fingerprint);
+    }
+    eprintln!("listening on {}", addr);
+    Server::builder()
+        .add_service(FibProverServer::new(FibService(Arc::new(service))))
+        .serve_with_shutdown(addr, async {
+            // Ctrl-C finishes the requests in flight. Without a handler, it stops the process as usual
+            if tokio::signal::ctrl_c().await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        })
+        .await
+        .map_err(|e| FibError::Internal(e.to_string()))
+}
+
+async fn connect(server: String) -> Result<FibProverClient<tonic::transport::Channel>, FibError> {
+    FibProverClient::connect(server).await.map_err(|e| FibError::Internal(format!("can't connect: {}", e)))
+}
+
+async fn run(cli: Cli) -> Result<(), FibError> {
+    match cli.command {
+        Command::Serve { addr, steps, k, params_dir } => serve(addr, &steps, k, params_dir).await,
+        Command::Prove { server, n, a, b, output } => {
+            let artifact = connect(server).await?.prove(ProveRequest { n, a, b }).await.map_err(fib_error)?.into_inner();
+            let bundle = ProofBundle::try_from(artifact)?;
+            fs::write(&output, bundle.to_json()).map_err(|e| FibError::io(&output, e))?;
+            println!("proof written to {} ({} bytes)", output.display(), bundle.proof.len());
+            Ok(())
+        }
+        Command::Verify { server, bundle } => {
+            let json = fs::read_to_string(&bundle).map_err(|e| FibError::io(&bundle, e))?;
+            let artifact = ProofArtifact::from(ProofBundle::from_json(&json)?);
+            let response = connect(server).await?.verify(artifact).await.map_err(fib_error)?.into_inner();
+            if !response.valid {
+                return Err(FibError::InvalidProof);
+            }
+            println!("valid proof of {} over 2^{} rows, verifying key {}", response.circuit_id, response.k, response.fingerprint);
+            Ok(())
+        }
+        Command::Vk { server, n } => {
+            let key = connect(server).await?.get_verifying_key(GetVerifyingKeyRequest { n }).await.map_err(fib_error)?.into_inner();
+            println!("{} over 2^{} rows, verifying key {}", key.circuit_id, key.k, key.fingerprint);
+            Ok(())
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(e.code())
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+
+use halo2_fibonacci_ex::{
+    params::ParamsCache,
+    prover::Limits,
+    server::{self, ServeArgs},
+};
+
+// The HTTP service of src/server.rs, with the `http` feature:
+//
+//   cargo run --release --features http --bin fib-server -- --steps 8 --steps 100
+
+#[derive(Parser)]
+#[command(name = "fib-server", about = "Prove and verify Fibonacci statements over HTTP")]
+struct Cli {
+    #[command(flatten)]
+    serve: ServeArgs,
+
+    /// Load the public parameters from this directory instead of generating them, see `fib --params-dir`
+    #[arg(long)]
+    params_dir: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match server::serve(cli.serve, cli.params_dir.map(ParamsCache::new), Limits::default().max_k).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(e.code())
+        }
+    }
+}
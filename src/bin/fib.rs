@@ -0,0 +1,865 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+    time::Duration,
+};
+
+use clap::{Args, Parser, Subcommand};
+use halo2_proofs::{
+    dev::{CircuitGates, MockProver},
+    pasta::Fp,
+    plonk::{keygen_vk, Circuit},
+    poly::commitment::Params,
+};
+use rand_chacha::{
+    rand_core::{OsRng, RngCore, SeedableRng},
+    ChaCha20Rng,
+};
+use serde::{Deserialize, Serialize};
+
+use halo2_fibonacci_ex::{
+    aggregate,
+    archive::{ArchiveMetadata, ProofArchive},
+    backend::{Backend, IpaPasta},
+    bundle::{Metadata, ProofBundle},
+    dump::witness_dump,
+    error::FibError,
+    fibonacci::{FibCircuit, FibInputs, FibInstance, Fibonacci},
+    field::{display_field, parse_field},
+    fingerprints::{FingerprintStore, KeyStatus},
+    golden,
+    import::ImportedWitness,
+    params::{self, ParamsCache},
+    prover::{self, CancellationToken, Limits},
+    registry::{verify_bundle, Registry},
+    report::FailureReport,
+    stats::{circuit_stats, utilization},
+    transcript::TranscriptKind,
+};
+#[cfg(feature = "http")]
+use halo2_fibonacci_ex::server::{self, ServeArgs};
+
+// Command line tools around the Fibonacci circuit of the library (src/fibonacci.rs).
+// Unlike the examples, which each walk through one idea, this binary is meant to be used while working on the chips.
+
+#[derive(Parser)]
+#[command(name = "fib", about = "Tools for the Fibonacci circuit")]
+struct Cli {
+    /// Print the results and the errors as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Refuse circuits with more than 2^max_k rows
+    #[arg(long, global = true, default_value_t = Limits::default().max_k)]
+    max_k: u32,
+
+    /// Print the rows, cells and copies of every region of the circuit to stderr before proving, and the failures of
+    /// the rejected corpus entries
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Keep the public parameters in this directory, to load them instead of generating them on every run
+    #[arg(long, global = true)]
+    params_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run every input file of a corpus directory through the MockProver and check the expected outcome
+    Replay {
+        /// Directory containing the .json input files
+        dir: PathBuf,
+    },
+    /// Run a Fibonacci statement through the MockProver
+    Mock {
+        #[command(flatten)]
+        statement: Statement,
+        /// The claimed output. Defaults to the output computed natively
+        #[arg(long)]
+        out: Option<String>,
+        /// Write every assigned cell (region, column, row, value) to this JSON file
+        #[arg(long)]
+        dump_witness: Option<PathBuf>,
+        /// Write the assigned cells as a CSV table, one line per row and one column per column of the circuit
+        #[arg(long)]
+        table_csv: Option<PathBuf>,
+    },
+    /// Run a witness computed elsewhere (a JSON dump of `mock --dump-witness`, or the same cells as CSV) through the
+    /// MockProver
+    Import {
+        #[arg(short, long)]
+        k: u32,
+        /// The public inputs a, b, out of a CSV witness, one --instance per value. A JSON witness has its own
+        #[arg(long)]
+        instance: Vec<String>,
+        /// The witness file. Files ending in .csv are read as CSV, the other ones as JSON
+        witness: PathBuf,
+    },
+    /// Create a proof of a Fibonacci statement. Ctrl-C cancels it after the current proving stage
+    Prove {
+        #[command(flatten)]
+        statement: Statement,
+        /// Where to write the proof bundle
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Give up after this many seconds, checked between the proving stages
+        #[arg(long)]
+        timeout: Option<u64>,
+        /// Print the rows of the table assigned so far to stderr, for long sequences
+        #[arg(long)]
+        progress: bool,
+        /// Draw the blinding factors from a ChaCha20 rng with this seed, so that the proof is the same on every run.
+        /// For tests and fixtures only: the seed reveals the witness
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Check the fingerprint of the keys against the one recorded in this directory, and warn when the circuit
+        /// changed since. The parameters are kept in <dir>/params. Keygen still runs, halo2_proofs 0.1.0 can't write
+        /// keys
+        #[arg(long, value_name = "DIR")]
+        fingerprints: Option<PathBuf>,
+    },
+    /// Verify a proof created by `prove` against the claimed output
+    Verify {
+        #[command(flatten)]
+        statement: Option<Statement>,
+        /// The claimed output, in decimal, in hex with a 0x prefix or as the bytes of its representation
+        #[arg(long, required_unless_present = "all")]
+        out: Option<String>,
+        /// Print where the proof comes from (crate version, backend, k, circuit id, creation time)
+        #[arg(long)]
+        metadata: bool,
+        /// Only accept the proof if the verifying key has this fingerprint (hex SHA-256, printed by --metadata)
+        #[arg(long)]
+        fingerprint: Option<String>,
+        /// The proof bundle created by `prove`
+        #[arg(required_unless_present = "all")]
+        bundle: Option<PathBuf>,
+        /// Verify every proof bundle (.json file) of this directory instead, whatever their statements, in batches
+        #[arg(long, value_name = "DIR", conflicts_with_all = ["out", "bundle", "k", "n", "a", "b", "fingerprint"])]
+        all: Option<PathBuf>,
+    },
+    /// Verify proof bundles of any statements in one batch and print an attestation of the statements they prove
+    Aggregate {
+        /// Also write the attestation to this JSON file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// The proof bundles created by `prove`
+        #[arg(required = true)]
+        bundles: Vec<PathBuf>,
+    },
+    /// Pack proof bundles of one circuit into an archive sharing their metadata, with the hashes of the verifying key
+    /// and the parameters
+    Bundle {
+        #[arg(short, long)]
+        output: PathBuf,
+        /// The proof bundles created by `prove`
+        #[arg(required = true)]
+        bundles: Vec<PathBuf>,
+    },
+    /// Write the proofs of an archive created by `bundle` back as proof bundles, proof-<i>.json in a directory
+    Unbundle {
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+        archive: PathBuf,
+    },
+    /// Print how many of the 2^k rows a statement uses, the rows reserved by the prover and the smallest k it fits in
+    Rows {
+        #[command(flatten)]
+        statement: Statement,
+    },
+    /// Print the gates of the circuit with their named constraints, and the number of operations they use
+    Gates,
+    /// Print a hash of the verifying key of a circuit with its k and circuit id, to check that a prover and a verifier
+    /// run the same version of the circuit
+    VkHash {
+        /// Rows of the circuit are 2^k
+        #[arg(short, long)]
+        k: u32,
+        /// Number of steps
+        #[arg(short, long)]
+        n: usize,
+    },
+    /// Write public parameters to a file, or check one, to share them between provers and verifiers
+    Params {
+        #[command(subcommand)]
+        command: ParamsCommand,
+    },
+    /// Compare the verifying keys of the circuits with the golden fixtures checked in under golden/
+    Golden {
+        /// Rewrite the fixtures with the keys of this build, after a change to a circuit made on purpose
+        #[arg(long)]
+        bless: bool,
+        #[arg(long, default_value = "golden")]
+        dir: PathBuf,
+    },
+    /// Serve the prover over HTTP like fib-server (the http feature). With --daemon, proofs are background jobs
+    #[cfg(feature = "http")]
+    Serve(ServeArgs),
+}
+
+#[derive(Subcommand)]
+enum ParamsCommand {
+    /// Generate the parameters for 2^k rows and write them to a file
+    Write {
+        #[arg(short, long)]
+        k: u32,
+        file: PathBuf,
+    },
+    /// Read the parameters for 2^k rows from a file and print its size and checksum
+    Check {
+        #[arg(short, long)]
+        k: u32,
+        /// Fail unless the file has this SHA-256, as printed by `write`
+        #[arg(long)]
+        sha256: Option<String>,
+        file: PathBuf,
+    },
+}
+
+// The shape of the circuit and its public inputs a, b. The field elements are strings read by field::parse_field
+#[derive(Args)]
+struct Statement {
+    #[arg(short, long)]
+    k: u32,
+    /// Number of steps
+    #[arg(short, long)]
+    n: usize,
+    #[arg(long, default_value = "1")]
+    a: String,
+    #[arg(long, default_value = "1")]
+    b: String,
+}
+
+impl Statement {
+    fn inputs(&self) -> Result<FibInputs<Fp>, FibError> {
+        Ok(FibInputs::new(parse_field(&self.a)?, parse_field(&self.b)?, self.n))
+    }
+}
+
+// An input file of the corpus. The field elements are strings read by field::parse_field, so that values close to the
+// modulus can be written down
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CorpusEntry {
+    #[serde(default)]
+    description: String,
+    k: u32,
+    a: String,
+    b: String,
+    n: usize,
+    // The claimed output. When missing, the output computed natively is used
+    out: Option<String>,
+    #[serde(default)]
+    expect: Expect,
+}
+
+// The outcome of replaying an entry. Error means the entry itself is broken (e.g. a value that is not a number),
+// which is never expected
+#[derive(Serialize, Deserialize, Default, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+enum Expect {
+    #[default]
+    Accept,
+    Reject,
+    #[serde(skip_deserializing)]
+    Error,
+}
+
+fn read_entry(file: &Path) -> Result<CorpusEntry, FibError> {
+    let s = fs::read_to_string(file).map_err(|e| FibError::io(file, e))?;
+    serde_json::from_str(&s).map_err(|e| FibError::Parse(format!("{}: {}", file.display(), e)))
+}
+
+// Mock-verifies one entry. A circuit that doesn't fit in 2^k rows or whose constraints fail is rejected. With
+// `verbose` the failures are printed to stderr, with the names of the columns
+fn replay(entry: &CorpusEntry, limits: &Limits, verbose: bool) -> Result<(), FibError> {
+    limits.check_k(entry.k)?;
+    let inputs = FibInputs::<Fp>::new(parse_field(&entry.a)?, parse_field(&entry.b)?, entry.n);
+    let mut instance = inputs.instance();
+    if let Some(out) = &entry.out {
+        instance.out = parse_field(out)?;
+    }
+
+    let prover = MockProver::run(entry.k, &inputs.circuit(), instance.to_instance_columns())?;
+    prover.verify().map_err(|failures| {
+        if verbose {
+            eprint!("{}", FailureReport::new(&failures, &FibCircuit::<Fp>::column_names()));
+        }
+        FibError::Unsatisfied(failures.len())
+    })
+}
+
+fn replay_dir(dir: &Path, limits: &Limits, json: bool, verbose: bool) -> Result<(), FibError> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| FibError::io(dir, e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+
+    let mut failures = 0;
+    for file in files.iter() {
+        // The error of the entry, or the error that made it rejected
+        let (entry, result) = match read_entry(file) {
+            Ok(entry) => {
+                let result = replay(&entry, limits, verbose);
+                (Some(entry), result)
+            }
+            Err(e) => (None, Err(e)),
+        };
+
+        let outcome = match &result {
+            Ok(()) => Expect::Accept,
+            Err(FibError::Circuit(_)) | Err(FibError::Unsatisfied(_)) => Expect::Reject,
+            Err(_) => Expect::Error,
+        };
+        let expected = entry.as_ref().map(|entry| entry.expect);
+        let ok = expected == Some(outcome);
+        if !ok {
+            failures += 1;
+        }
+
+        if json {
+            let line = serde_json::json!({
+                "file": file.display().to_string(),
+                "ok": ok,
+                "expect": expected,
+                "outcome": outcome,
+                "error": result.as_ref().err().map(FibError::to_json),
+            });
+            println!("{}", line);
+        } else if ok {
+            println!("ok    {}  {}", file.display(), entry.map(|entry| entry.description).unwrap_or_default());
+        } else {
+            let error = result.err().map(|e| format!(" ({})", e)).unwrap_or_default();
+            match expected {
+                Some(expected) => println!("FAIL  {}  expected {:?}, got {:?}{}", file.display(), expected, outcome, error),
+                None => println!("FAIL  {}{}", file.display(), error),
+            }
+        }
+    }
+
+    if !json {
+        println!("{} files, {} failures", files.len(), failures);
+    }
+    match failures {
+        0 => Ok(()),
+        _ => Err(FibError::Regression(failures)),
+    }
+}
+
+fn mock(
+    statement: &Statement,
+    out: Option<&str>,
+    dump_witness: Option<&Path>,
+    table_csv: Option<&Path>,
+    limits: &Limits,
+    json: bool,
+    verbose: bool,
+) -> Result<(), FibError> {
+    limits.check_k(statement.k)?;
+    let inputs = statement.inputs()?;
+    let mut instance = inputs.instance();
+    if let Some(out) = out {
+        instance.out = parse_field(out)?;
+    }
+
+    let names = FibCircuit::<Fp>::column_names();
+    if dump_witness.is_some() || table_csv.is_some() {
+        let dump = witness_dump(&inputs.circuit(), instance.to_instance_columns())?.with_names(&names);
+        if let Some(path) = dump_witness {
+            fs::write(path, dump.to_json()).map_err(|e| FibError::io(path, e))?;
+        }
+        if let Some(path) = table_csv {
+            fs::write(path, dump.to_csv()).map_err(|e| FibError::io(path, e))?;
+        }
+    }
+
+    let prover = MockProver::run(statement.k, &inputs.circuit(), instance.to_instance_columns())?;
+    prover.verify().map_err(|failures| {
+        if verbose {
+            eprint!("{}", FailureReport::new(&failures, &names));
+        }
+        FibError::Unsatisfied(failures.len())
+    })?;
+
+    if json {
+        println!("{}", serde_json::json!({ "satisfied": true }));
+    } else {
+        println!("the statement is satisfied");
+    }
+    Ok(())
+}
+
+fn import(k: u32, witness: &Path, instance: &[String], limits: &Limits, json: bool, verbose: bool) -> Result<(), FibError> {
+    limits.check_k(k)?;
+    let s = fs::read_to_string(witness).map_err(|e| FibError::io(witness, e))?;
+    let witness = if witness.extension().is_some_and(|ext| ext == "csv") {
+        let instance = instance.iter().map(|value| parse_field(value)).collect::<Result<Vec<Fp>, _>>()?;
+        ImportedWitness::from_csv::<Fibonacci>(&s, vec![instance])?
+    } else if instance.is_empty() {
+        ImportedWitness::from_json::<Fibonacci>(&s)?
+    } else {
+        return Err(FibError::InvalidInput("--instance is only for CSV witnesses".to_string()));
+    };
+
+    let prover = MockProver::run(k, &witness.circuit::<Fibonacci>(), witness.instance.clone())?;
+    prover.verify().map_err(|failures| {
+        if verbose {
+            eprint!("{}", FailureReport::new(&failures, &FibCircuit::<Fp>::column_names()));
+        }
+        FibError::Unsatisfied(failures.len())
+    })?;
+
+    if json {
+        println!("{}", serde_json::json!({ "satisfied": true, "steps": witness.steps() }));
+    } else {
+        println!("the witness of {} steps is satisfied", witness.steps());
+    }
+    Ok(())
+}
+
+// Prints the percentage of the rows assigned each time it changes, on a single line
+fn print_progress(done: usize, total: usize) {
+    if total == 0 || done * 100 / total == (done - 1) * 100 / total {
+        return;
+    }
+    eprint!("\rsynthesis: {}/{} rows ({}%)", done, total, done * 100 / total);
+    if done == total {
+        eprintln!();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn prove(
+    statement: &Statement,
+    output: &Path,
+    progress: bool,
+    seed: Option<u64>,
+    params: Option<&ParamsCache>,
+    fingerprints: Option<&FingerprintStore>,
+    limits: &Limits,
+    json: bool,
+    verbose: bool,
+) -> Result<(), FibError> {
+    let inputs = statement.inputs()?;
+    let instance = inputs.public_inputs();
+    if verbose {
+        let stats = circuit_stats(&inputs.circuit(), vec![instance.clone()])?;
+        eprint!("{}", stats.with_names(FibCircuit::<Fp>::column_names()));
+    }
+
+    let token = CancellationToken::new();
+    let handler_token = token.clone();
+    ctrlc::set_handler(move || {
+        eprintln!("cancelling after the current stage...");
+        handler_token.cancel();
+    })
+    .map_err(|e| FibError::InvalidInput(e.to_string()))?;
+
+    let mut circuit = inputs.circuit();
+    if progress {
+        circuit = circuit.with_progress(Arc::new(print_progress));
+    }
+    let mut rng: Box<dyn RngCore> = match seed {
+        Some(seed) => Box::new(ChaCha20Rng::seed_from_u64(seed)),
+        None => Box::new(OsRng),
+    };
+    let proof = match fingerprints {
+        Some(store) => {
+            limits.check_k(statement.k)?;
+            let (keys, status) = store.fibonacci(statement.k, statement.n)?;
+            if status == KeyStatus::Changed {
+                eprintln!("warning: the circuit changed since its fingerprint was recorded in {}", store.dir().display());
+            }
+            token.check()?;
+            TranscriptKind::Blake2b.create_proof_with_rng(&keys.params, &keys.pk, &circuit, &instance, &mut *rng)?
+        }
+        None => prover::prove_with_rng(statement.k, &circuit, &instance, &token, limits, params, &mut *rng)?,
+    };
+    let bundle = ProofBundle::new(Metadata::new(statement.k, inputs.circuit_id()), instance, proof);
+    fs::write(output, bundle.to_json()).map_err(|e| FibError::io(output, e))?;
+
+    if json {
+        println!("{}", serde_json::json!({ "bundle": output.display().to_string(), "proof_bytes": bundle.proof.len() }));
+    } else {
+        println!("proof written to {} ({} bytes)", output.display(), bundle.proof.len());
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn verify(
+    statement: &Statement,
+    out: &str,
+    bundle: &Path,
+    show_metadata: bool,
+    fingerprint: Option<&str>,
+    params: Option<&ParamsCache>,
+    limits: &Limits,
+    json: bool,
+) -> Result<(), FibError> {
+    limits.check_k(statement.k)?;
+    let inputs = statement.inputs()?;
+    let instance = FibInstance { out: parse_field(out)?, ..inputs.instance() }.column();
+    let bundle = fs::read_to_string(bundle).map_err(|e| FibError::io(bundle, e))?;
+    let bundle = ProofBundle::from_json(&bundle)?;
+
+    if show_metadata && !json {
+        let metadata = &bundle.metadata;
+        println!("crate version: {}", metadata.crate_version);
+        println!("backend:       {}", metadata.backend);
+        println!("k:             {}", metadata.k);
+        println!("circuit id:    {}", metadata.circuit_id);
+        println!("created at:    {}", metadata.created_at);
+    }
+
+    // The statement is the one given on the command line, the instance stored in the bundle is only compared to it
+    // to give a clearer error than an invalid proof
+    if bundle.instance != instance {
+        return Err(FibError::InvalidInput("the bundle was created for a different statement".to_string()));
+    }
+
+    let mut registry = Registry::new();
+    registry.register_fibonacci(statement.k, statement.n);
+    if let Some(cache) = params {
+        registry.set_params_cache(cache.clone());
+    }
+    if let Some(fingerprint) = fingerprint {
+        registry.pin_fingerprint(&inputs.circuit_id(), fingerprint)?;
+    }
+    let verified = verify_bundle(&bundle, &registry)?;
+
+    if json {
+        let mut result = serde_json::json!({ "verified": true });
+        if show_metadata {
+            result["metadata"] = serde_json::to_value(&bundle.metadata).expect("metadata can always be serialized");
+            result["fingerprint"] = verified.fingerprint.into();
+        }
+        println!("{}", result);
+    } else {
+        if show_metadata {
+            println!("vk fingerprint: {}", verified.fingerprint);
+        }
+        println!("the proof is valid");
+    }
+    Ok(())
+}
+
+// Reads proof bundles of any statements, and a registry of their circuits built from their metadata. The first bundle
+// of a circuit gives its k, the others must have the same one
+fn load_bundles(paths: &[PathBuf], params: Option<&ParamsCache>, limits: &Limits) -> Result<(Vec<ProofBundle>, Registry), FibError> {
+    let mut registry = Registry::new();
+    if let Some(cache) = params {
+        registry.set_params_cache(cache.clone());
+    }
+    let mut loaded = vec![];
+    for path in paths {
+        let bundle = fs::read_to_string(path).map_err(|e| FibError::io(path, e))?;
+        let bundle = ProofBundle::from_json(&bundle).map_err(|e| FibError::Parse(format!("{}: {}", path.display(), e)))?;
+        let metadata = &bundle.metadata;
+        limits.check_k(metadata.k)?;
+        let n = FibInputs::<Fp>::steps_of(&metadata.circuit_id);
+        let n = n.ok_or_else(|| FibError::InvalidInput(format!("{}: not a Fibonacci proof: {}", path.display(), metadata.circuit_id)))?;
+        if !registry.contains(&metadata.circuit_id) {
+            registry.register_fibonacci(metadata.k, n);
+        }
+        loaded.push(bundle);
+    }
+    Ok((loaded, registry))
+}
+
+// The metadata of the archive of the Fibonacci circuit with n steps over 2^k rows, as generated by this build
+fn archive_metadata(k: u32, n: usize, params: Option<&ParamsCache>) -> Result<ArchiveMetadata, FibError> {
+    let params = match params {
+        Some(cache) => cache.load_or_setup::<IpaPasta>(k)?,
+        None => Params::new(k),
+    };
+    ArchiveMetadata::fibonacci(k, n, &params)
+}
+
+fn bundle(bundles: &[PathBuf], output: &Path, params: Option<&ParamsCache>, limits: &Limits, json: bool) -> Result<(), FibError> {
+    let (loaded, _) = load_bundles(bundles, params, limits)?;
+    let first = &loaded[0].metadata;
+    let n = FibInputs::<Fp>::steps_of(&first.circuit_id).unwrap_or_default();
+    let mut archive = ProofArchive::new(archive_metadata(first.k, n, params)?);
+    for (path, bundle) in bundles.iter().zip(loaded) {
+        archive.append(bundle).map_err(|e| match e {
+            FibError::InvalidInput(message) => FibError::InvalidInput(format!("{}: {}", path.display(), message)),
+            e => e,
+        })?;
+    }
+    fs::write(output, archive.to_json()).map_err(|e| FibError::io(output, e))?;
+
+    let metadata = &archive.metadata;
+    if json {
+        let result = serde_json::json!({
+            "archive": output.display().to_string(),
+            "proofs": archive.len(),
+            "fingerprint": metadata.fingerprint,
+            "vk_hash": metadata.vk_hash,
+            "params_hash": metadata.params_hash,
+        });
+        println!("{}", result);
+    } else {
+        println!("{} proofs of {} written to {}", archive.len(), metadata.circuit_id, output.display());
+        println!("vk fingerprint {}", metadata.fingerprint);
+        println!("vk hash {}, params hash {}", metadata.vk_hash, metadata.params_hash);
+    }
+    Ok(())
+}
+
+// Warns when this build has other keys or parameters than the prover of the archive: its proofs won't verify here
+fn unbundle(archive: &Path, dir: &Path, params: Option<&ParamsCache>, limits: &Limits, json: bool) -> Result<(), FibError> {
+    let json_archive = fs::read_to_string(archive).map_err(|e| FibError::io(archive, e))?;
+    let archive = ProofArchive::from_json(&json_archive).map_err(|e| FibError::Parse(format!("{}: {}", archive.display(), e)))?;
+    let metadata = &archive.metadata;
+    limits.check_k(metadata.k)?;
+    if let Some(n) = FibInputs::<Fp>::steps_of(&metadata.circuit_id) {
+        let local = archive_metadata(metadata.k, n, params)?;
+        if local.vk_hash != metadata.vk_hash {
+            eprintln!("warning: the verifying key of {} differs in this build ({})", metadata.circuit_id, local.vk_hash);
+        }
+        if local.params_hash != metadata.params_hash {
+            eprintln!("warning: the parameters for k = {} differ in this build ({})", metadata.k, local.params_hash);
+        }
+    }
+
+    fs::create_dir_all(dir).map_err(|e| FibError::io(dir, e))?;
+    let mut written = vec![];
+    for (i, bundle) in archive.iter().enumerate() {
+        let path = dir.join(format!("proof-{}.json", i));
+        fs::write(&path, bundle.to_json()).map_err(|e| FibError::io(&path, e))?;
+        written.push(path.display().to_string());
+    }
+    if json {
+        println!("{}", serde_json::json!({ "bundles": written }));
+    } else {
+        println!("{} proofs of {} written to {}", written.len(), metadata.circuit_id, dir.display());
+    }
+    Ok(())
+}
+
+// The circuits are registered from the metadata of the bundles: the attestation lists the statements that were proven,
+// whatever they are
+fn aggregate(bundles: &[PathBuf], output: Option<&Path>, params: Option<&ParamsCache>, limits: &Limits, json: bool) -> Result<(), FibError> {
+    let (loaded, registry) = load_bundles(bundles, params, limits)?;
+
+    let attestation = match aggregate::aggregate(&loaded, &registry) {
+        Ok(attestation) => attestation,
+        Err(FibError::InvalidProof) => {
+            // The batch doesn't tell which proof is invalid, verify_batch does
+            if !json {
+                for (path, valid) in bundles.iter().zip(aggregate::verify_batch(&loaded, &registry)) {
+                    if !valid {
+                        eprintln!("{}: the proof doesn't verify", path.display());
+                    }
+                }
+            }
+            return Err(FibError::InvalidProof);
+        }
+        Err(e) => return Err(e),
+    };
+
+    if let Some(output) = output {
+        fs::write(output, attestation.to_json()).map_err(|e| FibError::io(output, e))?;
+    }
+    if json {
+        println!("{}", serde_json::to_string(&attestation).expect("an attestation can always be serialized"));
+    } else {
+        for statement in &attestation.statements {
+            let [a, b, out] = [0, 1, 2].map(|i| display_field(statement.instance[i]));
+            println!("{} (k = {}): a = {}, b = {}, out = {}", statement.circuit_id, statement.k, a, b, out);
+        }
+        println!("{} proofs verified, digest {}", attestation.statements.len(), attestation.digest);
+    }
+    Ok(())
+}
+
+// Every proof bundle of `dir`, verified in batches. Fails with FibError::InvalidProof if any of them doesn't verify
+fn verify_all(dir: &Path, params: Option<&ParamsCache>, limits: &Limits, json: bool) -> Result<(), FibError> {
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir).map_err(|e| FibError::io(dir, e))? {
+        let path = entry.map_err(|e| FibError::io(dir, e))?.path();
+        if path.extension().is_some_and(|extension| extension == "json") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    if paths.is_empty() {
+        return Err(FibError::InvalidInput(format!("no proof bundle in {}", dir.display())));
+    }
+
+    let (bundles, registry) = load_bundles(&paths, params, limits)?;
+    let valid = aggregate::verify_batch(&bundles, &registry);
+    let invalid = valid.iter().filter(|&&valid| !valid).count();
+    if json {
+        let results: Vec<_> = paths
+            .iter()
+            .zip(&valid)
+            .map(|(path, valid)| serde_json::json!({ "bundle": path.display().to_string(), "valid": valid }))
+            .collect();
+        println!("{}", serde_json::json!({ "results": results, "invalid": invalid }));
+    } else {
+        for (path, valid) in paths.iter().zip(&valid) {
+            println!("{}: {}", path.display(), if *valid { "valid" } else { "the proof doesn't verify" });
+        }
+        println!("{} of {} proofs verified", valid.len() - invalid, valid.len());
+    }
+    if invalid > 0 {
+        return Err(FibError::InvalidProof);
+    }
+    Ok(())
+}
+
+// A statement that doesn't fit in 2^k rows is not an error here, printing why is the point
+fn rows(statement: &Statement, json: bool) -> Result<(), FibError> {
+    let inputs = statement.inputs()?;
+    let report = utilization(&inputs.circuit(), vec![inputs.public_inputs()], statement.k)?;
+    if json {
+        let result = serde_json::json!({
+            "k": report.k,
+            "rows": report.rows(),
+            "rows_used": report.rows_used,
+            "reserved_rows": report.reserved_rows,
+            "usable_rows": report.usable_rows(),
+            "fits": report.fits(),
+            "min_k": report.min_k,
+        });
+        println!("{}", result);
+    } else {
+        print!("{}", report);
+    }
+    Ok(())
+}
+
+fn vk_hash(k: u32, n: usize, params: Option<&ParamsCache>, limits: &Limits, json: bool) -> Result<(), FibError> {
+    limits.check_k(k)?;
+    let inputs = FibInputs::new(Fp::zero(), Fp::zero(), n);
+    let params = match params {
+        Some(cache) => cache.load_or_setup::<IpaPasta>(k)?,
+        None => Params::new(k),
+    };
+    let vk = keygen_vk(&params, &inputs.circuit().without_witnesses())?;
+    let (circuit_id, hash) = (inputs.circuit_id(), prover::vk_hash(&inputs.circuit_id(), k, &vk));
+    if json {
+        println!("{}", serde_json::json!({ "circuit_id": circuit_id, "k": k, "vk_hash": hash }));
+    } else {
+        println!("{}  {} k={}", hash, circuit_id, k);
+    }
+    Ok(())
+}
+
+fn params_file(command: &ParamsCommand, limits: &Limits, json: bool) -> Result<(), FibError> {
+    let (k, file, info) = match command {
+        ParamsCommand::Write { k, file } => {
+            limits.check_k(*k)?;
+            (*k, file, params::write_params::<IpaPasta>(&IpaPasta::setup(*k), file)?)
+        }
+        ParamsCommand::Check { k, sha256, file } => {
+            let (_, info) = params::read_params::<IpaPasta>(file, *k)?;
+            match sha256 {
+                Some(sha256) if !sha256.eq_ignore_ascii_case(&info.sha256) => {
+                    return Err(FibError::InvalidInput(format!("{} has SHA-256 {}, not {}", file.display(), info.sha256, sha256)));
+                }
+                _ => (*k, file, info),
+            }
+        }
+    };
+    if json {
+        let result = serde_json::json!({ "file": file.display().to_string(), "k": k, "bytes": info.bytes, "sha256": info.sha256 });
+        println!("{}", result);
+    } else {
+        println!("{}: parameters for k = {}, {} bytes, sha256 {}", file.display(), k, info.bytes, info.sha256);
+    }
+    Ok(())
+}
+
+fn golden(dir: &Path, bless: bool, json: bool) -> Result<(), FibError> {
+    if bless {
+        let keys = golden::bless(dir)?;
+        if json {
+            let keys: Vec<_> = keys.iter().map(|key| serde_json::json!({ "name": key.name, "fingerprint": key.fingerprint })).collect();
+            println!("{}", serde_json::json!({ "blessed": keys }));
+        } else {
+            for key in &keys {
+                println!("{} {}", key.name, key.fingerprint);
+            }
+        }
+        return Ok(());
+    }
+
+    let mismatches = golden::check(dir)?;
+    if json {
+        let mismatches: Vec<_> = mismatches
+            .iter()
+            .map(|m| serde_json::json!({ "name": m.name, "line": m.line, "expected": m.expected, "actual": m.actual }))
+            .collect();
+        println!("{}", serde_json::json!({ "mismatches": mismatches }));
+    } else {
+        for m in &mismatches {
+            println!("{}.vk:{}: the verifying key changed", m.name, m.line);
+            println!("  expected: {}", m.expected.trim());
+            println!("  actual:   {}", m.actual.trim());
+        }
+    }
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(FibError::Regression(mismatches.len()))
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let limits = Limits { max_k: cli.max_k, ..Limits::default() };
+    let params = cli.params_dir.as_ref().map(ParamsCache::new);
+    let result = match cli.command {
+        Command::Replay { dir } => replay_dir(&dir, &limits, cli.json, cli.verbose),
+        Command::Mock { statement, out, dump_witness, table_csv } => {
+            mock(&statement, out.as_deref(), dump_witness.as_deref(), table_csv.as_deref(), &limits, cli.json, cli.verbose)
+        }
+        Command::Import { k, instance, witness } => import(k, &witness, &instance, &limits, cli.json, cli.verbose),
+        Command::Prove { statement, output, timeout, progress, seed, fingerprints } => {
+            let limits = Limits { timeout: timeout.map(Duration::from_secs), ..limits };
+            let fingerprints = fingerprints.map(FingerprintStore::new);
+            prove(&statement, &output, progress, seed, params.as_ref(), fingerprints.as_ref(), &limits, cli.json, cli.verbose)
+        }
+        Command::Verify { all: Some(dir), .. } => verify_all(&dir, params.as_ref(), &limits, cli.json),
+        Command::Verify { statement: Some(statement), out: Some(out), metadata, fingerprint, bundle: Some(bundle), .. } => {
+            verify(&statement, &out, &bundle, metadata, fingerprint.as_deref(), params.as_ref(), &limits, cli.json)
+        }
+        Command::Verify { .. } => Err(FibError::InvalidInput("verify needs -k, -n, --out and a bundle, or --all".to_string())),
+        Command::Aggregate { output, bundles } => aggregate(&bundles, output.as_deref(), params.as_ref(), &limits, cli.json),
+        Command::Bundle { output, bundles } => bundle(&bundles, &output, params.as_ref(), &limits, cli.json),
+        Command::Unbundle { dir, archive } => unbundle(&archive, &dir, params.as_ref(), &limits, cli.json),
+        Command::Rows { statement } => rows(&statement, cli.json),
+        Command::Gates => {
+            print!("{}", CircuitGates::collect::<Fp, FibCircuit<Fp>>());
+            Ok(())
+        }
+        Command::VkHash { k, n } => vk_hash(k, n, params.as_ref(), &limits, cli.json),
+        Command::Params { command } => params_file(&command, &limits, cli.json),
+        Command::Golden { bless, dir } => golden(&dir, bless, cli.json),
+        #[cfg(feature = "http")]
+        Command::Serve(args) => server::serve_blocking(args, params, limits.max_k),
+    };
+
+    // The exit code is the code of the error, so scripts can branch on it as well
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            if cli.json {
+                eprintln!("{}", e.to_json());
+            } else {
+                eprintln!("error: {}", e);
+            }
+            ExitCode::from(e.code())
+        }
+    }
+}
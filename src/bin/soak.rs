@@ -0,0 +1,138 @@
+use std::{
+    fs,
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use halo2_proofs::{arithmetic::Field, pasta::Fp};
+use rand_core::{OsRng, RngCore};
+
+use halo2_fibonacci_ex::{
+    bundle::{Metadata, ProofBundle},
+    error::FibError,
+    fibonacci::{max_steps, FibInputs},
+    field::display_field,
+    prover::{self, CancellationToken},
+    registry::{verify_bundle, Registry},
+};
+
+// Runs the whole pipeline on random statements for as long as asked: prove, write the bundle as JSON, read it back,
+// verify it through the registry, and check the result against a native computation written independently of the
+// library. Every proof is also verified with a wrong output, which must be rejected. Meant to run for hours before a
+// release, to catch failures that only show up on rare inputs and memory that grows from one proof to the next.
+//
+//   cargo run --release --bin soak -- --duration 4h -k 6
+//
+// A line is printed every --report-every iterations with the failure count and the resident memory (Linux only).
+// Ctrl-C stops after the current iteration. The exit code is the one of FibError::Regression if anything failed.
+
+#[derive(Parser)]
+#[command(name = "soak", about = "Prove and verify random Fibonacci statements for a long time")]
+struct Cli {
+    /// How long to run, e.g. "30m" or "4h"
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "10m")]
+    duration: Duration,
+
+    /// Rows of the circuit are 2^k. The number of steps is drawn up to the largest one that fits
+    #[arg(short, long, default_value_t = 5)]
+    k: u32,
+
+    /// Print a report line every this many iterations
+    #[arg(long, default_value_t = 10)]
+    report_every: u64,
+}
+
+// Fibonacci computed without the library, so that a bug in Sequence::step doesn't hide itself
+fn native_out(a: Fp, b: Fp, n: usize) -> Fp {
+    let (mut a, mut b) = (a, b);
+    for _ in 0..n {
+        (a, b) = (b, a + b);
+    }
+    b
+}
+
+// The resident set size in kB, from /proc (Linux only)
+fn rss_kb() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+// One statement through the pipeline. An error describes the first check that failed
+fn iteration(k: u32, inputs: &FibInputs<Fp>, registry: &Registry, token: &CancellationToken) -> Result<(), String> {
+    let instance = inputs.public_inputs();
+    let expected = native_out(inputs.a, inputs.b, inputs.n);
+    if instance[2] != expected {
+        return Err(format!("the library computes {}, the native computation {}", display_field(instance[2]), display_field(expected)));
+    }
+
+    let proof = prover::prove(k, &inputs.circuit(), &instance, token).map_err(|e| format!("prove: {}", e))?;
+    let bundle = ProofBundle::new(Metadata::new(k, inputs.circuit_id()), instance.clone(), proof);
+    let bundle = ProofBundle::from_json(&bundle.to_json()).map_err(|e| format!("bundle round trip: {}", e))?;
+
+    let verified = verify_bundle(&bundle, registry).map_err(|e| format!("verify: {}", e))?;
+    if verified.instance != instance {
+        return Err("the verified instance is not the one that was proven".to_string());
+    }
+
+    let mut forged = bundle;
+    forged.instance[2] += Fp::one();
+    match verify_bundle(&forged, registry) {
+        Err(FibError::InvalidProof) => Ok(()),
+        Ok(_) => Err("a wrong output was accepted".to_string()),
+        Err(e) => Err(format!("a wrong output was rejected with an unexpected error: {}", e)),
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let max_n = max_steps::<Fp>(cli.k);
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let handler_stop = stop.clone();
+    if let Err(e) = ctrlc::set_handler(move || handler_stop.store(true, Ordering::SeqCst)) {
+        eprintln!("error: {}", e);
+        return ExitCode::from(FibError::Internal(e.to_string()).code());
+    }
+
+    // Keys are generated once per number of steps and kept by the registry
+    let mut registry = Registry::new();
+    for n in 0..=max_n {
+        registry.register_fibonacci(cli.k, n);
+    }
+
+    let token = CancellationToken::new();
+    let start = Instant::now();
+    let first_rss = rss_kb();
+    let (mut iterations, mut failures) = (0u64, 0usize);
+    println!("soaking for {} with k = {}, n up to {}", humantime::format_duration(cli.duration), cli.k, max_n);
+
+    while start.elapsed() < cli.duration && !stop.load(Ordering::SeqCst) {
+        let n = (OsRng.next_u64() % (max_n as u64 + 1)) as usize;
+        let inputs = FibInputs::new(Fp::random(OsRng), Fp::random(OsRng), n);
+        if let Err(e) = iteration(cli.k, &inputs, &registry, &token) {
+            failures += 1;
+            println!("FAIL  n = {}, a = {:#x}, b = {:#x}: {}", inputs.n, display_field(inputs.a), display_field(inputs.b), e);
+        }
+
+        iterations += 1;
+        if iterations.is_multiple_of(cli.report_every) {
+            let rss = match (first_rss, rss_kb()) {
+                (Some(first), Some(rss)) => format!("rss {} kB ({:+} kB)", rss, rss as i64 - first as i64),
+                _ => "rss unknown".to_string(),
+            };
+            println!("{:>8} iterations  {:>4} failures  {}s  {}", iterations, failures, start.elapsed().as_secs(), rss);
+        }
+    }
+
+    println!("{} iterations, {} failures", iterations, failures);
+    match failures {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::from(FibError::Regression(failures).code()),
+    }
+}
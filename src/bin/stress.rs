@@ -0,0 +1,95 @@
+use std::{fs, iter, process::ExitCode, sync::Arc, time::Instant};
+
+use clap::Parser;
+use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+use halo2_fibonacci_ex::{
+    error::FibError,
+    fibonacci::{max_steps, FibCircuit, Fibonacci},
+    field::display_field,
+    sequence::{Sequence, StateStream},
+    stats::circuit_stats,
+};
+
+// Fills the largest Fibonacci table that fits in 2^k rows, 2^20 by default. The states come from a stream
+// (SequenceCircuit::with_stream) computing them as they are assigned, so the witness takes the same memory at any
+// number of rows. --trace materializes the whole trace first instead, to compare:
+//
+//   cargo run --release --bin stress
+//   cargo run --release --bin stress -- --trace
+//
+// The synthesis runs against the recorder of the statistics (stats::circuit_stats), which keeps no cells, so the
+// resident memory printed after the run (Linux only) is the one of the witness. --mock checks the table with the
+// MockProver instead. It keeps every cell and its checks take more than linear time in halo2_proofs 0.1.0 (40 s at
+// k = 16), so use it with a smaller k:
+//
+//   cargo run --release --bin stress -- -k 14 --mock
+
+#[derive(Parser)]
+#[command(name = "stress", about = "Fill a Fibonacci table of 2^k rows from a stream of states")]
+struct Cli {
+    #[arg(short, long, default_value_t = 20)]
+    k: u32,
+
+    /// Compute the whole trace before the synthesis instead of streaming the states
+    #[arg(long)]
+    trace: bool,
+
+    /// Check the table with the MockProver
+    #[arg(long)]
+    mock: bool,
+}
+
+// The states after (1, 1), computed one at a time
+fn stream() -> StateStream<Fp> {
+    Arc::new(|| {
+        let states = iter::successors(Some((Fp::one(), Fp::one())), |&(a, b)| Some((b, a + b)));
+        Box::new(states.skip(1).map(|(a, b)| vec![a, b]))
+    })
+}
+
+// The resident set size in kB, from /proc (Linux only)
+fn rss_kb() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn run(cli: &Cli) -> Result<(), FibError> {
+    let steps = max_steps::<Fp>(cli.k);
+    let initial = [Fp::one(), Fp::one()];
+    let circuit = if cli.trace {
+        FibCircuit::with_trace(initial.map(Some).to_vec(), steps, Fibonacci::run(&initial, steps))
+    } else {
+        FibCircuit::with_stream(initial.map(Some).to_vec(), steps, stream())
+    };
+    // Fibonacci::public_inputs runs the whole sequence, which would keep the trace in memory as well
+    let last = iter::once(initial.to_vec()).chain(stream()()).nth(steps).expect("the stream is infinite");
+    let instance = vec![initial[0], initial[1], last[1]];
+
+    let start = Instant::now();
+    if cli.mock {
+        let prover = MockProver::run(cli.k, &circuit, vec![instance.clone()])?;
+        prover.verify().map_err(|failures| FibError::Unsatisfied(failures.len()))?;
+        println!("k = {}, {} steps, out = {}: satisfied", cli.k, steps, display_field(instance[2]));
+    } else {
+        let stats = circuit_stats(&circuit, vec![instance])?;
+        println!("k = {}, {} steps: {} rows, {} advice cells assigned", cli.k, steps, stats.rows(), stats.advice_cells());
+    }
+    println!("{} in {:.1?}", if cli.trace { "trace" } else { "stream" }, start.elapsed());
+    if let Some(rss) = rss_kb() {
+        println!("resident memory: {} MB", rss / 1024);
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(e.code())
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::{annotate::ColumnNames, boolean::bool_check};
+
+// Decomposes a cell into N bits, so that other gadgets can work on them (square-and-multiply, comparisons, ...).
+// The bits are assigned most significant first, next to a running sum that starts from 0 and ends equal to the value:
+//
+//   acc_0 = 0,   acc_{i+1} = 2 acc_i + bit_i,   bit_i * (1 - bit_i) = 0
+//
+//   bit     | acc      | q_step
+// ------------------------------
+//   b_{N-1} |  0       |   1      <- acc is a constant
+//   b_{N-2} |  acc_1   |   1
+//   ...     |  ...     |  ...
+//   b_0     |  acc_N-1 |   1
+//           |  value   |   0      <- acc is copied from the cell given by the caller
+//
+// The running sum stays below 2^N, so for N < F::NUM_BITS the bits are the binary representation of the value and a
+// value that doesn't fit in N bits can't be decomposed.
+#[derive(Debug, Clone, Copy)]
+pub struct BitsConfig {
+    pub bit: Column<Advice>,
+    pub acc: Column<Advice>,
+    pub q_step: Selector,
+}
+
+impl BitsConfig {
+    // Names the columns in the diagnostics of the library (see src/annotate.rs)
+    pub fn annotate(&self, names: &mut ColumnNames) {
+        names.annotate(self.bit, "bit").annotate(self.acc, "acc");
+    }
+}
+
+/// Decomposes a cell into boolean cells, returned least significant first.
+///
+/// ```
+/// use halo2_proofs::{circuit::*, dev::MockProver, pasta::Fp, plonk::*};
+/// use halo2_fibonacci_ex::bits::{BitsChip, BitsConfig};
+///
+/// // Exposes the lowest and the highest of 4 bits of a private value
+/// #[derive(Default)]
+/// struct MyCircuit {
+///     value: Option<Fp>,
+/// }
+///
+/// impl Circuit<Fp> for MyCircuit {
+///     type Config = (BitsConfig, Column<Instance>);
+///     type FloorPlanner = SimpleFloorPlanner;
+///
+///     fn without_witnesses(&self) -> Self {
+///         Self::default()
+///     }
+///
+///     fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+///         let (bit, acc) = (meta.advice_column(), meta.advice_column());
+///         let instance = meta.instance_column();
+///         meta.enable_equality(instance);
+///         (BitsChip::configure(meta, bit, acc), instance)
+///     }
+///
+///     fn synthesize(&self, (config, instance): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+///         let chip = BitsChip::construct(config);
+///         let value = layouter.assign_region(|| "value", |mut region| {
+///             region.assign_advice(|| "value", config.acc, 0, || self.value.ok_or(Error::Synthesis))
+///         })?;
+///         let bits = chip.decompose(layouter.namespace(|| "bits"), &value, 4)?;
+///         layouter.constrain_instance(bits[0].cell(), instance, 0)?;
+///         layouter.constrain_instance(bits[3].cell(), instance, 1)
+///     }
+/// }
+///
+/// // 13 = 0b1101
+/// let circuit = MyCircuit { value: Some(Fp::from(13)) };
+/// MockProver::run(4, &circuit, vec![vec![Fp::one(), Fp::one()]]).unwrap().assert_satisfied();
+///
+/// // 16 doesn't fit in 4 bits
+/// let circuit = MyCircuit { value: Some(Fp::from(16)) };
+/// assert!(MockProver::run(4, &circuit, vec![vec![Fp::zero(), Fp::zero()]]).unwrap().verify().is_err());
+/// ```
+pub struct BitsChip<F: FieldExt> {
+    config: BitsConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> BitsChip<F> {
+    pub fn construct(config: BitsConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, bit: Column<Advice>, acc: Column<Advice>) -> BitsConfig {
+        let q_step = meta.selector();
+        let constant = meta.fixed_column();
+
+        meta.enable_equality(bit);
+        meta.enable_equality(acc);
+        meta.enable_constant(constant);
+
+        meta.create_gate("bit decomposition", |meta| {
+            let s = meta.query_selector(q_step);
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            Constraints::with_selector(
+                s,
+                [
+                    ("bit is boolean", bool_check(bit.clone())),
+                    ("acc doubles and adds the bit", acc * Expression::Constant(F::from(2)) + bit - acc_next),
+                ],
+            )
+        });
+
+        BitsConfig { bit, acc, q_step }
+    }
+
+    // Returns the n bits of the value, least significant first. Takes n + 1 rows
+    pub fn decompose(&self, mut layouter: impl Layouter<F>, value: &AssignedCell<F, F>, n: usize) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        assert!(n > 0 && n < F::NUM_BITS as usize, "unsupported number of bits: {}", n);
+        let config = &self.config;
+
+        layouter.assign_region(|| "bit decomposition", |mut region| {
+            let repr = value.value().map(|value| value.to_repr());
+            let mut acc = Some(F::zero());
+            region.assign_advice_from_constant(|| "acc", config.acc, 0, F::zero())?;
+
+            let mut bits = vec![];
+            for row in 0..n {
+                config.q_step.enable(&mut region, row)?;
+                let i = n - 1 - row;
+                let bit = repr.as_ref().map(|repr| F::from(((repr.as_ref()[i / 8] >> (i % 8)) & 1) as u64));
+                bits.push(region.assign_advice(|| "bit", config.bit, row, || bit.ok_or(Error::Synthesis))?);
+
+                acc = acc.zip(bit).map(|(acc, bit)| acc.double() + bit);
+                if row + 1 < n {
+                    region.assign_advice(|| "acc", config.acc, row + 1, || acc.ok_or(Error::Synthesis))?;
+                }
+            }
+
+            // The running sum ends equal to the value
+            value.copy_advice(|| "value", &mut region, config.acc, n)?;
+
+            bits.reverse();
+            Ok(bits)
+        })
+    }
+}
@@ -0,0 +1,120 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::annotate::ColumnNames;
+
+// A value is a bit if b * (1 - b) = 0: the polynomial only vanishes on 0 and 1. Every gadget that selects, compares
+// or decomposes relies on it, so the expression lives here. Gadgets with their own gate add bool_check to it
+// (CondSelectChip, LtChip, BitsChip); circuits that only need bits use the BoolChip, a column with the gate
+//
+//   q_bool * b * (1 - b) = 0
+
+// b * (1 - b), which vanishes iff b is 0 or 1
+pub fn bool_check<F: FieldExt>(b: Expression<F>) -> Expression<F> {
+    b.clone() * (Expression::Constant(F::one()) - b)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BoolConfig {
+    pub bit: Column<Advice>,
+    pub q_bool: Selector,
+}
+
+impl BoolConfig {
+    // Names the columns in the diagnostics of the library (see src/annotate.rs)
+    pub fn annotate(&self, names: &mut ColumnNames) {
+        names.annotate(self.bit, "bit");
+    }
+}
+
+/// Assigns cells constrained to be 0 or 1.
+///
+/// ```
+/// use halo2_proofs::{circuit::*, dev::MockProver, pasta::Fp, plonk::*};
+/// use halo2_fibonacci_ex::boolean::{BoolChip, BoolConfig};
+///
+/// // Assigns a private bit, and checks that a value assigned elsewhere is a bit too
+/// #[derive(Default)]
+/// struct MyCircuit {
+///     bit: Option<Fp>,
+///     other: Option<Fp>,
+/// }
+///
+/// impl Circuit<Fp> for MyCircuit {
+///     type Config = (BoolConfig, Column<Advice>);
+///     type FloorPlanner = SimpleFloorPlanner;
+///
+///     fn without_witnesses(&self) -> Self {
+///         Self::default()
+///     }
+///
+///     fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+///         let (bit, value) = (meta.advice_column(), meta.advice_column());
+///         meta.enable_equality(value);
+///         (BoolChip::configure(meta, bit), value)
+///     }
+///
+///     fn synthesize(&self, (config, value): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+///         let chip = BoolChip::construct(config);
+///         layouter.assign_region(|| "bit", |mut region| chip.assign_bit(&mut region, 0, self.bit))?;
+///         let other = layouter.assign_region(|| "other", |mut region| {
+///             region.assign_advice(|| "other", value, 0, || self.other.ok_or(Error::Synthesis))
+///         })?;
+///         chip.assert_bit(layouter.namespace(|| "other is a bit"), &other)
+///     }
+/// }
+///
+/// let circuit = |bit: u64, other: u64| MyCircuit { bit: Some(Fp::from(bit)), other: Some(Fp::from(other)) };
+/// MockProver::run(4, &circuit(0, 1), vec![]).unwrap().assert_satisfied();
+/// MockProver::run(4, &circuit(1, 0), vec![]).unwrap().assert_satisfied();
+/// assert!(MockProver::run(4, &circuit(2, 0), vec![]).unwrap().verify().is_err());
+/// assert!(MockProver::run(4, &circuit(1, 2), vec![]).unwrap().verify().is_err());
+/// ```
+pub struct BoolChip<F: FieldExt> {
+    config: BoolConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> BoolChip<F> {
+    pub fn construct(config: BoolConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, bit: Column<Advice>) -> BoolConfig {
+        let q_bool = meta.selector();
+
+        meta.enable_equality(bit);
+
+        meta.create_gate("boolean", |meta| {
+            let s = meta.query_selector(q_bool);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            Constraints::with_selector(s, [("bit is boolean", bool_check(bit))])
+        });
+
+        BoolConfig { bit, q_bool }
+    }
+
+    // Assigns a bit on the given row of the region. Call it from inside the assign_region closure
+    pub fn assign_bit(&self, region: &mut Region<'_, F>, offset: usize, value: Option<F>) -> Result<AssignedCell<F, F>, Error> {
+        self.config.q_bool.enable(region, offset)?;
+        region.assign_advice(|| "bit", self.config.bit, offset, || value.ok_or(Error::Synthesis))
+    }
+
+    // Constrains a cell assigned elsewhere to be a bit, by copying it into a row of the bit column
+    pub fn assert_bit(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>) -> Result<(), Error> {
+        layouter.assign_region(|| "assert bit", |mut region| {
+            self.config.q_bool.enable(&mut region, 0)?;
+            cell.copy_advice(|| "bit", &mut region, self.config.bit, 0)?;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,149 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+};
+
+// When a region has a dynamic number of rows (like the single region of example3) we usually need three kinds of constraints:
+// - constraints that only apply to the first row (e.g. the initial values)
+// - constraints that only apply to the last row (e.g. the output)
+// - constraints that apply to every step, where a step gate spans `span` rows starting from the row where it is enabled
+// The step gate must not be enabled on the last `span - 1` rows, otherwise it would query cells outside of the region.
+// This chip owns one selector for each kind and enables them for you, so you don't need to remember the
+// "enable the selector on all the rows but the last two" bookkeeping of example3.
+#[derive(Debug, Clone, Copy)]
+pub struct BoundaryConfig {
+    pub q_first: Selector,
+    pub q_last: Selector,
+    pub q_step: Selector,
+    // number of rows covered by a step gate, e.g. 3 for a gate querying Rotation::cur(), Rotation::next() and Rotation(2)
+    pub span: usize,
+}
+
+/// Enables the first row, last row and step selectors of a region.
+///
+/// ```
+/// use halo2_proofs::{arithmetic::FieldExt, circuit::*, dev::MockProver, pasta::Fp, plonk::*, poly::Rotation};
+/// use halo2_fibonacci_ex::boundary::{BoundaryChip, BoundaryConfig};
+///
+/// // Single column Fibonacci over `rows` rows: a + b = c on every step, the first two rows must be 1
+/// struct MyCircuit {
+///     rows: usize,
+/// }
+///
+/// impl Circuit<Fp> for MyCircuit {
+///     type Config = (Column<Advice>, BoundaryConfig);
+///     type FloorPlanner = SimpleFloorPlanner;
+///
+///     fn without_witnesses(&self) -> Self {
+///         Self { rows: self.rows }
+///     }
+///
+///     fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+///         let advice = meta.advice_column();
+///         let boundary = BoundaryChip::configure(meta, 3);
+///         BoundaryChip::create_step_gate(meta, &boundary, "add", |meta| {
+///             let a = meta.query_advice(advice, Rotation::cur());
+///             let b = meta.query_advice(advice, Rotation::next());
+///             let c = meta.query_advice(advice, Rotation(2));
+///             vec![("fib-add", a + b - c)]
+///         });
+///         BoundaryChip::create_first_row_gate(meta, &boundary, "start", |meta| {
+///             let a = meta.query_advice(advice, Rotation::cur());
+///             let b = meta.query_advice(advice, Rotation::next());
+///             let one = Expression::Constant(Fp::one());
+///             vec![("a is 1", a - one.clone()), ("b is 1", b - one)]
+///         });
+///         (advice, boundary)
+///     }
+///
+///     fn synthesize(&self, (advice, boundary): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+///         layouter.assign_region(|| "table", |mut region| {
+///             BoundaryChip::construct(boundary).enable(&mut region, self.rows)?;
+///             for (row, value) in [1, 1, 2, 3, 5].iter().take(self.rows).enumerate() {
+///                 region.assign_advice(|| "value", advice, row, || Ok(Fp::from(*value)))?;
+///             }
+///             Ok(())
+///         })
+///     }
+/// }
+///
+/// MockProver::run(4, &MyCircuit { rows: 5 }, vec![]).unwrap().assert_satisfied();
+///
+/// // A region shorter than the step gate is an error, not a panic
+/// assert!(MockProver::run(4, &MyCircuit { rows: 2 }, vec![]).is_err());
+/// ```
+pub struct BoundaryChip<F: FieldExt> {
+    config: BoundaryConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> BoundaryChip<F> {
+    pub fn construct(config: BoundaryConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, span: usize) -> BoundaryConfig {
+        assert!(span > 0);
+        BoundaryConfig {
+            q_first: meta.selector(),
+            q_last: meta.selector(),
+            q_step: meta.selector(),
+            span,
+        }
+    }
+
+    // The constraints returned by the closure only apply to the first row of the region
+    pub fn create_first_row_gate<C>(meta: &mut ConstraintSystem<F>, config: &BoundaryConfig, name: &'static str, constraints: C)
+    where
+        C: FnOnce(&mut VirtualCells<'_, F>) -> Vec<(&'static str, Expression<F>)>,
+    {
+        Self::create_gate(meta, config.q_first, name, constraints)
+    }
+
+    // The constraints returned by the closure only apply to the last row of the region
+    pub fn create_last_row_gate<C>(meta: &mut ConstraintSystem<F>, config: &BoundaryConfig, name: &'static str, constraints: C)
+    where
+        C: FnOnce(&mut VirtualCells<'_, F>) -> Vec<(&'static str, Expression<F>)>,
+    {
+        Self::create_gate(meta, config.q_last, name, constraints)
+    }
+
+    // The constraints returned by the closure apply to every row where a gate of `span` rows fits inside the region
+    pub fn create_step_gate<C>(meta: &mut ConstraintSystem<F>, config: &BoundaryConfig, name: &'static str, constraints: C)
+    where
+        C: FnOnce(&mut VirtualCells<'_, F>) -> Vec<(&'static str, Expression<F>)>,
+    {
+        Self::create_gate(meta, config.q_step, name, constraints)
+    }
+
+    fn create_gate<C>(meta: &mut ConstraintSystem<F>, selector: Selector, name: &'static str, constraints: C)
+    where
+        C: FnOnce(&mut VirtualCells<'_, F>) -> Vec<(&'static str, Expression<F>)>,
+    {
+        meta.create_gate(name, |meta| {
+            let s = meta.query_selector(selector);
+            Constraints::with_selector(s, constraints(meta))
+        });
+    }
+
+    // Enable the selectors of a region with `nrows` rows: call it from inside the assign_region closure. A region
+    // shorter than a step gate is Error::Synthesis
+    pub fn enable(&self, region: &mut Region<'_, F>, nrows: usize) -> Result<(), Error> {
+        if nrows < self.config.span || nrows == 0 {
+            return Err(Error::Synthesis);
+        }
+
+        self.config.q_first.enable(region, 0)?;
+        self.config.q_last.enable(region, nrows - 1)?;
+        for row in 0..=nrows - self.config.span {
+            self.config.q_step.enable(region, row)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use std::time::SystemTime;
+
+use halo2_proofs::pasta::Fp;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::{Backend, IpaPasta},
+    error::FibError,
+};
+
+// The file exchanged between a prover and a verifier: the proof, the instance column it was created for and some
+// metadata about where it comes from. It is stored as JSON, with the proof and the field elements hex encoded
+// (field elements as the little endian bytes of their representation):
+//
+//   {
+//     "metadata": {"crate_version": "0.1.0", "backend": "halo2_proofs 0.1.0, IPA over vesta, Blake2b transcript",
+//                  "k": 4, "circuit_id": "fibonacci-8", "created_at": "2026-10-18T09:00:00Z"},
+//     "instance": ["0100...", "0100...", "3700..."],
+//     "proof": "a3f0..."
+//   }
+//
+// The metadata is not part of the transcript: it is not bound to the proof and anyone can edit it without breaking
+// the verification. It is only there to help debugging proofs exchanged between teams running different builds.
+
+// The proving system used by `prover`, see src/backend.rs
+pub const BACKEND: &str = IpaPasta::NAME;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+    pub crate_version: String,
+    pub backend: String,
+    pub k: u32,
+    pub circuit_id: String,
+    // UTC, RFC 3339
+    pub created_at: String,
+}
+
+impl Metadata {
+    // The metadata of a proof created now by this build
+    pub fn new(k: u32, circuit_id: impl Into<String>) -> Self {
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            backend: BACKEND.to_string(),
+            k,
+            circuit_id: circuit_id.into(),
+            created_at: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofBundle {
+    pub metadata: Metadata,
+    #[serde(with = "hex_fields")]
+    pub instance: Vec<Fp>,
+    #[serde(with = "hex::serde")]
+    pub proof: Vec<u8>,
+}
+
+impl ProofBundle {
+    pub fn new(metadata: Metadata, instance: Vec<Fp>, proof: Vec<u8>) -> Self {
+        Self { metadata, instance, proof }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a bundle can always be serialized")
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, FibError> {
+        serde_json::from_str(s).map_err(|e| FibError::Parse(format!("invalid proof bundle: {}", e)))
+    }
+}
+
+// Field elements as hex strings, rejecting the encodings that are not canonical (i.e. not smaller than the modulus)
+pub(crate) mod hex_fields {
+    use halo2_proofs::pasta::{group::ff::PrimeField, Fp};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(values: &[Fp], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(|value| hex::encode(value.to_repr())))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Fp>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| {
+                let mut repr = <Fp as PrimeField>::Repr::default();
+                hex::decode_to_slice(s, &mut repr).map_err(D::Error::custom)?;
+                Option::from(Fp::from_repr(repr)).ok_or_else(|| D::Error::custom(format!("not a field element: {}", s)))
+            })
+            .collect()
+    }
+}
+
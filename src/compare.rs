@@ -0,0 +1,192 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::{annotate::ColumnNames, boolean::bool_check};
+
+// Compares two values known to fit in N = 8 * BYTES bits. In the field "a < b" has no meaning, but on integers
+// below 2^N
+//
+//   a < b   <=>   a - b + 2^N < 2^N
+//
+// and a - b + 2^N is in [1, 2^(N+1)), so the comparison is its bit N. The prover provides the boolean lt and the
+// bytes of a - b + lt * 2^N, which must fit in N bits:
+//
+//   a | b | lt | byte_0 ... byte_{BYTES-1} | q_lt
+// -------------------------------------------------
+//   a | b | lt |  ...                      |  1      <- a and b copied from the cells given by the caller
+//
+// q_lt (a complex selector, as it is used in the lookups) enables
+// - lt * (1 - lt) = 0
+// - a - b + lt * 2^N = sum 256^i byte_i, byte_i in [0, 256)
+// If a < b, lt = 0 would need a negative left side, which wraps around the modulus and doesn't fit in N bits. If
+// a >= b, lt = 1 would need a left side of at least 2^N.
+//
+// The chip doesn't range check a and b: they must already be known to fit in N bits (e.g. they are bytes of another
+// decomposition), otherwise the result means nothing.
+#[derive(Debug, Clone, Copy)]
+pub struct LtConfig<const BYTES: usize> {
+    // a, b, lt
+    pub advice: [Column<Advice>; 3],
+    pub bytes: [Column<Advice>; BYTES],
+    pub table: TableColumn,
+    pub q_lt: Selector,
+}
+
+impl<const BYTES: usize> LtConfig<BYTES> {
+    // Names the columns in the diagnostics of the library (see src/annotate.rs)
+    pub fn annotate(&self, names: &mut ColumnNames) {
+        for (column, name) in self.advice.iter().zip(["a", "b", "lt"]) {
+            names.annotate(*column, name);
+        }
+        for (i, column) in self.bytes.iter().enumerate() {
+            names.annotate(*column, format!("byte_{}", i));
+        }
+    }
+}
+
+/// Returns a boolean cell equal to 1 if `a < b`, for values of at most `8 * BYTES` bits.
+///
+/// ```
+/// use halo2_proofs::{circuit::*, dev::MockProver, pasta::Fp, plonk::*};
+/// use halo2_fibonacci_ex::compare::{LtChip, LtConfig};
+///
+/// // Exposes a < b for two 16 bit values
+/// #[derive(Default)]
+/// struct MyCircuit {
+///     a: Option<Fp>,
+///     b: Option<Fp>,
+/// }
+///
+/// impl Circuit<Fp> for MyCircuit {
+///     type Config = (LtConfig<2>, Column<Instance>);
+///     type FloorPlanner = SimpleFloorPlanner;
+///
+///     fn without_witnesses(&self) -> Self {
+///         Self::default()
+///     }
+///
+///     fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+///         let advice = [(); 3].map(|_| meta.advice_column());
+///         let bytes = [(); 2].map(|_| meta.advice_column());
+///         let instance = meta.instance_column();
+///         meta.enable_equality(instance);
+///         (LtChip::configure(meta, advice, bytes), instance)
+///     }
+///
+///     fn synthesize(&self, (config, instance): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+///         let chip = LtChip::construct(config);
+///         chip.load_table(layouter.namespace(|| "bytes"))?;
+///         let [a, b] = layouter.assign_region(|| "inputs", |mut region| {
+///             let a = region.assign_advice(|| "a", config.advice[0], 0, || self.a.ok_or(Error::Synthesis))?;
+///             let b = region.assign_advice(|| "b", config.advice[1], 0, || self.b.ok_or(Error::Synthesis))?;
+///             Ok([a, b])
+///         })?;
+///         let lt = chip.lt(layouter.namespace(|| "a < b"), &a, &b)?;
+///         layouter.constrain_instance(lt.cell(), instance, 0)
+///     }
+/// }
+///
+/// let circuit = |a: u64, b: u64| MyCircuit { a: Some(Fp::from(a)), b: Some(Fp::from(b)) };
+/// for (a, b, lt) in [(3, 5, 1), (5, 3, 0), (4, 4, 0), (0, 65535, 1), (65535, 0, 0)] {
+///     MockProver::run(9, &circuit(a, b), vec![vec![Fp::from(lt)]]).unwrap().assert_satisfied();
+/// }
+/// assert!(MockProver::run(9, &circuit(5, 3), vec![vec![Fp::one()]]).unwrap().verify().is_err());
+/// ```
+pub struct LtChip<F: FieldExt, const BYTES: usize> {
+    config: LtConfig<BYTES>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const BYTES: usize> LtChip<F, BYTES> {
+    pub fn construct(config: LtConfig<BYTES>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3], bytes: [Column<Advice>; BYTES]) -> LtConfig<BYTES> {
+        // a - b + 2^N must not wrap around the modulus
+        assert!(BYTES > 0 && 8 * BYTES + 1 < F::NUM_BITS as usize, "unsupported number of bytes: {}", BYTES);
+
+        let [a, b, lt] = advice;
+        let table = meta.lookup_table_column();
+        let q_lt = meta.complex_selector();
+
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+        meta.enable_equality(lt);
+
+        meta.create_gate("less than", |meta| {
+            let s = meta.query_selector(q_lt);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let lt = meta.query_advice(lt, Rotation::cur());
+            let recomposed = bytes.iter().rev().fold(Expression::Constant(F::zero()), |acc, byte| {
+                acc * Expression::Constant(F::from(256)) + meta.query_advice(*byte, Rotation::cur())
+            });
+            Constraints::with_selector(
+                s,
+                [
+                    ("lt is boolean", bool_check(lt.clone())),
+                    ("a - b + lt * 2^n is the bytes", a - b + lt * Expression::Constant(Self::two_pow_n()) - recomposed),
+                ],
+            )
+        });
+
+        // When q_lt = 0 the lookup input is 0, which is in the table
+        for byte in bytes {
+            meta.lookup(|meta| {
+                let s = meta.query_selector(q_lt);
+                let byte = meta.query_advice(byte, Rotation::cur());
+                vec![(s * byte, table)]
+            });
+        }
+
+        LtConfig { advice, bytes, table, q_lt }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "bytes",
+            |mut table| {
+                for i in 0..256 {
+                    table.assign_cell(|| "byte", self.config.table, i, || Ok(F::from(i as u64)))?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    // Returns the cell of lt, 1 if a < b and 0 otherwise
+    pub fn lt(&self, mut layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let [col_a, col_b, col_lt] = config.advice;
+
+        layouter.assign_region(|| "less than", |mut region| {
+            config.q_lt.enable(&mut region, 0)?;
+            a.copy_advice(|| "a", &mut region, col_a, 0)?;
+            b.copy_advice(|| "b", &mut region, col_b, 0)?;
+
+            // a - b + 2^N is below 2^(N+1): its bit N, the lowest bit of byte BYTES, is set iff a >= b, and its
+            // first BYTES bytes are the ones of a - b + lt * 2^N
+            let shifted = a.value().zip(b.value()).map(|(a, b)| (*a - b + Self::two_pow_n()).to_repr());
+            let lt = shifted.as_ref().map(|repr| F::from(1 - (repr.as_ref()[BYTES] & 1) as u64));
+            for (i, column) in config.bytes.iter().enumerate() {
+                let byte = shifted.as_ref().map(|repr| F::from(repr.as_ref()[i] as u64));
+                region.assign_advice(|| "byte", *column, 0, || byte.ok_or(Error::Synthesis))?;
+            }
+            region.assign_advice(|| "lt", col_lt, 0, || lt.ok_or(Error::Synthesis))
+        })
+    }
+
+    fn two_pow_n() -> F {
+        F::from(2).pow(&[8 * BYTES as u64, 0, 0, 0])
+    }
+}
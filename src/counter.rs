@@ -0,0 +1,115 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::annotate::ColumnNames;
+
+// A clock for circuits made of steps: a column that starts from 1 and is incremented by one on every row,
+//
+//   counter_next = counter + 1
+//
+// so its value on the last row is the number of steps that were applied. Exposing it binds the verifier to the
+// claimed number of steps (see example12). The counter lives in its own advice column, so it can be laid out next
+// to the rows of any other chip, inside the same region.
+#[derive(Debug, Clone, Copy)]
+pub struct CounterConfig {
+    pub counter: Column<Advice>,
+    pub q_inc: Selector,
+    pub instance: Column<Instance>,
+}
+
+impl CounterConfig {
+    // Names the columns in the diagnostics of the library (see src/annotate.rs)
+    pub fn annotate(&self, names: &mut ColumnNames) {
+        names.annotate(self.counter, "counter").annotate(self.instance, "instance");
+    }
+}
+
+/// Assigns an incrementing counter next to the rows of another chip.
+///
+/// ```
+/// use halo2_proofs::{circuit::*, dev::MockProver, pasta::Fp, plonk::*};
+/// use halo2_fibonacci_ex::counter::{CounterChip, CounterConfig};
+///
+/// // Counts 5 steps and exposes the count
+/// #[derive(Default)]
+/// struct MyCircuit;
+///
+/// impl Circuit<Fp> for MyCircuit {
+///     type Config = CounterConfig;
+///     type FloorPlanner = SimpleFloorPlanner;
+///
+///     fn without_witnesses(&self) -> Self {
+///         Self
+///     }
+///
+///     fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+///         let counter = meta.advice_column();
+///         let instance = meta.instance_column();
+///         CounterChip::configure(meta, counter, instance)
+///     }
+///
+///     fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+///         let chip = CounterChip::construct(config);
+///         let last = layouter.assign_region(|| "steps", |mut region| chip.assign(&mut region, 0, 5))?;
+///         chip.expose_public(layouter.namespace(|| "n"), &last, 0)
+///     }
+/// }
+///
+/// MockProver::run(4, &MyCircuit, vec![vec![Fp::from(5)]]).unwrap().assert_satisfied();
+/// assert!(MockProver::run(4, &MyCircuit, vec![vec![Fp::from(6)]]).unwrap().verify().is_err());
+/// ```
+pub struct CounterChip<F: FieldExt> {
+    config: CounterConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> CounterChip<F> {
+    pub fn construct(config: CounterConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, counter: Column<Advice>, instance: Column<Instance>) -> CounterConfig {
+        let q_inc = meta.selector();
+        let constant = meta.fixed_column();
+
+        meta.enable_equality(counter);
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("counter increment", |meta| {
+            let s = meta.query_selector(q_inc);
+            let counter_next = meta.query_advice(counter, Rotation::next());
+            let counter = meta.query_advice(counter, Rotation::cur());
+            Constraints::with_selector(s, [("counter increments", counter_next - counter - Expression::Constant(F::one()))])
+        });
+
+        CounterConfig { counter, q_inc, instance }
+    }
+
+    // Assign the values 1, 2, ..., steps on the rows offset, ..., offset + steps - 1 of the region and
+    // return the cell of the last one. Call it from inside the assign_region closure
+    pub fn assign(&self, region: &mut Region<'_, F>, offset: usize, steps: usize) -> Result<AssignedCell<F, F>, Error> {
+        assert!(steps > 0);
+
+        // The first value is a constant, the following ones are checked by the increment gate
+        let mut cell = region.assign_advice_from_constant(|| "counter", self.config.counter, offset, F::one())?;
+        for step in 1..steps {
+            self.config.q_inc.enable(region, offset + step - 1)?;
+            cell = region.assign_advice(|| "counter", self.config.counter, offset + step, || Ok(F::from(step as u64 + 1)))?;
+        }
+        Ok(cell)
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
@@ -0,0 +1,232 @@
+use halo2_proofs::{arithmetic::FieldExt, plonk::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    annotate::ColumnNames,
+    audit::{self, ColumnId, Columns},
+    error::FibError,
+    field::display_field,
+};
+
+// Every cell the synthesis of a circuit assigns, with its region, column, absolute row and value, as JSON:
+//
+//   {
+//     "cells": [
+//       {
+//         "region": "sequence table",
+//         "column": "column fib_a",
+//         "row": 0,
+//         "value": "1"
+//       },
+//       ...
+//     ],
+//     "instance": [["1", "1", "55"]]
+//   }
+//
+// halo2_proofs 0.1.0 keeps the cells of the MockProver private, so the synthesis runs again against a recorder that
+// keeps the values, like the one of the statistics (src/stats.rs) with the values in addition. The values are
+// printed by display_field, so they can be read back with parse_field. `fib mock --dump-witness <file>` writes the
+// dump of a statement next to its MockProver run, and src/import.rs reads dumps produced elsewhere.
+//
+// WitnessDump::to_csv lays the same cells out as the table of the circuit, to open in a spreadsheet: one line per row,
+// one column per column of the circuit, in the order they are first assigned, then the instance columns:
+//
+//   row,fib_a,fib_b,instance
+//   0,1,1,1
+//   1,1,2,1
+//   2,2,3,55
+//   3,3,5,
+//   ...
+//
+// Cells that are not assigned are left empty. `fib mock --table-csv <file>` writes it.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CellDump {
+    pub region: String,
+    pub column: String,
+    pub row: usize,
+    pub value: String,
+}
+
+/// The cells assigned by the synthesis of a circuit, in the order they were assigned.
+///
+/// ```
+/// use halo2_proofs::pasta::Fp;
+/// use halo2_fibonacci_ex::{dump::witness_dump, fibonacci::{FibCircuit, FibInputs}};
+///
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// let dump = witness_dump(&inputs.circuit(), vec![inputs.public_inputs()]).unwrap().with_names(&FibCircuit::<Fp>::column_names());
+///
+/// // 9 rows of 2 state columns
+/// assert_eq!(dump.cells.len(), 18);
+/// let last = dump.cells.iter().find(|cell| cell.column == "column fib_b" && cell.row == 8).unwrap();
+/// assert_eq!(last.value, "55");
+/// assert!(dump.to_json().contains("\"region\": \"sequence table\""));
+///
+/// let csv = dump.to_csv();
+/// assert_eq!(csv.lines().next(), Some("row,fib_a,fib_b,instance"));
+/// assert_eq!(csv.lines().last(), Some("8,34,55,"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessDump {
+    pub cells: Vec<CellDump>,
+    pub instance: Vec<Vec<String>>,
+    #[serde(skip)]
+    columns: Vec<ColumnId>,
+}
+
+impl WitnessDump {
+    // Names the columns of the cells. The column of every cell is kept, so names can be given at any time
+    pub fn with_names(mut self, names: &ColumnNames) -> Self {
+        for (cell, column) in self.cells.iter_mut().zip(self.columns.iter()) {
+            cell.column = names.describe(*column);
+        }
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a dump can always be serialized")
+    }
+
+    // The cells as a table, one line per row. Named columns lose their "column " prefix
+    pub fn to_csv(&self) -> String {
+        let mut columns: Vec<&str> = vec![];
+        for cell in self.cells.iter() {
+            if !columns.contains(&cell.column.as_str()) {
+                columns.push(&cell.column);
+            }
+        }
+        let rows = self.cells.iter().map(|cell| cell.row + 1).chain(self.instance.iter().map(Vec::len)).max().unwrap_or(0);
+
+        let mut table = vec![vec![""; columns.len() + self.instance.len()]; rows];
+        for cell in self.cells.iter() {
+            let column = columns.iter().position(|column| *column == cell.column).expect("every column was collected");
+            table[cell.row][column] = &cell.value;
+        }
+        for (i, values) in self.instance.iter().enumerate() {
+            for (row, value) in values.iter().enumerate() {
+                table[row][columns.len() + i] = value;
+            }
+        }
+
+        let mut header = vec!["row".to_string()];
+        header.extend(columns.iter().map(|column| column.strip_prefix("column ").unwrap_or(column).to_string()));
+        header.extend((0..self.instance.len()).map(|i| match self.instance.len() {
+            1 => "instance".to_string(),
+            _ => format!("instance {}", i),
+        }));
+
+        let mut csv = header.join(",") + "\n";
+        for (row, values) in table.iter().enumerate() {
+            csv += &format!("{},{}\n", row, values.join(","));
+        }
+        csv
+    }
+}
+
+// Runs the synthesis of `circuit` and keeps the value of every advice and fixed cell. A missing witness is an error,
+// as for the MockProver
+pub fn witness_dump<F: FieldExt, C: Circuit<F>>(circuit: &C, instance: Vec<Vec<F>>) -> Result<WitnessDump, FibError> {
+    let mut meta = ConstraintSystem::default();
+    let config = C::configure(&mut meta);
+    let constants = audit::constant_columns(&format!("{:?}", meta.pinned()))?;
+
+    let mut dumper = Dumper { instance, region: None, cells: vec![], columns: vec![], ids: Columns::default() };
+    let synthesis = C::FloorPlanner::synthesize(&mut dumper, circuit, config, constants);
+    dumper.ids.result(synthesis)?;
+
+    let instance = dumper.instance.iter().map(|column| column.iter().map(|value| display_field(*value).to_string()).collect()).collect();
+    Ok(WitnessDump { cells: dumper.cells, instance, columns: dumper.columns })
+}
+
+struct Dumper<F> {
+    instance: Vec<Vec<F>>,
+    region: Option<String>,
+    cells: Vec<CellDump>,
+    columns: Vec<ColumnId>,
+    ids: Columns,
+}
+
+impl<F: FieldExt> Dumper<F> {
+    fn assign<V, VR>(&mut self, column: ColumnId, row: usize, to: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<F>>,
+    {
+        let value = to()?.into().evaluate();
+        self.cells.push(CellDump {
+            region: self.region.clone().unwrap_or_else(|| audit::OUTSIDE.to_string()),
+            column: column.to_string(),
+            row,
+            value: display_field(value).to_string(),
+        });
+        self.columns.push(column);
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> Assignment<F> for Dumper<F> {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.region = Some(name_fn().into());
+    }
+
+    fn exit_region(&mut self) {
+        self.region = None;
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, _: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        Ok(())
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Option<F>, Error> {
+        let column = self.ids.of(column)?.index;
+        Ok(self.instance.get(column).and_then(|values| values.get(row)).copied())
+    }
+
+    fn assign_advice<V, VR, A, AR>(&mut self, _: A, column: Column<Advice>, row: usize, to: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let column = self.ids.of(column)?;
+        self.assign(column, row, to)
+    }
+
+    fn assign_fixed<V, VR, A, AR>(&mut self, _: A, column: Column<Fixed>, row: usize, to: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let column = self.ids.of(column)?;
+        self.assign(column, row, to)
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn fill_from_row(&mut self, _: Column<Fixed>, _: usize, _: Option<Assigned<F>>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
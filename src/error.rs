@@ -0,0 +1,124 @@
+use halo2_proofs::plonk;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+// The error type of the tools built on top of the library (the `fib` binary and the layers embedding it).
+// Programs that don't speak Rust can't match on the enum, so every variant has a numeric code and a kind that never
+// change once released, and `to_json` gives the same representation everywhere:
+//
+//   {"code": 4, "kind": "unsatisfied", "message": "constraints not satisfied: 2 failures"}
+//
+// New variants get new codes, existing codes are never reused.
+//
+//   code | kind          | meaning
+//  ------------------------------------------------------------------------
+//    1   | invalid_input | the inputs don't make sense (e.g. an unknown circuit id)
+//    2   | io            | a file couldn't be read or written
+//    3   | circuit       | halo2 refused the circuit (e.g. not enough rows)
+//    4   | unsatisfied   | the witness doesn't satisfy the constraints
+//    5   | regression    | some entries of a corpus or golden keys didn't give the expected outcome
+//    6   | cancelled     | the proof was cancelled through its CancellationToken
+//    7   | invalid_proof | the proof doesn't verify
+//    8   | limit_exceeded| the request is larger than the configured limits (e.g. k)
+//    9   | timeout       | the proof took longer than the configured timeout
+//   10   | internal      | a bug or a panic, e.g. a proving task that didn't complete
+//   11   | parse         | a number, an input file or a proof bundle can't be parsed
+//   12   | key_mismatch  | the verifying key doesn't have the pinned fingerprint
+
+#[derive(Debug, Error)]
+pub enum FibError {
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("{path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("circuit error: {0}")]
+    Circuit(#[from] plonk::Error),
+
+    #[error("constraints not satisfied: {0} failures")]
+    Unsatisfied(usize),
+
+    #[error("{0} corpus entries or golden keys didn't give the expected outcome")]
+    Regression(usize),
+
+    #[error("the proof was cancelled")]
+    Cancelled,
+
+    #[error("the proof doesn't verify")]
+    InvalidProof,
+
+    #[error("limit exceeded: {0}")]
+    LimitExceeded(String),
+
+    #[error("the proof took longer than {0:?}")]
+    Timeout(std::time::Duration),
+
+    #[error("internal error: {0}")]
+    Internal(String),
+
+    #[error("parse error: {0}")]
+    Parse(String),
+
+    #[error("the verifying key of {circuit_id} has fingerprint {actual}, {expected} is pinned")]
+    KeyMismatch {
+        circuit_id: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl FibError {
+    pub fn code(&self) -> u8 {
+        match self {
+            FibError::InvalidInput(_) => 1,
+            FibError::Io { .. } => 2,
+            FibError::Circuit(_) => 3,
+            FibError::Unsatisfied(_) => 4,
+            FibError::Regression(_) => 5,
+            FibError::Cancelled => 6,
+            FibError::InvalidProof => 7,
+            FibError::LimitExceeded(_) => 8,
+            FibError::Timeout(_) => 9,
+            FibError::Internal(_) => 10,
+            FibError::Parse(_) => 11,
+            FibError::KeyMismatch { .. } => 12,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FibError::InvalidInput(_) => "invalid_input",
+            FibError::Io { .. } => "io",
+            FibError::Circuit(_) => "circuit",
+            FibError::Unsatisfied(_) => "unsatisfied",
+            FibError::Regression(_) => "regression",
+            FibError::Cancelled => "cancelled",
+            FibError::InvalidProof => "invalid_proof",
+            FibError::LimitExceeded(_) => "limit_exceeded",
+            FibError::Timeout(_) => "timeout",
+            FibError::Internal(_) => "internal",
+            FibError::Parse(_) => "parse",
+            FibError::KeyMismatch { .. } => "key_mismatch",
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "code": self.code(),
+            "kind": self.kind(),
+            "message": self.to_string(),
+        })
+    }
+
+    pub fn io(path: impl AsRef<std::path::Path>, source: std::io::Error) -> Self {
+        FibError::Io {
+            path: path.as_ref().display().to_string(),
+            source,
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_pk, keygen_vk, Circuit, ProvingKey},
+    poly::commitment::Params,
+};
+
+use crate::{
+    bundle::{Metadata, ProofBundle},
+    error::FibError,
+    fibonacci::{FibInputs, FibInstance},
+    field::parse_field,
+    prover::{self, CancellationToken, Limits},
+    registry::{self, Registry},
+};
+
+// The prover and the verifier of the Fibonacci circuit for C, with the `ffi` feature, so that C, C++ and Go (cgo)
+// programs embed them. include/halo2_fibonacci_ex.h declares the functions below, generated by cbindgen (cbindgen.toml)
+// whenever they change:
+//
+//   cbindgen --config cbindgen.toml --output include/halo2_fibonacci_ex.h src/ffi.rs
+//
+//   FibKeys *keys;
+//   FibBuffer proof;
+//   if (fib_keys_new(4, 8, &keys) != FIB_OK) { puts(fib_last_error()); ... }
+//   fib_prove(keys, "1", "1", &proof);
+//   int32_t valid = fib_verify(keys, "1", "1", "55", proof.data, proof.len);   // FIB_OK, or 7 for an invalid proof
+//   fib_buffer_free(proof);
+//   fib_keys_free(keys);
+//
+// The keys are an opaque handle: the parameters and the proving key of the circuit with n steps over 2^k rows,
+// generated once and used by every proof and verification until fib_keys_free. A handle is only read after its
+// creation, so threads can share it. Proofs go through prover::prove_with_keys under the default Limits, and
+// verifications through registry::verify_bundle, with the verifying key of the handle registered for the circuit. The proofs are byte buffers owned by the library, which the caller gives back to
+// fib_buffer_free. The field elements are C strings read by field::parse_field.
+//
+// Every function returns FIB_OK (0) or the code of a FibError (see src/error.rs), and leaves the error, as the JSON of
+// FibError::to_json, for fib_last_error on the same thread. A panic doesn't cross the boundary: it is caught and
+// returned as an internal error (10).
+
+pub const FIB_OK: i32 = 0;
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// Generated keys of the circuit with n steps over 2^k rows, an opaque handle.
+///
+/// ```
+/// use std::{ffi::CStr, ptr};
+/// use halo2_fibonacci_ex::ffi::*;
+///
+/// unsafe {
+///     let mut keys = ptr::null_mut();
+///     assert_eq!(fib_keys_new(4, 8, &mut keys), FIB_OK);
+///     let mut fingerprint = [0u8; 32];
+///     assert_eq!(fib_keys_fingerprint(keys, fingerprint.as_mut_ptr()), FIB_OK);
+///
+///     let mut proof = FibBuffer { data: ptr::null_mut(), len: 0 };
+///     assert_eq!(fib_prove(keys, c"1".as_ptr(), c"1".as_ptr(), &mut proof), FIB_OK);
+///     assert_eq!(fib_verify(keys, c"1".as_ptr(), c"1".as_ptr(), c"55".as_ptr(), proof.data, proof.len), FIB_OK);
+///     assert_eq!(fib_verify(keys, c"1".as_ptr(), c"1".as_ptr(), c"56".as_ptr(), proof.data, proof.len), 7);
+///     assert!(CStr::from_ptr(fib_last_error()).to_str().unwrap().contains("invalid_proof"));
+///     fib_buffer_free(proof);
+///     fib_keys_free(keys);
+///
+///     // Too many steps for 2^4 rows: a circuit error
+///     assert_eq!(fib_keys_new(4, 100, &mut keys), 3);
+/// }
+/// ```
+pub struct FibKeys {
+    k: u32,
+    n: usize,
+    circuit_id: String,
+    params: Params<EqAffine>,
+    pk: ProvingKey<EqAffine>,
+    registry: Registry,
+}
+
+// Bytes allocated by the library, freed with fib_buffer_free
+#[repr(C)]
+pub struct FibBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl FibBuffer {
+    fn new(bytes: Vec<u8>) -> Self {
+        let bytes = Box::leak(bytes.into_boxed_slice());
+        FibBuffer { data: bytes.as_mut_ptr(), len: bytes.len() }
+    }
+}
+
+// Runs `f`, recording its error or panic for fib_last_error
+fn run(f: impl FnOnce() -> Result<(), FibError>) -> i32 {
+    let result = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(FibError::Internal(format!("panic: {}", message)))
+    });
+    match result {
+        Ok(()) => FIB_OK,
+        Err(e) => {
+            // serde_json escapes the control characters, so the JSON has no interior NUL
+            let json = CString::new(e.to_json().to_string()).unwrap_or_default();
+            LAST_ERROR.with(|last| *last.borrow_mut() = json);
+            i32::from(e.code())
+        }
+    }
+}
+
+fn non_null<'a, T>(pointer: *const T, name: &str) -> Result<&'a T, FibError> {
+    // SAFETY: the caller passes null or a valid pointer, as the header documents
+    unsafe { pointer.as_ref() }.ok_or_else(|| FibError::InvalidInput(format!("{} is null", name)))
+}
+
+fn field(s: *const c_char, name: &str) -> Result<String, FibError> {
+    non_null(s, name)?;
+    // SAFETY: a non-null string is NUL-terminated, as the header documents
+    let s = unsafe { CStr::from_ptr(s) };
+    Ok(s.to_str().map_err(|_| FibError::Parse(format!("{} is not UTF-8", name)))?.to_string())
+}
+
+/// Generates the keys of the circuit with n steps over 2^k rows into `*keys`.
+///
+///
+/// # Safety
+///
+/// `keys` is a valid pointer to write the handle to.
+#[no_mangle]
+pub unsafe extern "C" fn fib_keys_new(k: u32, n: usize, keys: *mut *mut FibKeys) -> i32 {
+    run(|| {
+        if keys.is_null() {
+            return Err(FibError::InvalidInput("keys is null".to_string()));
+        }
+        Limits::default().check_k(k)?;
+        let inputs = FibInputs::new(Fp::zero(), Fp::zero(), n);
+        let circuit = inputs.circuit().without_witnesses();
+        let params = Params::new(k);
+        let vk = keygen_vk(&params, &circuit)?;
+        let pk = keygen_pk(&params, vk.clone(), &circuit)?;
+        let circuit_id = inputs.circuit_id();
+        let mut registry = Registry::new();
+        registry.register_fibonacci(k, n);
+        registry.set_keys(&circuit_id, params.clone(), vk)?;
+        // SAFETY: checked non-null above, valid as the caller guarantees
+        unsafe { *keys = Box::into_raw(Box::new(FibKeys { k, n, circuit_id, params, pk, registry })) };
+        Ok(())
+    })
+}
+
+/// # Safety
+///
+/// `keys` is null or a handle created by fib_keys_new, not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn fib_keys_free(keys: *mut FibKeys) {
+    if !keys.is_null() {
+        // SAFETY: created by Box::into_raw in fib_keys_new
+        drop(unsafe { Box::from_raw(keys) });
+    }
+}
+
+/// Writes the 32 bytes of the fingerprint of the verifying key (prover::fingerprint) to `out`.
+///
+/// # Safety
+///
+/// `keys` is a handle created by fib_keys_new, `out` points to 32 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn fib_keys_fingerprint(keys: *const FibKeys, out: *mut u8) -> i32 {
+    run(|| {
+        let keys = non_null(keys, "keys")?;
+        non_null(out, "out")?;
+        let fingerprint = hex::decode(prover::fingerprint(keys.pk.get_vk())).map_err(|e| FibError::Internal(e.to_string()))?;
+        // SAFETY: out points to 32 bytes, as the caller guarantees
+        unsafe { ptr::copy_nonoverlapping(fingerprint.as_ptr(), out, fingerprint.len()) };
+        Ok(())
+    })
+}
+
+/// Creates a proof that n steps starting from (a, b) reach their output, into `*proof`.
+///
+/// # Safety
+///
+/// `keys` is a handle created by fib_keys_new, `a` and `b` are NUL-terminated strings, `proof` is a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn fib_prove(keys: *const FibKeys, a: *const c_char, b: *const c_char, proof: *mut FibBuffer) -> i32 {
+    run(|| {
+        let keys = non_null(keys, "keys")?;
+        let inputs = FibInputs::new(parse_field(&field(a, "a")?)?, parse_field(&field(b, "b")?)?, keys.n);
+        if proof.is_null() {
+            return Err(FibError::InvalidInput("proof is null".to_string()));
+        }
+        let (circuit, instance) = (inputs.circuit(), inputs.public_inputs());
+        let token = CancellationToken::new();
+        let bytes = prover::prove_with_keys(keys.k, &keys.params, &keys.pk, &circuit, &instance, &token, &Limits::default())?;
+        // SAFETY: checked non-null above, valid as the caller guarantees
+        unsafe { *proof = FibBuffer::new(bytes) };
+        Ok(())
+    })
+}
+
+/// Verifies that `proof` proves that n steps starting from (a, b) end at out: FIB_OK, or 7 for an invalid proof.
+///
+/// # Safety
+///
+/// `keys` is a handle created by fib_keys_new, `a`, `b` and `out` are NUL-terminated strings, `proof` points to `len`
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn fib_verify(
+    keys: *const FibKeys,
+    a: *const c_char,
+    b: *const c_char,
+    out: *const c_char,
+    proof: *const u8,
+    len: usize,
+) -> i32 {
+    run(|| {
+        let keys = non_null(keys, "keys")?;
+        let instance = FibInstance::from_strs(&field(a, "a")?, &field(b, "b")?, &field(out, "out")?)?.column();
+        non_null(proof, "proof")?;
+        // SAFETY: proof points to len bytes, as the caller guarantees
+        let proof = unsafe { slice::from_raw_parts(proof, len) };
+        let bundle = ProofBundle::new(Metadata::new(keys.k, &keys.circuit_id), instance, proof.to_vec());
+        registry::verify_bundle(&bundle, &keys.registry).map(|_| ())
+    })
+}
+
+/// # Safety
+///
+/// `buffer` was returned by the library and is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn fib_buffer_free(buffer: FibBuffer) {
+    if !buffer.data.is_null() {
+        // SAFETY: allocated as a boxed slice of len bytes by FibBuffer::new
+        drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(buffer.data, buffer.len)) });
+    }
+}
+
+// The last error on this thread as JSON, "" if there was none. The string lives until the next error on the thread
+#[no_mangle]
+pub extern "C" fn fib_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
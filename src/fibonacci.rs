@@ -0,0 +1,262 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    plonk::*,
+};
+use num_bigint::BigUint;
+
+use crate::{
+    error::FibError,
+    field::parse_field,
+    sequence::{Boundary, Sequence, SequenceCircuit},
+};
+
+// The Fibonacci circuit of the examples, packaged for the tools (the `fib` binary) rather than for teaching.
+// The state is a pair (a, b) and each step computes (b, a + b).
+// The instance column holds [a, b, out], where out is the second word of the state after n steps.
+
+pub struct Fibonacci;
+
+impl<F: FieldExt> Sequence<F> for Fibonacci {
+    const WIDTH: usize = 2;
+
+    fn transition(cur: &[Expression<F>], next: &[Expression<F>]) -> Vec<(&'static str, Expression<F>)> {
+        vec![
+            ("fib-shift", next[0].clone() - cur[1].clone()),
+            ("fib-add", next[1].clone() - (cur[0].clone() + cur[1].clone())),
+        ]
+    }
+
+    fn step(cur: &[F]) -> Vec<F> {
+        vec![cur[1], cur[0] + cur[1]]
+    }
+
+    fn boundaries() -> Vec<Boundary> {
+        vec![Boundary::first(0, 0), Boundary::first(1, 1), Boundary::last(1, 2)]
+    }
+
+    fn word_names() -> Vec<String> {
+        vec!["fib_a".to_string(), "fib_b".to_string()]
+    }
+}
+
+pub type FibCircuit<F> = SequenceCircuit<F, Fibonacci>;
+
+/// The statement "starting from (a, b), n steps of Fibonacci give out".
+///
+/// ```
+/// use halo2_proofs::{dev::MockProver, pasta::Fp};
+/// use halo2_fibonacci_ex::fibonacci::{max_steps, FibInputs};
+///
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// assert_eq!(inputs.public_inputs()[2], Fp::from(55));
+/// MockProver::run(4, &inputs.circuit(), vec![inputs.public_inputs()]).unwrap().assert_satisfied();
+///
+/// // One more step than max_steps doesn't fit in 2^k rows
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), max_steps::<Fp>(4) + 1);
+/// assert!(MockProver::run(4, &inputs.circuit(), vec![inputs.public_inputs()]).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FibInputs<F: FieldExt> {
+    pub a: F,
+    pub b: F,
+    pub n: usize,
+}
+
+impl<F: FieldExt> FibInputs<F> {
+    pub fn new(a: F, b: F, n: usize) -> Self {
+        Self { a, b, n }
+    }
+
+    pub fn circuit(&self) -> FibCircuit<F> {
+        FibCircuit::new(vec![Some(self.a), Some(self.b)], self.n)
+    }
+
+    // Identifies the shape of the circuit: the number of steps is baked into the keys
+    pub fn circuit_id(&self) -> String {
+        format!("fibonacci-{}", self.n)
+    }
+
+    // The number of steps of a circuit id, the inverse of circuit_id
+    pub fn steps_of(circuit_id: &str) -> Option<usize> {
+        circuit_id.strip_prefix("fibonacci-")?.parse().ok()
+    }
+
+    // The instance column [a, b, out] computed natively
+    pub fn public_inputs(&self) -> Vec<F> {
+        Fibonacci::public_inputs(&[self.a, self.b], self.n)
+    }
+
+    // The same, by name
+    pub fn instance(&self) -> FibInstance<F> {
+        let public_inputs = self.public_inputs();
+        FibInstance::new(public_inputs[0], public_inputs[1], public_inputs[2])
+    }
+}
+
+/// The public inputs of the Fibonacci circuit by name. The instance column is [a, b, out].
+///
+/// ```
+/// use halo2_proofs::{dev::MockProver, pasta::Fp};
+/// use halo2_fibonacci_ex::fibonacci::{FibInputs, FibInstance};
+///
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// let instance = FibInstance::from_strs("1", "1", "55").unwrap();
+/// assert_eq!(instance, inputs.instance());
+/// MockProver::run(4, &inputs.circuit(), instance.to_instance_columns()).unwrap().assert_satisfied();
+///
+/// let forged = FibInstance { out: Fp::from(56), ..instance };
+/// assert!(MockProver::run(4, &inputs.circuit(), forged.to_instance_columns()).unwrap().verify().is_err());
+/// assert!(FibInstance::<Fp>::from_strs("1", "1", "fifty-five").is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FibInstance<F: FieldExt> {
+    pub a: F,
+    pub b: F,
+    pub out: F,
+}
+
+impl<F: FieldExt> FibInstance<F> {
+    pub fn new(a: F, b: F, out: F) -> Self {
+        Self { a, b, out }
+    }
+
+    // From strings in any of the formats of parse_field (decimal, 0x hex or bytes)
+    pub fn from_strs(a: &str, b: &str, out: &str) -> Result<Self, FibError> {
+        Ok(Self::new(parse_field(a)?, parse_field(b)?, parse_field(out)?))
+    }
+
+    // The instance column, [a, b, out]
+    pub fn column(&self) -> Vec<F> {
+        vec![self.a, self.b, self.out]
+    }
+
+    // All the instance columns of the circuit, as MockProver::run and create_proof take them
+    pub fn to_instance_columns(&self) -> Vec<Vec<F>> {
+        vec![self.column()]
+    }
+}
+
+/// Builds the Fibonacci circuit together with its instance column, so that the public inputs always match the circuit.
+///
+/// ```
+/// use halo2_proofs::{dev::MockProver, pasta::Fp};
+/// use halo2_fibonacci_ex::fibonacci::FibCircuitBuilder;
+///
+/// let (circuit, public_inputs) = FibCircuitBuilder::new().start(Fp::one(), Fp::one()).steps(8).expose_output().build().unwrap();
+/// assert_eq!(public_inputs, vec![Fp::one(), Fp::one(), Fp::from(55)]);
+/// MockProver::run(4, &circuit, vec![public_inputs]).unwrap().assert_satisfied();
+///
+/// // A missing number of steps is an error rather than a circuit of 0 steps
+/// assert!(FibCircuitBuilder::new().start(Fp::one(), Fp::one()).expose_output().build().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FibCircuitBuilder<F: FieldExt> {
+    start: Option<(F, F)>,
+    steps: Option<usize>,
+    expose_output: bool,
+}
+
+impl<F: FieldExt> Default for FibCircuitBuilder<F> {
+    fn default() -> Self {
+        Self { start: None, steps: None, expose_output: false }
+    }
+}
+
+impl<F: FieldExt> FibCircuitBuilder<F> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The first two terms, public
+    pub fn start(self, a: F, b: F) -> Self {
+        Self { start: Some((a, b)), ..self }
+    }
+
+    pub fn steps(self, n: usize) -> Self {
+        Self { steps: Some(n), ..self }
+    }
+
+    // The output is always public in the Fibonacci circuit. Asking for it explicitly keeps the calls in the order of
+    // the instance column [a, b, out]
+    pub fn expose_output(self) -> Self {
+        Self { expose_output: true, ..self }
+    }
+
+    // The circuit and its instance column [a, b, out]
+    pub fn build(self) -> Result<(FibCircuit<F>, Vec<F>), FibError> {
+        let (a, b) = self.start.ok_or_else(|| FibError::InvalidInput("the first two terms are missing, call start".to_string()))?;
+        let n = self.steps.ok_or_else(|| FibError::InvalidInput("the number of steps is missing, call steps".to_string()))?;
+        if !self.expose_output {
+            return Err(FibError::InvalidInput("the Fibonacci circuit always exposes its output, call expose_output".to_string()));
+        }
+        let inputs = FibInputs::new(a, b, n);
+        Ok((inputs.circuit(), inputs.public_inputs()))
+    }
+}
+
+// The largest number of steps that fits in 2^k rows. The table has n + 1 rows, and the last
+// blinding_factors + 1 rows of the circuit are reserved by the prover
+pub fn max_steps<F: FieldExt>(k: u32) -> usize {
+    let mut cs = ConstraintSystem::<F>::default();
+    FibCircuit::<F>::configure(&mut cs);
+    (1usize << k).saturating_sub(cs.blinding_factors() + 1).saturating_sub(1)
+}
+
+// The modulus p of F, as an integer
+pub fn modulus<F: FieldExt>() -> BigUint {
+    BigUint::parse_bytes(F::MODULUS.trim_start_matches("0x").as_bytes(), 16).expect("FieldExt::MODULUS is hex")
+}
+
+// A field element as the integer in 0..p it stands for
+pub fn to_biguint<F: FieldExt>(value: F) -> BigUint {
+    BigUint::from_bytes_le(value.to_repr().as_ref())
+}
+
+// The output after n steps of the integer sequence: what public_inputs computes, without the reductions modulo p
+pub fn integer_out(a: &BigUint, b: &BigUint, n: usize) -> BigUint {
+    let (mut a, mut b) = (a.clone(), b.clone());
+    for _ in 0..n {
+        let c = &a + &b;
+        a = std::mem::replace(&mut b, c);
+    }
+    b
+}
+
+/// The smallest number of steps after which the output no longer fits in F, starting from (a, b): from there on the
+/// circuit proves the Fibonacci sequence modulo p rather than over the integers. None if the sequence stays at 0.
+///
+/// ```
+/// use halo2_proofs::pasta::Fp;
+/// use num_bigint::BigUint;
+/// use halo2_fibonacci_ex::fibonacci::{first_reduction, integer_out, modulus, to_biguint, FibInputs};
+///
+/// let one = BigUint::from(1u8);
+/// let n = first_reduction::<Fp>(&one, &one).unwrap();
+/// // The output after 366 steps is F(368), the first Fibonacci number larger than p
+/// assert_eq!(n, 366);
+///
+/// // Up to n - 1 steps the circuit computes the integer output, after n steps the output reduced modulo p
+/// for (steps, reduced) in [(n - 1, false), (n, true)] {
+///     let out = FibInputs::new(Fp::one(), Fp::one(), steps).public_inputs()[2];
+///     let integer = integer_out(&one, &one, steps);
+///     assert_eq!(to_biguint(out) != integer, reduced);
+///     assert_eq!(to_biguint(out), integer % modulus::<Fp>());
+/// }
+///
+/// assert_eq!(first_reduction::<Fp>(&BigUint::from(0u8), &BigUint::from(0u8)), None);
+/// ```
+pub fn first_reduction<F: FieldExt>(a: &BigUint, b: &BigUint) -> Option<usize> {
+    let p = modulus::<F>();
+    if *a == BigUint::from(0u8) && *b == BigUint::from(0u8) {
+        return None;
+    }
+    // From the second step on the terms grow at least like the Fibonacci numbers, so this ends after O(log p) steps
+    let (mut a, mut b) = (a.clone(), b.clone());
+    let mut n = 0;
+    while b < p {
+        let c = &a + &b;
+        a = std::mem::replace(&mut b, c);
+        n += 1;
+    }
+    Some(n)
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+
+use halo2_proofs::arithmetic::FieldExt;
+use num_bigint::BigUint;
+
+use crate::{
+    error::FibError,
+    fibonacci::{modulus, to_biguint},
+};
+
+// Field elements as users type them, in the command line and in the JSON input files:
+//
+//   55                      decimal
+//   0x37                    hex, most significant digit first like any number
+//   [55, 0, 0]              the bytes of the little endian representation (PrimeField::to_repr), trailing zeros optional
+//
+// Values that are not smaller than the modulus are rejected rather than reduced, so a typo in a large value can't
+// silently turn into another field element.
+//
+// The other way around, display_field prints field elements as the same integers: the Debug output of the Pasta
+// fields is the 64 hex digits of the representation, unreadable for the small values of the examples.
+
+/// Parses a field element written in decimal, in hex with a 0x prefix, or as the bytes of its representation.
+///
+/// ```
+/// use halo2_proofs::pasta::Fp;
+/// use halo2_fibonacci_ex::field::parse_field;
+///
+/// assert_eq!(parse_field::<Fp>("55").unwrap(), Fp::from(55));
+/// assert_eq!(parse_field::<Fp>("0x37").unwrap(), Fp::from(55));
+/// assert_eq!(parse_field::<Fp>("[55, 0]").unwrap(), Fp::from(55));
+///
+/// // p - 1 is the largest field element, p overflows
+/// let p_minus_one = "28948022309329048855892746252171976963363056481941560715954676764349967630336";
+/// assert_eq!(parse_field::<Fp>(p_minus_one).unwrap(), -Fp::one());
+/// assert!(parse_field::<Fp>("28948022309329048855892746252171976963363056481941560715954676764349967630337").is_err());
+/// assert!(parse_field::<Fp>("[256]").is_err());
+/// assert!(parse_field::<Fp>("-1").is_err());
+/// ```
+pub fn parse_field<F: FieldExt>(s: &str) -> Result<F, FibError> {
+    let s = s.trim();
+    let value = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        parse_digits(hex, 16).ok_or_else(|| FibError::Parse(format!("not a hex number: {:?}", s)))?
+    } else if let Some(bytes) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        let bytes = bytes
+            .split(',')
+            .map(|byte| byte.trim().parse::<u8>())
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|e| FibError::Parse(format!("not an array of bytes: {:?} ({})", s, e)))?;
+        if bytes.len() > F::Repr::default().as_ref().len() {
+            return Err(FibError::Parse(format!("{} bytes, a field element has {}", bytes.len(), F::Repr::default().as_ref().len())));
+        }
+        BigUint::from_bytes_le(&bytes)
+    } else {
+        parse_digits(s, 10).ok_or_else(|| FibError::Parse(format!("not a decimal number: {:?}", s)))?
+    };
+
+    if value >= modulus::<F>() {
+        return Err(FibError::Parse(format!("{} is not smaller than the modulus {}", s, F::MODULUS)));
+    }
+    let mut repr = F::Repr::default();
+    let bytes = value.to_bytes_le();
+    repr.as_mut()[..bytes.len()].copy_from_slice(&bytes);
+    Ok(F::from_repr(repr).expect("a value smaller than the modulus is canonical"))
+}
+
+// BigUint::parse_bytes accepts underscores, which are not numbers users mean to type
+fn parse_digits(s: &str, radix: u32) -> Option<BigUint> {
+    if s.is_empty() || !s.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    BigUint::parse_bytes(s.as_bytes(), radix)
+}
+
+/// A field element printed as the integer in 0..p it stands for: in decimal with `{}`, in hex with `{:x}` (`{:#x}` adds
+/// the 0x prefix, which parse_field reads back).
+///
+/// ```
+/// use halo2_proofs::pasta::Fp;
+/// use halo2_fibonacci_ex::field::{display_field, parse_field};
+///
+/// assert_eq!(display_field(Fp::from(55)).to_string(), "55");
+/// assert_eq!(format!("{:#x}", display_field(Fp::from(55))), "0x37");
+/// assert_eq!(parse_field::<Fp>(&display_field(-Fp::one()).to_string()).unwrap(), -Fp::one());
+/// ```
+pub fn display_field<F: FieldExt>(value: F) -> DisplayField<F> {
+    DisplayField(value)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayField<F: FieldExt>(pub F);
+
+impl<F: FieldExt> fmt::Display for DisplayField<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&to_biguint(self.0), f)
+    }
+}
+
+impl<F: FieldExt> fmt::LowerHex for DisplayField<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&to_biguint(self.0), f)
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_pk, keygen_vk, Circuit, ProvingKey},
+    poly::commitment::Params,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    backend::{Backend, IpaPasta},
+    error::FibError,
+    fibonacci::FibInputs,
+    params::ParamsCache,
+    prover,
+};
+
+// A store of the fingerprints of the keys of the Fibonacci circuits, to notice a circuit that changed under the same
+// circuit id. It is not a key cache: halo2_proofs 0.1.0 can't write a proving or a verifying key, so every process
+// runs keygen again.
+//
+// Each key gets a record, <dir>/<key id>.key.json, with the fingerprint of its verifying key. The key id is the
+// SHA-256 of the circuit id, n, k and the backend, so that a circuit of another size or another backend is never
+// compared with the record of this one. A different fingerprint means the circuit changed since the record was
+// written, by a build whose constraints differ under the same circuit id: the record is replaced and the status says
+// so, since the proofs created under the old record don't verify with the new keys.
+//
+// Along the way, the store keeps the parameters in <dir>/params (a ParamsCache) and the keys it generated in memory,
+// for the later proofs of the same process.
+
+// Where the keys returned by FingerprintStore::fibonacci come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyStatus {
+    // Generated earlier by this store
+    Memory,
+    // Generated now and matching the record on disk
+    Recorded,
+    // Generated now, with no record yet
+    New,
+    // Generated now and not matching the record on disk, which is replaced
+    Changed,
+}
+
+pub struct StoredKeys {
+    pub k: u32,
+    pub params: Params<EqAffine>,
+    pub pk: ProvingKey<EqAffine>,
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Record {
+    circuit_id: String,
+    n: usize,
+    k: u32,
+    backend: String,
+    fingerprint: String,
+}
+
+// The id the keys of a circuit are stored under, hex encoded
+pub fn key_id(circuit_id: &str, n: usize, k: u32, backend: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [circuit_id, &n.to_string(), &k.to_string(), backend] {
+        // Length-prefixed, so that the parts can't run into each other
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// The fingerprints of the keys of the Fibonacci circuits, recorded in a directory.
+///
+/// ```
+/// use halo2_fibonacci_ex::fingerprints::{FingerprintStore, KeyStatus};
+///
+/// let dir = std::env::temp_dir().join(format!("fingerprints-doctest-{}", std::process::id()));
+/// let store = FingerprintStore::new(&dir);
+/// let (keys, status) = store.fibonacci(4, 8).unwrap();
+/// assert_eq!(status, KeyStatus::New);
+/// assert_eq!(store.fibonacci(4, 8).unwrap().1, KeyStatus::Memory);
+///
+/// // Another process finds the record, and the keys it generates match it
+/// assert_eq!(FingerprintStore::new(&dir).fibonacci(4, 8).unwrap().1, KeyStatus::Recorded);
+///
+/// // A record written by a build with another circuit is replaced
+/// let record = store.record_path("fibonacci-8", 8, 4);
+/// std::fs::write(&record, std::fs::read_to_string(&record).unwrap().replace(&keys.fingerprint, "00")).unwrap();
+/// assert_eq!(FingerprintStore::new(&dir).fibonacci(4, 8).unwrap().1, KeyStatus::Changed);
+/// assert_eq!(FingerprintStore::new(&dir).fibonacci(4, 8).unwrap().1, KeyStatus::Recorded);
+/// std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub struct FingerprintStore {
+    dir: PathBuf,
+    params: ParamsCache,
+    // Keyed by key id. The map is only locked to find the slot of a key, and keygen only locks that slot, so the keys
+    // of different circuits are generated in parallel while a second caller for the same circuit waits for the first
+    keys: Mutex<HashMap<String, Arc<Slot>>>,
+}
+
+type Slot = Mutex<Option<Arc<StoredKeys>>>;
+
+impl FingerprintStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        Self { params: ParamsCache::new(dir.join("params")), dir, keys: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // The record of the keys of a circuit of the IPA backend
+    pub fn record_path(&self, circuit_id: &str, n: usize, k: u32) -> PathBuf {
+        self.dir.join(format!("{}.key.json", key_id(circuit_id, n, k, IpaPasta::ID)))
+    }
+
+    // The keys of the Fibonacci circuit with n steps over 2^k rows
+    pub fn fibonacci(&self, k: u32, n: usize) -> Result<(Arc<StoredKeys>, KeyStatus), FibError> {
+        let inputs = FibInputs::new(Fp::zero(), Fp::zero(), n);
+        let circuit_id = inputs.circuit_id();
+        let id = key_id(&circuit_id, n, k, IpaPasta::ID);
+        let slot = {
+            let mut keys = self.keys.lock().map_err(|_| FibError::Internal("a keygen panicked".to_string()))?;
+            keys.entry(id).or_default().clone()
+        };
+        let mut slot = slot.lock().map_err(|_| FibError::Internal("a keygen panicked".to_string()))?;
+        if let Some(stored) = slot.as_ref() {
+            return Ok((stored.clone(), KeyStatus::Memory));
+        }
+
+        let params = self.params.load_or_setup::<IpaPasta>(k)?;
+        let circuit = inputs.circuit().without_witnesses();
+        let vk = keygen_vk(&params, &circuit)?;
+        let fingerprint = prover::fingerprint(&vk);
+        let pk = keygen_pk(&params, vk, &circuit)?;
+
+        // A record that can't be read is as good as none
+        let path = self.record_path(&circuit_id, n, k);
+        let recorded = fs::read_to_string(&path).ok().and_then(|json| serde_json::from_str::<Record>(&json).ok());
+        let status = match recorded {
+            Some(record) if record.fingerprint == fingerprint => KeyStatus::Recorded,
+            Some(_) => KeyStatus::Changed,
+            None => KeyStatus::New,
+        };
+        if status != KeyStatus::Recorded {
+            let record = Record { circuit_id, n, k, backend: IpaPasta::ID.to_string(), fingerprint: fingerprint.clone() };
+            let json = serde_json::to_string_pretty(&record).map_err(|e| FibError::Internal(e.to_string()))?;
+            fs::create_dir_all(&self.dir).map_err(|e| FibError::io(&self.dir, e))?;
+            fs::write(&path, json).map_err(|e| FibError::io(&path, e))?;
+        }
+
+        let stored = Arc::new(StoredKeys { k, params, pk, fingerprint });
+        *slot = Some(stored.clone());
+        Ok((stored, status))
+    }
+}
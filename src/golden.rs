@@ -0,0 +1,118 @@
+use std::{fs, path::Path};
+
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_vk, Circuit, VerifyingKey},
+    poly::commitment::Params,
+};
+
+use crate::{
+    error::FibError,
+    fibonacci::FibInputs,
+    merkle::{MerkleCircuit, MerkleTree},
+    prover,
+};
+
+// Golden verifying keys: the keys of the circuits of the library, checked in under golden/, so that a refactor which
+// changes a constraint system by accident fails a test instead of silently invalidating the proofs issued so far.
+// Every change to a gate, a column, a selector or a fixed value changes the verifying key.
+//
+// halo2_proofs 0.1.0 can't serialize a verifying key, so a fixture is its pinned representation (the domain, the
+// constraint system, the fixed and permutation commitments) pretty-printed, one field per line, which is also what a
+// reviewer reads in the diff of a change that updates the fixtures on purpose:
+//
+//   golden/fibonacci-8-k4.vk
+//
+// `fib golden` compares the keys of this build with the fixtures and `fib golden --bless` rewrites them. The doc test
+// of `check` runs the comparison with `cargo test`.
+
+// A verifying key of the golden set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoldenKey {
+    // The file name of the fixture, without the extension: the circuit id and k
+    pub name: String,
+    pub fingerprint: String,
+    pub pinned: String,
+}
+
+impl GoldenKey {
+    fn new<C: Circuit<Fp>>(circuit_id: &str, k: u32, circuit: &C) -> Result<Self, FibError> {
+        let vk: VerifyingKey<EqAffine> = keygen_vk(&Params::new(k), circuit)?;
+        Ok(Self {
+            name: format!("{}-k{}", circuit_id, k),
+            fingerprint: prover::fingerprint(&vk),
+            pinned: format!("{:#?}\n", vk.pinned()),
+        })
+    }
+}
+
+// The first line where a verifying key differs from its fixture, numbered from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub name: String,
+    pub line: usize,
+    pub expected: String,
+    pub actual: String,
+}
+
+// The keys of the golden set: the Fibonacci circuit at a few sizes and the Merkle circuit
+pub fn golden_keys() -> Result<Vec<GoldenKey>, FibError> {
+    let mut keys = Vec::new();
+    for (k, n) in [(4, 8), (7, 100)] {
+        let inputs = FibInputs::new(Fp::zero(), Fp::zero(), n);
+        keys.push(GoldenKey::new(&inputs.circuit_id(), k, &inputs.circuit().without_witnesses())?);
+    }
+    let tree = MerkleTree::new(&[Fp::zero(); 4], 2)?;
+    keys.push(GoldenKey::new("merkle-2", 8, &MerkleCircuit::new(Fp::zero(), &tree.path(0)?).without_witnesses())?);
+    Ok(keys)
+}
+
+/// Compares the golden keys with the fixtures of `dir`. A missing fixture is an error.
+///
+/// ```
+/// use std::path::Path;
+/// use halo2_fibonacci_ex::golden;
+///
+/// let mismatches = golden::check(&Path::new(env!("CARGO_MANIFEST_DIR")).join("golden")).unwrap();
+/// assert!(
+///     mismatches.is_empty(),
+///     "a verifying key changed, which invalidates the proofs created before. If the change is on purpose, run \
+///      `cargo run --bin fib -- golden --bless` and commit the fixtures: {:#?}",
+///     mismatches,
+/// );
+/// ```
+pub fn check(dir: &Path) -> Result<Vec<Mismatch>, FibError> {
+    let mut mismatches = Vec::new();
+    for key in golden_keys()? {
+        let path = dir.join(format!("{}.vk", key.name));
+        let fixture = fs::read_to_string(&path).map_err(|e| FibError::io(&path, e))?;
+        if fixture == key.pinned {
+            continue;
+        }
+        // Lines past the end of the shorter text compare as empty
+        let (mut expected, mut actual) = (fixture.lines(), key.pinned.lines());
+        let mut line = 1;
+        loop {
+            match (expected.next(), actual.next()) {
+                (Some(e), Some(a)) if e == a => line += 1,
+                (e, a) => {
+                    let (expected, actual) = (e.unwrap_or_default().to_string(), a.unwrap_or_default().to_string());
+                    mismatches.push(Mismatch { name: key.name, line, expected, actual });
+                    break;
+                }
+            }
+        }
+    }
+    Ok(mismatches)
+}
+
+// Writes the fixtures of the golden keys to `dir`, replacing the ones there, and returns the keys
+pub fn bless(dir: &Path) -> Result<Vec<GoldenKey>, FibError> {
+    fs::create_dir_all(dir).map_err(|e| FibError::io(dir, e))?;
+    let keys = golden_keys()?;
+    for key in &keys {
+        let path = dir.join(format!("{}.vk", key.name));
+        fs::write(&path, &key.pinned).map_err(|e| FibError::io(&path, e))?;
+    }
+    Ok(keys)
+}
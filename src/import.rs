@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use halo2_proofs::{arithmetic::FieldExt, plonk::*};
+
+use crate::{
+    audit::ColumnId,
+    dump::{CellDump, WitnessDump},
+    error::FibError,
+    field::parse_field,
+    sequence::{BoundaryRow, Sequence, SequenceCircuit},
+};
+
+// Witnesses computed outside of the library, e.g. by another implementation of the witness generation, checked by
+// the MockProver against the constraints of the library. The formats are the ones of the witness dump (src/dump.rs):
+//
+// - JSON, the dump itself: the cells and the instance columns
+// - CSV, one cell per line after a `region,column,row,value` header. The instance is given separately
+//
+// The values are read by parse_field. Only the cells of the state columns of a SequenceCircuit are read, rows counted
+// from the first one of the table, so that the table doesn't have to start at the row the floor planner picks. The
+// circuit of the witness (ImportedWitness::circuit) assigns the imported states as they are, through the trace of
+// SequenceCircuit::with_trace: nothing is computed again, so a wrong state is reported by the MockProver like it
+// would be for a malicious prover. The public words of the first state are copied from the instance by the circuit,
+// so their imported values are compared to the instance here instead.
+
+/// The states of a [`Sequence`] and the instance, imported from a witness dump.
+///
+/// ```
+/// use halo2_proofs::{dev::MockProver, pasta::Fp};
+/// use halo2_fibonacci_ex::{
+///     dump::witness_dump,
+///     fibonacci::{FibCircuit, FibInputs, Fibonacci},
+///     import::ImportedWitness,
+/// };
+///
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// let json = witness_dump(&inputs.circuit(), vec![inputs.public_inputs()]).unwrap().with_names(&FibCircuit::<Fp>::column_names()).to_json();
+///
+/// let witness = ImportedWitness::<Fp>::from_json::<Fibonacci>(&json).unwrap();
+/// MockProver::run(4, &witness.circuit::<Fibonacci>(), witness.instance.clone()).unwrap().assert_satisfied();
+///
+/// // The same cells as CSV, with a wrong last state
+/// let csv = "region,column,row,value\n".to_string()
+///     + &(0..=8u64).map(|row| format!("t,column fib_a,{},1\nt,column fib_b,{},1\n", row, row)).collect::<String>();
+/// let witness = ImportedWitness::<Fp>::from_csv::<Fibonacci>(&csv, vec![inputs.public_inputs()]).unwrap();
+/// assert!(MockProver::run(4, &witness.circuit::<Fibonacci>(), witness.instance.clone()).unwrap().verify().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedWitness<F: FieldExt> {
+    // The states, from the initial one to the last one
+    pub trace: Vec<Vec<F>>,
+    pub instance: Vec<Vec<F>>,
+}
+
+impl<F: FieldExt> ImportedWitness<F> {
+    pub fn from_json<S: Sequence<F>>(s: &str) -> Result<Self, FibError> {
+        let dump: WitnessDump = serde_json::from_str(s).map_err(|e| FibError::Parse(format!("witness: {}", e)))?;
+        let instance = dump
+            .instance
+            .iter()
+            .map(|column| column.iter().map(|value| parse_field(value)).collect())
+            .collect::<Result<Vec<Vec<F>>, FibError>>()?;
+        Self::from_cells::<S>(&dump.cells, instance)
+    }
+
+    pub fn from_csv<S: Sequence<F>>(s: &str, instance: Vec<Vec<F>>) -> Result<Self, FibError> {
+        let mut lines = s.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        match lines.next() {
+            Some((_, header)) if header.split(',').map(str::trim).eq(["region", "column", "row", "value"]) => {}
+            _ => return Err(FibError::Parse("witness: the CSV header must be region,column,row,value".to_string())),
+        }
+
+        let cells = lines
+            .map(|(number, line)| {
+                let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+                match fields[..] {
+                    [region, column, row, value] => Ok(CellDump {
+                        region: region.to_string(),
+                        column: column.to_string(),
+                        row: row.parse().map_err(|e| FibError::Parse(format!("witness line {}: row {:?} ({})", number + 1, row, e)))?,
+                        value: value.to_string(),
+                    }),
+                    _ => Err(FibError::Parse(format!("witness line {}: expected 4 fields, got {}", number + 1, fields.len()))),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_cells::<S>(&cells, instance)
+    }
+
+    fn from_cells<S: Sequence<F>>(cells: &[CellDump], instance: Vec<Vec<F>>) -> Result<Self, FibError> {
+        let config = SequenceCircuit::<F, S>::configure(&mut ConstraintSystem::default());
+        let names = SequenceCircuit::<F, S>::column_names();
+        let words = config
+            .state
+            .iter()
+            .enumerate()
+            .map(|(word, column)| Ok((names.describe(ColumnId::of(*column)?), word)))
+            .collect::<Result<HashMap<String, usize>, FibError>>()?;
+
+        let first = cells.iter().map(|cell| cell.row).min().ok_or_else(|| FibError::Parse("witness: no cells".to_string()))?;
+        let rows = cells.iter().map(|cell| cell.row).max().unwrap_or(first) - first + 1;
+        let mut states: Vec<Vec<Option<F>>> = vec![vec![None; S::WIDTH]; rows];
+        for cell in cells {
+            let word = *words.get(&cell.column).ok_or_else(|| FibError::Parse(format!("witness: unknown {}", cell.column)))?;
+            let value = &mut states[cell.row - first][word];
+            if value.is_some() {
+                return Err(FibError::Parse(format!("witness: {} is assigned twice at row {}", cell.column, cell.row)));
+            }
+            *value = Some(parse_field(&cell.value)?);
+        }
+
+        let trace = states
+            .into_iter()
+            .enumerate()
+            .map(|(row, state)| {
+                state.into_iter().collect::<Option<Vec<F>>>().ok_or_else(|| FibError::Parse(format!("witness: row {} is incomplete", row + first)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for boundary in S::boundaries().iter().filter(|b| b.row == BoundaryRow::First) {
+            let public = instance.first().and_then(|column| column.get(config.instance_offset + boundary.instance_row));
+            if public != Some(&trace[0][boundary.word]) {
+                return Err(FibError::InvalidInput(format!("the initial word {} of the witness is not the one of the instance", boundary.word)));
+            }
+        }
+        Ok(Self { trace, instance })
+    }
+
+    pub fn steps(&self) -> usize {
+        self.trace.len() - 1
+    }
+
+    // The circuit assigning the imported states
+    pub fn circuit<S: Sequence<F>>(&self) -> SequenceCircuit<F, S> {
+        let initial = self.trace[0].iter().copied().map(Some).collect();
+        SequenceCircuit::with_trace(initial, self.steps(), self.trace.clone())
+    }
+}
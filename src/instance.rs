@@ -0,0 +1,126 @@
+use std::fmt;
+
+use halo2_proofs::{arithmetic::FieldExt, plonk::*};
+
+use crate::error::FibError;
+
+// A circuit made of several chips usually exposes values of each of them: a Fibonacci output, a Merkle root,
+// a nullifier... Every chip takes the instance row of its values as a parameter, so each of them can share the same
+// instance column, but someone has to decide which row belongs to whom, and the code building the public inputs has
+// to agree with it. Hardcoding the rows in both places breaks as soon as a chip is added or moved.
+//
+// The InstanceAllocator hands out the rows of a shared instance column in `configure`, under a name, and records the
+// resulting InstanceLayout. The layout is kept in the config of the circuit and builds the public inputs from named
+// values, so the rows are only decided in one place:
+//
+//   let mut instance = InstanceAllocator::new(meta.instance_column());
+//   let root = instance.allocate("merkle.root");
+//   let nullifier = instance.allocate("nullifier");
+//   ...
+//   layout.build(&[("merkle.root", root), ("nullifier", nullifier)])  ->  vec![root, nullifier]
+
+#[derive(Debug, Clone)]
+pub struct InstanceAllocator {
+    column: Column<Instance>,
+    names: Vec<String>,
+}
+
+/// The rows handed out by an [`InstanceAllocator`], in order. Builds the instance column from named values.
+///
+/// ```
+/// use halo2_proofs::{pasta::Fp, plonk::ConstraintSystem};
+/// use halo2_fibonacci_ex::instance::InstanceAllocator;
+///
+/// let mut meta = ConstraintSystem::<Fp>::default();
+/// let mut allocator = InstanceAllocator::new(meta.instance_column());
+/// assert_eq!(allocator.allocate_block("fibonacci", &["a", "b", "out"]), 0);
+/// assert_eq!(allocator.allocate("merkle.root"), 3);
+///
+/// let layout = allocator.layout();
+/// assert_eq!(layout.row("fibonacci.out"), Some(2));
+///
+/// let values = [("merkle.root", Fp::from(7)), ("fibonacci.a", Fp::one()), ("fibonacci.b", Fp::one()), ("fibonacci.out", Fp::from(55))];
+/// assert_eq!(layout.build(&values).unwrap(), vec![Fp::one(), Fp::one(), Fp::from(55), Fp::from(7)]);
+///
+/// // Every row must be given a value
+/// assert!(layout.build(&values[..3]).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceLayout {
+    names: Vec<String>,
+}
+
+impl InstanceAllocator {
+    pub fn new(column: Column<Instance>) -> Self {
+        Self { column, names: vec![] }
+    }
+
+    pub fn column(&self) -> Column<Instance> {
+        self.column
+    }
+
+    // Returns the row given to `name`. Names must be unique
+    pub fn allocate(&mut self, name: &str) -> usize {
+        assert!(!self.names.iter().any(|n| n == name), "instance row {:?} allocated twice", name);
+        self.names.push(name.to_string());
+        self.names.len() - 1
+    }
+
+    // Allocates consecutive rows named "prefix.name" for a chip whose public values are relative to a first row
+    // (e.g. the boundaries of a Sequence) and returns the first row
+    pub fn allocate_block(&mut self, prefix: &str, names: &[&str]) -> usize {
+        let first = self.names.len();
+        for name in names {
+            self.allocate(&format!("{}.{}", prefix, name));
+        }
+        first
+    }
+
+    pub fn layout(&self) -> InstanceLayout {
+        InstanceLayout { names: self.names.clone() }
+    }
+}
+
+impl InstanceLayout {
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn row(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    // The instance column for the given values, which must name every row exactly once
+    pub fn build<F: FieldExt>(&self, values: &[(&str, F)]) -> Result<Vec<F>, FibError> {
+        let mut column: Vec<Option<F>> = vec![None; self.names.len()];
+        for (name, value) in values {
+            let row = self.row(name).ok_or_else(|| FibError::InvalidInput(format!("no instance row named {:?}", name)))?;
+            if column[row].replace(*value).is_some() {
+                return Err(FibError::InvalidInput(format!("instance row {:?} given twice", name)));
+            }
+        }
+        column
+            .into_iter()
+            .zip(self.names.iter())
+            .map(|(value, name)| value.ok_or_else(|| FibError::InvalidInput(format!("missing instance row {:?}", name))))
+            .collect()
+    }
+}
+
+// One line per row, e.g. "  0  fibonacci.a"
+impl fmt::Display for InstanceLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (row, name) in self.names.iter().enumerate() {
+            writeln!(f, "{:>3}  {}", row, name)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread,
+    time::Duration,
+};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    bundle::ProofBundle,
+    error::FibError,
+    prover::{CancellationToken, Limits},
+    service::Service,
+};
+
+// Proofs as background jobs, for services whose proofs take longer than a client waits for a response (at large k a
+// proof takes minutes). A request enqueues a statement and gets a job id back at once; a pool of worker threads
+// creates the proofs in the order they were submitted, and the client polls the job until it is done:
+//
+//   queued -> running -> done (with the proof bundle) | failed (with the JSON of FibError::to_json)
+//
+// The queue is bounded: a submission beyond `capacity` waiting jobs fails with FibError::LimitExceeded, so that a
+// burst of requests can't grow the memory of the service without limit. For the same reason only the last
+// FINISHED_KEPT finished jobs are kept, the older ones are forgotten and their ids become unknown.
+//
+// No client waits on a job to cancel it, so a job runs under a timeout of the queue instead: each job gets a token
+// and a deadline counted from the moment a worker picks it up, checked between the stages of the proof, and a job
+// past its deadline fails with FibError::Timeout.
+//
+// The workers stop once the JobQueue is dropped, after the jobs they are running.
+
+const FINISHED_KEPT: usize = 1024;
+
+pub type JobId = u64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobStatus {
+    // The number of jobs ahead of it in the queue
+    Queued { position: usize },
+    Running,
+    Done { bundle: ProofBundle },
+    Failed { error: Value },
+}
+
+struct Request {
+    id: JobId,
+    n: usize,
+    a: String,
+    b: String,
+}
+
+#[derive(Default)]
+struct State {
+    next_id: JobId,
+    queue: VecDeque<Request>,
+    running: Vec<JobId>,
+    finished: HashMap<JobId, JobStatus>,
+    // The finished jobs, oldest first
+    finished_order: VecDeque<JobId>,
+    closed: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    available: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> Result<MutexGuard<'_, State>, FibError> {
+        self.state.lock().map_err(|_| FibError::Internal("a job worker panicked".to_string()))
+    }
+}
+
+/// A queue of proofs run by a pool of worker threads.
+///
+/// ```
+/// use std::{sync::Arc, thread, time::Duration};
+/// use halo2_fibonacci_ex::{jobs::{JobQueue, JobStatus}, service::Service};
+///
+/// let service = Arc::new(Service::new(&[8], None, None).unwrap());
+/// let queue = JobQueue::new(service.clone(), 2, 16, None);
+/// let id = queue.submit(8, "1", "1").unwrap();
+/// let bundle = loop {
+///     match queue.status(id).unwrap() {
+///         JobStatus::Done { bundle } => break bundle,
+///         JobStatus::Failed { error } => panic!("{}", error),
+///         _ => thread::sleep(Duration::from_millis(50)),
+///     }
+/// };
+/// assert_eq!(bundle.metadata.circuit_id, "fibonacci-8");
+///
+/// // A circuit that isn't served fails the job, not the submission
+/// let id = queue.submit(9, "1", "1").unwrap();
+/// while !matches!(queue.status(id), Some(JobStatus::Failed { .. })) {
+///     thread::sleep(Duration::from_millis(50));
+/// }
+/// assert!(queue.status(id + 1).is_none());
+///
+/// // A job past the timeout of its queue fails with a timeout (code 9)
+/// let queue = JobQueue::new(service, 1, 16, Some(Duration::ZERO));
+/// let id = queue.submit(8, "1", "1").unwrap();
+/// let error = loop {
+///     match queue.status(id).unwrap() {
+///         JobStatus::Failed { error } => break error,
+///         _ => thread::sleep(Duration::from_millis(50)),
+///     }
+/// };
+/// assert_eq!(error["code"], 9);
+/// ```
+pub struct JobQueue {
+    shared: Arc<Shared>,
+    capacity: usize,
+}
+
+impl JobQueue {
+    // Starts `workers` threads proving with `service`. At most `capacity` jobs wait in the queue, and a job fails once
+    // it has run for `timeout`. The other limits are those of the service
+    pub fn new(service: Arc<Service>, workers: usize, capacity: usize, timeout: Option<Duration>) -> Self {
+        let shared = Arc::new(Shared { state: Mutex::new(State::default()), available: Condvar::new() });
+        let limits = Limits { timeout, ..service.limits() };
+        for _ in 0..workers.max(1) {
+            let shared = shared.clone();
+            let service = service.clone();
+            thread::spawn(move || work(&shared, &service, &limits));
+        }
+        Self { shared, capacity }
+    }
+
+    pub fn submit(&self, n: usize, a: &str, b: &str) -> Result<JobId, FibError> {
+        let mut state = self.shared.lock()?;
+        if state.queue.len() >= self.capacity {
+            return Err(FibError::LimitExceeded(format!("{} jobs are already waiting", state.queue.len())));
+        }
+        let id = state.next_id;
+        state.next_id += 1;
+        state.queue.push_back(Request { id, n, a: a.to_string(), b: b.to_string() });
+        self.shared.available.notify_one();
+        Ok(id)
+    }
+
+    // None for an id that was never submitted or whose result was forgotten
+    pub fn status(&self, id: JobId) -> Option<JobStatus> {
+        let state = self.shared.lock().ok()?;
+        if let Some(position) = state.queue.iter().position(|request| request.id == id) {
+            return Some(JobStatus::Queued { position });
+        }
+        if state.running.contains(&id) {
+            return Some(JobStatus::Running);
+        }
+        state.finished.get(&id).cloned()
+    }
+}
+
+impl Drop for JobQueue {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.lock() {
+            state.closed = true;
+        }
+        self.shared.available.notify_all();
+    }
+}
+
+// The loop of a worker: takes the oldest job, proves it without holding the lock, and records the result
+fn work(shared: &Shared, service: &Service, limits: &Limits) {
+    loop {
+        let request = {
+            let Ok(mut state) = shared.lock() else { return };
+            loop {
+                if state.closed {
+                    return;
+                }
+                if let Some(request) = state.queue.pop_front() {
+                    state.running.push(request.id);
+                    break request;
+                }
+                state = match shared.available.wait(state) {
+                    Ok(state) => state,
+                    Err(_) => return,
+                };
+            }
+        };
+
+        // A panicking proof fails its job rather than the worker. The deadline of the job starts now
+        let token = CancellationToken::new();
+        let prove = || service.prove_with_limits(request.n, &request.a, &request.b, &token, limits);
+        let result = panic::catch_unwind(AssertUnwindSafe(prove))
+            .unwrap_or_else(|_| Err(FibError::Internal("the prover panicked".to_string())));
+        let status = match result {
+            Ok(bundle) => JobStatus::Done { bundle },
+            Err(e) => JobStatus::Failed { error: e.to_json() },
+        };
+
+        let Ok(mut state) = shared.lock() else { return };
+        state.running.retain(|&id| id != request.id);
+        state.finished.insert(request.id, status);
+        state.finished_order.push_back(request.id);
+        while state.finished_order.len() > FINISHED_KEPT {
+            if let Some(oldest) = state.finished_order.pop_front() {
+                state.finished.remove(&oldest);
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+// The examples inside src/bin are self contained on purpose: each one walks through a single idea.
+// Chips that are shared by more than one example live here so that they can be imported
+// with `use halo2_fibonacci_ex::...` instead of being copy-pasted across binaries.
+// Each exported chip documents a minimal circuit using it, which `cargo test --doc` runs with the MockProver.
+
+pub mod aggregate;
+pub mod annotate;
+pub mod archive;
+pub mod audit;
+pub mod backend;
+pub mod bits;
+pub mod boolean;
+pub mod boundary;
+pub mod bundle;
+pub mod compare;
+pub mod counter;
+pub mod dump;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fibonacci;
+pub mod field;
+pub mod fingerprints;
+pub mod golden;
+pub mod import;
+pub mod instance;
+pub mod jobs;
+pub mod merkle;
+#[cfg(feature = "node")]
+pub mod node;
+pub mod params;
+pub mod planner;
+pub mod poseidon;
+pub mod prover;
+pub mod ptau;
+pub mod registry;
+pub mod report;
+pub mod select;
+pub mod sequence;
+#[cfg(feature = "http")]
+pub mod server;
+pub mod service;
+pub mod stats;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod transcript;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod witness;
@@ -0,0 +1,299 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::{
+    annotate::ColumnNames,
+    error::FibError,
+    planner::DefaultPlanner,
+    poseidon::{self, PoseidonChip, PoseidonConfig},
+};
+
+// A Merkle tree where each node is the Poseidon hash of its two children: node = Poseidon(left, right).
+// This is the native tree builder, use it to compute the root and the authentication paths fed to the circuit.
+#[derive(Debug, Clone)]
+pub struct MerkleTree<F: FieldExt> {
+    // levels[0] are the leaves, the last level only contains the root
+    levels: Vec<Vec<F>>,
+}
+
+// The authentication path of a leaf: the sibling at each level, from the leaves up to the root,
+// and whether the node we are hashing is the right child (true) or the left child (false) at that level
+#[derive(Debug, Clone)]
+pub struct MerklePath<F: FieldExt> {
+    pub siblings: Vec<F>,
+    pub is_right: Vec<bool>,
+}
+
+impl<F: FieldExt> MerkleTree<F> {
+    // The leaves are padded with zeros up to 2^depth
+    pub fn new(leaves: &[F], depth: usize) -> Result<Self, FibError> {
+        if leaves.len() > 1 << depth {
+            return Err(FibError::InvalidInput(format!("{} leaves don't fit in a tree of depth {}", leaves.len(), depth)));
+        }
+
+        let mut level = leaves.to_vec();
+        level.resize(1 << depth, F::zero());
+
+        let mut levels = vec![level];
+        for _ in 0..depth {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| poseidon::hash([pair[0], pair[1]]))
+                .collect();
+            levels.push(next);
+        }
+        Ok(Self { levels })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    pub fn root(&self) -> F {
+        self.levels[self.depth()][0]
+    }
+
+    pub fn path(&self, mut index: usize) -> Result<MerklePath<F>, FibError> {
+        if index >= self.levels[0].len() {
+            return Err(FibError::InvalidInput(format!("no leaf {} in a tree of {} leaves", index, self.levels[0].len())));
+        }
+
+        let mut siblings = Vec::with_capacity(self.depth());
+        let mut is_right = Vec::with_capacity(self.depth());
+        for level in self.levels.iter().take(self.depth()) {
+            siblings.push(level[index ^ 1]);
+            is_right.push(index & 1 == 1);
+            index >>= 1;
+        }
+        Ok(MerklePath { siblings, is_right })
+    }
+}
+
+impl<F: FieldExt> MerklePath<F> {
+    // Recompute the root natively starting from a leaf
+    pub fn root(&self, leaf: F) -> F {
+        self.siblings
+            .iter()
+            .zip(self.is_right.iter())
+            .fold(leaf, |node, (sibling, is_right)| {
+                if *is_right {
+                    poseidon::hash([*sibling, node])
+                } else {
+                    poseidon::hash([node, *sibling])
+                }
+            })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MerkleConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 3],
+    pub s_swap: Selector,
+    pub instance: Column<Instance>,
+    pub poseidon: PoseidonConfig<F>,
+}
+
+impl<F: FieldExt> MerkleConfig<F> {
+    // Names the columns in the diagnostics of the library (see src/annotate.rs), the ones of the Poseidon chip included
+    pub fn annotate(&self, names: &mut ColumnNames) {
+        for (column, name) in self.advice.iter().zip(["node", "sibling", "is_right"]) {
+            names.annotate(*column, name);
+        }
+        names.annotate(self.instance, "instance");
+        self.poseidon.annotate(names);
+    }
+}
+
+/// Recomputes a Merkle root from a leaf and its authentication path.
+/// [`MerkleCircuit`] is a complete circuit built on top of it.
+///
+/// ```
+/// use halo2_proofs::{dev::MockProver, pasta::Fp};
+/// use halo2_fibonacci_ex::merkle::{MerkleCircuit, MerkleTree};
+///
+/// let leaves: Vec<Fp> = (1..=4u64).map(Fp::from).collect();
+/// let tree = MerkleTree::new(&leaves, 2).unwrap();
+///
+/// let circuit = MerkleCircuit::new(leaves[3], &tree.path(3).unwrap());
+/// MockProver::run(8, &circuit, vec![vec![tree.root()]]).unwrap().assert_satisfied();
+///
+/// // A leaf that is not in the tree doesn't reach the root
+/// let circuit = MerkleCircuit::new(Fp::from(5), &tree.path(3).unwrap());
+/// assert!(MockProver::run(8, &circuit, vec![vec![tree.root()]]).unwrap().verify().is_err());
+///
+/// // Bad inputs are errors: more leaves than the depth allows, and a direction missing for a sibling
+/// assert!(MerkleTree::new(&leaves, 1).is_err());
+/// let mut circuit = MerkleCircuit::new(leaves[3], &tree.path(3).unwrap());
+/// circuit.is_right.pop();
+/// assert!(MockProver::run(8, &circuit, vec![vec![tree.root()]]).is_err());
+/// ```
+pub struct MerkleChip<F: FieldExt> {
+    config: MerkleConfig<F>,
+}
+
+impl<F: FieldExt> MerkleChip<F> {
+    pub fn construct(config: MerkleConfig<F>) -> Self {
+        Self { config }
+    }
+
+    // The 3 advice columns are shared between the swap gate and the Poseidon chip
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        instance: Column<Instance>,
+    ) -> MerkleConfig<F> {
+        let s_swap = meta.selector();
+        for column in advice.iter() {
+            meta.enable_equality(*column);
+        }
+        meta.enable_equality(instance);
+
+        // At each level we need to decide whether the current node is the left or the right input of the hash.
+        // The bit is a witness, so the gate has to constrain it to be boolean and to order the pair accordingly
+        //  advice[0] | advice[1] | advice[2] | s_swap
+        // ---------------------------------------------
+        //   node     |  sibling  |    bit    |   1
+        //   left     |   right   |           |
+        // if bit = 0: left = node and right = sibling
+        // if bit = 1: left = sibling and right = node
+        meta.create_gate("swap", |meta| {
+            let s = meta.query_selector(s_swap);
+            let node = meta.query_advice(advice[0], Rotation::cur());
+            let sibling = meta.query_advice(advice[1], Rotation::cur());
+            let bit = meta.query_advice(advice[2], Rotation::cur());
+            let left = meta.query_advice(advice[0], Rotation::next());
+            let right = meta.query_advice(advice[1], Rotation::next());
+            let one = Expression::Constant(F::one());
+
+            Constraints::with_selector(
+                s,
+                [
+                    ("bit is boolean", bit.clone() * (one - bit.clone())),
+                    ("left", left - (node.clone() + bit.clone() * (sibling.clone() - node.clone()))),
+                    ("right", right - (sibling.clone() + bit * (node - sibling))),
+                ],
+            )
+        });
+
+        let poseidon = PoseidonChip::configure(meta, advice);
+
+        MerkleConfig { advice, s_swap, instance, poseidon }
+    }
+
+    // Walk up the tree from `leaf` and return the cell holding the computed root
+    pub fn compute_root(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaf: AssignedCell<F, F>,
+        siblings: &[Option<F>],
+        is_right: &[Option<bool>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        // One direction per sibling
+        if siblings.len() != is_right.len() {
+            return Err(Error::Synthesis);
+        }
+        let poseidon_chip = PoseidonChip::construct(self.config.poseidon.clone());
+
+        let mut node = leaf;
+        for (level, (sibling, is_right)) in siblings.iter().zip(is_right.iter()).enumerate() {
+            let (left, right) = layouter.assign_region(
+                || format!("swap level {}", level),
+                |mut region| {
+                    self.config.s_swap.enable(&mut region, 0)?;
+
+                    let node = node.copy_advice(|| "node", &mut region, self.config.advice[0], 0)?;
+                    let sibling = region.assign_advice(|| "sibling", self.config.advice[1], 0, || sibling.ok_or(Error::Synthesis))?;
+                    region.assign_advice(
+                        || "bit",
+                        self.config.advice[2],
+                        0,
+                        || is_right.map(|bit| if bit { F::one() } else { F::zero() }).ok_or(Error::Synthesis),
+                    )?;
+
+                    let ordered = node.value().zip(sibling.value()).zip(*is_right).map(|((node, sibling), is_right)| {
+                        if is_right {
+                            (*sibling, *node)
+                        } else {
+                            (*node, *sibling)
+                        }
+                    });
+                    let left = region.assign_advice(|| "left", self.config.advice[0], 1, || ordered.map(|o| o.0).ok_or(Error::Synthesis))?;
+                    let right = region.assign_advice(|| "right", self.config.advice[1], 1, || ordered.map(|o| o.1).ok_or(Error::Synthesis))?;
+                    Ok((left, right))
+                },
+            )?;
+
+            node = poseidon_chip.hash(layouter.namespace(|| format!("hash level {}", level)), [left, right])?;
+        }
+
+        Ok(node)
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+// Proves "I know a leaf and a path to the public root". The depth of the tree is the length of the path.
+#[derive(Debug, Clone)]
+pub struct MerkleCircuit<F: FieldExt> {
+    pub leaf: Option<F>,
+    pub siblings: Vec<Option<F>>,
+    pub is_right: Vec<Option<bool>>,
+}
+
+impl<F: FieldExt> MerkleCircuit<F> {
+    pub fn new(leaf: F, path: &MerklePath<F>) -> Self {
+        Self {
+            leaf: Some(leaf),
+            siblings: path.siblings.iter().copied().map(Some).collect(),
+            is_right: path.is_right.iter().copied().map(Some).collect(),
+        }
+    }
+
+    // The names of the columns of the circuit, from a fresh configuration: the columns are the same in every one
+    pub fn column_names() -> ColumnNames {
+        let mut names = ColumnNames::new();
+        Self::configure(&mut ConstraintSystem::default()).annotate(&mut names);
+        names
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MerkleCircuit<F> {
+    type Config = MerkleConfig<F>;
+    type FloorPlanner = DefaultPlanner;
+
+    // The depth is part of the shape of the circuit, so it is kept
+    fn without_witnesses(&self) -> Self {
+        Self {
+            leaf: None,
+            siblings: vec![None; self.siblings.len()],
+            is_right: vec![None; self.is_right.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        MerkleChip::configure(meta, advice, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = MerkleChip::construct(config.clone());
+
+        let leaf = layouter.assign_region(
+            || "leaf",
+            |mut region| region.assign_advice(|| "leaf", config.advice[0], 0, || self.leaf.ok_or(Error::Synthesis)),
+        )?;
+        let root = chip.compute_root(layouter.namespace(|| "merkle path"), leaf, &self.siblings, &self.is_right)?;
+
+        // The root is the only public input
+        chip.expose_public(layouter.namespace(|| "root"), &root, 0)
+    }
+}
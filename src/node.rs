@@ -0,0 +1,191 @@
+use std::sync::{OnceLock, RwLock};
+
+use halo2_proofs::pasta::Fp;
+use napi::{
+    bindgen_prelude::{AsyncTask, Buffer},
+    Env, Error, Status, Task,
+};
+use napi_derive::napi;
+
+use crate::{
+    bundle::{Metadata, ProofBundle},
+    error::FibError,
+    fibonacci::{FibInputs, FibInstance},
+    field::parse_field,
+    prover::{self, CancellationToken, Limits},
+    registry::{verify_bundle, Registry},
+};
+
+// The prover and the verifier of the Fibonacci circuit for Node.js, with the `node` feature. The functions return
+// promises and run on the thread pool of libuv, so a server keeps answering while a proof is created:
+//
+//   const fib = require("./halo2_fibonacci_ex.node");   // target/release/libhalo2_fibonacci_ex.so, renamed
+//
+//   const fingerprint = await fib.keygen(4, 8);          // Buffer, the SHA-256 of the verifying key
+//   const proof = await fib.prove(4, 8, "1", "1");       // Buffer
+//   await fib.verify(4, 8, "1", "1", "55", proof);      // true, false for an invalid proof
+//
+// The field elements are strings read by field::parse_field. Errors other than an invalid proof reject the promise
+// with the JSON of FibError::to_json as message.
+//
+// halo2_proofs 0.1.0 can't serialize keys, so keygen doesn't return them: it generates the verifying key of a circuit
+// into a registry that lives as long as the process, and returns its fingerprint (prover::fingerprint), which a
+// deployment can compare with the one of its prover. verify uses that registry, so only the first verification of a
+// circuit pays for the keys, and keygen at startup takes that cost away from the first request.
+//
+// The symbols of N-API are looked up in the node process when the module is loaded (napi's dyn-symbols), so the
+// library links as usual on Linux and Windows. macOS needs `-C link-arg=-undefined -C link-arg=dynamic_lookup`.
+
+fn registry() -> &'static RwLock<Registry> {
+    static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(Registry::new()))
+}
+
+// The circuit id of the Fibonacci circuit with n steps over 2^k rows, registered if it isn't yet. A circuit id
+// registered with another k is replaced
+fn register(k: u32, n: usize) -> Result<String, FibError> {
+    Limits::default().check_k(k)?;
+    let circuit_id = FibInputs::new(Fp::zero(), Fp::zero(), n).circuit_id();
+    let current = registry().read().map_err(poisoned)?.k(&circuit_id);
+    if current != Some(k) {
+        registry().write().map_err(poisoned)?.register_fibonacci(k, n);
+    }
+    Ok(circuit_id)
+}
+
+fn poisoned<T>(_: T) -> FibError {
+    FibError::Internal("a verification panicked".to_string())
+}
+
+fn napi_error(e: FibError) -> Error {
+    Error::new(Status::GenericFailure, e.to_json().to_string())
+}
+
+/// The tasks behind the functions, which run `compute` on the thread pool and `resolve` on the main thread of node.
+///
+/// ```
+/// use halo2_fibonacci_ex::node::{Keygen, Prove, Verify};
+/// use napi::Task;
+///
+/// let fingerprint = Keygen::new(4, 8).compute().unwrap();
+/// assert_eq!(fingerprint.len(), 32);
+/// let proof = Prove::new(4, 8, "1", "1").compute().unwrap();
+/// assert!(Verify::new(4, 8, ["1", "1", "55"], proof.clone()).compute().unwrap());
+/// assert!(!Verify::new(4, 8, ["1", "1", "56"], proof).compute().unwrap());
+/// ```
+pub struct Keygen {
+    k: u32,
+    n: usize,
+}
+
+impl Keygen {
+    pub fn new(k: u32, n: usize) -> Self {
+        Self { k, n }
+    }
+}
+
+impl Task for Keygen {
+    type Output = Vec<u8>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let keygen = || -> Result<Vec<u8>, FibError> {
+            let circuit_id = register(self.k, self.n)?;
+            let registry = registry().read().map_err(poisoned)?;
+            let fingerprint = registry.fingerprint(&circuit_id)?;
+            hex::decode(fingerprint).map_err(|e| FibError::Internal(e.to_string()))
+        };
+        keygen().map_err(napi_error)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output.into())
+    }
+}
+
+pub struct Prove {
+    k: u32,
+    n: usize,
+    a: String,
+    b: String,
+}
+
+impl Prove {
+    pub fn new(k: u32, n: usize, a: impl Into<String>, b: impl Into<String>) -> Self {
+        Self { k, n, a: a.into(), b: b.into() }
+    }
+}
+
+impl Task for Prove {
+    type Output = Vec<u8>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let prove = || -> Result<Vec<u8>, FibError> {
+            let inputs = FibInputs::new(parse_field(&self.a)?, parse_field(&self.b)?, self.n);
+            let limits = Limits::default();
+            prover::prove_with_limits(self.k, &inputs.circuit(), &inputs.public_inputs(), &CancellationToken::new(), &limits)
+        };
+        prove().map_err(napi_error)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output.into())
+    }
+}
+
+pub struct Verify {
+    k: u32,
+    n: usize,
+    instance: [String; 3],
+    proof: Vec<u8>,
+}
+
+impl Verify {
+    pub fn new(k: u32, n: usize, [a, b, out]: [&str; 3], proof: Vec<u8>) -> Self {
+        Self { k, n, instance: [a.to_string(), b.to_string(), out.to_string()], proof }
+    }
+}
+
+impl Task for Verify {
+    type Output = bool;
+    type JsValue = bool;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let verify = || -> Result<(), FibError> {
+            let [a, b, out] = &self.instance;
+            let instance = FibInstance::from_strs(a, b, out)?.column();
+            let circuit_id = register(self.k, self.n)?;
+            let bundle = ProofBundle::new(Metadata::new(self.k, circuit_id), instance, self.proof.clone());
+            let registry = registry().read().map_err(poisoned)?;
+            verify_bundle(&bundle, &registry).map(|_| ())
+        };
+        match verify() {
+            Ok(()) => Ok(true),
+            Err(FibError::InvalidProof) => Ok(false),
+            Err(e) => Err(napi_error(e)),
+        }
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+// Generates the verifying key of the circuit with n steps over 2^k rows, resolving to its fingerprint
+#[napi(ts_return_type = "Promise<Buffer>")]
+pub fn keygen(k: u32, n: u32) -> AsyncTask<Keygen> {
+    AsyncTask::new(Keygen::new(k, n as usize))
+}
+
+// Creates a proof that n steps starting from (a, b) fit in 2^k rows, resolving to the proof bytes
+#[napi(ts_return_type = "Promise<Buffer>")]
+pub fn prove(k: u32, n: u32, a: String, b: String) -> AsyncTask<Prove> {
+    AsyncTask::new(Prove::new(k, n as usize, a, b))
+}
+
+// Resolves to whether `proof` proves that n steps starting from (a, b) end at out
+#[napi(ts_return_type = "Promise<boolean>")]
+pub fn verify(k: u32, n: u32, a: String, b: String, out: String, proof: Buffer) -> AsyncTask<Verify> {
+    AsyncTask::new(Verify::new(k, n as usize, [&a, &b, &out], proof.to_vec()))
+}
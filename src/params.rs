@@ -0,0 +1,151 @@
+use std::{
+    fs,
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{backend::Backend, error::FibError};
+
+// Public parameters kept on disk between runs. Generating them takes most of the time of a small proof (the 2^k
+// generators and their Lagrange basis), and they only depend on k and the backend, so demos that prove again and again
+// load them instead. The cache directory holds one file per backend and k:
+//
+//   <dir>/ipa-pasta-k4.params
+//
+// The IPA parameters of halo2_proofs 0.1.0 are derived from a hash, not from a secret, so a cached file is as good as
+// a new one, and a damaged one is simply generated again. Files are written to a temporary name first and renamed,
+// so that a run interrupted while writing doesn't leave a truncated file behind, and two processes filling the same
+// cache don't read each other's half-written files.
+//
+// `fib --params-dir <dir>` makes prove and verify go through the cache. `write_params` and `read_params` do the same
+// for a single file, with its size and SHA-256, for parameters generated once and copied to the machines of the
+// provers and the verifiers: `fib params write` and `fib params check`.
+
+/// A directory of public parameters, generated on first use.
+///
+/// ```
+/// use halo2_proofs::pasta::Fp;
+/// use halo2_fibonacci_ex::{backend::{Backend, IpaPasta}, fibonacci::FibInputs, params::ParamsCache};
+///
+/// let dir = std::env::temp_dir().join(format!("params-doctest-{}", std::process::id()));
+/// let cache = ParamsCache::new(&dir);
+///
+/// // Generated and written the first time, read the second time
+/// let params = cache.load_or_setup::<IpaPasta>(4).unwrap();
+/// assert!(cache.path::<IpaPasta>(4).exists());
+/// let cached = cache.load_or_setup::<IpaPasta>(4).unwrap();
+///
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// let proof = IpaPasta::prove(&params, &inputs.circuit(), &inputs.public_inputs()).unwrap();
+/// IpaPasta::verify(&cached, &inputs.circuit(), &inputs.public_inputs(), &proof).unwrap();
+///
+/// // A damaged file is replaced
+/// std::fs::write(cache.path::<IpaPasta>(4), b"garbage").unwrap();
+/// cache.load_or_setup::<IpaPasta>(4).unwrap();
+/// assert!(std::fs::metadata(cache.path::<IpaPasta>(4)).unwrap().len() > 7);
+/// std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamsCache {
+    dir: PathBuf,
+}
+
+impl ParamsCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // The file of the parameters of backend B for 2^k rows
+    pub fn path<B: Backend>(&self, k: u32) -> PathBuf {
+        self.dir.join(format!("{}-k{}.params", B::ID, k))
+    }
+
+    // The cached parameters, or new ones which are then cached. Only a failure to write the cache is an error
+    pub fn load_or_setup<B: Backend>(&self, k: u32) -> Result<B::Params, FibError> {
+        let path = self.path::<B>(k);
+        if let Some(params) = read::<B>(&path, k) {
+            return Ok(params);
+        }
+
+        let params = B::setup(k);
+        fs::create_dir_all(&self.dir).map_err(|e| FibError::io(&self.dir, e))?;
+        write_params::<B>(&params, &path)?;
+        Ok(params)
+    }
+}
+
+// The parameters in `path`, if it holds valid parameters for 2^k rows
+fn read<B: Backend>(path: &Path, k: u32) -> Option<B::Params> {
+    let mut reader = BufReader::new(fs::File::open(path).ok()?);
+    B::read_params(&mut reader, k).ok()
+}
+
+// The size and the checksum of a parameters file, for the prover and the verifier to check that they share the same
+// parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamsInfo {
+    pub bytes: u64,
+    // Hex encoded
+    pub sha256: String,
+}
+
+impl ParamsInfo {
+    fn of(bytes: &[u8]) -> Self {
+        Self { bytes: bytes.len() as u64, sha256: hex::encode(Sha256::digest(bytes)) }
+    }
+}
+
+/// Writes `params` to `path` and returns the size and the checksum of the file. The file is written to a temporary
+/// name first and renamed, as in the cache.
+///
+/// ```
+/// use halo2_fibonacci_ex::{backend::{Backend, IpaPasta}, params::{read_params, write_params}};
+///
+/// let path = std::env::temp_dir().join(format!("params-file-doctest-{}", std::process::id()));
+/// let written = write_params::<IpaPasta>(&IpaPasta::setup(4), &path).unwrap();
+/// let (_, read) = read_params::<IpaPasta>(&path, 4).unwrap();
+/// assert_eq!(written, read);
+///
+/// // The file is for k = 4 only
+/// assert!(read_params::<IpaPasta>(&path, 5).is_err());
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn write_params<B: Backend>(params: &B::Params, path: &Path) -> Result<ParamsInfo, FibError> {
+    let mut bytes = Vec::new();
+    B::write_params(params, &mut bytes).map_err(|e| FibError::io(path, e))?;
+
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{}.{}", name, std::process::id()));
+    let write = || -> io::Result<()> {
+        let mut writer = BufWriter::new(fs::File::create(&tmp)?);
+        writer.write_all(&bytes)?;
+        writer.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()?;
+        fs::rename(&tmp, path)
+    };
+    write().map_err(|e| {
+        // The temporary file is useless once the write failed
+        fs::remove_file(&tmp).ok();
+        FibError::io(path, e)
+    })?;
+    Ok(ParamsInfo::of(&bytes))
+}
+
+// The size and the checksum of the file `write_params` writes, without writing it
+pub fn params_info<B: Backend>(params: &B::Params) -> ParamsInfo {
+    let mut bytes = Vec::new();
+    B::write_params(params, &mut bytes).expect("writing to a Vec doesn't fail");
+    ParamsInfo::of(&bytes)
+}
+
+// Reads the parameters for 2^k rows written by `write_params`, with the size and the checksum of the file
+pub fn read_params<B: Backend>(path: &Path, k: u32) -> Result<(B::Params, ParamsInfo), FibError> {
+    let bytes = fs::read(path).map_err(|e| FibError::io(path, e))?;
+    let params = B::read_params(&mut bytes.as_slice(), k).map_err(|e| FibError::Parse(format!("{}: {}", path.display(), e)))?;
+    Ok((params, ParamsInfo::of(&bytes)))
+}
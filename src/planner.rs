@@ -0,0 +1,275 @@
+use std::{fmt, marker::PhantomData};
+
+#[cfg(feature = "region-spans")]
+use std::time::Instant;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::SimpleFloorPlanner,
+    plonk::*,
+};
+
+use crate::{audit::ColumnId, stats::RegionStats};
+
+// A floor planner that places the regions like the floor planner P and prints, for every region, its name, the rows
+// it was given and the columns it uses, as it is assigned:
+//
+//   layout: region "sequence table" at rows 0..9 (9 rows): advice column 0, advice column 1, 8 selectors
+//
+// The layout is printed once per synthesis, so `prove` prints it three times (verifying key, proving key, proof). It
+// only sees the final placement: the measurement pass of floor_planner::V1 doesn't reach the constraint system, so
+// it isn't printed. Use it as the FloorPlanner of a circuit under study, e.g. `type FloorPlanner =
+// Tracing<floor_planner::V1>`. The circuits of the library use DefaultPlanner, which is Tracing<SimpleFloorPlanner>
+// with the `trace-layout` feature and SimpleFloorPlanner without it:
+//
+//   cargo run --features trace-layout --bin fib -- prove -k 4 -n 8 -o proof.json
+//
+// The lines go to stderr, since the synthesis also runs in the prover and in tools whose stdout is parsed.
+//
+// With the `region-spans` feature, Spans<P> reports the same regions to the `tracing` crate instead: every region is
+// assigned inside an INFO span named "region", with the fields `name`, `offset` (its first row), `rows` and
+// `duration_us`, recorded when the region is exited. Any subscriber then shows the order of the synthesis and where
+// the time goes in large circuits, e.g. with tracing-subscriber:
+//
+//   tracing_subscriber::fmt().with_span_events(FmtSpan::CLOSE).init();
+//
+// DefaultPlanner wraps its regions in spans when the feature is enabled, so the library circuits need no change.
+
+#[cfg(all(feature = "trace-layout", feature = "region-spans"))]
+pub type DefaultPlanner = Tracing<Spans<SimpleFloorPlanner>>;
+#[cfg(all(feature = "trace-layout", not(feature = "region-spans")))]
+pub type DefaultPlanner = Tracing<SimpleFloorPlanner>;
+#[cfg(all(not(feature = "trace-layout"), feature = "region-spans"))]
+pub type DefaultPlanner = Spans<SimpleFloorPlanner>;
+#[cfg(all(not(feature = "trace-layout"), not(feature = "region-spans")))]
+pub type DefaultPlanner = SimpleFloorPlanner;
+
+/// Wraps the floor planner `P` and prints the placement of every region to stderr.
+///
+/// ```
+/// use halo2_proofs::{circuit::{floor_planner::V1, *}, dev::MockProver, pasta::Fp, plonk::*};
+/// use halo2_fibonacci_ex::planner::Tracing;
+///
+/// // Two regions on two columns, which V1 places side by side
+/// #[derive(Default)]
+/// struct MyCircuit;
+///
+/// impl Circuit<Fp> for MyCircuit {
+///     type Config = [Column<Advice>; 2];
+///     type FloorPlanner = Tracing<V1>;
+///
+///     fn without_witnesses(&self) -> Self {
+///         Self
+///     }
+///
+///     fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+///         [meta.advice_column(), meta.advice_column()]
+///     }
+///
+///     fn synthesize(&self, columns: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+///         for (i, column) in columns.into_iter().enumerate() {
+///             layouter.assign_region(|| format!("region {}", i), |mut region| {
+///                 region.assign_advice(|| "x", column, 0, || Ok(Fp::one()))?;
+///                 region.assign_advice(|| "y", column, 1, || Ok(Fp::one()))
+///             })?;
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// // layout: region "region 0" at rows 0..2 (2 rows): advice column 0
+/// // layout: region "region 1" at rows 0..2 (2 rows): advice column 1
+/// MockProver::run(4, &MyCircuit, vec![]).unwrap().assert_satisfied();
+/// ```
+#[derive(Debug, Default)]
+pub struct Tracing<P: FloorPlanner>(PhantomData<P>);
+
+impl<P: FloorPlanner> FloorPlanner for Tracing<P> {
+    fn synthesize<F: Field, CS: Assignment<F>, C: Circuit<F>>(
+        cs: &mut CS,
+        circuit: &C,
+        config: C::Config,
+        constants: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        let mut tracer = Tracer { cs, output: Output::Stderr, current: None, _marker: PhantomData };
+        P::synthesize(&mut tracer, circuit, config, constants)
+    }
+}
+
+/// Wraps the floor planner `P` and assigns every region inside a `tracing` span.
+#[cfg(feature = "region-spans")]
+#[derive(Debug, Default)]
+pub struct Spans<P: FloorPlanner>(PhantomData<P>);
+
+#[cfg(feature = "region-spans")]
+impl<P: FloorPlanner> FloorPlanner for Spans<P> {
+    fn synthesize<F: Field, CS: Assignment<F>, C: Circuit<F>>(
+        cs: &mut CS,
+        circuit: &C,
+        config: C::Config,
+        constants: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        let mut tracer = Tracer { cs, output: Output::Spans, current: None, _marker: PhantomData };
+        P::synthesize(&mut tracer, circuit, config, constants)
+    }
+}
+
+// Where the Tracer reports the regions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Stderr,
+    #[cfg(feature = "region-spans")]
+    Spans,
+}
+
+// The region being assigned: its statistics and the columns it touched, in the order they were first touched
+struct RegionTrace {
+    stats: RegionStats,
+    columns: Vec<ColumnId>,
+    #[cfg(feature = "region-spans")]
+    started: Instant,
+    #[cfg(feature = "region-spans")]
+    span: Option<tracing::span::EnteredSpan>,
+}
+
+impl RegionTrace {
+    fn touch(&mut self, column: Option<ColumnId>, row: usize) {
+        self.stats.touch(row);
+        if let Some(column) = column.filter(|column| !self.columns.contains(column)) {
+            self.columns.push(column);
+        }
+    }
+}
+
+impl fmt::Display for RegionTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "region {:?}", self.stats.name)?;
+        match (self.stats.first_row, self.stats.last_row) {
+            (Some(first), Some(last)) => write!(f, " at rows {}..{} ({} rows)", first, last + 1, self.stats.rows())?,
+            _ => write!(f, " assigns nothing")?,
+        }
+        let mut shape: Vec<String> = self.columns.iter().map(ColumnId::to_string).collect();
+        if self.stats.selectors > 0 {
+            shape.push(format!("{} selectors", self.stats.selectors));
+        }
+        if !shape.is_empty() {
+            write!(f, ": {}", shape.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// Forwards everything to the constraint system of the prover and records what the current region touches
+struct Tracer<'a, F: Field, CS: Assignment<F>> {
+    cs: &'a mut CS,
+    output: Output,
+    current: Option<RegionTrace>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field, CS: Assignment<F>> Tracer<'_, F, CS> {
+    fn touch(&mut self, column: Option<ColumnId>, row: usize) {
+        if let Some(region) = self.current.as_mut() {
+            region.touch(column, row);
+        }
+    }
+}
+
+impl<F: Field, CS: Assignment<F>> Assignment<F> for Tracer<'_, F, CS> {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        let name: String = name_fn().into();
+        self.current = Some(RegionTrace {
+            stats: RegionStats::new(name.clone()),
+            columns: vec![],
+            #[cfg(feature = "region-spans")]
+            started: Instant::now(),
+            #[cfg(feature = "region-spans")]
+            span: (self.output == Output::Spans).then(|| {
+                let empty = tracing::field::Empty;
+                tracing::info_span!("region", name = name.as_str(), offset = empty, rows = empty, duration_us = empty).entered()
+            }),
+        });
+        self.cs.enter_region(|| name);
+    }
+
+    fn exit_region(&mut self) {
+        if let Some(region) = self.current.take() {
+            match self.output {
+                Output::Stderr => eprintln!("layout: {}", region),
+                #[cfg(feature = "region-spans")]
+                Output::Spans => {
+                    if let Some(span) = region.span {
+                        if let Some(offset) = region.stats.first_row {
+                            span.record("offset", offset);
+                        }
+                        span.record("rows", region.stats.rows());
+                        span.record("duration_us", region.started.elapsed().as_micros() as u64);
+                    }
+                }
+            }
+        }
+        self.cs.exit_region();
+    }
+
+    fn enable_selector<A, AR>(&mut self, annotation: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(None, row);
+        if let Some(region) = self.current.as_mut() {
+            region.stats.selectors += 1;
+        }
+        self.cs.enable_selector(annotation, selector, row)
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Option<F>, Error> {
+        self.cs.query_instance(column, row)
+    }
+
+    fn assign_advice<V, VR, A, AR>(&mut self, annotation: A, column: Column<Advice>, row: usize, to: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(ColumnId::of(column).ok(), row);
+        self.cs.assign_advice(annotation, column, row, to)
+    }
+
+    fn assign_fixed<V, VR, A, AR>(&mut self, annotation: A, column: Column<Fixed>, row: usize, to: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(ColumnId::of(column).ok(), row);
+        self.cs.assign_fixed(annotation, column, row, to)
+    }
+
+    fn copy(&mut self, left_column: Column<Any>, left_row: usize, right_column: Column<Any>, right_row: usize) -> Result<(), Error> {
+        self.cs.copy(left_column, left_row, right_column, right_row)
+    }
+
+    fn fill_from_row(&mut self, column: Column<Fixed>, row: usize, to: Option<Assigned<F>>) -> Result<(), Error> {
+        self.cs.fill_from_row(column, row, to)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.cs.pop_namespace(gadget_name)
+    }
+}
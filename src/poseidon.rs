@@ -0,0 +1,531 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::annotate::ColumnNames;
+
+// Poseidon with a state of 3 field elements: 2 of them (the rate) absorb the message,
+// the remaining one (the capacity) is never touched by the message.
+// The round numbers are those of the `P128Pow5T3` spec of halo2_gadgets for the pasta fields: x^5 S-box, 8 full rounds
+// (4 at the beginning, 4 at the end) and 56 partial rounds in the middle. The constants are generated here with the
+// same Grain LFSR, and over the pasta fields the digests match those of halo2_gadgets (see the test of `hash`). Over
+// other fields the parameters were not chosen for them, so the hash is not claimed to be secure there.
+pub const WIDTH: usize = 3;
+pub const RATE: usize = 2;
+
+// The names of the constraints of the round and absorb gates, one per word of the state
+const MIX_NAMES: [&str; WIDTH] = ["mix word 0", "mix word 1", "mix word 2"];
+const ABSORB_NAMES: [&str; WIDTH] = ["absorb word 0", "absorb word 1", "keep capacity"];
+pub const FULL_ROUNDS: usize = 8;
+pub const PARTIAL_ROUNDS: usize = 56;
+pub const ROUNDS: usize = FULL_ROUNDS + PARTIAL_ROUNDS;
+
+// Round constants and MDS matrix of the permutation.
+// They are derived with the Grain LFSR described in the Poseidon paper, so they are "nothing up my sleeve" numbers.
+#[derive(Debug, Clone)]
+pub struct PoseidonSpec<F: FieldExt> {
+    pub round_constants: Vec<[F; WIDTH]>,
+    pub mds: [[F; WIDTH]; WIDTH],
+}
+
+impl<F: FieldExt> Default for PoseidonSpec<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: FieldExt> PoseidonSpec<F> {
+    pub fn new() -> Self {
+        let mut grain = Grain::<F>::new(WIDTH as u16, FULL_ROUNDS as u16, PARTIAL_ROUNDS as u16);
+
+        // One row of WIDTH constants for each round
+        let round_constants = (0..ROUNDS)
+            .map(|_| {
+                let mut row = [F::zero(); WIDTH];
+                for rc in row.iter_mut() {
+                    *rc = grain.next_field_element();
+                }
+                row
+            })
+            .collect();
+
+        // The MDS matrix is a Cauchy matrix m[i][j] = 1 / (x_i + y_j) where xs and ys are sampled from the same LFSR
+        let (xs, ys) = loop {
+            let vals: Vec<F> = (0..2 * WIDTH)
+                .map(|_| grain.next_field_element_without_rejection())
+                .collect();
+            let mut unique = vals.clone();
+            unique.sort_unstable();
+            unique.dedup();
+            if unique.len() == vals.len() {
+                break (vals[..WIDTH].to_vec(), vals[WIDTH..].to_vec());
+            }
+        };
+        let mut mds = [[F::zero(); WIDTH]; WIDTH];
+        for (i, row) in mds.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                let sum = xs[i] + ys[j];
+                assert!(sum != F::zero());
+                *entry = sum.invert().unwrap();
+            }
+        }
+
+        Self {
+            round_constants,
+            mds,
+        }
+    }
+
+    // A round is full for the first and last FULL_ROUNDS / 2 rounds, partial otherwise
+    pub fn is_full_round(round: usize) -> bool {
+        !(FULL_ROUNDS / 2..FULL_ROUNDS / 2 + PARTIAL_ROUNDS).contains(&round)
+    }
+
+    // Native Poseidon permutation. This is what the chip below computes inside the circuit.
+    pub fn permute(&self, state: &mut [F; WIDTH]) {
+        for round in 0..ROUNDS {
+            *state = self.round(round, *state);
+        }
+    }
+
+    // A single round: add the round constants, apply the S-box and mix the words with the MDS matrix.
+    // A full round applies the S-box to every word, a partial round only to the first one
+    pub fn round(&self, round: usize, mut state: [F; WIDTH]) -> [F; WIDTH] {
+        for (word, rc) in state.iter_mut().zip(self.round_constants[round].iter()) {
+            *word += rc;
+        }
+        if Self::is_full_round(round) {
+            for word in state.iter_mut() {
+                *word = pow5(*word);
+            }
+        } else {
+            state[0] = pow5(state[0]);
+        }
+        self.apply_mds(&state)
+    }
+
+    fn apply_mds(&self, state: &[F; WIDTH]) -> [F; WIDTH] {
+        let mut new_state = [F::zero(); WIDTH];
+        for (i, word) in new_state.iter_mut().enumerate() {
+            for (j, value) in state.iter().enumerate() {
+                *word += self.mds[i][j] * value;
+            }
+        }
+        new_state
+    }
+}
+
+fn pow5<F: FieldExt>(x: F) -> F {
+    x.square().square() * x
+}
+
+// The capacity element is initialized with the length of the message, like the `ConstantLength` domain of halo2_gadgets.
+// In this way hashing [x] and [x, 0] gives two different results even though the padded message is the same.
+pub fn initial_capacity<F: FieldExt>(message_len: usize) -> F {
+    F::from_u128((message_len as u128) << 64)
+}
+
+/// Native Poseidon hash of a fixed length message. Use it to compute the public inputs that the circuit is expected to produce.
+///
+/// ```
+/// use halo2_gadgets::poseidon::primitives::{ConstantLength, Hash, P128Pow5T3};
+/// use halo2_proofs::pasta::{Fp, Fq};
+/// use halo2_fibonacci_ex::poseidon;
+///
+/// // The same digests as the P128Pow5T3 spec of halo2_gadgets, over both pasta fields
+/// let message = [Fp::from(1), Fp::from(2)];
+/// assert_eq!(poseidon::hash(message), Hash::<_, P128Pow5T3, ConstantLength<2>, 3, 2>::init().hash(message));
+/// let message = [Fq::from(3), Fq::from(4), Fq::from(5)];
+/// assert_eq!(poseidon::hash(message), Hash::<_, P128Pow5T3, ConstantLength<3>, 3, 2>::init().hash(message));
+/// ```
+pub fn hash<F: FieldExt, const L: usize>(message: [F; L]) -> F {
+    let spec = PoseidonSpec::<F>::new();
+    let mut state = [F::zero(), F::zero(), initial_capacity::<F>(L)];
+    // The message is padded with zeros up to a multiple of RATE, then absorbed RATE elements at a time
+    for chunk in pad(&message).chunks(RATE) {
+        for (word, m) in state.iter_mut().zip(chunk.iter()) {
+            if let Some(m) = m {
+                *word += m;
+            }
+        }
+        spec.permute(&mut state);
+    }
+    state[0]
+}
+
+fn pad<T: Clone>(message: &[T]) -> Vec<Option<T>> {
+    let padded_len = message.len().div_ceil(RATE).max(1) * RATE;
+    let mut padded: Vec<Option<T>> = message.iter().cloned().map(Some).collect();
+    padded.resize(padded_len, None);
+    padded
+}
+
+// The Grain LFSR used by the Poseidon reference implementation to generate the round constants.
+struct Grain<F: FieldExt> {
+    state: [bool; 80],
+    next_bit: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Grain<F> {
+    fn new(width: u16, full_rounds: u16, partial_rounds: u16) -> Self {
+        let mut state = [true; 80];
+        // The initial state encodes the parameters of the instance, most significant bit first
+        let mut set_bits = |offset: usize, len: usize, value: u16| {
+            for i in 0..len {
+                state[offset + len - 1 - i] = (value >> i) & 1 != 0;
+            }
+        };
+        set_bits(0, 2, 1); // the field is a prime field
+        set_bits(2, 4, 0); // the S-box is x^alpha
+        set_bits(6, 12, F::NUM_BITS as u16);
+        set_bits(18, 12, width);
+        set_bits(30, 10, full_rounds);
+        set_bits(40, 10, partial_rounds);
+
+        let mut grain = Grain {
+            state,
+            next_bit: 80,
+            _marker: PhantomData,
+        };
+        // The first 160 bits are discarded
+        for _ in 0..20 {
+            grain.load_next_8_bits();
+            grain.next_bit = 80;
+        }
+        grain
+    }
+
+    fn load_next_8_bits(&mut self) {
+        let mut new_bits = 0u8;
+        for i in 0..8 {
+            let bit = self.state[i + 62]
+                ^ self.state[i + 51]
+                ^ self.state[i + 38]
+                ^ self.state[i + 23]
+                ^ self.state[i + 13]
+                ^ self.state[i];
+            new_bits |= (bit as u8) << i;
+        }
+        self.state.rotate_left(8);
+        self.next_bit -= 8;
+        for i in 0..8 {
+            self.state[self.next_bit + i] = (new_bits >> i) & 1 != 0;
+        }
+    }
+
+    fn get_next_bit(&mut self) -> bool {
+        if self.next_bit == 80 {
+            self.load_next_8_bits();
+        }
+        let bit = self.state[self.next_bit];
+        self.next_bit += 1;
+        bit
+    }
+
+    // Bits are produced in pairs: if the first bit is 1 the second one is emitted, otherwise the pair is discarded
+    fn next_output_bit(&mut self) -> bool {
+        while !self.get_next_bit() {
+            self.get_next_bit();
+        }
+        self.get_next_bit()
+    }
+
+    // The reference implementation reads the bits most significant first, while the field repr is little endian
+    fn next_bytes(&mut self, bytes: &mut [u8]) {
+        for i in (0..F::NUM_BITS as usize).rev() {
+            if self.next_output_bit() {
+                bytes[i / 8] |= 1 << (i % 8);
+            }
+        }
+    }
+
+    fn next_field_element(&mut self) -> F {
+        // Sample until we get a value smaller than the modulus
+        loop {
+            let mut repr = F::Repr::default();
+            self.next_bytes(repr.as_mut());
+            if let Some(value) = F::from_repr_vartime(repr) {
+                break value;
+            }
+        }
+    }
+
+    fn next_field_element_without_rejection(&mut self) -> F {
+        let mut bytes = [0u8; 64];
+        self.next_bytes(&mut bytes);
+        F::from_bytes_wide(&bytes)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PoseidonConfig<F: FieldExt> {
+    pub state: [Column<Advice>; WIDTH],
+    pub round_constants: [Column<Fixed>; WIDTH],
+    pub s_full: Selector,
+    pub s_partial: Selector,
+    pub s_absorb: Selector,
+    pub spec: PoseidonSpec<F>,
+}
+
+impl<F: FieldExt> PoseidonConfig<F> {
+    // Names the columns in the diagnostics of the library (see src/annotate.rs)
+    pub fn annotate(&self, names: &mut ColumnNames) {
+        for (i, (state, round_constant)) in self.state.iter().zip(self.round_constants.iter()).enumerate() {
+            names.annotate(*state, format!("state_{}", i)).annotate(*round_constant, format!("rc_{}", i));
+        }
+    }
+}
+
+/// Hashes assigned cells with Poseidon, the result matches [`hash`].
+///
+/// ```
+/// use halo2_proofs::{circuit::*, dev::MockProver, pasta::Fp, plonk::*};
+/// use halo2_fibonacci_ex::poseidon::{self, PoseidonChip, PoseidonConfig};
+///
+/// // Proves knowledge of a preimage of a public digest
+/// #[derive(Default)]
+/// struct MyCircuit {
+///     message: [Option<Fp>; 2],
+/// }
+///
+/// impl Circuit<Fp> for MyCircuit {
+///     type Config = (PoseidonConfig<Fp>, Column<Instance>);
+///     type FloorPlanner = SimpleFloorPlanner;
+///
+///     fn without_witnesses(&self) -> Self {
+///         Self::default()
+///     }
+///
+///     fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+///         let state = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+///         let instance = meta.instance_column();
+///         meta.enable_equality(instance);
+///         (PoseidonChip::configure(meta, state), instance)
+///     }
+///
+///     fn synthesize(&self, (config, instance): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+///         let state = config.state;
+///         let message = layouter.assign_region(|| "message", |mut region| {
+///             let a = region.assign_advice(|| "a", state[0], 0, || self.message[0].ok_or(Error::Synthesis))?;
+///             let b = region.assign_advice(|| "b", state[1], 0, || self.message[1].ok_or(Error::Synthesis))?;
+///             Ok([a, b])
+///         })?;
+///         let digest = PoseidonChip::construct(config).hash(layouter.namespace(|| "hash"), message)?;
+///         layouter.constrain_instance(digest.cell(), instance, 0)
+///     }
+/// }
+///
+/// let message = [Fp::from(1), Fp::from(2)];
+/// let circuit = MyCircuit { message: message.map(Some) };
+/// let digest = poseidon::hash(message);
+/// MockProver::run(7, &circuit, vec![vec![digest]]).unwrap().assert_satisfied();
+/// ```
+pub struct PoseidonChip<F: FieldExt> {
+    config: PoseidonConfig<F>,
+}
+
+impl<F: FieldExt> PoseidonChip<F> {
+    pub fn construct(config: PoseidonConfig<F>) -> Self {
+        Self { config }
+    }
+
+    // The state columns are passed in, so they can be shared with other chips (as done in example2 for the FiboChip).
+    // The round constants live in 3 fixed columns created here. The first one is also used to store the constants
+    // of the initial state and of the padding, that's why it is enabled as a constant column.
+    pub fn configure(meta: &mut ConstraintSystem<F>, state: [Column<Advice>; WIDTH]) -> PoseidonConfig<F> {
+        let round_constants = [meta.fixed_column(), meta.fixed_column(), meta.fixed_column()];
+        let s_full = meta.selector();
+        let s_partial = meta.selector();
+        let s_absorb = meta.selector();
+        let spec = PoseidonSpec::<F>::new();
+
+        for column in state.iter() {
+            meta.enable_equality(*column);
+        }
+        meta.enable_constant(round_constants[0]);
+
+        // Every round takes one row: the state before the round is in the current row, the state after the round in the next one.
+        //  state[0] | state[1] | state[2] | rc[0] | rc[1] | rc[2] | s_full
+        // ---------------------------------------------------------------
+        //    x0     |    x1    |    x2    |  c0   |  c1   |  c2   |   1
+        //    y0     |    y1    |    y2    |       |       |       |
+        // with y = MDS * ((x + c)^5)
+        let mds = spec.mds;
+        meta.create_gate("poseidon full round", |meta| {
+            let s = meta.query_selector(s_full);
+            let sboxed: Vec<Expression<F>> = (0..WIDTH)
+                .map(|i| {
+                    let x = meta.query_advice(state[i], Rotation::cur()) + meta.query_fixed(round_constants[i], Rotation::cur());
+                    x.clone() * x.clone() * x.clone() * x.clone() * x
+                })
+                .collect();
+            let next: Vec<Expression<F>> = (0..WIDTH).map(|i| meta.query_advice(state[i], Rotation::next())).collect();
+
+            Constraints::with_selector(s, MIX_NAMES.into_iter().zip(mix(&mds, &sboxed, &next)))
+        });
+
+        // The partial round has the same shape but the S-box is only applied to the first word
+        meta.create_gate("poseidon partial round", |meta| {
+            let s = meta.query_selector(s_partial);
+            let sboxed: Vec<Expression<F>> = (0..WIDTH)
+                .map(|i| {
+                    let x = meta.query_advice(state[i], Rotation::cur()) + meta.query_fixed(round_constants[i], Rotation::cur());
+                    if i == 0 {
+                        x.clone() * x.clone() * x.clone() * x.clone() * x
+                    } else {
+                        x
+                    }
+                })
+                .collect();
+            let next: Vec<Expression<F>> = (0..WIDTH).map(|i| meta.query_advice(state[i], Rotation::next())).collect();
+
+            Constraints::with_selector(s, MIX_NAMES.into_iter().zip(mix(&mds, &sboxed, &next)))
+        });
+
+        // Absorbing a message block adds it to the rate part of the state and leaves the capacity untouched
+        //  state[0] | state[1] | state[2] | s_absorb
+        // -------------------------------------------
+        //    x0     |    x1    |    x2    |    1
+        //    m0     |    m1    |          |
+        //    y0     |    y1    |    y2    |
+        meta.create_gate("poseidon absorb", |meta| {
+            let s = meta.query_selector(s_absorb);
+            let constraints = (0..WIDTH).map(|i| {
+                let x = meta.query_advice(state[i], Rotation::cur());
+                let y = meta.query_advice(state[i], Rotation(2));
+                if i < RATE {
+                    let m = meta.query_advice(state[i], Rotation::next());
+                    (ABSORB_NAMES[i], x + m - y)
+                } else {
+                    (ABSORB_NAMES[i], x - y)
+                }
+            });
+            Constraints::with_selector(s, constraints.collect::<Vec<_>>())
+        });
+
+        PoseidonConfig {
+            state,
+            round_constants,
+            s_full,
+            s_partial,
+            s_absorb,
+            spec,
+        }
+    }
+
+    // Hash a message of L assigned cells and return the cell containing the digest.
+    // The whole sponge is laid out in a single region: the initial state, then for each block the message row,
+    // the absorbed state and the ROUNDS rows of the permutation. The output of a permutation is the state row of the next block.
+    pub fn hash<const L: usize>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: [AssignedCell<F, F>; L],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(
+            || "poseidon hash",
+            |mut region| {
+                // The initial state is [0, 0, L * 2^64]. These are constants: assigning them from the constant column
+                // makes sure the prover can't start the sponge from any other state
+                let initial = [F::zero(), F::zero(), initial_capacity::<F>(L)];
+                let mut state_cells = Vec::with_capacity(WIDTH);
+                for (i, value) in initial.iter().enumerate() {
+                    state_cells.push(region.assign_advice_from_constant(
+                        || format!("initial state {}", i),
+                        config.state[i],
+                        0,
+                        *value,
+                    )?);
+                }
+                let mut state: Option<[F; WIDTH]> = Some(initial);
+
+                let mut offset = 0;
+                for block in pad(&message).chunks(RATE) {
+                    config.s_absorb.enable(&mut region, offset)?;
+
+                    // Message row: copy the input cells, pad with constant zeros
+                    let mut block_values = Vec::with_capacity(RATE);
+                    for (i, word) in block.iter().enumerate() {
+                        let cell = match word {
+                            Some(cell) => cell.copy_advice(|| format!("message {}", i), &mut region, config.state[i], offset + 1)?,
+                            None => region.assign_advice_from_constant(|| "padding", config.state[i], offset + 1, F::zero())?,
+                        };
+                        block_values.push(cell.value().copied());
+                    }
+
+                    // Absorbed state row
+                    state = state.and_then(|mut state| {
+                        for (word, m) in state.iter_mut().zip(block_values.iter()) {
+                            *word += (*m)?;
+                        }
+                        Some(state)
+                    });
+                    offset += 2;
+                    state_cells = self.assign_state(&mut region, offset, state)?;
+
+                    // One row for each round of the permutation
+                    for round in 0..ROUNDS {
+                        if PoseidonSpec::<F>::is_full_round(round) {
+                            config.s_full.enable(&mut region, offset)?;
+                        } else {
+                            config.s_partial.enable(&mut region, offset)?;
+                        }
+                        for i in 0..WIDTH {
+                            let rc = config.spec.round_constants[round][i];
+                            region.assign_fixed(|| format!("round constant {} {}", round, i), config.round_constants[i], offset, || Ok(rc))?;
+                        }
+
+                        state = state.map(|state| config.spec.round(round, state));
+                        offset += 1;
+                        state_cells = self.assign_state(&mut region, offset, state)?;
+                    }
+                }
+
+                // The digest is the first word of the final state
+                Ok(state_cells[0].clone())
+            },
+        )
+    }
+
+    fn assign_state(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: Option<[F; WIDTH]>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        (0..WIDTH)
+            .map(|i| {
+                region.assign_advice(
+                    || format!("state {}", i),
+                    self.config.state[i],
+                    offset,
+                    || state.map(|state| state[i]).ok_or(Error::Synthesis),
+                )
+            })
+            .collect()
+    }
+
+}
+
+// Constraints enforcing next = MDS * sboxed
+fn mix<F: FieldExt>(
+    mds: &[[F; WIDTH]; WIDTH],
+    sboxed: &[Expression<F>],
+    next: &[Expression<F>],
+) -> Vec<Expression<F>> {
+    (0..WIDTH)
+        .map(|i| {
+            let mixed = (0..WIDTH).fold(Expression::Constant(F::zero()), |acc, j| {
+                acc + sboxed[j].clone() * mds[i][j]
+            });
+            mixed - next[i].clone()
+        })
+        .collect()
+}
@@ -0,0 +1,404 @@
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::*,
+    poly::commitment::Params,
+};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    backend::{Backend, IpaPasta},
+    error::FibError,
+    params::ParamsCache,
+    transcript::TranscriptKind,
+};
+
+// Real proofs for the circuits of the library, as in example15: IPA commitments over Vesta, so the circuits are over Fp,
+// and a Blake2b transcript (backend::IpaPasta). The examples call keygen_vk, keygen_pk, create_proof and verify_proof
+// by hand, the tools go through these wrappers instead.
+//
+// Proving is made of stages (parameters, verifying key, proving key, proof) and large circuits spend seconds in each
+// of them. A CancellationToken lets another thread (a signal handler, a server whose client went away, ...) abort
+// the proof: the token is checked between stages and the wrapper returns FibError::Cancelled. The proof itself is made
+// of stages as well, and the token is checked before each message create_proof writes to the transcript
+// (TranscriptKind::create_proof_checked). A stage that already started runs to completion, halo2 itself can't be
+// interrupted.
+//
+// Requests coming from someone else can also ask for a huge circuit: the memory and the time of every stage grow with
+// 2^k. Limits bounds both, k is checked before anything is allocated and the deadline is checked between stages, like
+// the token.
+//
+// With the `async` feature, prove_async and verify_async run the same functions on tokio's blocking thread pool, so
+// async services can await a proof without stalling their runtime. The blocking functions remain the primary API.
+
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    // Returns Err(FibError::Cancelled) once cancel has been called
+    pub fn check(&self) -> Result<(), FibError> {
+        match self.is_cancelled() {
+            true => Err(FibError::Cancelled),
+            false => Ok(()),
+        }
+    }
+
+    // Cancels the token when the returned guard is dropped, e.g. with the future of a request whose client went away
+    pub fn cancel_on_drop(&self) -> CancelOnDrop {
+        CancelOnDrop(self.clone())
+    }
+}
+
+#[must_use = "the token is cancelled as soon as the guard is dropped"]
+pub struct CancelOnDrop(CancellationToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+// Resource limits for proofs and mock runs requested by untrusted callers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_k: u32,
+    // Wall-clock time allowed for a whole proof, None for no limit
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Limits {
+    // 2^20 rows and no timeout: enough for every circuit of the repo, while k = 30 would need hundreds of GB
+    fn default() -> Self {
+        Self { max_k: 20, timeout: None }
+    }
+}
+
+impl Limits {
+    pub fn check_k(&self, k: u32) -> Result<(), FibError> {
+        match k > self.max_k {
+            true => Err(FibError::LimitExceeded(format!("k = {} is larger than the maximum {}", k, self.max_k))),
+            false => Ok(()),
+        }
+    }
+}
+
+// What is checked between two stages of a proof
+#[must_use]
+struct Guard<'a> {
+    token: &'a CancellationToken,
+    deadline: Option<(Instant, Duration)>,
+}
+
+impl<'a> Guard<'a> {
+    // The deadline starts now
+    fn new(token: &'a CancellationToken, limits: &Limits) -> Self {
+        Self { token, deadline: limits.timeout.map(|timeout| (Instant::now() + timeout, timeout)) }
+    }
+
+    fn check(&self) -> Result<(), FibError> {
+        self.token.check()?;
+        match self.deadline {
+            Some((deadline, timeout)) if Instant::now() > deadline => Err(FibError::Timeout(timeout)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Creates a proof of `circuit` for the instance column `instance`.
+///
+/// ```
+/// use halo2_proofs::pasta::Fp;
+/// use halo2_fibonacci_ex::{error::FibError, fibonacci::FibInputs, prover::{prove, verify, CancellationToken}};
+///
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// let token = CancellationToken::new();
+/// let proof = prove(4, &inputs.circuit(), &inputs.public_inputs(), &token).unwrap();
+/// verify(4, &inputs.circuit(), &inputs.public_inputs(), &proof).unwrap();
+///
+/// // A token cancelled before the start stops at the first check
+/// token.cancel();
+/// let result = prove(4, &inputs.circuit(), &inputs.public_inputs(), &token);
+/// assert!(matches!(result, Err(FibError::Cancelled)));
+/// ```
+#[must_use = "the proof is the only output of proving"]
+pub fn prove<C: Circuit<Fp>>(k: u32, circuit: &C, instance: &[Fp], token: &CancellationToken) -> Result<Vec<u8>, FibError> {
+    prove_with_limits(k, circuit, instance, token, &Limits { max_k: u32::MAX, timeout: None })
+}
+
+// The same as `prove`, but k can't be larger than limits.max_k and the proof fails with FibError::Timeout once
+// limits.timeout has elapsed
+#[must_use = "the proof is the only output of proving"]
+pub fn prove_with_limits<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+    instance: &[Fp],
+    token: &CancellationToken,
+    limits: &Limits,
+) -> Result<Vec<u8>, FibError> {
+    prove_stages(k, circuit, instance, token, limits, TranscriptKind::Blake2b, || Ok(Params::new(k)), OsRng)
+}
+
+// The same as `prove_with_limits`, with the challenges drawn from `transcript` (see src/transcript.rs). The proof
+// only verifies with `verify_with_transcript` and the same kind
+#[must_use = "the proof is the only output of proving"]
+pub fn prove_with_transcript<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+    instance: &[Fp],
+    token: &CancellationToken,
+    limits: &Limits,
+    transcript: TranscriptKind,
+) -> Result<Vec<u8>, FibError> {
+    prove_stages(k, circuit, instance, token, limits, transcript, || Ok(Params::new(k)), OsRng)
+}
+
+// The same as `prove_with_limits`, with the parameters loaded from `cache` (see src/params.rs)
+#[must_use = "the proof is the only output of proving"]
+pub fn prove_with_cache<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+    instance: &[Fp],
+    token: &CancellationToken,
+    limits: &Limits,
+    cache: &ParamsCache,
+) -> Result<Vec<u8>, FibError> {
+    let setup = || cache.load_or_setup::<IpaPasta>(k);
+    prove_stages(k, circuit, instance, token, limits, TranscriptKind::Blake2b, setup, OsRng)
+}
+
+/// The same as `prove_with_limits`, with the blinding factors of the proof drawn from `rng` rather than OsRng, and the
+/// parameters loaded from `cache` when there is one. With a seeded rng the proof is the same bytes on every run, for
+/// tests and golden fixtures. Only there: the blinding factors are what hides the witness, so anyone who knows the seed
+/// can recover it from the proof.
+///
+/// ```
+/// use halo2_proofs::pasta::Fp;
+/// use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+/// use halo2_fibonacci_ex::{fibonacci::FibInputs, prover::{prove_with_rng, verify, CancellationToken, Limits}};
+///
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// let (circuit, instance) = (inputs.circuit(), inputs.public_inputs());
+/// let prove = |seed| {
+///     let rng = ChaCha20Rng::seed_from_u64(seed);
+///     prove_with_rng(4, &circuit, &instance, &CancellationToken::new(), &Limits::default(), None, rng).unwrap()
+/// };
+///
+/// let proof = prove(7);
+/// assert_eq!(proof, prove(7));
+/// assert_ne!(proof, prove(8));
+/// verify(4, &circuit, &instance, &proof).unwrap();
+/// ```
+#[must_use = "the proof is the only output of proving"]
+pub fn prove_with_rng<C: Circuit<Fp>, R: RngCore>(
+    k: u32,
+    circuit: &C,
+    instance: &[Fp],
+    token: &CancellationToken,
+    limits: &Limits,
+    cache: Option<&ParamsCache>,
+    rng: R,
+) -> Result<Vec<u8>, FibError> {
+    let setup = || match cache {
+        Some(cache) => cache.load_or_setup::<IpaPasta>(k),
+        None => Ok(Params::new(k)),
+    };
+    prove_stages(k, circuit, instance, token, limits, TranscriptKind::Blake2b, setup, rng)
+}
+
+/// The same as `prove_with_limits`, with keys generated beforehand from `params`, e.g. by a service proving the same
+/// circuit for many requests. Only the proof itself is left: the token and the deadline are checked before it starts
+/// and between its stages.
+///
+/// ```
+/// use std::time::Duration;
+/// use halo2_proofs::{pasta::Fp, plonk::{keygen_pk, keygen_vk, Circuit}, poly::commitment::Params};
+/// use halo2_fibonacci_ex::{error::FibError, fibonacci::FibInputs, prover::{prove_with_keys, verify, CancellationToken, Limits}};
+///
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// let (circuit, instance) = (inputs.circuit(), inputs.public_inputs());
+/// let params = Params::new(4);
+/// let vk = keygen_vk(&params, &circuit.without_witnesses()).unwrap();
+/// let pk = keygen_pk(&params, vk, &circuit.without_witnesses()).unwrap();
+///
+/// let token = CancellationToken::new();
+/// let proof = prove_with_keys(4, &params, &pk, &circuit, &instance, &token, &Limits::default()).unwrap();
+/// verify(4, &circuit, &instance, &proof).unwrap();
+///
+/// // A deadline that passed stops the proof as well
+/// let limits = Limits { timeout: Some(Duration::ZERO), ..Limits::default() };
+/// let result = prove_with_keys(4, &params, &pk, &circuit, &instance, &token, &limits);
+/// assert!(matches!(result, Err(FibError::Timeout(_))));
+///
+/// drop(token.cancel_on_drop());
+/// let result = prove_with_keys(4, &params, &pk, &circuit, &instance, &token, &Limits::default());
+/// assert!(matches!(result, Err(FibError::Cancelled)));
+/// ```
+#[must_use = "the proof is the only output of proving"]
+pub fn prove_with_keys<C: Circuit<Fp>>(
+    k: u32,
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: &C,
+    instance: &[Fp],
+    token: &CancellationToken,
+    limits: &Limits,
+) -> Result<Vec<u8>, FibError> {
+    limits.check_k(k)?;
+    let guard = Guard::new(token, limits);
+    guard.check()?;
+    create_proof(&guard, TranscriptKind::Blake2b, params, pk, circuit, instance, OsRng)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn prove_stages<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+    instance: &[Fp],
+    token: &CancellationToken,
+    limits: &Limits,
+    transcript: TranscriptKind,
+    setup: impl FnOnce() -> Result<Params<EqAffine>, FibError>,
+    rng: impl RngCore,
+) -> Result<Vec<u8>, FibError> {
+    limits.check_k(k)?;
+    let guard = Guard::new(token, limits);
+
+    guard.check()?;
+    let params = setup()?;
+    guard.check()?;
+    let vk = keygen_vk(&params, circuit)?;
+    guard.check()?;
+    let pk = keygen_pk(&params, vk, circuit)?;
+    guard.check()?;
+
+    create_proof(&guard, transcript, &params, &pk, circuit, instance, rng)
+}
+
+// The last stage, with the guard checked between the stages of create_proof as well
+fn create_proof<C: Circuit<Fp>>(
+    guard: &Guard,
+    transcript: TranscriptKind,
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: &C,
+    instance: &[Fp],
+    rng: impl RngCore,
+) -> Result<Vec<u8>, FibError> {
+    let check = || guard.check().map_err(io::Error::other);
+    transcript.create_proof_checked(params, pk, circuit, instance, rng, check).map_err(|e| match guard.check() {
+        // The transcript refused a write because the token was cancelled or the deadline passed
+        Err(stopped) => stopped,
+        Ok(()) => e.into(),
+    })
+}
+
+// Verifies a proof created by `prove`. The circuit is only used to generate the verifying key, so it can be the
+// circuit without witnesses
+#[must_use = "the proof is only verified if the result is checked"]
+pub fn verify<C: Circuit<Fp>>(k: u32, circuit: &C, instance: &[Fp], proof: &[u8]) -> Result<(), FibError> {
+    IpaPasta::verify(&IpaPasta::setup(k), circuit, instance, proof)
+}
+
+// The same as `verify`, with keys generated beforehand, e.g. kept by a Registry across verifications
+#[must_use = "the proof is only verified if the result is checked"]
+pub fn verify_with_vk(params: &Params<EqAffine>, vk: &VerifyingKey<EqAffine>, instance: &[Fp], proof: &[u8]) -> Result<(), FibError> {
+    TranscriptKind::Blake2b.verify_proof(params, vk, instance, proof).map_err(|_| FibError::InvalidProof)
+}
+
+// Verifies a proof created by `prove_with_transcript` with the same transcript
+#[must_use = "the proof is only verified if the result is checked"]
+pub fn verify_with_transcript<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+    instance: &[Fp],
+    proof: &[u8],
+    transcript: TranscriptKind,
+) -> Result<(), FibError> {
+    let params = Params::new(k);
+    let vk = keygen_vk(&params, circuit)?;
+    transcript.verify_proof(&params, &vk, instance, proof).map_err(|_| FibError::InvalidProof)
+}
+
+// Identifies a verifying key: the SHA-256 of its pinned representation (the domain, the constraint system, the fixed
+// and permutation commitments), hex encoded. Two builds whose circuits differ in any constraint or fixed value get
+// different fingerprints
+pub fn fingerprint(vk: &VerifyingKey<EqAffine>) -> String {
+    hex::encode(Sha256::digest(format!("{:?}", vk.pinned())))
+}
+
+/// A hash to compare the circuit of two deployments, e.g. a prover and a verifier, printed by `fib vk-hash`: the
+/// Blake2b-256 of the circuit id, k and the [`fingerprint`] of the verifying key, hex encoded. The fingerprint is the
+/// one identity of a key; the hash binds it to the id and k the key is served under.
+///
+/// ```
+/// use halo2_proofs::{pasta::Fp, plonk::{keygen_vk, Circuit}, poly::commitment::Params};
+/// use halo2_fibonacci_ex::{fibonacci::FibInputs, prover::vk_hash};
+///
+/// let circuit = FibInputs::new(Fp::zero(), Fp::zero(), 8).circuit().without_witnesses();
+/// let vk = keygen_vk(&Params::new(4), &circuit).unwrap();
+/// assert_eq!(vk_hash("fibonacci-8", 4, &vk), "e69fa86b9973314e9c4554ba1e2eb82969e7fd7024ea431fa8b9d08337712648");
+/// assert_ne!(vk_hash("fibonacci-8", 4, &vk), vk_hash("fibonacci-9", 4, &vk));
+/// ```
+pub fn vk_hash(circuit_id: &str, k: u32, vk: &VerifyingKey<EqAffine>) -> String {
+    let mut state = blake2b_simd::Params::new().hash_length(32).personal(b"fib-vk-hash").to_state();
+    // The id is length-prefixed, so that no two (id, k) pairs hash the same bytes
+    state.update(&(circuit_id.len() as u64).to_le_bytes());
+    state.update(circuit_id.as_bytes());
+    state.update(&k.to_le_bytes());
+    state.update(fingerprint(vk).as_bytes());
+    state.finalize().to_hex().to_string()
+}
+
+/// Runs `prove_with_limits` on tokio's blocking thread pool. The inputs are owned because they move to another thread.
+///
+/// ```
+/// use halo2_proofs::pasta::Fp;
+/// use halo2_fibonacci_ex::{fibonacci::FibInputs, prover::{prove_async, verify_async, CancellationToken, Limits}};
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// runtime.block_on(async {
+///     let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+///     let proof = prove_async(4, inputs.circuit(), inputs.public_inputs(), CancellationToken::new(), Limits::default()).await.unwrap();
+///     verify_async(4, inputs.circuit(), inputs.public_inputs(), proof).await.unwrap();
+/// });
+/// ```
+#[cfg(feature = "async")]
+pub async fn prove_async<C: Circuit<Fp> + Send + 'static>(
+    k: u32,
+    circuit: C,
+    instance: Vec<Fp>,
+    token: CancellationToken,
+    limits: Limits,
+) -> Result<Vec<u8>, FibError> {
+    tokio::task::spawn_blocking(move || prove_with_limits(k, &circuit, &instance, &token, &limits))
+        .await
+        .map_err(|e| FibError::Internal(e.to_string()))?
+}
+
+#[cfg(feature = "async")]
+pub async fn verify_async<C: Circuit<Fp> + Send + 'static>(k: u32, circuit: C, instance: Vec<Fp>, proof: Vec<u8>) -> Result<(), FibError> {
+    tokio::task::spawn_blocking(move || verify(k, &circuit, &instance, &proof))
+        .await
+        .map_err(|e| FibError::Internal(e.to_string()))?
+}
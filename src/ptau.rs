@@ -0,0 +1,259 @@
+use num_bigint::BigUint;
+
+use crate::error::FibError;
+
+// The modulus of the base field of BN254
+const BN254_Q: &str = "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47";
+
+pub fn bn254_q() -> BigUint {
+    BigUint::parse_bytes(BN254_Q.as_bytes(), 16).expect("a hex constant")
+}
+
+// The powers of tau of a trusted setup, read from a .ptau file of the Perpetual Powers of Tau ceremony or of snarkjs
+// (`snarkjs powersoftau ...`), for KZG parameters built from a real setup instead of a secret generated locally.
+//
+// A .ptau file is "ptau", a version and a list of sections, each a type (u32), a size (u64) and its data, all little
+// endian:
+//
+//   1  header: n8 (bytes per base field element), q (the modulus, n8 bytes), power, ceremony power
+//   2  tau G1: [tau^i] G1 for i < 2^(power + 1) - 1, each x, y
+//   3  tau G2: [tau^i] G2 for i < 2^power, each x.c0, x.c1, y.c0, y.c1
+//   ...the other sections (alpha, beta, the contributions) are not needed for KZG
+//
+// The coordinates are stored in Montgomery form (x * 2^(8 n8) mod q) and are returned as plain integers, affine, with
+// (0, 0) for the point at infinity. Only the first 2^k powers of G1 and the first two of G2 are read, which is what
+// KZG parameters for circuits of 2^k rows need.
+//
+// Only BN254 setups are accepted, the curve of the ceremony: the header must give n8 = 32 and the modulus of its base
+// field, the sections must have the sizes the power of the header gives them, and every point read must be on the
+// curve, y^2 = x^3 + 3 for G1 and y^2 = x^3 + 3 / (9 + u) over Fq[u] / (u^2 + 1) for G2. A file that fails any of
+// these is a FibError::Parse. The points are not checked to be in the subgroup, nor to be powers of the same tau.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct G1Point {
+    pub x: BigUint,
+    pub y: BigUint,
+}
+
+// Coordinates in the quadratic extension, c0 + c1 u
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct G2Point {
+    pub x: [BigUint; 2],
+    pub y: [BigUint; 2],
+}
+
+/// The powers of tau of a .ptau file needed for circuits of 2^k rows.
+///
+/// ```
+/// use num_bigint::BigUint;
+/// use halo2_fibonacci_ex::{error::FibError, ptau::{bn254_q, Ptau}};
+///
+/// // A file of power 1 for tau = 1: 3 G1 points and 2 G2 points, all the generators
+/// let q = bn254_q();
+/// let element = |x: &str| {
+///     // Montgomery form, 32 bytes little endian
+///     let mut bytes = ((BigUint::parse_bytes(x.as_bytes(), 10).unwrap() << 256usize) % &q).to_bytes_le();
+///     bytes.resize(32, 0);
+///     bytes
+/// };
+/// let section = |kind: u32, data: Vec<u8>| [kind.to_le_bytes().to_vec(), (data.len() as u64).to_le_bytes().to_vec(), data].concat();
+/// let g1 = [element("1"), element("2")].concat();
+/// let g2 = [
+///     element("10857046999023057135944570762232829481370756359578518086990519993285655852781"),
+///     element("11559732032986387107991004021392285783925812861821192530917403151452391805634"),
+///     element("8495653923123431417604973247489272438418190587263600148770280649306958101930"),
+///     element("4082367875863433681332203403145435568316851327593401208105741076214120093531"),
+/// ]
+/// .concat();
+/// let file = |header: Vec<u8>, g1: Vec<u8>| {
+///     let mut file = b"ptau".to_vec();
+///     file.extend(1u32.to_le_bytes());
+///     file.extend(3u32.to_le_bytes());
+///     file.extend(section(1, header));
+///     file.extend(section(2, g1));
+///     file.extend(section(3, g2.repeat(2)));
+///     file
+/// };
+/// let mut q_bytes = q.to_bytes_le();
+/// q_bytes.resize(32, 0);
+/// let header = [32u32.to_le_bytes().to_vec(), q_bytes, 1u32.to_le_bytes().to_vec(), 1u32.to_le_bytes().to_vec()].concat();
+///
+/// let ptau = Ptau::read(&file(header.clone(), g1.repeat(3)), 1).unwrap();
+/// assert_eq!((ptau.power, ptau.g1.len()), (1, 2));
+/// assert_eq!((ptau.g1[1].x.clone(), ptau.g1[1].y.clone()), (BigUint::from(1u32), BigUint::from(2u32)));
+///
+/// // A ceremony of power 1 has no parameters for 2^2 rows
+/// assert!(matches!(Ptau::read(&file(header.clone(), g1.repeat(3)), 2), Err(FibError::InvalidInput(_))));
+/// // A point off the curve, a section of the wrong size, a header of another curve and another format are rejected
+/// let off_curve = [element("1"), element("3"), g1.repeat(2)].concat();
+/// assert!(matches!(Ptau::read(&file(header.clone(), off_curve), 1), Err(FibError::Parse(_))));
+/// assert!(matches!(Ptau::read(&file(header.clone(), g1.repeat(2)), 1), Err(FibError::Parse(_))));
+/// let other_curve = [vec![0; 4], header[4..].to_vec()].concat();
+/// assert!(matches!(Ptau::read(&file(other_curve, g1.repeat(3)), 1), Err(FibError::Parse(_))));
+/// assert!(matches!(Ptau::read(b"zkey", 1), Err(FibError::Parse(_))));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ptau {
+    // The file holds the powers for circuits of up to 2^power rows
+    pub power: u32,
+    pub ceremony_power: u32,
+    // The modulus of the base field
+    pub q: BigUint,
+    // [tau^i] G1 for i < 2^k
+    pub g1: Vec<G1Point>,
+    // G2 and [tau] G2
+    pub g2: Vec<G2Point>,
+}
+
+impl Ptau {
+    pub fn read(bytes: &[u8], k: u32) -> Result<Self, FibError> {
+        let mut reader = Reader { bytes, position: 0 };
+        if reader.take(4)? != b"ptau" {
+            return Err(FibError::Parse("not a .ptau file".to_string()));
+        }
+        let _version = reader.u32()?;
+        let sections = reader.u32()?;
+
+        // The sections can come in any order
+        let mut found: [Option<&[u8]>; 4] = [None; 4];
+        for _ in 0..sections {
+            let kind = reader.u32()?;
+            let size = usize::try_from(reader.u64()?).map_err(|_| FibError::Parse("ptau: section too large".to_string()))?;
+            let data = reader.take(size)?;
+            if let Some(slot) = found.get_mut(kind as usize) {
+                *slot = Some(data);
+            }
+        }
+        let section = |kind: usize| found[kind].ok_or_else(|| FibError::Parse(format!("ptau: section {} is missing", kind)));
+
+        let mut header = Reader { bytes: section(1)?, position: 0 };
+        let n8 = header.u32()? as usize;
+        if n8 != 32 {
+            return Err(FibError::Parse(format!("ptau: {}-byte field elements, BN254 has 32", n8)));
+        }
+        let q = BigUint::from_bytes_le(header.take(n8)?);
+        if q != bn254_q() {
+            return Err(FibError::Parse(format!("ptau: the base field {:x} is not the one of BN254", q)));
+        }
+        let power = header.u32()?;
+        let ceremony_power = header.u32()?;
+        if power >= 32 {
+            return Err(FibError::Parse(format!("ptau: power {} is larger than any ceremony", power)));
+        }
+        if k > power {
+            return Err(FibError::InvalidInput(format!("the setup has powers for 2^{} rows, not 2^{}", power, k)));
+        }
+
+        // 2^(power + 1) - 1 points of G1 and 2^power of G2, of 2 and 4 elements
+        let (g1_bytes, g2_bytes) = (section(2)?, section(3)?);
+        let sizes = [(2, g1_bytes.len(), ((2usize << power) - 1) * 2 * n8), (3, g2_bytes.len(), (1usize << power) * 4 * n8)];
+        for (kind, size, expected) in sizes {
+            if size != expected {
+                return Err(FibError::Parse(format!("ptau: section {} has {} bytes, expected {}", kind, size, expected)));
+            }
+        }
+
+        // Montgomery form to integers: multiply by R^-1 = R^(q - 2) mod q
+        let r_inv = (BigUint::from(1u32) << (8 * n8)).modpow(&(&q - 2u32), &q);
+        let element = |reader: &mut Reader| -> Result<BigUint, FibError> {
+            let element = BigUint::from_bytes_le(reader.take(n8)?);
+            if element >= q {
+                return Err(FibError::Parse("ptau: a coordinate is not reduced".to_string()));
+            }
+            Ok(element * &r_inv % &q)
+        };
+        let curve = Curve::new(&q);
+
+        let mut g1_reader = Reader { bytes: g1_bytes, position: 0 };
+        let g1 = (0..1usize << k)
+            .map(|i| {
+                let point = G1Point { x: element(&mut g1_reader)?, y: element(&mut g1_reader)? };
+                match curve.has_g1(&point) {
+                    true => Ok(point),
+                    false => Err(FibError::Parse(format!("ptau: G1 point {} is not on BN254", i))),
+                }
+            })
+            .collect::<Result<Vec<_>, FibError>>()?;
+        let mut g2_reader = Reader { bytes: g2_bytes, position: 0 };
+        let g2 = (0..2)
+            .map(|i| {
+                let x = [element(&mut g2_reader)?, element(&mut g2_reader)?];
+                let y = [element(&mut g2_reader)?, element(&mut g2_reader)?];
+                let point = G2Point { x, y };
+                match curve.has_g2(&point) {
+                    true => Ok(point),
+                    false => Err(FibError::Parse(format!("ptau: G2 point {} is not on BN254", i))),
+                }
+            })
+            .collect::<Result<Vec<_>, FibError>>()?;
+
+        Ok(Self { power, ceremony_power, q, g1, g2 })
+    }
+}
+
+// The equations of BN254 over Fq and Fq2 = Fq[u] / (u^2 + 1), on integers modulo q
+struct Curve<'q> {
+    q: &'q BigUint,
+    // 3 / (9 + u) = 3 (9 - u) / 82
+    b2: [BigUint; 2],
+}
+
+impl<'q> Curve<'q> {
+    fn new(q: &'q BigUint) -> Self {
+        let inv_82 = BigUint::from(82u32).modpow(&(q - 2u32), q);
+        let b2 = [BigUint::from(27u32) * &inv_82 % q, (q - BigUint::from(3u32)) * &inv_82 % q];
+        Self { q, b2 }
+    }
+
+    // (0, 0) is the point at infinity
+    fn has_g1(&self, point: &G1Point) -> bool {
+        let q = self.q;
+        let zero = BigUint::from(0u32);
+        if point.x == zero && point.y == zero {
+            return true;
+        }
+        let rhs = (&point.x * &point.x % q * &point.x + 3u32) % q;
+        &point.y * &point.y % q == rhs
+    }
+
+    fn has_g2(&self, point: &G2Point) -> bool {
+        let zero = BigUint::from(0u32);
+        if point.x.iter().chain(point.y.iter()).all(|c| *c == zero) {
+            return true;
+        }
+        let x3 = self.mul(&self.mul(&point.x, &point.x), &point.x);
+        let rhs = [(&x3[0] + &self.b2[0]) % self.q, (&x3[1] + &self.b2[1]) % self.q];
+        self.mul(&point.y, &point.y) == rhs
+    }
+
+    // (a0 + a1 u)(b0 + b1 u) with u^2 = -1
+    fn mul(&self, a: &[BigUint; 2], b: &[BigUint; 2]) -> [BigUint; 2] {
+        let q = self.q;
+        let real = (&a[0] * &b[0] % q + q - &a[1] * &b[1] % q) % q;
+        let imaginary = (&a[0] * &b[1] + &a[1] * &b[0]) % q;
+        [real, imaginary]
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], FibError> {
+        let end = self.position.checked_add(n).filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or_else(|| FibError::Parse("ptau: the file ends early".to_string()))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, FibError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().expect("4 bytes")))
+    }
+
+    fn u64(&mut self) -> Result<u64, FibError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().expect("8 bytes")))
+    }
+}
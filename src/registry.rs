@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::*,
+    poly::commitment::Params,
+};
+
+use crate::{
+    backend::IpaPasta,
+    bundle::ProofBundle,
+    error::FibError,
+    fibonacci::FibInputs,
+    params::ParamsCache,
+    prover,
+};
+
+// The circuits a verifier accepts proofs for, keyed by the circuit id of the bundle metadata (e.g. "fibonacci-8").
+// verify_bundle is the single entry point for checking a ProofBundle: the fib tool calls it, and so should any
+// server or binding built on the library, so that every one of them applies the same checks in the same order:
+//
+//   1. the circuit id is registered
+//   2. the bundle was created for the registered k
+//   3. the verifying key has the pinned fingerprint, if one was pinned
+//   4. the instance has the length the circuit expects
+//   5. the proof verifies
+//
+// Pinning the fingerprint of a verifying key (prover::fingerprint) catches a build whose circuit changed: the keys it
+// generates would reject the proofs of every other build, or accept proofs of a different statement.
+//
+// Keys are generated on the first verification of a circuit and kept for the following ones. The parameters they are
+// generated from come from a ParamsCache (src/params.rs) when the registry has one.
+
+type Keygen = Box<dyn Fn(&Params<EqAffine>) -> Result<VerifyingKey<EqAffine>, Error> + Send + Sync>;
+pub(crate) type Keys = Arc<(Params<EqAffine>, VerifyingKey<EqAffine>)>;
+
+struct Entry {
+    k: u32,
+    instance_len: usize,
+    fingerprint: Option<String>,
+    keygen: Keygen,
+    keys: Mutex<Option<Keys>>,
+}
+
+impl Entry {
+    fn keys(&self, cache: Option<&ParamsCache>) -> Result<Keys, FibError> {
+        let mut keys = self.keys.lock().map_err(|_| FibError::Internal("a keygen panicked".to_string()))?;
+        if let Some(keys) = keys.as_ref() {
+            return Ok(keys.clone());
+        }
+        let params = match cache {
+            Some(cache) => cache.load_or_setup::<IpaPasta>(self.k)?,
+            None => Params::<EqAffine>::new(self.k),
+        };
+        let vk = (self.keygen)(&params)?;
+        Ok(keys.insert(Arc::new((params, vk))).clone())
+    }
+}
+
+// What a verified bundle proves: the circuit and the public inputs
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verified {
+    pub circuit_id: String,
+    pub k: u32,
+    pub instance: Vec<Fp>,
+    pub fingerprint: String,
+}
+
+/// The circuits accepted by [`verify_bundle`].
+///
+/// ```
+/// use halo2_proofs::pasta::Fp;
+/// use halo2_fibonacci_ex::{
+///     bundle::{Metadata, ProofBundle},
+///     error::FibError,
+///     fibonacci::FibInputs,
+///     prover::{prove, CancellationToken},
+///     registry::{verify_bundle, Registry},
+/// };
+///
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// let proof = prove(4, &inputs.circuit(), &inputs.public_inputs(), &CancellationToken::new()).unwrap();
+/// let bundle = ProofBundle::new(Metadata::new(4, inputs.circuit_id()), inputs.public_inputs(), proof);
+///
+/// let mut registry = Registry::new();
+/// registry.register_fibonacci(4, 8);
+/// let verified = verify_bundle(&bundle, &registry).unwrap();
+/// assert_eq!(verified.instance[2], Fp::from(55));
+///
+/// // Pinning another fingerprint rejects the bundle before verifying the proof
+/// registry.pin_fingerprint("fibonacci-8", "00").unwrap();
+/// assert!(matches!(verify_bundle(&bundle, &registry), Err(FibError::KeyMismatch { .. })));
+///
+/// // So does an unknown circuit
+/// assert!(verify_bundle(&bundle, &Registry::new()).is_err());
+/// ```
+#[derive(Default)]
+pub struct Registry {
+    entries: HashMap<String, Entry>,
+    params: Option<ParamsCache>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Accepts proofs of `circuit` (usually without witnesses) over 2^k rows with `instance_len` public inputs.
+    // Registering an id again replaces the previous entry
+    pub fn register<C: Circuit<Fp> + Send + Sync + 'static>(&mut self, circuit_id: impl Into<String>, k: u32, circuit: C, instance_len: usize) {
+        let entry = Entry {
+            k,
+            instance_len,
+            fingerprint: None,
+            keygen: Box::new(move |params| keygen_vk(params, &circuit)),
+            keys: Mutex::new(None),
+        };
+        self.entries.insert(circuit_id.into(), entry);
+    }
+
+    // Loads the parameters of the circuits from `cache` instead of generating them
+    pub fn set_params_cache(&mut self, cache: ParamsCache) {
+        self.params = Some(cache);
+    }
+
+    // The Fibonacci circuit of the library with n steps, registered under its circuit id
+    pub fn register_fibonacci(&mut self, k: u32, n: usize) {
+        let inputs = FibInputs::new(Fp::zero(), Fp::zero(), n);
+        self.register(inputs.circuit_id(), k, inputs.circuit().without_witnesses(), inputs.public_inputs().len());
+    }
+
+    // Only accepts the bundles of `circuit_id` if its verifying key has this fingerprint
+    pub fn pin_fingerprint(&mut self, circuit_id: &str, fingerprint: impl Into<String>) -> Result<(), FibError> {
+        self.entry_mut(circuit_id)?.fingerprint = Some(fingerprint.into());
+        Ok(())
+    }
+
+    // Verifies the proofs of `circuit_id` with keys generated beforehand, instead of generating them on the first
+    // verification
+    pub(crate) fn set_keys(&mut self, circuit_id: &str, params: Params<EqAffine>, vk: VerifyingKey<EqAffine>) -> Result<(), FibError> {
+        let entry = self.entry_mut(circuit_id)?;
+        *entry.keys.get_mut().map_err(|_| FibError::Internal("a keygen panicked".to_string()))? = Some(Arc::new((params, vk)));
+        Ok(())
+    }
+
+    // The fingerprint of the verifying key of `circuit_id`, as generated by this build
+    pub fn fingerprint(&self, circuit_id: &str) -> Result<String, FibError> {
+        Ok(prover::fingerprint(&self.entry(circuit_id)?.keys(self.params.as_ref())?.1))
+    }
+
+    pub fn contains(&self, circuit_id: &str) -> bool {
+        self.entries.contains_key(circuit_id)
+    }
+
+    // The k `circuit_id` was registered with
+    pub fn k(&self, circuit_id: &str) -> Option<u32> {
+        self.entries.get(circuit_id).map(|entry| entry.k)
+    }
+
+    fn entry(&self, circuit_id: &str) -> Result<&Entry, FibError> {
+        self.entries.get(circuit_id).ok_or_else(|| FibError::InvalidInput(format!("unknown circuit id {:?}", circuit_id)))
+    }
+
+    fn entry_mut(&mut self, circuit_id: &str) -> Result<&mut Entry, FibError> {
+        self.entries.get_mut(circuit_id).ok_or_else(|| FibError::InvalidInput(format!("unknown circuit id {:?}", circuit_id)))
+    }
+}
+
+// Checks a bundle against the registry, see the list at the top of the file. The metadata other than the circuit id
+// and k is informative only and is not checked
+#[must_use = "the bundle is only verified if the result is checked"]
+pub fn verify_bundle(bundle: &ProofBundle, registry: &Registry) -> Result<Verified, FibError> {
+    let (keys, verified) = check_bundle(bundle, registry)?;
+    let (params, vk) = keys.as_ref();
+    prover::verify_with_vk(params, vk, &bundle.instance, &bundle.proof)?;
+    Ok(verified)
+}
+
+// Steps 1 to 4 of the list, everything but the proof. Returns the keys to verify it with and what it proves once it
+// verifies, for the callers verifying proofs in other ways (e.g. in a batch, src/aggregate.rs)
+pub(crate) fn check_bundle(bundle: &ProofBundle, registry: &Registry) -> Result<(Keys, Verified), FibError> {
+    let circuit_id = &bundle.metadata.circuit_id;
+    let entry = registry.entry(circuit_id)?;
+
+    if bundle.metadata.k != entry.k {
+        return Err(FibError::InvalidInput(format!("{} is registered with k = {}, the bundle has k = {}", circuit_id, entry.k, bundle.metadata.k)));
+    }
+
+    let keys = entry.keys(registry.params.as_ref())?;
+    let fingerprint = prover::fingerprint(&keys.1);
+    if let Some(pinned) = &entry.fingerprint {
+        if *pinned != fingerprint {
+            return Err(FibError::KeyMismatch { circuit_id: circuit_id.clone(), expected: pinned.clone(), actual: fingerprint });
+        }
+    }
+
+    if bundle.instance.len() != entry.instance_len {
+        return Err(FibError::InvalidInput(format!(
+            "{} has {} public inputs, the bundle has {}",
+            circuit_id,
+            entry.instance_len,
+            bundle.instance.len()
+        )));
+    }
+
+    Ok((keys, Verified { circuit_id: circuit_id.clone(), k: entry.k, instance: bundle.instance.clone(), fingerprint }))
+}
@@ -0,0 +1,119 @@
+use std::fmt;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    dev::{MockProver, VerifyFailure},
+};
+
+use crate::{
+    annotate::{rename_columns, ColumnNames},
+    audit::ColumnId,
+};
+
+// The failures of a MockProver as a table, one line per failure: what failed, the gate or the copy that failed, where,
+// and the cells involved with their values. `assert_satisfied` prints the same information as a block per failure,
+// which gets hard to read beyond a couple of failures. The examples call `expect_satisfied` instead. A wrong output
+// of the Fibonacci circuit, as printed by `fib replay --verbose`:
+//
+//   failure   check  location                                    cells
+//   equality  copy   in Region 0 ('sequence table') at offset 8  column fib_b
+//   equality  copy   outside any region, on row 2                column instance
+//
+// The columns are named with the ColumnNames given to FailureReport::new (src/annotate.rs). `expect_satisfied` has no
+// names, and uses halo2's.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailureRow {
+    pub failure: &'static str,
+    pub check: String,
+    pub location: String,
+    pub cells: String,
+}
+
+impl FailureRow {
+    fn new(failure: &VerifyFailure, names: &ColumnNames) -> Self {
+        let (kind, check, location, cells) = match failure {
+            VerifyFailure::CellNotAssigned { gate, region, gate_offset, column, offset } => (
+                "not assigned",
+                gate.to_string(),
+                format!("in {} at offset {}", region, gate_offset),
+                match ColumnId::of(*column) {
+                    Ok(column) => format!("{} at offset {}", names.describe(column), offset),
+                    Err(_) => format!("{:?} at offset {}", column, offset),
+                },
+            ),
+            VerifyFailure::ConstraintNotSatisfied { constraint, location, cell_values } => (
+                "constraint",
+                constraint.to_string(),
+                location.to_string(),
+                cell_values
+                    .iter()
+                    .map(|(cell, value)| format!("{} = {}", rename_columns(&cell.to_string(), names), value))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            VerifyFailure::ConstraintPoisoned { constraint } => {
+                ("poisoned", constraint.to_string(), "an unusable row".to_string(), String::new())
+            }
+            VerifyFailure::Lookup { lookup_index, location } => {
+                ("lookup", format!("Lookup {}", lookup_index), location.to_string(), String::new())
+            }
+            VerifyFailure::Permutation { column, location } => {
+                ("equality", "copy".to_string(), location.to_string(), rename_columns(&column.to_string(), names))
+            }
+        };
+        Self { failure: kind, check, location, cells }
+    }
+}
+
+/// The failures of a MockProver, printed as a table.
+///
+/// ```
+/// use halo2_proofs::{dev::MockProver, pasta::Fp};
+/// use halo2_fibonacci_ex::{fibonacci::{FibCircuit, FibInputs}, report::FailureReport};
+///
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// let mut public_inputs = inputs.public_inputs();
+/// public_inputs[2] = Fp::from(56);
+///
+/// let failures = MockProver::run(4, &inputs.circuit(), vec![public_inputs]).unwrap().verify().unwrap_err();
+/// let report = FailureReport::new(&failures, &FibCircuit::<Fp>::column_names());
+/// assert_eq!(report.rows.len(), failures.len());
+/// assert!(report.to_string().lines().any(|line| line.starts_with("equality") && line.contains("column fib_b")));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailureReport {
+    pub rows: Vec<FailureRow>,
+}
+
+impl FailureReport {
+    pub fn new(failures: &[VerifyFailure], names: &ColumnNames) -> Self {
+        Self { rows: failures.iter().map(|failure| FailureRow::new(failure, names)).collect() }
+    }
+}
+
+impl fmt::Display for FailureReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failure = self.rows.iter().map(|row| row.failure.len()).max().unwrap_or(0).max("failure".len());
+        let check = self.rows.iter().map(|row| row.check.len()).max().unwrap_or(0).max("check".len());
+        let location = self.rows.iter().map(|row| row.location.len()).max().unwrap_or(0).max("location".len());
+        writeln!(f, "{:<failure$}  {:<check$}  {:<location$}  cells", "failure", "check", "location")?;
+        for row in &self.rows {
+            writeln!(f, "{:<failure$}  {:<check$}  {:<location$}  {}", row.failure, row.check, row.location, row.cells)?;
+        }
+        Ok(())
+    }
+}
+
+// `MockProver::assert_satisfied` with the failures printed as a FailureReport
+pub trait ExpectSatisfied {
+    fn expect_satisfied(&self);
+}
+
+impl<F: FieldExt> ExpectSatisfied for MockProver<F> {
+    fn expect_satisfied(&self) {
+        if let Err(failures) = self.verify() {
+            panic!("circuit was not satisfied:\n{}", FailureReport::new(&failures, &ColumnNames::new()));
+        }
+    }
+}
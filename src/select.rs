@@ -0,0 +1,150 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::{annotate::ColumnNames, boolean::bool_check};
+
+// A circuit can't branch: both sides of an "if" are computed and one of them is selected with a boolean,
+//
+//   out = cond * a + (1 - cond) * b,   cond * (1 - cond) = 0
+//
+// Without the boolean constraint cond = 2 would give out = 2a - b, any value the prover likes.
+// Every selection takes one row:
+//
+//   cond | a | b | out | q_select
+// --------------------------------
+//    c   | a | b | out |    1      <- cond, a and b copied from the cells given by the caller
+#[derive(Debug, Clone, Copy)]
+pub struct CondSelectConfig {
+    // cond, a, b, out
+    pub advice: [Column<Advice>; 4],
+    pub q_select: Selector,
+}
+
+impl CondSelectConfig {
+    // Names the columns in the diagnostics of the library (see src/annotate.rs)
+    pub fn annotate(&self, names: &mut ColumnNames) {
+        for (column, name) in self.advice.iter().zip(["cond", "a", "b", "out"]) {
+            names.annotate(*column, name);
+        }
+    }
+}
+
+/// Selects `a` if `cond` is 1 and `b` if `cond` is 0, constraining `cond` to be boolean.
+///
+/// ```
+/// use halo2_proofs::{circuit::*, dev::MockProver, pasta::Fp, plonk::*};
+/// use halo2_fibonacci_ex::select::{CondSelectChip, CondSelectConfig};
+///
+/// // Exposes cond ? a : b
+/// #[derive(Default)]
+/// struct MyCircuit {
+///     cond: Option<Fp>,
+///     a: Option<Fp>,
+///     b: Option<Fp>,
+/// }
+///
+/// impl Circuit<Fp> for MyCircuit {
+///     type Config = (CondSelectConfig, Column<Instance>);
+///     type FloorPlanner = SimpleFloorPlanner;
+///
+///     fn without_witnesses(&self) -> Self {
+///         Self::default()
+///     }
+///
+///     fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+///         let advice = [(); 4].map(|_| meta.advice_column());
+///         let instance = meta.instance_column();
+///         meta.enable_equality(instance);
+///         (CondSelectChip::configure(meta, advice), instance)
+///     }
+///
+///     fn synthesize(&self, (config, instance): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+///         let chip = CondSelectChip::construct(config);
+///         let [cond, a, b] = layouter.assign_region(|| "inputs", |mut region| {
+///             let cond = region.assign_advice(|| "cond", config.advice[0], 0, || self.cond.ok_or(Error::Synthesis))?;
+///             let a = region.assign_advice(|| "a", config.advice[1], 0, || self.a.ok_or(Error::Synthesis))?;
+///             let b = region.assign_advice(|| "b", config.advice[2], 0, || self.b.ok_or(Error::Synthesis))?;
+///             Ok([cond, a, b])
+///         })?;
+///         let out = chip.select(layouter.namespace(|| "select"), &cond, &a, &b)?;
+///         layouter.constrain_instance(out.cell(), instance, 0)
+///     }
+/// }
+///
+/// let circuit = |cond: u64| MyCircuit { cond: Some(Fp::from(cond)), a: Some(Fp::from(3)), b: Some(Fp::from(5)) };
+/// MockProver::run(4, &circuit(1), vec![vec![Fp::from(3)]]).unwrap().assert_satisfied();
+/// MockProver::run(4, &circuit(0), vec![vec![Fp::from(5)]]).unwrap().assert_satisfied();
+///
+/// // cond = 2 would give 2a - b = 1, but it isn't boolean
+/// assert!(MockProver::run(4, &circuit(2), vec![vec![Fp::from(1)]]).unwrap().verify().is_err());
+/// ```
+pub struct CondSelectChip<F: FieldExt> {
+    config: CondSelectConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> CondSelectChip<F> {
+    pub fn construct(config: CondSelectConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 4]) -> CondSelectConfig {
+        let [cond, a, b, out] = advice;
+        let q_select = meta.selector();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("conditional select", |meta| {
+            let s = meta.query_selector(q_select);
+            let one = Expression::Constant(F::one());
+            let cond = meta.query_advice(cond, Rotation::cur());
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+            Constraints::with_selector(
+                s,
+                [
+                    ("cond is boolean", bool_check(cond.clone())),
+                    ("out is a or b", cond.clone() * a + (one - cond) * b - out),
+                ],
+            )
+        });
+
+        CondSelectConfig { advice, q_select }
+    }
+
+    // Returns the cell of cond ? a : b. Without witnesses the output is unknown too
+    pub fn select(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cond: &AssignedCell<F, F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [col_cond, col_a, col_b, col_out] = self.config.advice;
+
+        layouter.assign_region(|| "select", |mut region| {
+            self.config.q_select.enable(&mut region, 0)?;
+            cond.copy_advice(|| "cond", &mut region, col_cond, 0)?;
+            a.copy_advice(|| "a", &mut region, col_a, 0)?;
+            b.copy_advice(|| "b", &mut region, col_b, 0)?;
+
+            let out = match (cond.value(), a.value(), b.value()) {
+                (Some(cond), Some(a), Some(b)) => Some(*cond * a + (F::one() - cond) * b),
+                _ => None,
+            };
+            region.assign_advice(|| "out", col_out, 0, || out.ok_or(Error::Synthesis))
+        })
+    }
+}
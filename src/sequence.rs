@@ -0,0 +1,440 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::{annotate::ColumnNames, planner::DefaultPlanner};
+
+// A tiny DSL for circuits proving that a sequence of states was computed by repeatedly applying a transition function.
+// Example3 does this by hand for Fibonacci: it creates the columns, writes the gate and assigns the table row by row.
+// Here you only describe the sequence and the chip generates the config, the gate and the assignment loop for you.
+//
+// The table has one advice column for each word of the state and one row for each state:
+//  state[0] | state[1] | ... | selector
+// ----------------------------------------
+//    x0     |    y0    | ... |    1       <- initial state
+//    x1     |    y1    | ... |    1
+//   ...     |   ...    | ... |   ...
+//    xn     |    yn    | ... |    0       <- last state
+// The selector is enabled on every row but the last one, and the transition constraints relate the current row to the next one.
+
+// Where a boundary constraint applies: the first state (the inputs) or the last state (the output)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundaryRow {
+    First,
+    Last,
+}
+
+// A boundary constraint binds a word of the first or last state to a row of the instance column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Boundary {
+    pub row: BoundaryRow,
+    pub word: usize,
+    pub instance_row: usize,
+}
+
+impl Boundary {
+    pub fn first(word: usize, instance_row: usize) -> Self {
+        Self { row: BoundaryRow::First, word, instance_row }
+    }
+
+    pub fn last(word: usize, instance_row: usize) -> Self {
+        Self { row: BoundaryRow::Last, word, instance_row }
+    }
+}
+
+// This is what a user of the DSL implements to describe a sequence
+pub trait Sequence<F: FieldExt> {
+    // Number of words in the state. Each word gets its own advice column
+    const WIDTH: usize;
+
+    // The constraints that must hold between two consecutive states, with the names they get in the failures and in
+    // the gates dump (dev::CircuitGates). Each returned expression must be equal to 0. There is no need to multiply
+    // them by the selector, the chip does it for you
+    fn transition(cur: &[Expression<F>], next: &[Expression<F>]) -> Vec<(&'static str, Expression<F>)>;
+
+    // The same transition computed natively, used to fill the witness
+    fn step(cur: &[F]) -> Vec<F>;
+
+    // The words of the first and last state that are public
+    fn boundaries() -> Vec<Boundary>;
+
+    // The names of the columns of the words in diagnostics (see src/annotate.rs)
+    fn word_names() -> Vec<String> {
+        (0..Self::WIDTH).map(|word| format!("state_{}", word)).collect()
+    }
+
+    // Run the sequence natively: returns all the states, from the initial one to the one after `steps` transitions
+    fn run(initial: &[F], steps: usize) -> Vec<Vec<F>> {
+        let mut states = vec![initial.to_vec()];
+        for _ in 0..steps {
+            let next = Self::step(states.last().unwrap());
+            states.push(next);
+        }
+        states
+    }
+
+    // Build the instance column expected by the circuit for the given initial state and number of steps
+    fn public_inputs(initial: &[F], steps: usize) -> Vec<F> {
+        let states = Self::run(initial, steps);
+        let boundaries = Self::boundaries();
+        let mut instance = vec![F::zero(); boundaries.iter().map(|b| b.instance_row + 1).max().unwrap_or(0)];
+        for boundary in boundaries {
+            let state = match boundary.row {
+                BoundaryRow::First => &states[0],
+                BoundaryRow::Last => &states[steps],
+            };
+            instance[boundary.instance_row] = state[boundary.word];
+        }
+        instance
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SequenceConfig {
+    pub state: Vec<Column<Advice>>,
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+    // The instance rows of the boundaries are relative to this row. It is 0 unless the instance column is shared
+    // with other chips (see InstanceAllocator in src/instance.rs)
+    pub instance_offset: usize,
+}
+
+impl SequenceConfig {
+    // Names the columns in the diagnostics of the library (see src/annotate.rs): the state columns after the words of
+    // the sequence (Sequence::word_names), and the instance column
+    pub fn annotate(&self, names: &mut ColumnNames, word_names: &[String]) {
+        for (column, name) in self.state.iter().zip(word_names) {
+            names.annotate(*column, name.as_str());
+        }
+        names.annotate(self.instance, "instance");
+    }
+}
+
+/// Generates the gate and the table of a [`Sequence`]. [`SequenceCircuit`] is a complete circuit built on top of it.
+///
+/// ```
+/// use halo2_proofs::{arithmetic::FieldExt, dev::MockProver, pasta::Fp, plonk::{Error, Expression}};
+/// use halo2_fibonacci_ex::sequence::{Boundary, Sequence, SequenceCircuit};
+///
+/// // Powers of two: the state is a single word that doubles at every step
+/// struct Doubling;
+///
+/// impl<F: FieldExt> Sequence<F> for Doubling {
+///     const WIDTH: usize = 1;
+///
+///     fn transition(cur: &[Expression<F>], next: &[Expression<F>]) -> Vec<(&'static str, Expression<F>)> {
+///         vec![("double", next[0].clone() - cur[0].clone() * Expression::Constant(F::from(2)))]
+///     }
+///
+///     fn step(cur: &[F]) -> Vec<F> {
+///         vec![cur[0].double()]
+///     }
+///
+///     fn boundaries() -> Vec<Boundary> {
+///         vec![Boundary::first(0, 0), Boundary::last(0, 1)]
+///     }
+/// }
+///
+/// let circuit = SequenceCircuit::<Fp, Doubling>::new(vec![Some(Fp::one())], 10);
+/// let public_inputs = Doubling::public_inputs(&[Fp::one()], 10);
+/// assert_eq!(public_inputs[1], Fp::from(1024));
+/// MockProver::run(5, &circuit, vec![public_inputs]).unwrap().assert_satisfied();
+///
+/// // The errors of the assignment surface: 20 steps don't fit in the usable rows of 2^4
+/// let circuit = SequenceCircuit::<Fp, Doubling>::new(vec![Some(Fp::one())], 20);
+/// let result = MockProver::run(4, &circuit, vec![Doubling::public_inputs(&[Fp::one()], 20)]);
+/// assert!(matches!(result, Err(Error::NotEnoughRowsAvailable { .. })));
+/// ```
+pub struct SequenceChip<F: FieldExt, S: Sequence<F>> {
+    config: SequenceConfig,
+    progress: Option<Progress>,
+    _marker: PhantomData<(F, S)>,
+}
+
+/// Called by the assignment loop of a [`SequenceChip`] after every transition, with the number of transitions
+/// assigned so far and the total, to report the progress of long sequences. The SimpleFloorPlanner runs the loop
+/// twice per synthesis (once to measure the region, once to assign it), and a proof runs the synthesis three times
+/// (verifying key, proving key, proof), so the count starts over a few times.
+///
+/// ```
+/// use std::sync::{atomic::{AtomicUsize, Ordering}, Arc};
+/// use halo2_proofs::{dev::MockProver, pasta::Fp};
+/// use halo2_fibonacci_ex::fibonacci::FibInputs;
+///
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// let assigned = Arc::new(AtomicUsize::new(0));
+/// let counter = assigned.clone();
+/// let circuit = inputs.circuit().with_progress(Arc::new(move |done, total| {
+///     assert!(done <= total);
+///     counter.fetch_add(1, Ordering::Relaxed);
+/// }));
+///
+/// MockProver::run(4, &circuit, vec![inputs.public_inputs()]).unwrap().assert_satisfied();
+/// assert_eq!(assigned.load(Ordering::Relaxed), 2 * 8);
+/// ```
+pub type Progress = Arc<dyn Fn(usize, usize) + Send + Sync>;
+
+impl<F: FieldExt, S: Sequence<F>> SequenceChip<F, S> {
+    pub fn construct(config: SequenceConfig) -> Self {
+        Self {
+            config,
+            progress: None,
+            _marker: PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub fn with_progress(self, progress: Progress) -> Self {
+        Self { progress: Some(progress), ..self }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> SequenceConfig {
+        let state: Vec<Column<Advice>> = (0..S::WIDTH).map(|_| meta.advice_column()).collect();
+        let selector = meta.selector();
+
+        for column in state.iter() {
+            meta.enable_equality(*column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("transition", |meta| {
+            let s = meta.query_selector(selector);
+            let cur: Vec<Expression<F>> = state.iter().map(|column| meta.query_advice(*column, Rotation::cur())).collect();
+            let next: Vec<Expression<F>> = state.iter().map(|column| meta.query_advice(*column, Rotation::next())).collect();
+
+            Constraints::with_selector(s, S::transition(&cur, &next))
+        });
+
+        SequenceConfig { state, selector, instance, instance_offset: 0 }
+    }
+
+    // The word names of the sequence for the state columns, and "instance"
+    pub fn column_names(config: &SequenceConfig) -> ColumnNames {
+        let mut names = ColumnNames::new();
+        config.annotate(&mut names, &S::word_names());
+        names
+    }
+
+    // Assign the whole table in a single region, as example3 does, and return the cells of the last state.
+    // The words of the first state that are public are copied from the instance column, the other ones come from `initial`.
+    pub fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        initial: &[Option<F>],
+        steps: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        self.assign_with_trace(layouter, initial, steps, None)
+    }
+
+    // Same as assign, but the states after the first one are read from `trace` (as returned by Sequence::run) instead
+    // of being computed with Sequence::step. A trace that doesn't match the inputs gives a proof that doesn't verify
+    pub fn assign_with_trace(
+        &self,
+        layouter: impl Layouter<F>,
+        initial: &[Option<F>],
+        steps: usize,
+        trace: Option<&[Vec<F>]>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        match trace {
+            Some(trace) if trace.len() != steps + 1 || trace.iter().any(|state| state.len() != S::WIDTH) => Err(Error::Synthesis),
+            Some(trace) => self.assign_states(layouter, initial, steps, || {
+                let mut states = trace[1..].iter();
+                move |_: Option<Vec<F>>| states.next().cloned()
+            }),
+            None => self.assign_states(layouter, initial, steps, || |cur: Option<Vec<F>>| cur.map(|cur| S::step(&cur))),
+        }
+    }
+
+    // Same as assign, but the states after the first one are pulled one at a time from the iterator returned by
+    // `states`, so that they never have to be in memory together. The region is assigned more than once by the floor
+    // planner, with a new iterator each time. An iterator that ends early or yields a state of the wrong width makes
+    // the synthesis fail
+    pub fn assign_streaming<I: Iterator<Item = Vec<F>>>(
+        &self,
+        layouter: impl Layouter<F>,
+        initial: &[Option<F>],
+        steps: usize,
+        states: impl Fn() -> I,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        self.assign_states(layouter, initial, steps, || {
+            let mut states = states();
+            move |_: Option<Vec<F>>| states.next().filter(|state| state.len() == S::WIDTH)
+        })
+    }
+
+    // The assignment loop shared by the assign functions. `next` gives, for each pass of the floor planner, a function
+    // returning the state after the current one (None when it is unknown, e.g. without witnesses)
+    fn assign_states<N: FnMut(Option<Vec<F>>) -> Option<Vec<F>>>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        initial: &[Option<F>],
+        steps: usize,
+        next: impl Fn() -> N,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let config = &self.config;
+        let boundaries = S::boundaries();
+
+        layouter.assign_region(
+            || "sequence table",
+            |mut region| {
+                let mut next_state = next();
+                let mut cells = Vec::with_capacity(S::WIDTH);
+                for (word, column) in config.state.iter().enumerate() {
+                    let public = boundaries.iter().find(|b| b.row == BoundaryRow::First && b.word == word);
+                    let cell = match public {
+                        Some(boundary) => region.assign_advice_from_instance(
+                            || format!("state {}", word),
+                            config.instance,
+                            config.instance_offset + boundary.instance_row,
+                            *column,
+                            0,
+                        )?,
+                        None => region.assign_advice(
+                            || format!("state {}", word),
+                            *column,
+                            0,
+                            || initial[word].ok_or(Error::Synthesis),
+                        )?,
+                    };
+                    cells.push(cell);
+                }
+
+                for row in 1..=steps {
+                    // The transition between row - 1 and row
+                    config.selector.enable(&mut region, row - 1)?;
+
+                    let cur: Option<Vec<F>> = cells.iter().map(|cell| cell.value().copied()).collect();
+                    let next = next_state(cur);
+
+                    cells = config
+                        .state
+                        .iter()
+                        .enumerate()
+                        .map(|(word, column)| {
+                            region.assign_advice(
+                                || format!("state {}", word),
+                                *column,
+                                row,
+                                || next.as_ref().map(|next| next[word]).ok_or(Error::Synthesis),
+                            )
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+
+                    if let Some(progress) = &self.progress {
+                        progress(row, steps);
+                    }
+                }
+
+                Ok(cells)
+            },
+        )
+    }
+
+    // Enforce the boundary constraints on the last state
+    pub fn expose_last(&self, mut layouter: impl Layouter<F>, last: &[AssignedCell<F, F>]) -> Result<(), Error> {
+        for boundary in S::boundaries().iter().filter(|b| b.row == BoundaryRow::Last) {
+            layouter.constrain_instance(last[boundary.word].cell(), self.config.instance, self.config.instance_offset + boundary.instance_row)?;
+        }
+        Ok(())
+    }
+}
+
+// A ready to use circuit for any Sequence: the number of steps is part of the circuit shape, the initial state is the witness.
+// `trace` optionally holds the states precomputed by Sequence::run (see WitnessCache in src/witness.rs), `stream`
+// optionally produces them one at a time instead (SequenceChip::assign_streaming), and `progress` is given to the
+// chip to report the rows assigned
+pub struct SequenceCircuit<F: FieldExt, S: Sequence<F>> {
+    pub initial: Vec<Option<F>>,
+    pub steps: usize,
+    pub trace: Option<Vec<Vec<F>>>,
+    pub stream: Option<StateStream<F>>,
+    pub progress: Option<Progress>,
+    _marker: PhantomData<S>,
+}
+
+impl<F: FieldExt, S: Sequence<F>> SequenceCircuit<F, S> {
+    pub fn new(initial: Vec<Option<F>>, steps: usize) -> Self {
+        assert_eq!(initial.len(), S::WIDTH);
+        Self {
+            initial,
+            steps,
+            trace: None,
+            stream: None,
+            progress: None,
+            _marker: PhantomData,
+        }
+    }
+
+    // The names of the columns of the circuit, from a fresh configuration: the columns are the same in every one
+    pub fn column_names() -> ColumnNames {
+        let config = Self::configure(&mut ConstraintSystem::default());
+        SequenceChip::<F, S>::column_names(&config)
+    }
+
+    pub fn with_trace(initial: Vec<Option<F>>, steps: usize, trace: Vec<Vec<F>>) -> Self {
+        Self { trace: Some(trace), ..Self::new(initial, steps) }
+    }
+
+    pub fn with_stream(initial: Vec<Option<F>>, steps: usize, stream: StateStream<F>) -> Self {
+        Self { stream: Some(stream), ..Self::new(initial, steps) }
+    }
+
+    #[must_use]
+    pub fn with_progress(self, progress: Progress) -> Self {
+        Self { progress: Some(progress), ..self }
+    }
+}
+
+/// A source of the states of a sequence after the initial one, for [`SequenceCircuit::with_stream`]: each call
+/// returns a new iterator over them, so that the witness takes the same memory at any number of rows.
+///
+/// ```
+/// use std::{iter, sync::Arc};
+/// use halo2_proofs::{dev::MockProver, pasta::Fp};
+/// use halo2_fibonacci_ex::{fibonacci::{FibCircuit, Fibonacci}, sequence::{Sequence, StateStream}};
+///
+/// // The states computed as they are needed, from a generator that knows nothing of the library
+/// let stream: StateStream<Fp> = Arc::new(|| {
+///     let states = iter::successors(Some((Fp::one(), Fp::one())), |&(a, b)| Some((b, a + b)));
+///     Box::new(states.skip(1).map(|(a, b)| vec![a, b]))
+/// });
+/// let circuit = FibCircuit::with_stream(vec![Some(Fp::one()), Some(Fp::one())], 8, stream.clone());
+/// let instance = Fibonacci::public_inputs(&[Fp::one(), Fp::one()], 8);
+/// MockProver::run(4, &circuit, vec![instance.clone()]).unwrap().assert_satisfied();
+///
+/// // A stream that ends too early can't fill the table
+/// let circuit = FibCircuit::with_stream(vec![Some(Fp::one()), Some(Fp::one())], 8, Arc::new(move || Box::new(stream().take(4))));
+/// assert!(MockProver::run(4, &circuit, vec![instance]).is_err());
+/// ```
+pub type StateStream<F> = Arc<dyn Fn() -> Box<dyn Iterator<Item = Vec<F>>> + Send + Sync>;
+
+impl<F: FieldExt, S: Sequence<F>> Circuit<F> for SequenceCircuit<F, S> {
+    type Config = SequenceConfig;
+    type FloorPlanner = DefaultPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(vec![None; S::WIDTH], self.steps)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        SequenceChip::<F, S>::configure(meta, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let mut chip = SequenceChip::<F, S>::construct(config);
+        if let Some(progress) = &self.progress {
+            chip = chip.with_progress(progress.clone());
+        }
+        let last = match &self.stream {
+            Some(stream) => chip.assign_streaming(layouter.namespace(|| "table"), &self.initial, self.steps, || stream())?,
+            None => chip.assign_with_trace(layouter.namespace(|| "table"), &self.initial, self.steps, self.trace.as_deref())?,
+        };
+        chip.expose_last(layouter.namespace(|| "output"), &last)
+    }
+}
@@ -0,0 +1,211 @@
+use std::{net::SocketAddr, sync::Arc, thread, time::Duration};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    bundle::ProofBundle,
+    error::FibError,
+    jobs::{JobId, JobQueue},
+    params::ParamsCache,
+    prover::{CancellationToken, Limits},
+    service::Service,
+};
+
+// The prover and the verifier of the Fibonacci circuit as an HTTP service, with the `http` feature. The fib-server
+// binary runs it, and so does `fib serve` with the same options:
+//
+//   cargo run --release --features http --bin fib-server -- --steps 8 --steps 100
+//   cargo run --release --features http --bin fib -- serve --steps 8 --steps 100
+//
+//   curl -d '{"n": 8, "a": "1", "b": "1"}' -H 'content-type: application/json' localhost:8080/prove > proof.json
+//   curl -d @proof.json -H 'content-type: application/json' localhost:8080/verify
+//
+// POST /prove takes the public inputs {"n", "a", "b"}, the field elements as strings read by field::parse_field, and
+// returns the proof bundle (src/bundle.rs), the same JSON `fib prove` writes. POST /verify takes a bundle and returns
+// {"valid": true, "circuit_id", "k", "fingerprint"}, or {"valid": false} for a proof that doesn't verify. The other
+// errors are the JSON of FibError::to_json, with a 4xx status for the errors of the request and 5xx for the others.
+//
+// The circuits are fixed at startup, one per --steps, over the smallest 2^k rows they fit in unless -k is given. Their
+// keys are generated then, before the service listens (see src/service.rs). Proofs run on the blocking thread pool of
+// tokio, one thread per proof. A client that goes away, or a request that outlives --timeout (a 504), cancels its
+// proof: one still waiting for a thread never starts, one that started stops at the next of its stages.
+//
+// At large k a proof takes longer than clients and proxies wait for a response. With --daemon, proofs are jobs
+// (src/jobs.rs) run by a pool of --workers threads: POST /prove returns 202 and {"job": id} at once, and
+// GET /jobs/{id} returns {"state": "queued", "position"}, {"state": "running"}, {"state": "done", "bundle"} or
+// {"state": "failed", "error"}. When --queue jobs are already waiting, POST /prove returns 503, and a job that runs
+// for longer than --timeout fails with a timeout.
+//
+//   curl localhost:8080/jobs/0
+
+// The options of the service, shared by fib-server and `fib serve`
+#[derive(Debug, clap::Args)]
+pub struct ServeArgs {
+    /// The address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub addr: SocketAddr,
+
+    /// Serve the circuit with this many steps. Repeat for several circuits
+    #[arg(long = "steps", required = true)]
+    pub steps: Vec<usize>,
+
+    /// Rows of the circuits are 2^k. Defaults to the smallest k each circuit fits in
+    #[arg(short, long)]
+    pub k: Option<u32>,
+
+    /// Run the proofs as background jobs, polled with GET /jobs/{id}
+    #[arg(long)]
+    pub daemon: bool,
+
+    /// The number of proofs run at once with --daemon. Defaults to the number of CPUs
+    #[arg(long)]
+    pub workers: Option<usize>,
+
+    /// The number of jobs that can wait with --daemon
+    #[arg(long, default_value_t = 64)]
+    pub queue: usize,
+
+    /// Give up on a proof after this many seconds: a POST /prove answers 504, a job with --daemon fails with a timeout
+    #[arg(long, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+}
+
+struct AppState {
+    service: Arc<Service>,
+    timeout: Option<Duration>,
+    // With --daemon
+    jobs: Option<JobQueue>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProveRequest {
+    n: usize,
+    a: String,
+    b: String,
+}
+
+// A FibError as a response
+struct ApiError(FibError);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self.0 {
+            FibError::InvalidInput(_) | FibError::Parse(_) | FibError::LimitExceeded(_) => StatusCode::BAD_REQUEST,
+            FibError::KeyMismatch { .. } | FibError::Unsatisfied(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            FibError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self.0.to_json())).into_response()
+    }
+}
+
+impl From<FibError> for ApiError {
+    fn from(e: FibError) -> Self {
+        ApiError(e)
+    }
+}
+
+// Runs `f` on the blocking thread pool, where the prover doesn't stall the requests served meanwhile
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T, FibError> + Send + 'static) -> Result<T, FibError> {
+    tokio::task::spawn_blocking(f).await.map_err(|e| FibError::Internal(format!("the task didn't complete: {}", e)))?
+}
+
+// The bodies are parsed by hand rather than by the Json extractor, so that a malformed one is a FibError as well
+async fn prove(State(state): State<Arc<AppState>>, body: String) -> Result<Response, ApiError> {
+    let request: ProveRequest = serde_json::from_str(&body).map_err(|e| FibError::Parse(format!("request: {}", e)))?;
+    if let Some(jobs) = &state.jobs {
+        return match jobs.submit(request.n, &request.a, &request.b) {
+            Ok(id) => Ok((StatusCode::ACCEPTED, Json(json!({ "job": id }))).into_response()),
+            // The queue is full, the same request can succeed later
+            Err(e @ FibError::LimitExceeded(_)) => Ok((StatusCode::SERVICE_UNAVAILABLE, Json(e.to_json())).into_response()),
+            Err(e) => Err(e.into()),
+        };
+    }
+    let service = state.service.clone();
+    // axum drops the future of a request whose client went away, and the timeout drops it as well
+    let token = CancellationToken::new();
+    let _cancel = token.cancel_on_drop();
+    let proof = blocking(move || service.prove(request.n, &request.a, &request.b, &token));
+    let bundle = match state.timeout {
+        Some(timeout) => tokio::time::timeout(timeout, proof).await.map_err(|_| FibError::Timeout(timeout))??,
+        None => proof.await?,
+    };
+    Ok(Json(bundle).into_response())
+}
+
+async fn job(State(state): State<Arc<AppState>>, Path(id): Path<JobId>) -> Result<Response, ApiError> {
+    let jobs = state.jobs.as_ref().ok_or_else(|| FibError::InvalidInput("jobs only exist with --daemon".to_string()))?;
+    match jobs.status(id) {
+        Some(status) => Ok(Json(status).into_response()),
+        None => Ok((StatusCode::NOT_FOUND, Json(FibError::InvalidInput(format!("unknown job {}", id)).to_json())).into_response()),
+    }
+}
+
+async fn verify(State(state): State<Arc<AppState>>, body: String) -> Result<Json<Value>, ApiError> {
+    let bundle = ProofBundle::from_json(&body)?;
+    let service = state.service.clone();
+    match blocking(move || service.verify(&bundle)).await {
+        Ok(verified) => Ok(Json(json!({
+            "valid": true,
+            "circuit_id": verified.circuit_id,
+            "k": verified.k,
+            "fingerprint": verified.fingerprint,
+        }))),
+        Err(FibError::InvalidProof) => Ok(Json(json!({ "valid": false }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Generates the keys of the circuits of `args`, loading the parameters from `cache` when there is one, and serves
+// them until Ctrl-C. No circuit can be larger than 2^max_k rows
+pub async fn serve(args: ServeArgs, cache: Option<ParamsCache>, max_k: u32) -> Result<(), FibError> {
+    let mut service = Service::new(&args.steps, args.k, cache)?;
+    service.set_limits(Limits { max_k, ..service.limits() });
+    for n in service.steps() {
+        let key = service.verifying_key(n)?;
+        eprintln!("serving {} over 2^{} rows, verifying key {}", key.circuit_id, key.k, key.fingerprint);
+    }
+
+    let service = Arc::new(service);
+    let timeout = args.timeout.map(Duration::from_secs);
+    let jobs = args.daemon.then(|| {
+        let workers = args.workers.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+        eprintln!("proving as jobs on {} workers", workers);
+        JobQueue::new(service.clone(), workers, args.queue, timeout)
+    });
+
+    let app = Router::new()
+        .route("/prove", post(prove))
+        .route("/verify", post(verify))
+        .route("/jobs/:id", get(job))
+        .with_state(Arc::new(AppState { service, timeout, jobs }));
+    let listener = tokio::net::TcpListener::bind(args.addr).await.map_err(|e| FibError::io(args.addr.to_string(), e))?;
+    eprintln!("listening on {}", args.addr);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            // Ctrl-C finishes the requests in flight. Without a handler, it stops the process as usual
+            if tokio::signal::ctrl_c().await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        })
+        .await
+        .map_err(|e| FibError::Internal(e.to_string()))
+}
+
+// `serve` on a runtime of its own, for the callers that don't run one (`fib serve`)
+pub fn serve_blocking(args: ServeArgs, cache: Option<ParamsCache>, max_k: u32) -> Result<(), FibError> {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| FibError::Internal(format!("can't start the runtime: {}", e)))?
+        .block_on(serve(args, cache, max_k))
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_pk, keygen_vk, Circuit, ProvingKey},
+    poly::commitment::Params,
+};
+
+use crate::{
+    backend::IpaPasta,
+    bundle::{Metadata, ProofBundle},
+    error::FibError,
+    fibonacci::{max_steps, FibInputs},
+    field::parse_field,
+    params::ParamsCache,
+    prover::{self, CancellationToken, Limits},
+    registry::{verify_bundle, Registry, Verified},
+};
+
+// The state of a long-running prover, shared by the services built on the library (the fib-server and fib-grpc
+// binaries): the keys of a fixed set of Fibonacci circuits, generated once when the service starts so that no request
+// pays for a keygen. Proofs are created with the proving keys kept here, and verified through a Registry like every
+// other verifier of the library, with the verifying keys generated for the provers.
+//
+// The methods block for as long as a proof or a verification takes. The services call them from a thread of their
+// own (e.g. tokio's spawn_blocking), and share the Service between those threads behind an Arc. A proof goes through
+// prover::prove_with_keys, so it stops before it starts once its token is cancelled, e.g. because the client of the
+// request went away while the proof waited for a thread, and obeys the Limits of the service.
+
+// The keys of a circuit
+struct Prover {
+    k: u32,
+    params: Params<EqAffine>,
+    pk: ProvingKey<EqAffine>,
+}
+
+// What a client needs to know about the verifying key of a circuit. halo2_proofs 0.1.0 can't serialize a verifying
+// key, `pinned` is its pinned representation as text, which prover::fingerprint hashes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyingKeyInfo {
+    pub circuit_id: String,
+    pub k: u32,
+    pub fingerprint: String,
+    pub pinned: String,
+}
+
+/// Proves and verifies the Fibonacci circuits with the given numbers of steps.
+///
+/// ```
+/// use halo2_fibonacci_ex::{error::FibError, prover::CancellationToken, service::Service};
+///
+/// // 8 steps over the smallest k they fit in
+/// let service = Service::new(&[8], None, None).unwrap();
+/// let token = CancellationToken::new();
+/// let bundle = service.prove(8, "1", "1", &token).unwrap();
+/// assert_eq!(bundle.metadata.k, 4);
+/// let verified = service.verify(&bundle).unwrap();
+/// assert_eq!(verified.fingerprint, service.verifying_key(8).unwrap().fingerprint);
+///
+/// assert!(matches!(service.prove(9, "1", "1", &token), Err(FibError::InvalidInput(_))));
+///
+/// token.cancel();
+/// assert!(matches!(service.prove(8, "1", "1", &token), Err(FibError::Cancelled)));
+/// ```
+pub struct Service {
+    // Keyed by the number of steps
+    provers: HashMap<usize, Prover>,
+    registry: Registry,
+    limits: Limits,
+}
+
+impl Service {
+    // Generates the keys of the circuits with `steps` steps over 2^k rows, or over the smallest k each fits in. The
+    // parameters come from `cache` when there is one
+    pub fn new(steps: &[usize], k: Option<u32>, cache: Option<ParamsCache>) -> Result<Self, FibError> {
+        let limits = Limits::default();
+        let mut registry = Registry::new();
+        if let Some(cache) = &cache {
+            registry.set_params_cache(cache.clone());
+        }
+        let mut provers = HashMap::new();
+        for &n in steps {
+            let k = match k {
+                Some(k) => k,
+                None => (1..=limits.max_k)
+                    .find(|&k| max_steps::<Fp>(k) >= n)
+                    .ok_or_else(|| FibError::LimitExceeded(format!("{} steps don't fit in 2^{} rows", n, limits.max_k)))?,
+            };
+            limits.check_k(k)?;
+            let inputs = FibInputs::new(Fp::zero(), Fp::zero(), n);
+            let circuit = inputs.circuit().without_witnesses();
+            let params = match &cache {
+                Some(cache) => cache.load_or_setup::<IpaPasta>(k)?,
+                None => Params::new(k),
+            };
+            let vk = keygen_vk(&params, &circuit)?;
+            let pk = keygen_pk(&params, vk.clone(), &circuit)?;
+            registry.register_fibonacci(k, n);
+            // The verifier uses the same keys rather than generating them on the first verification
+            registry.set_keys(&inputs.circuit_id(), params.clone(), vk)?;
+            provers.insert(n, Prover { k, params, pk });
+        }
+        Ok(Self { provers, registry, limits })
+    }
+
+    // The limits of every proof, Limits::default() until set. The timeout counts from the start of Service::prove
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    // The numbers of steps of the circuits served, in increasing order
+    pub fn steps(&self) -> Vec<usize> {
+        let mut steps: Vec<usize> = self.provers.keys().copied().collect();
+        steps.sort_unstable();
+        steps
+    }
+
+    fn prover(&self, n: usize) -> Result<&Prover, FibError> {
+        self.provers.get(&n).ok_or_else(|| FibError::InvalidInput(format!("no circuit with {} steps is served", n)))
+    }
+
+    // Proves that n steps starting from (a, b), strings read by field::parse_field, reach their output
+    pub fn prove(&self, n: usize, a: &str, b: &str, token: &CancellationToken) -> Result<ProofBundle, FibError> {
+        self.prove_with_limits(n, a, b, token, &self.limits)
+    }
+
+    // The same under other limits than those of the service, e.g. the timeout of a job (src/jobs.rs)
+    pub fn prove_with_limits(
+        &self,
+        n: usize,
+        a: &str,
+        b: &str,
+        token: &CancellationToken,
+        limits: &Limits,
+    ) -> Result<ProofBundle, FibError> {
+        let prover = self.prover(n)?;
+        let inputs = FibInputs::new(parse_field(a)?, parse_field(b)?, n);
+        let instance = inputs.public_inputs();
+        let proof = prover::prove_with_keys(prover.k, &prover.params, &prover.pk, &inputs.circuit(), &instance, token, limits)?;
+        Ok(ProofBundle::new(Metadata::new(prover.k, inputs.circuit_id()), instance, proof))
+    }
+
+    pub fn verify(&self, bundle: &ProofBundle) -> Result<Verified, FibError> {
+        verify_bundle(bundle, &self.registry)
+    }
+
+    pub fn verifying_key(&self, n: usize) -> Result<VerifyingKeyInfo, FibError> {
+        let prover = self.prover(n)?;
+        let vk = prover.pk.get_vk();
+        Ok(VerifyingKeyInfo {
+            circuit_id: FibInputs::new(Fp::zero(), Fp::zero(), n).circuit_id(),
+            k: prover.k,
+            fingerprint: prover::fingerprint(vk),
+            pinned: format!("{:?}", vk.pinned()),
+        })
+    }
+}
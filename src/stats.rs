@@ -0,0 +1,246 @@
+use std::fmt;
+
+use halo2_proofs::{arithmetic::FieldExt, plonk::*};
+
+use crate::{
+    annotate::ColumnNames,
+    audit::{self, ColumnId},
+    error::FibError,
+};
+
+// How much of the circuit every region takes: the rows it spans, the cells it assigns and the copy constraints it
+// creates. The synthesis runs against the recorder of the equality audit (src/audit.rs), which keeps no value, so
+// collecting the statistics is cheap compared to the MockProver. The fib tool prints them with --verbose:
+//
+//   fib prove -k 4 -n 8 -o proof.json --verbose
+//   region                   rows  advice   fixed  selectors  copies
+//   sequence table              9      18       0          8       2
+//   <outside of a region>       0       0       0          0       1
+//   total                       9      18       0          8       3
+//
+//   column                  cells
+//   column fib_a                9
+//   column fib_b                9
+//
+// The columns are named with the ColumnNames given to with_names (src/annotate.rs).
+//
+// utilization compares the rows a circuit uses with the 2^k rows it is given. The prover reserves the last
+// blinding_factors + 1 rows of every column (6 for the circuits of the library), so a table of 10 rows fits in
+// k = 4, but 11 rows, one more step, don't and give NotEnoughRowsAvailable. `fib rows` prints it for a statement:
+//
+//   fib rows -k 4 -n 8
+//   k = 4: 9 of 16 rows used (56%), 6 reserved by the prover, 1 to spare
+//   smallest k: 4
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionStats {
+    pub name: String,
+    // The absolute rows of the first and the last cell, if the region assigns anything
+    pub first_row: Option<usize>,
+    pub last_row: Option<usize>,
+    pub advice_cells: usize,
+    pub fixed_cells: usize,
+    pub selectors: usize,
+    pub copies: usize,
+}
+
+impl RegionStats {
+    pub(crate) fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), first_row: None, last_row: None, advice_cells: 0, fixed_cells: 0, selectors: 0, copies: 0 }
+    }
+
+    pub(crate) fn touch(&mut self, row: usize) {
+        self.first_row = Some(self.first_row.map_or(row, |first| first.min(row)));
+        self.last_row = Some(self.last_row.map_or(row, |last| last.max(row)));
+    }
+
+    pub fn rows(&self) -> usize {
+        match (self.first_row, self.last_row) {
+            (Some(first), Some(last)) => last - first + 1,
+            _ => 0,
+        }
+    }
+}
+
+/// The statistics of every region of a circuit, in the order they were assigned.
+///
+/// ```
+/// use halo2_proofs::pasta::Fp;
+/// use halo2_fibonacci_ex::{fibonacci::{FibCircuit, FibInputs}, stats::circuit_stats};
+///
+/// // 8 steps take 9 rows of 2 advice cells, and the boundaries add 3 copies to the instance column
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// let stats = circuit_stats(&inputs.circuit(), vec![inputs.public_inputs()]).unwrap();
+/// assert_eq!(stats.rows(), 9);
+/// assert_eq!(stats.advice_cells(), 18);
+///
+/// let stats = stats.with_names(FibCircuit::<Fp>::column_names());
+/// assert!(stats.to_string().contains("column fib_a"));
+/// println!("{}", stats);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitStats {
+    pub regions: Vec<RegionStats>,
+    // The cells assigned in every advice and fixed column, in the order the columns were first assigned
+    pub columns: Vec<(ColumnId, usize)>,
+    // Used by Display only
+    pub names: ColumnNames,
+}
+
+impl CircuitStats {
+    pub fn with_names(self, names: ColumnNames) -> Self {
+        Self { names, ..self }
+    }
+
+    // The rows used by the circuit, from row 0 to the last assigned one
+    pub fn rows(&self) -> usize {
+        self.regions.iter().filter_map(|region| region.last_row).max().map_or(0, |last| last + 1)
+    }
+
+    pub fn advice_cells(&self) -> usize {
+        self.regions.iter().map(|region| region.advice_cells).sum()
+    }
+
+    pub fn fixed_cells(&self) -> usize {
+        self.regions.iter().map(|region| region.fixed_cells).sum()
+    }
+
+    pub fn selectors(&self) -> usize {
+        self.regions.iter().map(|region| region.selectors).sum()
+    }
+
+    pub fn copies(&self) -> usize {
+        self.regions.iter().map(|region| region.copies).sum()
+    }
+}
+
+impl fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.regions.iter().map(|region| region.name.len()).max().unwrap_or(0).max("region".len());
+        writeln!(f, "{:<width$}  {:>6}  {:>6}  {:>6}  {:>9}  {:>6}", "region", "rows", "advice", "fixed", "selectors", "copies")?;
+        for region in &self.regions {
+            writeln!(
+                f,
+                "{:<width$}  {:>6}  {:>6}  {:>6}  {:>9}  {:>6}",
+                region.name,
+                region.rows(),
+                region.advice_cells,
+                region.fixed_cells,
+                region.selectors,
+                region.copies
+            )?;
+        }
+        writeln!(
+            f,
+            "{:<width$}  {:>6}  {:>6}  {:>6}  {:>9}  {:>6}",
+            "total",
+            self.rows(),
+            self.advice_cells(),
+            self.fixed_cells(),
+            self.selectors(),
+            self.copies()
+        )?;
+
+        let columns: Vec<(String, usize)> = self.columns.iter().map(|(column, cells)| (self.names.describe(*column), *cells)).collect();
+        let width = columns.iter().map(|(name, _)| name.len()).max().unwrap_or(0).max(width);
+        writeln!(f)?;
+        writeln!(f, "{:<width$}  {:>6}", "column", "cells")?;
+        for (name, cells) in columns {
+            writeln!(f, "{:<width$}  {:>6}", name, cells)?;
+        }
+        Ok(())
+    }
+}
+
+// Runs the synthesis of `circuit` and collects the statistics of its regions. `instance` is only used to answer the
+// instance queries of the circuit
+pub fn circuit_stats<F: FieldExt, C: Circuit<F>>(circuit: &C, instance: Vec<Vec<F>>) -> Result<CircuitStats, FibError> {
+    let (recorder, _) = audit::record(circuit, instance)?;
+    Ok(CircuitStats { regions: recorder.regions().to_vec(), columns: recorder.cells().to_vec(), names: ColumnNames::new() })
+}
+
+/// The rows a circuit uses out of the 2^k rows of the circuit, and the smallest k it fits in.
+///
+/// ```
+/// use halo2_proofs::pasta::Fp;
+/// use halo2_fibonacci_ex::{fibonacci::FibInputs, stats::utilization};
+///
+/// // 9 rows fit in the 16 - 6 usable rows of k = 4, and so would 10
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// let report = utilization(&inputs.circuit(), vec![inputs.public_inputs()], 4).unwrap();
+/// assert_eq!((report.rows_used, report.reserved_rows, report.min_k), (9, 6, 4));
+/// assert!(report.fits());
+///
+/// // One more step doesn't
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 10);
+/// let report = utilization(&inputs.circuit(), vec![inputs.public_inputs()], 4).unwrap();
+/// assert!(!report.fits());
+/// assert_eq!(report.min_k, 5);
+/// println!("{}", report);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Utilization {
+    pub k: u32,
+    // The rows of the regions, or of the instance columns if they are longer
+    pub rows_used: usize,
+    // The last blinding_factors + 1 rows, which the prover fills with random values
+    pub reserved_rows: usize,
+    // The smallest k whose usable rows hold rows_used, and large enough for the blinding rows
+    pub min_k: u32,
+}
+
+impl Utilization {
+    pub fn rows(&self) -> usize {
+        1 << self.k
+    }
+
+    pub fn usable_rows(&self) -> usize {
+        self.rows().saturating_sub(self.reserved_rows)
+    }
+
+    pub fn fits(&self) -> bool {
+        self.k >= self.min_k
+    }
+
+    // rows_used / 2^k
+    pub fn ratio(&self) -> f64 {
+        self.rows_used as f64 / self.rows() as f64
+    }
+}
+
+impl fmt::Display for Utilization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "k = {}: {} of {} rows used ({:.0}%), {} reserved by the prover, ",
+            self.k,
+            self.rows_used,
+            self.rows(),
+            self.ratio() * 100.0,
+            self.reserved_rows
+        )?;
+        if self.fits() {
+            writeln!(f, "{} to spare", self.usable_rows() - self.rows_used)?;
+        } else {
+            writeln!(f, "{} missing", self.rows_used - self.usable_rows())?;
+        }
+        writeln!(f, "smallest k: {}", self.min_k)
+    }
+}
+
+// Runs the synthesis of `circuit` like circuit_stats and compares the rows it uses with 2^k
+pub fn utilization<F: FieldExt, C: Circuit<F>>(circuit: &C, instance: Vec<Vec<F>>, k: u32) -> Result<Utilization, FibError> {
+    let mut meta = ConstraintSystem::<F>::default();
+    C::configure(&mut meta);
+    let reserved_rows = meta.blinding_factors() + 1;
+
+    let instance_rows = instance.iter().map(Vec::len).max().unwrap_or(0);
+    let rows_used = circuit_stats(circuit, instance)?.rows().max(instance_rows);
+    let min_k = (1..usize::BITS)
+        .find(|k| {
+            let rows = 1usize << k;
+            rows >= meta.minimum_rows() && rows - reserved_rows >= rows_used
+        })
+        .ok_or_else(|| FibError::LimitExceeded(format!("{} rows don't fit in any k", rows_used)))?;
+    Ok(Utilization { k, rows_used, reserved_rows, min_k })
+}
@@ -0,0 +1,54 @@
+use halo2_proofs::{arithmetic::FieldExt, pasta::Fp};
+use proptest::{collection::SizeRange, prelude::*};
+
+use crate::fibonacci::FibInputs;
+
+// Proptest strategies for the inputs of circuits built on the library, behind the `testing` feature. Circuits
+// that are tested with the same strategies are tested on the same kind of inputs: uniform field elements would
+// almost never hit the values where chips break (0, 1, small integers, values just below the modulus), so arb_fp
+// draws those as often as uniform ones.
+
+/// A field element: small, just below the modulus or uniform, each a third of the time.
+///
+/// ```
+/// use halo2_proofs::dev::MockProver;
+/// use proptest::prelude::*;
+/// use halo2_fibonacci_ex::testing::{arb_fib_inputs, arb_instance_vec};
+///
+/// proptest! {
+///     #![proptest_config(ProptestConfig::with_cases(8))]
+///     fn fibonacci_accepts_only_its_native_output(inputs in arb_fib_inputs(8), forged in arb_instance_vec(3)) {
+///         let prover = MockProver::run(4, &inputs.circuit(), vec![inputs.public_inputs()]).unwrap();
+///         prop_assert!(prover.verify().is_ok());
+///
+///         prop_assume!(forged != inputs.public_inputs());
+///         let prover = MockProver::run(4, &inputs.circuit(), vec![forged]).unwrap();
+///         prop_assert!(prover.verify().is_err());
+///     }
+/// }
+///
+/// fibonacci_accepts_only_its_native_output();
+/// ```
+pub fn arb_fp() -> impl Strategy<Value = Fp> {
+    prop_oneof![
+        (0..16u64).prop_map(Fp::from),
+        (1..16u64).prop_map(|x| -Fp::from(x)),
+        (any::<[u8; 32]>(), any::<[u8; 32]>()).prop_map(|(lo, hi)| {
+            let mut bytes = [0u8; 64];
+            bytes[..32].copy_from_slice(&lo);
+            bytes[32..].copy_from_slice(&hi);
+            Fp::from_bytes_wide(&bytes)
+        }),
+    ]
+}
+
+// Inputs of the Fibonacci circuit with up to max_n steps. Use fibonacci::max_steps(k) to stay within 2^k rows
+pub fn arb_fib_inputs(max_n: usize) -> impl Strategy<Value = FibInputs<Fp>> {
+    (arb_fp(), arb_fp(), 0..=max_n).prop_map(|(a, b, n)| FibInputs::new(a, b, n))
+}
+
+// An instance column of `len` elements, e.g. `arb_instance_vec(3)` or `arb_instance_vec(0..8)`. Useful to check that
+// a circuit rejects public inputs that it didn't compute
+pub fn arb_instance_vec(len: impl Into<SizeRange>) -> impl Strategy<Value = Vec<Fp>> {
+    proptest::collection::vec(arb_fp(), len)
+}
@@ -0,0 +1,502 @@
+use std::{
+    io::{self, Read, Write},
+    marker::PhantomData,
+};
+
+use halo2_proofs::{
+    arithmetic::{Coordinates, CurveAffine, FieldExt},
+    pasta::{group::ff::PrimeField, vesta, EqAffine, Fp},
+    plonk::{create_proof, verify_proof, Circuit, Error, ProvingKey, SingleVerifier, VerificationStrategy, VerifyingKey},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge, Transcript, TranscriptRead, TranscriptWrite},
+};
+use rand_core::{OsRng, RngCore};
+use sha3::{Digest, Keccak256};
+
+use crate::poseidon::{PoseidonSpec, RATE, WIDTH};
+
+// The Fiat-Shamir transcripts a proof can be created with. The prover (src/prover.rs) only passes a TranscriptKind
+// down to TranscriptKind::create_proof and TranscriptKind::verify_proof, which pick the reader and writer: a new
+// transcript is a variant and an arm in each of them, and neither the circuits nor the prover change.
+//
+// Blake2b is the transcript of halo2_proofs 0.1.0. Poseidon
+// is built here on the permutation of the Poseidon chip (src/poseidon.rs), over the base field of the curve: a circuit
+// verifying a proof over that field recomputes its challenges with a few hundred constraints instead of the tens of
+// thousands of Blake2b, which is what recursion needs.
+//
+// The Poseidon transcript is a sponge of width 3 and rate 2. Every message is absorbed as field elements after a tag
+// telling points, scalars and challenges apart:
+//
+//   point    POINT, x, y                  (affine coordinates, points at infinity are refused as by Blake2b)
+//   scalar   SCALAR, s                    (the scalar field of the Pasta curves fits in their base field)
+//   squeeze  CHALLENGE, then permute and take the first word of the state
+//
+// The proof itself contains the same bytes as with Blake2b (compressed points and scalar representations), only the
+// challenges differ, so a proof only verifies under the transcript it was created with.
+//
+// The challenge is the 32 bytes of the squeezed base field element, reduced into the scalar field by Challenge255.
+//
+// Keccak works like Blake2b, a hash of the bytes of the messages, each after a prefix byte, with Keccak-256 (the hash
+// of the EVM) in place of Blake2b-512. A challenge is the digest of everything absorbed so far, so its 32 bytes are
+// reduced into the scalar field.
+
+/// The transcript of a proof, chosen when proving with `prover::prove_with_transcript`.
+///
+/// ```
+/// use halo2_proofs::pasta::Fp;
+/// use halo2_fibonacci_ex::{
+///     error::FibError,
+///     fibonacci::FibInputs,
+///     prover::{prove_with_transcript, verify_with_transcript, CancellationToken, Limits},
+///     transcript::TranscriptKind,
+/// };
+///
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+/// let token = CancellationToken::new();
+/// let (circuit, instance) = (inputs.circuit(), inputs.public_inputs());
+/// let prove = |kind| prove_with_transcript(4, &circuit, &instance, &token, &Limits::default(), kind).unwrap();
+/// let verify = |proof: &[u8], kind| verify_with_transcript(4, &circuit, &instance, proof, kind);
+///
+/// // A proof only verifies under the transcript it was created with
+/// for created in TranscriptKind::ALL {
+///     let proof = prove(created);
+///     for verified in TranscriptKind::ALL {
+///         let result = verify(&proof, verified);
+///         assert_eq!(result.is_ok(), created == verified);
+///         assert!(result.is_ok() || matches!(result, Err(FibError::InvalidProof)));
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TranscriptKind {
+    #[default]
+    Blake2b,
+    Poseidon,
+    Keccak,
+}
+
+impl TranscriptKind {
+    pub const ALL: [TranscriptKind; 3] = [TranscriptKind::Blake2b, TranscriptKind::Poseidon, TranscriptKind::Keccak];
+
+    // Runs create_proof for one circuit with a transcript of this kind and returns the proof
+    pub fn create_proof<C: Circuit<Fp>>(
+        self,
+        params: &Params<EqAffine>,
+        pk: &ProvingKey<EqAffine>,
+        circuit: &C,
+        instance: &[Fp],
+    ) -> Result<Vec<u8>, Error> {
+        self.create_proof_with_rng(params, pk, circuit, instance, OsRng)
+    }
+
+    // The same with the blinding factors drawn from `rng` rather than OsRng. A seeded rng gives the same proof bytes
+    // on every run, which only tests and fixtures want: whoever knows the seed can unblind the witness
+    pub fn create_proof_with_rng<C: Circuit<Fp>, R: RngCore>(
+        self,
+        params: &Params<EqAffine>,
+        pk: &ProvingKey<EqAffine>,
+        circuit: &C,
+        instance: &[Fp],
+        rng: R,
+    ) -> Result<Vec<u8>, Error> {
+        self.create_proof_checked(params, pk, circuit, instance, rng, || Ok(()))
+    }
+
+    // The same with `check` run before every message the prover writes to the transcript, that is between the stages
+    // of create_proof (advice commitments, lookups, permutation, vanishing argument, evaluations, opening). The proof
+    // stops with Error::Transcript at the first check that fails
+    pub fn create_proof_checked<C: Circuit<Fp>, R: RngCore>(
+        self,
+        params: &Params<EqAffine>,
+        pk: &ProvingKey<EqAffine>,
+        circuit: &C,
+        instance: &[Fp],
+        rng: R,
+        check: impl Fn() -> io::Result<()>,
+    ) -> Result<Vec<u8>, Error> {
+        match self {
+            TranscriptKind::Blake2b => {
+                let mut transcript = Blake2bWrite::<_, vesta::Affine, Challenge255<_>>::init(vec![]);
+                prove_into(params, pk, circuit, instance, rng, &mut Checked { inner: &mut transcript, check })?;
+                Ok(transcript.finalize())
+            }
+            TranscriptKind::Poseidon => {
+                let mut transcript = PoseidonWrite::<_, vesta::Affine>::init(vec![]);
+                prove_into(params, pk, circuit, instance, rng, &mut Checked { inner: &mut transcript, check })?;
+                Ok(transcript.finalize())
+            }
+            TranscriptKind::Keccak => {
+                let mut transcript = KeccakWrite::<_, vesta::Affine>::init(vec![]);
+                prove_into(params, pk, circuit, instance, rng, &mut Checked { inner: &mut transcript, check })?;
+                Ok(transcript.finalize())
+            }
+        }
+    }
+
+    // Runs verify_proof on a proof created with a transcript of this kind
+    pub fn verify_proof(
+        self,
+        params: &Params<EqAffine>,
+        vk: &VerifyingKey<EqAffine>,
+        instance: &[Fp],
+        proof: &[u8],
+    ) -> Result<(), Error> {
+        self.verify_with_strategy(params, vk, SingleVerifier::new(params), instance, proof)
+    }
+
+    // The same with any strategy, e.g. a BatchVerifier accumulating several proofs (see src/aggregate.rs)
+    pub fn verify_with_strategy<'params, V: VerificationStrategy<'params, EqAffine>>(
+        self,
+        params: &'params Params<EqAffine>,
+        vk: &VerifyingKey<EqAffine>,
+        strategy: V,
+        instance: &[Fp],
+        proof: &[u8],
+    ) -> Result<V::Output, Error> {
+        match self {
+            TranscriptKind::Blake2b => {
+                let mut transcript = Blake2bRead::<_, vesta::Affine, Challenge255<_>>::init(proof);
+                verify_proof(params, vk, strategy, &[&[instance]], &mut transcript)
+            }
+            TranscriptKind::Poseidon => {
+                let mut transcript = PoseidonRead::<_, vesta::Affine>::init(proof);
+                verify_proof(params, vk, strategy, &[&[instance]], &mut transcript)
+            }
+            TranscriptKind::Keccak => {
+                let mut transcript = KeccakRead::<_, vesta::Affine>::init(proof);
+                verify_proof(params, vk, strategy, &[&[instance]], &mut transcript)
+            }
+        }
+    }
+}
+
+fn prove_into<C: Circuit<Fp>, R: RngCore, T: TranscriptWrite<vesta::Affine, Challenge255<vesta::Affine>>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: &C,
+    instance: &[Fp],
+    rng: R,
+    transcript: &mut T,
+) -> Result<(), Error> {
+    create_proof(params, pk, std::slice::from_ref(circuit), &[&[instance]], rng, transcript)
+}
+
+// A transcript writer running `check` before each message, for TranscriptKind::create_proof_checked
+struct Checked<'a, T, F> {
+    inner: &'a mut T,
+    check: F,
+}
+
+impl<C: CurveAffine, E: EncodedChallenge<C>, T: TranscriptWrite<C, E>, F: Fn() -> io::Result<()>> Transcript<C, E>
+    for Checked<'_, T, F>
+{
+    fn squeeze_challenge(&mut self) -> E {
+        self.inner.squeeze_challenge()
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        self.inner.common_point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.inner.common_scalar(scalar)
+    }
+}
+
+impl<C: CurveAffine, E: EncodedChallenge<C>, T: TranscriptWrite<C, E>, F: Fn() -> io::Result<()>> TranscriptWrite<C, E>
+    for Checked<'_, T, F>
+{
+    fn write_point(&mut self, point: C) -> io::Result<()> {
+        (self.check)()?;
+        self.inner.write_point(point)
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        (self.check)()?;
+        self.inner.write_scalar(scalar)
+    }
+}
+
+const TAG_CHALLENGE: u64 = 0;
+const TAG_POINT: u64 = 1;
+const TAG_SCALAR: u64 = 2;
+
+// The sponge shared by the reader and the writer
+#[derive(Debug, Clone)]
+struct Sponge<F: FieldExt> {
+    spec: PoseidonSpec<F>,
+    state: [F; WIDTH],
+    // Absorbed elements that don't fill the rate yet
+    pending: Vec<F>,
+}
+
+impl<F: FieldExt> Sponge<F> {
+    fn new() -> Self {
+        // The capacity holds a domain separator, so the state never starts at zero
+        let mut state = [F::zero(); WIDTH];
+        state[RATE] = F::from_u128(u128::from_le_bytes(*b"Halo2-Transcript"));
+        Self { spec: PoseidonSpec::new(), state, pending: vec![] }
+    }
+
+    fn absorb(&mut self, value: F) {
+        self.pending.push(value);
+        if self.pending.len() == RATE {
+            self.permute();
+        }
+    }
+
+    fn permute(&mut self) {
+        for (word, value) in self.state.iter_mut().zip(self.pending.drain(..)) {
+            *word += value;
+        }
+        self.spec.permute(&mut self.state);
+    }
+
+    fn squeeze(&mut self) -> F {
+        self.absorb(F::from(TAG_CHALLENGE));
+        if !self.pending.is_empty() {
+            self.permute();
+        }
+        self.state[0]
+    }
+}
+
+fn common_point<C: CurveAffine>(sponge: &mut Sponge<C::Base>, point: C) -> io::Result<()> {
+    let coords: Coordinates<C> = Option::from(point.coordinates())
+        .ok_or_else(|| io::Error::other("cannot write points at infinity to the transcript"))?;
+    sponge.absorb(C::Base::from(TAG_POINT));
+    sponge.absorb(*coords.x());
+    sponge.absorb(*coords.y());
+    Ok(())
+}
+
+// The scalar as an element of the base field, from the bytes of its representation
+fn common_scalar<C: CurveAffine>(sponge: &mut Sponge<C::Base>, scalar: C::Scalar) {
+    let mut bytes = [0u8; 64];
+    let repr = scalar.to_repr();
+    bytes[..repr.as_ref().len()].copy_from_slice(repr.as_ref());
+    sponge.absorb(C::Base::from(TAG_SCALAR));
+    sponge.absorb(C::Base::from_bytes_wide(&bytes));
+}
+
+fn challenge<C: CurveAffine>(sponge: &mut Sponge<C::Base>) -> Challenge255<C> {
+    let mut bytes = [0u8; 64];
+    let repr = sponge.squeeze().to_repr();
+    bytes[..repr.as_ref().len()].copy_from_slice(repr.as_ref());
+    Challenge255::new(&bytes)
+}
+
+/// A Poseidon transcript reading a proof, usable with `verify_proof` like `Blake2bRead`.
+#[derive(Debug, Clone)]
+pub struct PoseidonRead<R: Read, C: CurveAffine> {
+    sponge: Sponge<C::Base>,
+    reader: R,
+}
+
+impl<R: Read, C: CurveAffine> PoseidonRead<R, C> {
+    pub fn init(reader: R) -> Self {
+        Self { sponge: Sponge::new(), reader }
+    }
+}
+
+impl<R: Read, C: CurveAffine> Transcript<C, Challenge255<C>> for PoseidonRead<R, C> {
+    fn squeeze_challenge(&mut self) -> Challenge255<C> {
+        challenge(&mut self.sponge)
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        common_point(&mut self.sponge, point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        common_scalar::<C>(&mut self.sponge, scalar);
+        Ok(())
+    }
+}
+
+impl<R: Read, C: CurveAffine> TranscriptRead<C, Challenge255<C>> for PoseidonRead<R, C> {
+    fn read_point(&mut self) -> io::Result<C> {
+        let point = read_point(&mut self.reader)?;
+        self.common_point(point)?;
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        let scalar = read_scalar::<_, C>(&mut self.reader)?;
+        self.common_scalar(scalar)?;
+        Ok(scalar)
+    }
+}
+
+/// A Poseidon transcript writing a proof, usable with `create_proof` like `Blake2bWrite`.
+#[derive(Debug, Clone)]
+pub struct PoseidonWrite<W: Write, C: CurveAffine> {
+    sponge: Sponge<C::Base>,
+    writer: W,
+}
+
+impl<W: Write, C: CurveAffine> PoseidonWrite<W, C> {
+    pub fn init(writer: W) -> Self {
+        Self { sponge: Sponge::new(), writer }
+    }
+
+    // The proof written so far
+    pub fn finalize(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write, C: CurveAffine> Transcript<C, Challenge255<C>> for PoseidonWrite<W, C> {
+    fn squeeze_challenge(&mut self) -> Challenge255<C> {
+        challenge(&mut self.sponge)
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        common_point(&mut self.sponge, point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        common_scalar::<C>(&mut self.sponge, scalar);
+        Ok(())
+    }
+}
+
+impl<W: Write, C: CurveAffine> TranscriptWrite<C, Challenge255<C>> for PoseidonWrite<W, C> {
+    fn write_point(&mut self, point: C) -> io::Result<()> {
+        self.common_point(point)?;
+        self.writer.write_all(point.to_bytes().as_ref())
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.common_scalar(scalar)?;
+        self.writer.write_all(scalar.to_repr().as_ref())
+    }
+}
+
+const KECCAK_PREFIX_CHALLENGE: u8 = 0;
+const KECCAK_PREFIX_POINT: u8 = 1;
+const KECCAK_PREFIX_SCALAR: u8 = 2;
+
+fn keccak_point<C: CurveAffine>(state: &mut Keccak256, point: C) -> io::Result<()> {
+    let coords: Coordinates<C> = Option::from(point.coordinates())
+        .ok_or_else(|| io::Error::other("cannot write points at infinity to the transcript"))?;
+    state.update([KECCAK_PREFIX_POINT]);
+    state.update(coords.x().to_repr());
+    state.update(coords.y().to_repr());
+    Ok(())
+}
+
+fn keccak_scalar<C: CurveAffine>(state: &mut Keccak256, scalar: C::Scalar) {
+    state.update([KECCAK_PREFIX_SCALAR]);
+    state.update(scalar.to_repr());
+}
+
+fn keccak_challenge<C: CurveAffine>(state: &mut Keccak256) -> Challenge255<C> {
+    state.update([KECCAK_PREFIX_CHALLENGE]);
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(&state.clone().finalize());
+    Challenge255::new(&bytes)
+}
+
+/// A Keccak-256 transcript reading a proof, usable with `verify_proof` like `Blake2bRead`.
+#[derive(Debug, Clone)]
+pub struct KeccakRead<R: Read, C: CurveAffine> {
+    state: Keccak256,
+    reader: R,
+    _marker: PhantomData<C>,
+}
+
+impl<R: Read, C: CurveAffine> KeccakRead<R, C> {
+    pub fn init(reader: R) -> Self {
+        Self { state: Keccak256::new(), reader, _marker: PhantomData }
+    }
+}
+
+impl<R: Read, C: CurveAffine> Transcript<C, Challenge255<C>> for KeccakRead<R, C> {
+    fn squeeze_challenge(&mut self) -> Challenge255<C> {
+        keccak_challenge(&mut self.state)
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        keccak_point(&mut self.state, point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        keccak_scalar::<C>(&mut self.state, scalar);
+        Ok(())
+    }
+}
+
+impl<R: Read, C: CurveAffine> TranscriptRead<C, Challenge255<C>> for KeccakRead<R, C> {
+    fn read_point(&mut self) -> io::Result<C> {
+        let point = read_point(&mut self.reader)?;
+        self.common_point(point)?;
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        let scalar = read_scalar::<_, C>(&mut self.reader)?;
+        self.common_scalar(scalar)?;
+        Ok(scalar)
+    }
+}
+
+/// A Keccak-256 transcript writing a proof, usable with `create_proof` like `Blake2bWrite`.
+#[derive(Debug, Clone)]
+pub struct KeccakWrite<W: Write, C: CurveAffine> {
+    state: Keccak256,
+    writer: W,
+    _marker: PhantomData<C>,
+}
+
+impl<W: Write, C: CurveAffine> KeccakWrite<W, C> {
+    pub fn init(writer: W) -> Self {
+        Self { state: Keccak256::new(), writer, _marker: PhantomData }
+    }
+
+    // The proof written so far
+    pub fn finalize(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write, C: CurveAffine> Transcript<C, Challenge255<C>> for KeccakWrite<W, C> {
+    fn squeeze_challenge(&mut self) -> Challenge255<C> {
+        keccak_challenge(&mut self.state)
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        keccak_point(&mut self.state, point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        keccak_scalar::<C>(&mut self.state, scalar);
+        Ok(())
+    }
+}
+
+impl<W: Write, C: CurveAffine> TranscriptWrite<C, Challenge255<C>> for KeccakWrite<W, C> {
+    fn write_point(&mut self, point: C) -> io::Result<()> {
+        self.common_point(point)?;
+        self.writer.write_all(point.to_bytes().as_ref())
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.common_scalar(scalar)?;
+        self.writer.write_all(scalar.to_repr().as_ref())
+    }
+}
+
+// The proof encoding shared by the transcripts: compressed points and the representations of the scalars, as in
+// halo2_proofs
+fn read_point<R: Read, C: CurveAffine>(reader: &mut R) -> io::Result<C> {
+    let mut compressed = C::Repr::default();
+    reader.read_exact(compressed.as_mut())?;
+    Option::from(C::from_bytes(&compressed)).ok_or_else(|| io::Error::other("invalid point encoding in proof"))
+}
+
+fn read_scalar<R: Read, C: CurveAffine>(reader: &mut R) -> io::Result<C::Scalar> {
+    let mut data = <C::Scalar as PrimeField>::Repr::default();
+    reader.read_exact(data.as_mut())?;
+    Option::from(C::Scalar::from_repr(data)).ok_or_else(|| io::Error::other("invalid field element encoding in proof"))
+}
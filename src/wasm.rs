@@ -0,0 +1,154 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use halo2_proofs::pasta::Fp;
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    bundle::{Metadata, ProofBundle},
+    error::FibError,
+    fibonacci::{max_steps, FibInputs, FibInstance},
+    field::parse_field,
+    prover::{self, CancellationToken, Limits},
+    registry::{verify_bundle, Registry},
+};
+
+// The prover and the verifier of the Fibonacci circuit for JavaScript, with the `wasm` feature:
+//
+//   cargo build --release --lib --target wasm32-unknown-unknown --features wasm
+//   wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/halo2_fibonacci_ex.wasm
+//
+//   const bundle = prove_fib("1", "1", 8);            // the proof bundle, as JSON (src/bundle.rs)
+//   verify_fib(bundle, '["1", "1", "55"]');           // true, false for a proof that doesn't verify, or throws
+//
+// The field elements are strings read by field::parse_field, since a JavaScript number can't hold them. The proof is
+// created for the smallest k the n steps fit in, which the bundle records. A proof that is well formed but doesn't
+// verify for the statement given, the bundle of another statement included, is `false`; every other error (malformed input, unknown circuit, ...) is thrown as the JSON of
+// FibError::to_json, so that JavaScript can branch on their code.
+//
+// Pages that only verify proofs created elsewhere call verify_fib_proof with the proof alone, base64 encoded, and the
+// public statement as JSON, the shape of the circuit included since the proof doesn't carry it:
+//
+//   verify_fib_proof("yxzDN...", '{"k": 4, "n": 8, "a": "1", "b": "1", "out": "55"}');
+//
+// examples/web/index.html is such a page. Both verifiers read their arguments into a ProofBundle of the statement given
+// and verify it the same way, so a proof gets the same answer from either.
+//
+// Creating a proof draws randomness from getrandom, which has no source on wasm32-unknown-unknown unless it is told to
+// use the crypto.getRandomValues of the browser: the feature turns on its `js` backend. The prover runs on a single
+// thread, rayon falls back to the calling thread when the target can't spawn any.
+
+/// Creates a proof that n steps starting from (a, b) reach the output of the bundle.
+///
+/// ```
+/// use halo2_fibonacci_ex::wasm::{prove_fib, verify_fib};
+///
+/// let bundle = prove_fib("1", "1", 8).unwrap();
+/// assert!(verify_fib(&bundle, r#"["1", "1", "55"]"#).unwrap());
+/// ```
+#[wasm_bindgen]
+pub fn prove_fib(a: &str, b: &str, n: usize) -> Result<String, JsError> {
+    prove(a, b, n).map_err(js_error)
+}
+
+/// Verifies a bundle created by prove_fib against the public inputs [a, b, out], a JSON array of strings.
+///
+/// ```
+/// use halo2_fibonacci_ex::{bundle::ProofBundle, wasm::{prove_fib, verify_fib}};
+///
+/// let mut bundle = ProofBundle::from_json(&prove_fib("1", "1", 8).unwrap()).unwrap();
+/// let last = bundle.proof.len() - 1;
+/// bundle.proof[last] ^= 1;
+/// assert!(!verify_fib(&bundle.to_json(), r#"["1", "1", "55"]"#).unwrap());
+///
+/// // A valid bundle of another statement is false as well
+/// let bundle = prove_fib("1", "1", 8).unwrap();
+/// assert!(!verify_fib(&bundle, r#"["1", "1", "56"]"#).unwrap());
+/// ```
+#[wasm_bindgen]
+pub fn verify_fib(bundle: &str, publics: &str) -> Result<bool, JsError> {
+    verified(read_bundle(bundle, publics).and_then(|bundle| verify(&bundle)))
+}
+
+fn prove(a: &str, b: &str, n: usize) -> Result<String, FibError> {
+    let inputs = FibInputs::new(parse_field(a)?, parse_field(b)?, n);
+    let limits = Limits::default();
+    let k = (1..=limits.max_k)
+        .find(|&k| max_steps::<Fp>(k) >= n)
+        .ok_or_else(|| FibError::LimitExceeded(format!("{} steps don't fit in 2^{} rows", n, limits.max_k)))?;
+    let proof = prover::prove_with_limits(k, &inputs.circuit(), &inputs.public_inputs(), &CancellationToken::new(), &limits)?;
+    Ok(ProofBundle::new(Metadata::new(k, inputs.circuit_id()), inputs.public_inputs(), proof).to_json())
+}
+
+// The bundle with the instance of the public inputs given by the caller: a bundle created for another statement
+// doesn't verify, it isn't an error
+fn read_bundle(bundle: &str, publics: &str) -> Result<ProofBundle, FibError> {
+    let bundle = ProofBundle::from_json(bundle)?;
+    let publics: Vec<String> = serde_json::from_str(publics).map_err(|e| FibError::Parse(format!("public inputs: {}", e)))?;
+    let [a, b, out] = <[String; 3]>::try_from(publics)
+        .map_err(|publics| FibError::InvalidInput(format!("expected the 3 public inputs a, b, out, got {}", publics.len())))?;
+    let instance = FibInstance::from_strs(&a, &b, &out)?.column();
+    Ok(ProofBundle { instance, ..bundle })
+}
+
+// The public statement of verify_fib_proof
+#[derive(Deserialize)]
+struct Statement {
+    k: u32,
+    n: usize,
+    a: String,
+    b: String,
+    out: String,
+}
+
+/// Verifies a proof, base64 encoded, against a statement `{"k", "n", "a", "b", "out"}` given as JSON.
+///
+/// ```
+/// use base64::{engine::general_purpose::STANDARD, Engine};
+/// use halo2_fibonacci_ex::{bundle::ProofBundle, wasm::{prove_fib, verify_fib, verify_fib_proof}};
+///
+/// let bundle = ProofBundle::from_json(&prove_fib("1", "1", 8).unwrap()).unwrap();
+/// let proof = STANDARD.encode(&bundle.proof);
+/// assert!(verify_fib_proof(&proof, r#"{"k": 4, "n": 8, "a": "1", "b": "1", "out": "55"}"#).unwrap());
+///
+/// // The same proof for another output is false, not an error, as with verify_fib
+/// assert!(!verify_fib_proof(&proof, r#"{"k": 4, "n": 8, "a": "1", "b": "1", "out": "56"}"#).unwrap());
+/// assert!(!verify_fib(&bundle.to_json(), r#"["1", "1", "56"]"#).unwrap());
+/// ```
+#[wasm_bindgen]
+pub fn verify_fib_proof(proof: &str, statement: &str) -> Result<bool, JsError> {
+    verified(read_proof(proof, statement).and_then(|bundle| verify(&bundle)))
+}
+
+// The bundle of a proof and its statement, as read_bundle gives for a whole bundle
+fn read_proof(proof: &str, statement: &str) -> Result<ProofBundle, FibError> {
+    let proof = STANDARD.decode(proof.trim()).map_err(|e| FibError::Parse(format!("proof: {}", e)))?;
+    let statement: Statement = serde_json::from_str(statement).map_err(|e| FibError::Parse(format!("statement: {}", e)))?;
+    let instance = FibInstance::from_strs(&statement.a, &statement.b, &statement.out)?.column();
+    let circuit_id = FibInputs::new(Fp::zero(), Fp::zero(), statement.n).circuit_id();
+    Ok(ProofBundle::new(Metadata::new(statement.k, circuit_id), instance, proof))
+}
+
+// The verification behind verify_fib and verify_fib_proof, so that both answer the same for the same proof and
+// statement: the bundle is verified against the Fibonacci circuit named by its circuit id
+fn verify(bundle: &ProofBundle) -> Result<(), FibError> {
+    let metadata = &bundle.metadata;
+    Limits::default().check_k(metadata.k)?;
+    let n = FibInputs::<Fp>::steps_of(&metadata.circuit_id)
+        .ok_or_else(|| FibError::InvalidInput(format!("not a Fibonacci proof: {}", metadata.circuit_id)))?;
+    let mut registry = Registry::new();
+    registry.register_fibonacci(metadata.k, n);
+    verify_bundle(bundle, &registry).map(|_| ())
+}
+
+// A proof that doesn't verify is an answer, not an error
+fn verified(result: Result<(), FibError>) -> Result<bool, JsError> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(FibError::InvalidProof) => Ok(false),
+        Err(e) => Err(js_error(e)),
+    }
+}
+
+fn js_error(e: FibError) -> JsError {
+    JsError::new(&e.to_json().to_string())
+}
@@ -0,0 +1,221 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    pasta::{group::ff::PrimeField, Fp},
+};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    fibonacci::{FibCircuit, FibInputs, Fibonacci},
+    sequence::{Sequence, SequenceCircuit},
+};
+
+// Caches the witness traces of statements that are proven more than once, e.g. when the same statements are proven
+// again after a change of transcript or backend. The key is a hash of the circuit id and the inputs, the value is the
+// trace serialized as the 32 byte representations of its field elements, so that the memory used by the cache is
+// known exactly.
+//
+// The trace only saves the native computation of the witness (Sequence::run): halo2 still runs synthesize on every
+// proof to assign it. This is worth it for circuits whose witness is expensive to compute (hashes, decompositions)
+// rather than for Fibonacci, which is what the cache is wired to for now.
+//
+// precompute_circuits separates the two steps for a batch of statements: the traces of all of them are computed up
+// front, on the rayon thread pool with the `parallel` feature, and the circuits only assign them. The states of one
+// sequence depend on each other, so the parallelism is across statements.
+//
+// Entries are evicted least recently used first, once there are more than `max_entries` of them or they take more
+// than `max_bytes` together. The cache has its own lock, so one instance can be shared by the threads of a prover.
+
+// Bounds the size of a WitnessCache. A trace larger than max_bytes is never cached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheLimits {
+    pub max_entries: usize,
+    pub max_bytes: usize,
+}
+
+impl Default for CacheLimits {
+    // 64 MB holds a million field elements, the traces of a thousand circuits with 2^10 rows of Fibonacci
+    fn default() -> Self {
+        Self { max_entries: 1024, max_bytes: 64 << 20 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+struct Entry {
+    width: usize,
+    bytes: Vec<u8>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<String, Entry>,
+    clock: u64,
+    stats: CacheStats,
+}
+
+/// A bounded cache of witness traces, keyed by [`witness_key`].
+///
+/// ```
+/// use halo2_proofs::{dev::MockProver, pasta::Fp};
+/// use halo2_fibonacci_ex::{fibonacci::FibInputs, witness::{CacheLimits, WitnessCache}};
+///
+/// let cache = WitnessCache::new(CacheLimits { max_entries: 1, ..CacheLimits::default() });
+/// let inputs = FibInputs::new(Fp::one(), Fp::one(), 8);
+///
+/// // The second circuit reuses the trace of the first one
+/// for _ in 0..2 {
+///     let circuit = cache.fibonacci_circuit(&inputs);
+///     MockProver::run(4, &circuit, vec![inputs.public_inputs()]).unwrap().assert_satisfied();
+/// }
+/// assert_eq!((cache.stats().misses, cache.stats().hits), (1, 1));
+///
+/// // Another statement evicts it
+/// cache.fibonacci_circuit(&FibInputs::new(Fp::one(), Fp::from(2), 8));
+/// assert_eq!((cache.stats().entries, cache.stats().evictions), (1, 1));
+/// ```
+pub struct WitnessCache {
+    limits: CacheLimits,
+    inner: Mutex<Inner>,
+}
+
+impl WitnessCache {
+    pub fn new(limits: CacheLimits) -> Self {
+        Self { limits, inner: Mutex::new(Inner::default()) }
+    }
+
+    // The trace stored under `key`, or the one returned by `compute`, which is then stored. Every state of a trace
+    // must have the same number of words
+    pub fn get_or_insert_with(&self, key: &str, compute: impl FnOnce() -> Vec<Vec<Fp>>) -> Vec<Vec<Fp>> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.clock += 1;
+        let clock = inner.clock;
+
+        if let Some(entry) = inner.entries.get_mut(key) {
+            entry.last_used = clock;
+            let trace = deserialize(entry.width, &entry.bytes);
+            inner.stats.hits += 1;
+            return trace;
+        }
+
+        inner.stats.misses += 1;
+        let trace = compute();
+        let entry = Entry { width: trace.first().map_or(0, Vec::len), bytes: serialize(&trace), last_used: clock };
+        if entry.bytes.len() <= self.limits.max_bytes && self.limits.max_entries > 0 {
+            inner.stats.bytes += entry.bytes.len();
+            inner.entries.insert(key.to_string(), entry);
+            self.evict(&mut inner);
+        }
+        inner.stats.entries = inner.entries.len();
+        trace
+    }
+
+    // The Fibonacci circuit of `inputs`, with its trace taken from the cache when possible
+    pub fn fibonacci_circuit(&self, inputs: &FibInputs<Fp>) -> FibCircuit<Fp> {
+        let initial = [inputs.a, inputs.b];
+        let key = witness_key(&inputs.circuit_id(), &initial);
+        let trace = self.get_or_insert_with(&key, || Fibonacci::run(&initial, inputs.n));
+        FibCircuit::with_trace(vec![Some(inputs.a), Some(inputs.b)], inputs.n, trace)
+    }
+
+    pub fn remove(&self, key: &str) -> bool {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let removed = inner.entries.remove(key);
+        if let Some(entry) = &removed {
+            inner.stats.bytes -= entry.bytes.len();
+            inner.stats.entries = inner.entries.len();
+        }
+        removed.is_some()
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.entries.clear();
+        inner.stats.entries = 0;
+        inner.stats.bytes = 0;
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).stats
+    }
+
+    // Drops the least recently used entries until the cache fits its limits
+    fn evict(&self, inner: &mut Inner) {
+        while inner.entries.len() > self.limits.max_entries || inner.stats.bytes > self.limits.max_bytes {
+            let Some(oldest) = inner.entries.iter().min_by_key(|(_, entry)| entry.last_used).map(|(key, _)| key.clone()) else {
+                break;
+            };
+            let entry = inner.entries.remove(&oldest).expect("the key was just found");
+            inner.stats.bytes -= entry.bytes.len();
+            inner.stats.evictions += 1;
+        }
+    }
+}
+
+/// The circuits of a batch of statements `(initial state, steps)` of a [`Sequence`], each with its trace computed up
+/// front (see [`SequenceCircuit::with_trace`]). With the `parallel` feature the traces are computed in parallel.
+///
+/// ```
+/// use halo2_proofs::{dev::MockProver, pasta::Fp};
+/// use halo2_fibonacci_ex::{fibonacci::Fibonacci, sequence::Sequence, witness::precompute_circuits};
+///
+/// let statements: Vec<(Vec<Fp>, usize)> = (1..=4u64).map(|a| (vec![Fp::from(a), Fp::one()], 8)).collect();
+/// let circuits = precompute_circuits::<Fp, Fibonacci>(&statements);
+///
+/// for ((initial, steps), circuit) in statements.iter().zip(circuits.iter()) {
+///     assert_eq!(circuit.trace.as_ref(), Some(&Fibonacci::run(initial, *steps)));
+///     MockProver::run(4, circuit, vec![Fibonacci::public_inputs(initial, *steps)]).unwrap().assert_satisfied();
+/// }
+/// ```
+pub fn precompute_circuits<F: FieldExt, S: Sequence<F>>(statements: &[(Vec<F>, usize)]) -> Vec<SequenceCircuit<F, S>> {
+    #[cfg(feature = "parallel")]
+    let traces: Vec<Vec<Vec<F>>> = statements.par_iter().map(|(initial, steps)| S::run(initial, *steps)).collect();
+    #[cfg(not(feature = "parallel"))]
+    let traces: Vec<Vec<Vec<F>>> = statements.iter().map(|(initial, steps)| S::run(initial, *steps)).collect();
+
+    statements
+        .iter()
+        .zip(traces)
+        .map(|((initial, steps), trace)| SequenceCircuit::with_trace(initial.iter().copied().map(Some).collect(), *steps, trace))
+        .collect()
+}
+
+// Identifies a statement: the hex SHA-256 of the circuit id (which includes the shape, e.g. "fibonacci-8") and of the
+// private inputs the trace is computed from
+pub fn witness_key(circuit_id: &str, inputs: &[Fp]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update((circuit_id.len() as u64).to_le_bytes());
+    hasher.update(circuit_id.as_bytes());
+    for input in inputs {
+        hasher.update(input.to_repr());
+    }
+    hex::encode(hasher.finalize())
+}
+
+fn serialize(trace: &[Vec<Fp>]) -> Vec<u8> {
+    trace.iter().flatten().flat_map(|value| value.to_repr()).collect()
+}
+
+// The bytes were serialized from field elements by this process, so they are canonical representations
+fn deserialize(width: usize, bytes: &[u8]) -> Vec<Vec<Fp>> {
+    let values: Vec<Fp> = bytes
+        .chunks_exact(32)
+        .map(|chunk| {
+            let mut repr = <Fp as PrimeField>::Repr::default();
+            repr.copy_from_slice(chunk);
+            Fp::from_repr(repr).expect("the cache only stores canonical field elements")
+        })
+        .collect();
+    values.chunks(width.max(1)).map(<[Fp]>::to_vec).collect()
+}